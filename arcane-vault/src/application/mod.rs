@@ -1,3 +1,7 @@
+mod schema;
 mod service;
+mod storage;
 
-pub use service::*;
\ No newline at end of file
+pub use schema::*;
+pub use service::*;
+pub use storage::*;
\ No newline at end of file
@@ -0,0 +1,13 @@
+use crate::{domain::error::ArcaneVaultError, infrastructure::repository::DbContext};
+
+/// Checks that the database provides every SQL function arcane-vault calls, returning the columns
+/// its row mappers read. The error message lists each mismatch.
+///
+/// This is the standalone check, on a pool of its own that is closed afterwards;
+/// [`crate::Storage::create_repositories`] checks the pool it serves requests with.
+pub async fn check_schema() -> Result<(), ArcaneVaultError> {
+    let db_context = DbContext::new_unobserved().await?;
+    let result = db_context.check_schema().await;
+    db_context.close();
+    result
+}
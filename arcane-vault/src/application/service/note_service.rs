@@ -0,0 +1,525 @@
+use std::time::{Duration, SystemTime};
+
+use ethereal_core::proto::{Attachment, DiffLine, NoteRevision};
+use futures_util::{StreamExt, stream::BoxStream};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{
+            DIFF_LINE_DELETED, DIFF_LINE_INSERTED, DIFF_LINE_UNCHANGED, NOTES_READ_SCOPE,
+            NOTES_WRITE_SCOPE, Principal,
+        },
+        error::ArcaneVaultError,
+        repository::{AttachmentRepository, NoteRevisionRepository},
+    },
+    infrastructure::{attachment::AttachmentStore, error_code::raise_error},
+};
+
+// Size of the chunks files are downloaded in.
+const ATTACHMENT_CHUNK_SIZE: usize = 64 * 1024;
+// Blobs deleted per statement when collecting, so no transaction runs long.
+const ATTACHMENT_COLLECT_BATCH_SIZE: i32 = 100;
+// Lines of two revisions together beyond which they are not diffed, as diffing takes time of the
+// number of lines times the number of changed ones.
+const DIFF_MAX_LINES: usize = 5_000;
+
+pub struct NoteService {
+    attachment_repository: Box<dyn AttachmentRepository>,
+    attachment_store: AttachmentStore,
+    note_revision_repository: Box<dyn NoteRevisionRepository>,
+    // Revisions are kept while among this many latest of their note or replaced within this
+    // period; without either they are kept for good.
+    revision_keep_count: Option<i32>,
+    revision_keep_period: Option<Duration>,
+}
+
+impl NoteService {
+    /// Fails when the blob store in `arcane-vault[0]` is invalid.
+    pub fn create(
+        attachment_repository: Box<dyn AttachmentRepository>,
+        note_revision_repository: Box<dyn NoteRevisionRepository>,
+    ) -> Result<Box<dyn crate::domain::service::NoteService>, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        Ok(Box::new(Self {
+            attachment_repository,
+            attachment_store: AttachmentStore::new()?,
+            note_revision_repository,
+            revision_keep_count: config
+                .get::<i32>("arcane-vault[0].note_revision_keep_count")
+                .ok(),
+            revision_keep_period: config
+                .get::<u64>("arcane-vault[0].note_revision_keep_days")
+                .ok()
+                .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::service::NoteService for NoteService {
+    #[tracing::instrument(name = "note_service.upload_attachment", skip_all, fields(user_id = %principal.user_id, note_id = %note_id))]
+    async fn upload_attachment(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        file_name: &str,
+        content_type: &str,
+        mut chunks: BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>,
+    ) -> Result<Attachment, ArcaneVaultError> {
+        const FUNCTION: &str = "note_service.upload_attachment";
+        principal.check_scope(NOTES_WRITE_SCOPE)?;
+        // Notes of other users fail before the file is read.
+        self.attachment_repository
+            .query_attachments(principal.user_id, note_id)
+            .await?;
+
+        let max_size = self.attachment_store.get_max_size();
+        let mut data = Vec::new();
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            if data.len() + chunk.len() > max_size {
+                return Err(raise_error(FUNCTION, "PN007", &[&max_size.to_string()]));
+            }
+            hasher.update(&chunk);
+            data.extend_from_slice(&chunk);
+        }
+        let sha256 = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+
+        let content_type = match content_type {
+            "" => "application/octet-stream",
+            content_type => content_type,
+        };
+        let attachment = self
+            .attachment_repository
+            .add_attachment(
+                principal.user_id,
+                note_id,
+                &sha256,
+                data.len() as i64,
+                file_name,
+                content_type,
+            )
+            .await?;
+        // Also after an upload of the same file that failed or is still being written; the file
+        // is the same either way.
+        if !attachment.is_stored {
+            let result = match self
+                .attachment_store
+                .put_blob(principal.user_id, &sha256, attachment.blob_id, data)
+                .await
+            {
+                Ok(()) => {
+                    self.attachment_repository
+                        .set_attachment_blob_stored(attachment.blob_id)
+                        .await
+                }
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                // Leaves the blob to the collector unless other attachments refer to it.
+                if let Err(err) = self
+                    .attachment_repository
+                    .delete_attachment(principal.user_id, attachment.id)
+                    .await
+                {
+                    tracing::warn!(attachment_id = %attachment.id, error = %err.message, "attachment of a failed upload not deleted");
+                }
+                return Err(err);
+            }
+        }
+        Ok(attachment.into())
+    }
+
+    #[tracing::instrument(name = "note_service.list_attachments", skip_all, fields(user_id = %principal.user_id, note_id = %note_id))]
+    async fn list_attachments(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+    ) -> Result<Vec<Attachment>, ArcaneVaultError> {
+        principal.check_scope(NOTES_READ_SCOPE)?;
+        let attachments = self
+            .attachment_repository
+            .query_attachments(principal.user_id, note_id)
+            .await?;
+        Ok(attachments.into_iter().map(Attachment::from).collect())
+    }
+
+    #[tracing::instrument(name = "note_service.download_attachment", skip_all, fields(user_id = %principal.user_id, attachment_id = %attachment_id))]
+    async fn download_attachment(
+        &self,
+        principal: &Principal,
+        attachment_id: Uuid,
+    ) -> Result<
+        (
+            Attachment,
+            BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>,
+        ),
+        ArcaneVaultError,
+    > {
+        const FUNCTION: &str = "note_service.download_attachment";
+        principal.check_scope(NOTES_READ_SCOPE)?;
+        let attachment = self
+            .attachment_repository
+            .query_attachment(principal.user_id, attachment_id)
+            .await?;
+        let data = if attachment.is_stored {
+            self.attachment_store
+                .get_blob(principal.user_id, &attachment.sha256, attachment.blob_id)
+                .await?
+        } else {
+            None
+        };
+        // Not yet stored, or lost from the blob store.
+        let Some(data) = data else {
+            return Err(raise_error(
+                FUNCTION,
+                "PN006",
+                &[&attachment_id.to_string()],
+            ));
+        };
+        let chunks = data
+            .chunks(ATTACHMENT_CHUNK_SIZE)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect::<Vec<_>>();
+        Ok((
+            attachment.into(),
+            futures_util::stream::iter(chunks).boxed(),
+        ))
+    }
+
+    #[tracing::instrument(name = "note_service.delete_attachment", skip_all, fields(user_id = %principal.user_id, attachment_id = %attachment_id))]
+    async fn delete_attachment(
+        &self,
+        principal: &Principal,
+        attachment_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        principal.check_scope(NOTES_WRITE_SCOPE)?;
+        self.attachment_repository
+            .delete_attachment(principal.user_id, attachment_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "note_service.collect_attachment_blobs", skip_all)]
+    async fn collect_attachment_blobs(&self) -> Result<usize, ArcaneVaultError> {
+        let orphaned_before = SystemTime::now() - self.attachment_store.get_gc_grace_period();
+        let mut collected = 0;
+        loop {
+            let blobs = self
+                .attachment_repository
+                .collect_attachment_blobs(orphaned_before, ATTACHMENT_COLLECT_BATCH_SIZE)
+                .await?;
+            for blob in &blobs {
+                // The row is gone already, so a file not deleted is only left behind.
+                if let Err(err) = self
+                    .attachment_store
+                    .delete_blob(blob.user_id, &blob.sha256, blob.id)
+                    .await
+                {
+                    tracing::warn!(blob_id = %blob.id, error = %err.message, "attachment blob file not deleted");
+                }
+            }
+            collected += blobs.len();
+            if blobs.len() < ATTACHMENT_COLLECT_BATCH_SIZE as usize {
+                return Ok(collected);
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "note_service.list_note_revisions", skip_all, fields(user_id = %principal.user_id, note_id = %note_id))]
+    async fn list_note_revisions(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<NoteRevision>, ArcaneVaultError> {
+        principal.check_scope(NOTES_READ_SCOPE)?;
+        let revisions = self
+            .note_revision_repository
+            .query_note_revisions(principal.user_id, note_id, offset, limit)
+            .await?;
+        Ok(revisions.into_iter().map(NoteRevision::from).collect())
+    }
+
+    #[tracing::instrument(name = "note_service.get_note_revision", skip_all, fields(user_id = %principal.user_id, note_id = %note_id, revision))]
+    async fn get_note_revision(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<NoteRevision, ArcaneVaultError> {
+        principal.check_scope(NOTES_READ_SCOPE)?;
+        let revision = self
+            .note_revision_repository
+            .query_note_revision(principal.user_id, note_id, revision)
+            .await?;
+        Ok(revision.into())
+    }
+
+    #[tracing::instrument(name = "note_service.diff_note_revisions", skip_all, fields(user_id = %principal.user_id, note_id = %note_id, from_revision, to_revision))]
+    async fn diff_note_revisions(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        from_revision: i32,
+        to_revision: i32,
+    ) -> Result<(Vec<DiffLine>, Vec<DiffLine>), ArcaneVaultError> {
+        principal.check_scope(NOTES_READ_SCOPE)?;
+        let from = self
+            .note_revision_repository
+            .query_note_revision(principal.user_id, note_id, from_revision)
+            .await?;
+        let to = self
+            .note_revision_repository
+            .query_note_revision(principal.user_id, note_id, to_revision)
+            .await?;
+        // Pretty-printed, so a changed key shows as a changed line.
+        let from_meta = serde_json::to_string_pretty(&from.meta).unwrap_or_default();
+        let to_meta = serde_json::to_string_pretty(&to.meta).unwrap_or_default();
+        Ok((
+            get_line_diff(&from.content, &to.content),
+            get_line_diff(&from_meta, &to_meta),
+        ))
+    }
+
+    #[tracing::instrument(name = "note_service.restore_note_revision", skip_all, fields(user_id = %principal.user_id, note_id = %note_id, revision))]
+    async fn restore_note_revision(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<i32, ArcaneVaultError> {
+        principal.check_scope(NOTES_WRITE_SCOPE)?;
+        self.note_revision_repository
+            .restore_note_revision(principal.user_id, note_id, revision)
+            .await
+    }
+
+    #[tracing::instrument(name = "note_service.prune_note_revisions", skip_all)]
+    async fn prune_note_revisions(&self) -> Result<usize, ArcaneVaultError> {
+        if self.revision_keep_count.is_none() && self.revision_keep_period.is_none() {
+            return Ok(0);
+        }
+        let keep_after = self
+            .revision_keep_period
+            .map(|keep_period| SystemTime::now() - keep_period);
+        let pruned = self
+            .note_revision_repository
+            .prune_note_revisions(self.revision_keep_count, keep_after)
+            .await?;
+        Ok(pruned as usize)
+    }
+}
+
+// Myers' diff of the lines of `from` and `to`, in the order of the lines. Revisions of more than
+// `DIFF_MAX_LINES` together show as replaced as a whole.
+fn get_line_diff(from: &str, to: &str) -> Vec<DiffLine> {
+    let from = from.lines().collect::<Vec<_>>();
+    let to = to.lines().collect::<Vec<_>>();
+    let mut lines = Vec::with_capacity(from.len() + to.len());
+    if from.len() + to.len() > DIFF_MAX_LINES {
+        add_diff_lines(&mut lines, DIFF_LINE_DELETED, &from);
+        add_diff_lines(&mut lines, DIFF_LINE_INSERTED, &to);
+    } else {
+        add_line_diff(&mut lines, &from, &to);
+    }
+    lines
+}
+
+// The linear space variant of the diff: the lines both sides start and end with are unchanged,
+// and what is left in between is split at a point of a shortest edit script and diffed in halves.
+fn add_line_diff(lines: &mut Vec<DiffLine>, from: &[&str], to: &[&str]) {
+    let prefix_len = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+    let suffix_len = from[prefix_len..]
+        .iter()
+        .rev()
+        .zip(to[prefix_len..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let inner_from = &from[prefix_len..from.len() - suffix_len];
+    let inner_to = &to[prefix_len..to.len() - suffix_len];
+    add_diff_lines(lines, DIFF_LINE_UNCHANGED, &from[..prefix_len]);
+    if inner_from.is_empty() || inner_to.is_empty() {
+        add_diff_lines(lines, DIFF_LINE_DELETED, inner_from);
+        add_diff_lines(lines, DIFF_LINE_INSERTED, inner_to);
+    } else {
+        let (x, y) = get_middle_snake(inner_from, inner_to);
+        add_line_diff(lines, &inner_from[..x], &inner_to[..y]);
+        add_line_diff(lines, &inner_from[x..], &inner_to[y..]);
+    }
+    add_diff_lines(lines, DIFF_LINE_UNCHANGED, &from[from.len() - suffix_len..]);
+}
+
+// Where the furthest reaching paths from the start and from the end of `from` and `to`, which
+// neither start nor end alike, first overlap. That splits a shortest edit script in about half,
+// with at least one edit on either side.
+fn get_middle_snake(from: &[&str], to: &[&str]) -> (usize, usize) {
+    let (n, m) = (from.len() as isize, to.len() as isize);
+    let delta = n - m;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // Furthest `x` of every diagonal `k = x - y` after `d` edits, from the start and, counting
+    // backwards, from the end; the forward diagonal of a backward one is `delta - k`.
+    let mut forward = vec![0isize; (2 * max + 3) as usize];
+    let mut backward = forward.clone();
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && forward[index - 1] < forward[index + 1]) {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let (start_x, start_y) = (x, x - k);
+            while x < n && x - k < m && from[x as usize] == to[(x - k) as usize] {
+                x += 1;
+            }
+            forward[index] = x;
+            if delta % 2 != 0
+                && (k - delta).abs() < d
+                && x + backward[(delta - k + offset) as usize] >= n
+            {
+                return (start_x as usize, start_y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && backward[index - 1] < backward[index + 1]) {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            while x < n && x - k < m && from[(n - x - 1) as usize] == to[(m - x + k - 1) as usize] {
+                x += 1;
+            }
+            backward[index] = x;
+            if delta % 2 == 0
+                && (k - delta).abs() <= d
+                && x + forward[(delta - k + offset) as usize] >= n
+            {
+                return ((n - x) as usize, (m - x + k) as usize);
+            }
+        }
+    }
+    // Not reached, as the paths overlap within `max` edits; deleting everything first is still a
+    // valid split.
+    (n as usize, 0)
+}
+
+fn add_diff_lines(lines: &mut Vec<DiffLine>, operation: i32, texts: &[&str]) {
+    lines.extend(texts.iter().map(|text| DiffLine {
+        operation,
+        text: text.to_string(),
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_operations(lines: &[DiffLine]) -> Vec<(i32, &str)> {
+        lines
+            .iter()
+            .map(|line| (line.operation, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_empty_revisions() {
+        assert!(get_line_diff("", "").is_empty());
+        assert_eq!(
+            get_operations(&get_line_diff("", "a\nb")),
+            [(DIFF_LINE_INSERTED, "a"), (DIFF_LINE_INSERTED, "b")]
+        );
+        assert_eq!(
+            get_operations(&get_line_diff("a\nb", "")),
+            [(DIFF_LINE_DELETED, "a"), (DIFF_LINE_DELETED, "b")]
+        );
+    }
+
+    #[test]
+    fn diffs_identical_revisions() {
+        assert_eq!(
+            get_operations(&get_line_diff("a\nb\nc", "a\nb\nc")),
+            [
+                (DIFF_LINE_UNCHANGED, "a"),
+                (DIFF_LINE_UNCHANGED, "b"),
+                (DIFF_LINE_UNCHANGED, "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diffs_inserted_lines() {
+        assert_eq!(
+            get_operations(&get_line_diff("a\nc", "x\na\nb\nc\ny")),
+            [
+                (DIFF_LINE_INSERTED, "x"),
+                (DIFF_LINE_UNCHANGED, "a"),
+                (DIFF_LINE_INSERTED, "b"),
+                (DIFF_LINE_UNCHANGED, "c"),
+                (DIFF_LINE_INSERTED, "y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diffs_deleted_lines() {
+        assert_eq!(
+            get_operations(&get_line_diff("x\na\nb\nc\ny", "a\nc")),
+            [
+                (DIFF_LINE_DELETED, "x"),
+                (DIFF_LINE_UNCHANGED, "a"),
+                (DIFF_LINE_DELETED, "b"),
+                (DIFF_LINE_UNCHANGED, "c"),
+                (DIFF_LINE_DELETED, "y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_longest_common_lines_of_changed_revisions() {
+        let lines = get_line_diff("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc");
+
+        let unchanged = lines
+            .iter()
+            .filter(|line| line.operation == DIFF_LINE_UNCHANGED)
+            .count();
+        let from = lines
+            .iter()
+            .filter(|line| line.operation != DIFF_LINE_INSERTED)
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>();
+        let to = lines
+            .iter()
+            .filter(|line| line.operation != DIFF_LINE_DELETED)
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(unchanged, 4);
+        assert_eq!(from.join("\n"), "a\nb\nc\na\nb\nb\na");
+        assert_eq!(to.join("\n"), "c\nb\na\nb\na\nc");
+    }
+
+    #[test]
+    fn replaces_revisions_of_too_many_lines_as_a_whole() {
+        let from = vec!["a"; DIFF_MAX_LINES].join("\n");
+        let to = format!("{}\nb", from);
+
+        let lines = get_line_diff(&from, &to);
+
+        assert_eq!(lines.len(), 2 * DIFF_MAX_LINES + 1);
+        assert!(
+            lines
+                .iter()
+                .all(|line| line.operation != DIFF_LINE_UNCHANGED)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    entity::ReferenceData, error::ArcaneVaultError, repository::ReferenceDataRepository,
+};
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Serves the lookup tables from memory, loading them again once they are older than
+/// `arcane-vault[0].reference_data_refresh_secs`.
+pub struct ReferenceDataService {
+    reference_data_repository: Box<dyn ReferenceDataRepository>,
+    refresh_interval: Duration,
+    cache: RwLock<Option<CachedReferenceData>>,
+}
+
+struct CachedReferenceData {
+    reference_data: Arc<ReferenceData>,
+    loaded_at: Instant,
+}
+
+impl ReferenceDataService {
+    pub fn create(
+        reference_data_repository: Box<dyn ReferenceDataRepository>,
+    ) -> Box<dyn crate::domain::service::ReferenceDataService> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        let refresh_interval = config
+            .get::<u64>("arcane-vault[0].reference_data_refresh_secs")
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REFRESH_INTERVAL);
+        Box::new(Self {
+            reference_data_repository,
+            refresh_interval,
+            cache: RwLock::new(None),
+        })
+    }
+
+    fn get_fresh(&self, cache: &Option<CachedReferenceData>) -> Option<Arc<ReferenceData>> {
+        cache
+            .as_ref()
+            .filter(|cached| cached.loaded_at.elapsed() < self.refresh_interval)
+            .map(|cached| cached.reference_data.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::service::ReferenceDataService for ReferenceDataService {
+    #[tracing::instrument(name = "reference_data_service.get_reference_data", skip_all)]
+    async fn get_reference_data(&self) -> Result<Arc<ReferenceData>, ArcaneVaultError> {
+        if let Some(reference_data) = self.get_fresh(&*self.cache.read().await) {
+            return Ok(reference_data);
+        }
+
+        let mut cache = self.cache.write().await;
+        // Another request may have refreshed the cache while this one waited for the lock.
+        if let Some(reference_data) = self.get_fresh(&cache) {
+            return Ok(reference_data);
+        }
+        match self.reference_data_repository.query_reference_data().await {
+            Ok(reference_data) => {
+                let reference_data = Arc::new(reference_data);
+                *cache = Some(CachedReferenceData {
+                    reference_data: reference_data.clone(),
+                    loaded_at: Instant::now(),
+                });
+                Ok(reference_data)
+            }
+            // The tables rarely change, so an outdated copy beats failing the request.
+            Err(err) => match cache.as_ref() {
+                Some(cached) => {
+                    tracing::warn!(error = %err, "refreshing reference data failed, serving the cached copy");
+                    Ok(cached.reference_data.clone())
+                }
+                None => Err(err),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+    use crate::domain::service::ReferenceDataService as _;
+
+    /// Numbers each load in `timezones` and fails the loads after the first `successes`.
+    struct CountingRepository {
+        loads: Arc<AtomicU32>,
+        successes: u32,
+    }
+
+    #[async_trait::async_trait]
+    impl ReferenceDataRepository for CountingRepository {
+        async fn query_reference_data(&self) -> Result<ReferenceData, ArcaneVaultError> {
+            let load = self.loads.fetch_add(1, Ordering::SeqCst) + 1;
+            if load > self.successes {
+                return Err(ArcaneVaultError {
+                    message: "connection refused".into(),
+                    code: None,
+                    metadata: HashMap::new(),
+                });
+            }
+            Ok(ReferenceData {
+                timezones: vec![format!("load {}", load)],
+                ..Default::default()
+            })
+        }
+    }
+
+    fn create_service(
+        refresh_interval: Duration,
+        successes: u32,
+    ) -> (ReferenceDataService, Arc<AtomicU32>) {
+        let loads = Arc::new(AtomicU32::new(0));
+        let service = ReferenceDataService {
+            reference_data_repository: Box::new(CountingRepository {
+                loads: loads.clone(),
+                successes,
+            }),
+            refresh_interval,
+            cache: RwLock::new(None),
+        };
+        (service, loads)
+    }
+
+    #[tokio::test]
+    async fn serves_the_cache_until_it_is_outdated() {
+        let (service, loads) = create_service(Duration::from_secs(3600), u32::MAX);
+
+        let first = service.get_reference_data().await.unwrap();
+        let second = service.get_reference_data().await.unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn loads_the_tables_again_once_outdated() {
+        let (service, loads) = create_service(Duration::ZERO, u32::MAX);
+
+        service.get_reference_data().await.unwrap();
+        let reference_data = service.get_reference_data().await.unwrap();
+
+        assert_eq!(reference_data.timezones, ["load 2"]);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn serves_the_outdated_cache_when_loading_fails() {
+        let (service, loads) = create_service(Duration::ZERO, 1);
+
+        service.get_reference_data().await.unwrap();
+        let reference_data = service.get_reference_data().await.unwrap();
+
+        assert_eq!(reference_data.timezones, ["load 1"]);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_when_nothing_is_cached() {
+        let (service, _) = create_service(Duration::ZERO, 0);
+
+        let err = service.get_reference_data().await.unwrap_err();
+
+        assert_eq!(err.message, "connection refused");
+    }
+}
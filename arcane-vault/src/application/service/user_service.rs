@@ -1,151 +1,1152 @@
-use ethereal_core::proto::User;
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+
+use ethereal_core::proto::{
+    AuditEvent, ExternalIdentity, PersonalAccessToken, Session, SessionTokens, User,
+};
+use futures_util::{StreamExt, stream::BoxStream};
 use uuid::Uuid;
 
-use crate::{domain::error::{ArcaneVaultError, ArcaneVaultErrorCode}, infrastructure::repository::DbContext};
+use crate::{
+    domain::{
+        entity::{
+            AUDIT_EVENT_EMAIL_CHANGED, AUDIT_EVENT_LOGIN_FAILED, AUDIT_EVENT_LOGIN_SUCCEEDED,
+            AUDIT_EVENT_PROFILE_CHANGED, AUDIT_EVENT_REGISTERED, AUDIT_EVENT_VERIFIED, Credential,
+            NewAuditEvent, NewUser, PERSONAL_ACCESS_TOKEN_SCOPES, Principal,
+        },
+        error::{ArcaneVaultError, ArcaneVaultErrorCode},
+        repository::{
+            AuditRepository, OidcRepository, PersonalAccessTokenRepository, SessionRepository,
+            TotpRepository, UserRepository,
+        },
+        service::{LoginOutcome, Mailer, PasswordHasher, RequestContext, SessionDevice},
+    },
+    infrastructure::{
+        avatar::{AVATAR_MAX_SIZE, AvatarStore, get_avatar_thumbnails},
+        error_code::raise_error,
+        mailer::create_mailer,
+        oidc::{OidcAuthenticator, OidcClaims},
+        password_hasher::Argon2PasswordHasher,
+        rule::{
+            DEFAULT_TIMEZONE, EMAIL_CHANGE_EXPIRY, NO_PASSWORD, RECENT_LOGIN_WINDOW,
+            USER_ROLE_ADMIN,
+        },
+        token::{
+            PERSONAL_ACCESS_TOKEN_PREFIX, SessionTokenIssuer, generate_personal_access_token,
+            get_token_hash,
+        },
+        totp::{TotpAuthenticator, generate_recovery_codes, get_recovery_code_hash, is_totp_code},
+    },
+};
 
 pub struct UserService {
-    db_context: DbContext,
+    user_repository: Box<dyn UserRepository>,
+    totp_repository: Box<dyn TotpRepository>,
+    session_repository: Box<dyn SessionRepository>,
+    personal_access_token_repository: Box<dyn PersonalAccessTokenRepository>,
+    oidc_repository: Box<dyn OidcRepository>,
+    audit_repository: Box<dyn AuditRepository>,
+    password_hasher: Arc<dyn PasswordHasher>,
+    mailer: Arc<dyn Mailer>,
+    totp_authenticator: TotpAuthenticator,
+    session_token_issuer: SessionTokenIssuer,
+    oidc_authenticator: OidcAuthenticator,
+    avatar_store: AvatarStore,
 }
 
 impl UserService {
-    pub async fn new() -> Box<dyn crate::domain::service::UserService> {
-        Box::new(Self {
-            db_context: DbContext::new().await.expect("Create db_context failed"),
+    /// Fails when the Argon2 parameters, the SMTP settings, the TOTP key, the token lifetimes, the
+    /// OpenID Connect providers or the blob store in `arcane-vault[0]` are invalid.
+    pub fn create(
+        user_repository: Box<dyn UserRepository>,
+        totp_repository: Box<dyn TotpRepository>,
+        session_repository: Box<dyn SessionRepository>,
+        personal_access_token_repository: Box<dyn PersonalAccessTokenRepository>,
+        oidc_repository: Box<dyn OidcRepository>,
+        audit_repository: Box<dyn AuditRepository>,
+    ) -> Result<Box<dyn crate::domain::service::UserService>, ArcaneVaultError> {
+        Ok(Box::new(Self {
+            user_repository,
+            totp_repository,
+            session_repository,
+            personal_access_token_repository,
+            oidc_repository,
+            audit_repository,
+            password_hasher: Arc::new(Argon2PasswordHasher::new()?),
+            mailer: create_mailer()?,
+            totp_authenticator: TotpAuthenticator::new()?,
+            session_token_issuer: SessionTokenIssuer::new()?,
+            oidc_authenticator: OidcAuthenticator::new()?,
+            avatar_store: AvatarStore::new()?,
+        }))
+    }
+
+    async fn hash_password(&self, password: &str) -> Result<String, ArcaneVaultError> {
+        let (password_hasher, password) = (self.password_hasher.clone(), password.to_string());
+        spawn_blocking(move || password_hasher.hash_password(&password)).await
+    }
+
+    // The repositories only accept the stored hash, so an empty one fails like a wrong password.
+    // Users registered by an OpenID Connect login have no password to verify.
+    async fn get_verified_password_hash(
+        &self,
+        password: &str,
+        password_hash: Option<String>,
+    ) -> Result<String, ArcaneVaultError> {
+        let Some(password_hash) = password_hash.filter(|hash| hash != NO_PASSWORD) else {
+            return Ok(String::new());
+        };
+        let (password_hasher, password) = (self.password_hasher.clone(), password.to_string());
+        spawn_blocking(move || {
+            let is_verified = password_hasher.verify_password(&password, &password_hash)?;
+            Ok(if is_verified {
+                password_hash
+            } else {
+                String::new()
+            })
         })
+        .await
+    }
+
+    // Replaces bcrypt hashes and Argon2 ones of outdated parameters. The login already succeeded,
+    // so a failure is only logged and the upgrade is tried again next time.
+    async fn upgrade_password_hash(&self, id: Uuid, password: &str, password_hash: &str) {
+        if !self.password_hasher.needs_rehash(password_hash) {
+            return;
+        }
+        let result = match self.hash_password(password).await {
+            Ok(password_hash) => self.user_repository.set_password(id, &password_hash).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => tracing::info!(%id, "password hash upgraded"),
+            Err(err) => tracing::warn!(%id, error = %err.message, "password hash upgrade failed"),
+        }
+    }
+
+    async fn add_session(
+        &self,
+        user_id: Uuid,
+        device: &SessionDevice,
+    ) -> Result<SessionTokens, ArcaneVaultError> {
+        let (mut tokens, token_hashes) = self.session_token_issuer.issue_tokens();
+        let session_id = self
+            .session_repository
+            .add_session(
+                user_id,
+                &device.device_label,
+                &device.user_agent,
+                &token_hashes,
+            )
+            .await?;
+        tokens.session_id = session_id.to_string();
+        Ok(tokens)
+    }
+
+    // What a login of `user_id` leads to once its first factor checked out.
+    async fn get_login_outcome(
+        &self,
+        user_id: Uuid,
+        device: &SessionDevice,
+    ) -> Result<LoginOutcome, ArcaneVaultError> {
+        match self.totp_repository.query_totp(user_id).await? {
+            Some(totp) if totp.is_enabled => Ok(LoginOutcome::TotpRequired(
+                self.totp_repository.add_totp_challenge(user_id).await?,
+            )),
+            _ => Ok(LoginOutcome::LoggedIn(
+                user_id,
+                self.add_session(user_id, device).await?,
+            )),
+        }
+    }
+
+    // Stores what finishing the login takes under the hash of its `state`, which the provider
+    // returns along with the code.
+    async fn start_oidc_attempt(
+        &self,
+        function: &str,
+        provider: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<String, ArcaneVaultError> {
+        let authorization = self
+            .oidc_authenticator
+            .get_authorization(function, provider)
+            .await?;
+        self.oidc_repository
+            .add_oidc_login_attempt(
+                &get_token_hash(&authorization.state),
+                provider,
+                &authorization.pkce_verifier,
+                &authorization.nonce,
+                user_id,
+            )
+            .await?;
+        Ok(authorization.url)
+    }
+
+    // Uses up the attempt of `state`, which must have been started for `user_id`, and redeems
+    // `code` with its provider.
+    async fn finish_oidc_attempt(
+        &self,
+        function: &str,
+        state: &str,
+        code: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<(String, OidcClaims), ArcaneVaultError> {
+        let attempt = self
+            .oidc_repository
+            .use_oidc_login_attempt(&get_token_hash(state))
+            .await?
+            .filter(|attempt| attempt.user_id == user_id)
+            .ok_or_else(|| raise_error(function, "PA027", &[]))?;
+        let claims = self
+            .oidc_authenticator
+            .exchange_code(
+                function,
+                &attempt.provider,
+                code,
+                &attempt.pkce_verifier,
+                &attempt.nonce,
+            )
+            .await?;
+        Ok((attempt.provider, claims))
+    }
+
+    // Records an event that already happened, so a failure is only logged. The user and the
+    // account are looked up from each other when only one of them is known.
+    async fn add_audit_event(&self, context: &RequestContext, mut event: NewAuditEvent) {
+        match (event.user_id, event.account.is_empty()) {
+            (Some(user_id), true) => {
+                if let Ok(Some(user)) = self.user_repository.query_user_by_id(user_id).await {
+                    event.account = user.email_account;
+                }
+            }
+            (None, false) => {
+                if let Ok(Some(user)) = self
+                    .user_repository
+                    .query_user_by_account(&event.account)
+                    .await
+                {
+                    event.user_id = Uuid::parse_str(&user.id).ok();
+                }
+            }
+            _ => {}
+        }
+        event.ip_address = context.ip_address.clone();
+        event.user_agent = context.user_agent.clone();
+        event.request_id = context.request_id.clone();
+        if let Err(err) = self.audit_repository.add_audit_event(&event).await {
+            tracing::warn!(event_type = event.event_type, error = %err.message, "audit event not recorded");
+        }
+    }
+
+    // Records how a login with `method` ended, failed when `error` is set. Only rejections count
+    // as failed logins, not e.g. an unavailable database.
+    async fn add_login_event(
+        &self,
+        context: &RequestContext,
+        user_id: Option<Uuid>,
+        account: &str,
+        method: &str,
+        error: Option<&ArcaneVaultError>,
+    ) {
+        let (event_type, details) = match error {
+            None => (AUDIT_EVENT_LOGIN_SUCCEEDED, method.to_string()),
+            Some(err) => match err.code.as_ref().and_then(|code| code.get_error_code()) {
+                Some(error_code) => (
+                    AUDIT_EVENT_LOGIN_FAILED,
+                    format!("{} {}", method, error_code),
+                ),
+                None => return,
+            },
+        };
+        let event = NewAuditEvent {
+            event_type,
+            user_id,
+            actor_id: user_id.filter(|_| error.is_none()),
+            account: account.to_string(),
+            details,
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
+    }
+
+    // The first factor of a password login.
+    async fn check_password(&self, email: &str, password: &str) -> Result<Uuid, ArcaneVaultError> {
+        let password_hash = self.user_repository.query_password_hash(email).await?;
+        let password_hash = self
+            .get_verified_password_hash(password, password_hash)
+            .await?;
+        let id = self
+            .user_repository
+            .login_user(email, &password_hash)
+            .await?;
+        self.upgrade_password_hash(id, password, &password_hash)
+            .await;
+        Ok(id)
     }
+
+    // The user the identity of an OpenID Connect login signs in as, registered on its first login,
+    // with the provider.
+    async fn get_oidc_user(
+        &self,
+        function: &str,
+        state: &str,
+        code: &str,
+        context: &RequestContext,
+    ) -> Result<(String, Uuid), ArcaneVaultError> {
+        let (provider, claims) = self
+            .finish_oidc_attempt(function, state, code, None)
+            .await?;
+        let email = claims.email.clone().unwrap_or_default();
+        if let Some(user_id) = self
+            .oidc_repository
+            .login_user_identity(&provider, &claims.subject, &email)
+            .await?
+        {
+            return Ok((provider, user_id));
+        }
+        // An unverified email could be anyone's, so it must not become an account.
+        if email.is_empty() || !claims.is_email_verified {
+            return Err(raise_error(function, "PA029", &[&provider]));
+        }
+        let user_id = self
+            .oidc_repository
+            .register_user_identity(
+                &provider,
+                &claims.subject,
+                &email,
+                &claims.firstname,
+                &claims.lastname,
+                &claims.locale,
+                &claims.timezone,
+            )
+            .await?;
+        let event = NewAuditEvent {
+            event_type: AUDIT_EVENT_REGISTERED,
+            user_id: Some(user_id),
+            actor_id: Some(user_id),
+            account: email,
+            details: format!("oidc:{}", provider),
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
+        Ok((provider, user_id))
+    }
+
+    // Raises `PA033` unless `principal` is a user with the admin role.
+    async fn check_admin(
+        &self,
+        function: &str,
+        principal: &Principal,
+    ) -> Result<(), ArcaneVaultError> {
+        let is_admin = self
+            .user_repository
+            .query_user_by_id(principal.user_id)
+            .await?
+            .is_some_and(|user| user.role == USER_ROLE_ADMIN);
+        if !is_admin {
+            return Err(raise_error(function, "PA033", &[]));
+        }
+        Ok(())
+    }
+
+    // Same as the `func_*` raising `PA007` when the user does not exist.
+    async fn get_account(&self, function: &str, user_id: Uuid) -> Result<String, ArcaneVaultError> {
+        match self.user_repository.query_user_by_id(user_id).await? {
+            Some(user) => Ok(user.email_account),
+            None => Err(raise_error(function, "PA007", &[&user_id.to_string()])),
+        }
+    }
+
+    // Accepts a TOTP code of a step not used yet, or an unused recovery code, and uses it up.
+    // Codes are counted per user, not per challenge, so every RPC checking them shares the lockout.
+    async fn check_second_factor(
+        &self,
+        function: &str,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        let totp = match self.totp_repository.query_totp(user_id).await? {
+            Some(totp) if totp.is_enabled => totp,
+            _ => {
+                let account = self.get_account(function, user_id).await?;
+                return Err(raise_error(function, "PA015", &[&account]));
+            }
+        };
+        if !self.totp_repository.use_totp_attempt(user_id).await? {
+            return Err(raise_error(function, "PA036", &[]));
+        }
+        let is_verified = if is_totp_code(code) {
+            match self
+                .totp_authenticator
+                .verify_code(user_id, &totp.secret, code)?
+            {
+                Some(step) => self.totp_repository.use_totp_step(user_id, step).await?,
+                None => false,
+            }
+        } else {
+            self.totp_repository
+                .use_recovery_code(user_id, &get_recovery_code_hash(code))
+                .await?
+        };
+        if !is_verified {
+            return Err(raise_error(function, "PA016", &[]));
+        }
+        self.totp_repository.reset_totp_attempts(user_id).await
+    }
+
+    // The password of the user of `principal`, for what a stolen access token must not do alone.
+    // Without one, the login of a session started moments ago stands in for it.
+    async fn check_password_or_recent_login(
+        &self,
+        function: &str,
+        principal: &Principal,
+        password: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        if password.is_empty() {
+            let Credential::Session(session_id) = principal.credential else {
+                return Err(raise_error(function, "PA024", &[]));
+            };
+            let session_id = session_id.to_string();
+            let is_recent = self
+                .session_repository
+                .query_sessions(principal.user_id)
+                .await?
+                .into_iter()
+                .find(|session| session.id == session_id)
+                .and_then(|session| SystemTime::try_from(session.created_at?).ok())
+                .is_some_and(|created_at| {
+                    created_at
+                        .elapsed()
+                        .is_ok_and(|elapsed| elapsed < RECENT_LOGIN_WINDOW)
+                });
+            if !is_recent {
+                return Err(raise_error(function, "PA037", &[]));
+            }
+            return Ok(());
+        }
+
+        let account = self.get_account(function, principal.user_id).await?;
+        let password_hash = self.user_repository.query_password_hash(&account).await?;
+        let password_hash = self
+            .get_verified_password_hash(password, password_hash)
+            .await?;
+        if password_hash.is_empty() {
+            return Err(raise_error(function, "PA005", &[&account]));
+        }
+        Ok(())
+    }
+}
+
+// Hashing is deliberately slow, so it is kept off the async workers.
+async fn spawn_blocking<T, F>(f: F) -> Result<T, ArcaneVaultError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ArcaneVaultError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| ArcaneVaultError {
+            message: e.to_string(),
+            code: Some(ArcaneVaultErrorCode::InnerError(
+                "blocking task error".into(),
+            )),
+            metadata: HashMap::new(),
+        })?
 }
 
 #[async_trait::async_trait]
 impl crate::domain::service::UserService for UserService {
+    // Spans record user ids rather than email addresses, which must not end up in exported traces.
+    #[tracing::instrument(name = "user_service.create_user", skip_all)]
     async fn create_user(
         &self,
-        email: &str,
+        user: &NewUser,
         password: &str,
-        firstname: &str,
-        lastname: &str,
-        gender: i32,
-        locale: i32,
-        avatar: &str,
-        signature: &str,
+        context: &RequestContext,
     ) -> Result<String, ArcaneVaultError> {
-        let sql_statement = r#"
-            SELECT func_register_user(
-                $1, $2, $3, $4, $5, $6, $7, $8
-            ) AS verification_code
-        "#;
+        let mut user = user.clone();
+        if user.timezone.is_empty() {
+            user.timezone = DEFAULT_TIMEZONE.to_string();
+        }
+        let password_hash = self.hash_password(password).await?;
         let verification_code = self
-            .db_context
-            .get_repository()
-            .await
-            .query_one(
-                sql_statement,
-                &[
-                    &email, &password, &firstname, &lastname, &gender, &locale, &avatar, &signature,
-                ],
-                get_verification_code_from_row,
-            )
+            .user_repository
+            .register_user(&user, &password_hash)
             .await?;
-
+        let event = NewAuditEvent {
+            event_type: AUDIT_EVENT_REGISTERED,
+            account: user.account,
+            details: "password".into(),
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
         Ok(verification_code)
     }
-    
+
+    #[tracing::instrument(name = "user_service.verify_user", skip_all, fields(user_id = tracing::field::Empty))]
     async fn verify_user(
         &self,
         email: &str,
         password: &str,
         verification_code: &str,
+        context: &RequestContext,
     ) -> Result<Uuid, ArcaneVaultError> {
-        let sql_statement = r#"
-            SELECT func_verify_user(
-                $1, $2, $3
-            ) AS user_id
-        "#;
-        let user_id = self
-            .db_context
-            .get_repository()
+        let password_hash = self
+            .user_repository
+            .query_pending_password_hash(email)
+            .await?;
+        let password_hash = self
+            .get_verified_password_hash(password, password_hash)
+            .await?;
+        let id = self
+            .user_repository
+            .verify_user(email, &password_hash, verification_code)
+            .await?;
+        tracing::Span::current().record("user_id", tracing::field::display(id));
+        self.upgrade_password_hash(id, password, &password_hash)
+            .await;
+        let event = NewAuditEvent {
+            event_type: AUDIT_EVENT_VERIFIED,
+            user_id: Some(id),
+            actor_id: Some(id),
+            account: email.to_string(),
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
+        Ok(id)
+    }
+
+    #[tracing::instrument(name = "user_service.login_user", skip_all, fields(user_id = tracing::field::Empty))]
+    async fn login_user(
+        &self,
+        email: &str,
+        password: &str,
+        device: &SessionDevice,
+        context: &RequestContext,
+    ) -> Result<LoginOutcome, ArcaneVaultError> {
+        let result = match self.check_password(email, password).await {
+            Ok(id) => {
+                tracing::Span::current().record("user_id", tracing::field::display(id));
+                self.get_login_outcome(id, device).await
+            }
+            Err(err) => Err(err),
+        };
+        match &result {
+            Ok(LoginOutcome::LoggedIn(user_id, _)) => {
+                self.add_login_event(context, Some(*user_id), email, "password", None)
+                    .await;
+            }
+            // Recorded once the second factor checks out.
+            Ok(LoginOutcome::TotpRequired(_)) => {}
+            Err(err) => {
+                self.add_login_event(context, None, email, "password", Some(err))
+                    .await;
+            }
+        }
+        result
+    }
+
+    #[tracing::instrument(name = "user_service.verify_totp_challenge", skip_all, fields(challenge_id = %challenge_id))]
+    async fn verify_totp_challenge(
+        &self,
+        challenge_id: Uuid,
+        code: &str,
+        device: &SessionDevice,
+        context: &RequestContext,
+    ) -> Result<(Uuid, SessionTokens), ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.verify_totp_challenge";
+        let Some(user_id) = self
+            .totp_repository
+            .use_totp_challenge(challenge_id)
+            .await?
+        else {
+            return Err(raise_error(FUNCTION, "PA017", &[]));
+        };
+        let result = match self.check_second_factor(FUNCTION, user_id, code).await {
+            Ok(()) => match self
+                .totp_repository
+                .delete_totp_challenge(challenge_id)
+                .await
+            {
+                Ok(()) => self.add_session(user_id, device).await,
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(err),
+        };
+        self.add_login_event(context, Some(user_id), "", "totp", result.as_ref().err())
+            .await;
+        Ok((user_id, result?))
+    }
+
+    #[tracing::instrument(name = "user_service.refresh_session", skip_all)]
+    async fn refresh_session(
+        &self,
+        refresh_token: &str,
+    ) -> Result<SessionTokens, ArcaneVaultError> {
+        let (mut tokens, token_hashes) = self.session_token_issuer.issue_tokens();
+        let Some(session_id) = self
+            .session_repository
+            .refresh_session(&get_token_hash(refresh_token), &token_hashes)
+            .await?
+        else {
+            return Err(raise_error("user_service.refresh_session", "PA018", &[]));
+        };
+        tokens.session_id = session_id.to_string();
+        Ok(tokens)
+    }
+
+    #[tracing::instrument(name = "user_service.list_sessions", skip_all, fields(user_id = %principal.user_id))]
+    async fn list_sessions(&self, principal: &Principal) -> Result<Vec<Session>, ArcaneVaultError> {
+        principal.check_session("user_service.list_sessions")?;
+        self.session_repository
+            .query_sessions(principal.user_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.revoke_session", skip_all, fields(user_id = %principal.user_id, session_id = %session_id))]
+    async fn revoke_session(
+        &self,
+        principal: &Principal,
+        session_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        principal.check_session("user_service.revoke_session")?;
+        self.session_repository
+            .revoke_session(principal.user_id, session_id)
             .await
-            .query_one(
-                sql_statement,
-                &[
-                    &email, &password, &verification_code
-                ],
-                get_user_id_from_row,
+    }
+
+    #[tracing::instrument(name = "user_service.logout_everywhere", skip_all, fields(user_id = %principal.user_id))]
+    async fn logout_everywhere(&self, principal: &Principal) -> Result<i32, ArcaneVaultError> {
+        principal.check_session("user_service.logout_everywhere")?;
+        self.session_repository
+            .revoke_sessions(principal.user_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.authenticate", skip_all)]
+    async fn authenticate(&self, token: &str) -> Result<Principal, ArcaneVaultError> {
+        let token_hash = get_token_hash(token);
+        let principal = if token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX) {
+            self.personal_access_token_repository
+                .use_personal_access_token(&token_hash)
+                .await?
+        } else if !token.is_empty() {
+            self.session_repository
+                .query_session_by_access_token(&token_hash)
+                .await?
+        } else {
+            None
+        };
+        principal.ok_or_else(|| raise_error("user_service.authenticate", "PA020", &[]))
+    }
+
+    #[tracing::instrument(name = "user_service.create_personal_access_token", skip_all, fields(user_id = %principal.user_id))]
+    async fn create_personal_access_token(
+        &self,
+        principal: &Principal,
+        name: &str,
+        scopes: &[String],
+        expires_at: Option<prost_types::Timestamp>,
+    ) -> Result<(PersonalAccessToken, String), ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.create_personal_access_token";
+        principal.check_session(FUNCTION)?;
+        if scopes.is_empty()
+            || scopes
+                .iter()
+                .any(|scope| !PERSONAL_ACCESS_TOKEN_SCOPES.contains(&scope.as_str()))
+        {
+            return Err(raise_error(FUNCTION, "PA022", &[&scopes.join(" ")]));
+        }
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+        let expires_at = match expires_at.map(SystemTime::try_from) {
+            None => None,
+            Some(Ok(expires_at)) if expires_at > SystemTime::now() => Some(expires_at),
+            Some(_) => return Err(raise_error(FUNCTION, "PA025", &[])),
+        };
+
+        let token = generate_personal_access_token();
+        let personal_access_token = self
+            .personal_access_token_repository
+            .add_personal_access_token(
+                principal.user_id,
+                name,
+                &get_token_hash(&token),
+                &scopes,
+                expires_at,
             )
             .await?;
+        Ok((personal_access_token, token))
+    }
 
-        Ok(user_id)
+    #[tracing::instrument(name = "user_service.list_personal_access_tokens", skip_all, fields(user_id = %principal.user_id))]
+    async fn list_personal_access_tokens(
+        &self,
+        principal: &Principal,
+    ) -> Result<Vec<PersonalAccessToken>, ArcaneVaultError> {
+        principal.check_session("user_service.list_personal_access_tokens")?;
+        self.personal_access_token_repository
+            .query_personal_access_tokens(principal.user_id)
+            .await
     }
 
-    async fn query_user_by_id(&self, id: Uuid) -> Result<Option<User>, ArcaneVaultError> {
-        let sql_statement = r#"
-            SELECT * FROM func_query_user_by_id(
-                $1
+    #[tracing::instrument(name = "user_service.revoke_personal_access_token", skip_all, fields(user_id = %principal.user_id, token_id = %token_id))]
+    async fn revoke_personal_access_token(
+        &self,
+        principal: &Principal,
+        token_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        principal.check_session("user_service.revoke_personal_access_token")?;
+        self.personal_access_token_repository
+            .revoke_personal_access_token(principal.user_id, token_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.start_oidc_login", skip_all, fields(provider = %provider))]
+    async fn start_oidc_login(&self, provider: &str) -> Result<String, ArcaneVaultError> {
+        self.start_oidc_attempt("user_service.start_oidc_login", provider, None)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.finish_oidc_login", skip_all)]
+    async fn finish_oidc_login(
+        &self,
+        state: &str,
+        code: &str,
+        device: &SessionDevice,
+        context: &RequestContext,
+    ) -> Result<LoginOutcome, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.finish_oidc_login";
+        let (method, result) = match self.get_oidc_user(FUNCTION, state, code, context).await {
+            Ok((provider, user_id)) => (
+                format!("oidc:{}", provider),
+                self.get_login_outcome(user_id, device).await,
+            ),
+            Err(err) => ("oidc".to_string(), Err(err)),
+        };
+        match &result {
+            Ok(LoginOutcome::LoggedIn(user_id, _)) => {
+                self.add_login_event(context, Some(*user_id), "", &method, None)
+                    .await;
+            }
+            Ok(LoginOutcome::TotpRequired(_)) => {}
+            Err(err) => {
+                self.add_login_event(context, None, "", &method, Some(err))
+                    .await;
+            }
+        }
+        result
+    }
+
+    #[tracing::instrument(name = "user_service.start_oidc_link", skip_all, fields(user_id = %principal.user_id, provider = %provider))]
+    async fn start_oidc_link(
+        &self,
+        principal: &Principal,
+        provider: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.start_oidc_link";
+        principal.check_session(FUNCTION)?;
+        self.start_oidc_attempt(FUNCTION, provider, Some(principal.user_id))
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.finish_oidc_link", skip_all, fields(user_id = %principal.user_id))]
+    async fn finish_oidc_link(
+        &self,
+        principal: &Principal,
+        state: &str,
+        code: &str,
+    ) -> Result<ExternalIdentity, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.finish_oidc_link";
+        principal.check_session(FUNCTION)?;
+        let (provider, claims) = self
+            .finish_oidc_attempt(FUNCTION, state, code, Some(principal.user_id))
+            .await?;
+        self.oidc_repository
+            .link_user_identity(
+                principal.user_id,
+                &provider,
+                &claims.subject,
+                &claims.email.unwrap_or_default(),
             )
-        "#;
-        let row = self
-            .db_context
-            .get_repository()
             .await
-            .query_one_row(sql_statement, &[&id])
-            .await;
-        match row {
-            Ok(row) => Ok(Some(get_user_from_row(&row))),
-            Err(e) if e.code == Some(ArcaneVaultErrorCode::NoData) => Ok(None),
-            Err(e) => Err(e.into()),
+    }
+
+    #[tracing::instrument(name = "user_service.list_external_identities", skip_all, fields(user_id = %principal.user_id))]
+    async fn list_external_identities(
+        &self,
+        principal: &Principal,
+    ) -> Result<Vec<ExternalIdentity>, ArcaneVaultError> {
+        principal.check_session("user_service.list_external_identities")?;
+        self.oidc_repository
+            .query_user_identities(principal.user_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.unlink_external_identity", skip_all, fields(user_id = %principal.user_id, provider = %provider))]
+    async fn unlink_external_identity(
+        &self,
+        principal: &Principal,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        principal.check_session("user_service.unlink_external_identity")?;
+        self.oidc_repository
+            .unlink_user_identity(principal.user_id, provider, subject)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.request_email_change", skip_all, fields(user_id = %principal.user_id))]
+    async fn request_email_change(
+        &self,
+        principal: &Principal,
+        password: &str,
+        new_email: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.request_email_change";
+        principal.check_session(FUNCTION)?;
+        self.check_password_or_recent_login(FUNCTION, principal, password)
+            .await?;
+        let verification_code = self
+            .user_repository
+            .request_email_change(principal.user_id, new_email)
+            .await?;
+        let body = format!(
+            "Your verification code is {}. It expires in {} minutes.\n\n\
+             If you did not ask to change the email address of your account to this one, ignore \
+             this mail.",
+            verification_code,
+            EMAIL_CHANGE_EXPIRY.as_secs() / 60
+        );
+        self.mailer
+            .send_mail(new_email, "Confirm your new email address", &body)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.confirm_email_change", skip_all, fields(user_id = %principal.user_id))]
+    async fn confirm_email_change(
+        &self,
+        principal: &Principal,
+        verification_code: &str,
+        context: &RequestContext,
+    ) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.confirm_email_change";
+        principal.check_session(FUNCTION)?;
+        // Counted apart from the confirmation, whose errors would roll the count back.
+        if !self
+            .user_repository
+            .use_email_change_attempt(principal.user_id)
+            .await?
+        {
+            return Err(raise_error(FUNCTION, "PA009", &[]));
+        }
+        let email_change = self
+            .user_repository
+            .confirm_email_change(principal.user_id, verification_code)
+            .await?;
+        // The address is already changed, so a failed alert is only logged.
+        let body = format!(
+            "The email address of your account was changed to {}.\n\n\
+             If you did not do this, your account may be compromised.",
+            email_change.new_account
+        );
+        if let Err(err) = self
+            .mailer
+            .send_mail(
+                &email_change.old_account,
+                "Your email address was changed",
+                &body,
+            )
+            .await
+        {
+            tracing::warn!(user_id = %principal.user_id, error = %err.message, "email change alert failed");
+        }
+        let event = NewAuditEvent {
+            event_type: AUDIT_EVENT_EMAIL_CHANGED,
+            user_id: Some(principal.user_id),
+            actor_id: Some(principal.user_id),
+            account: email_change.new_account.clone(),
+            details: format!("from {}", email_change.old_account),
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
+        Ok(email_change.new_account)
+    }
+
+    #[tracing::instrument(name = "user_service.list_audit_events", skip_all, fields(user_id = %principal.user_id, offset = offset, limit = limit))]
+    async fn list_audit_events(
+        &self,
+        principal: &Principal,
+        user_id: Option<Uuid>,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<AuditEvent>, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.list_audit_events";
+        principal.check_session(FUNCTION)?;
+        let user_id = user_id.unwrap_or(principal.user_id);
+        if user_id != principal.user_id {
+            self.check_admin(FUNCTION, principal).await?;
+        }
+        self.audit_repository
+            .query_audit_events(user_id, offset, limit)
+            .await
+    }
+
+    #[tracing::instrument(name = "user_service.enroll_totp", skip_all, fields(user_id = %principal.user_id))]
+    async fn enroll_totp(
+        &self,
+        principal: &Principal,
+        password: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.enroll_totp";
+        principal.check_session(FUNCTION)?;
+        self.check_password_or_recent_login(FUNCTION, principal, password)
+            .await?;
+        let user_id = principal.user_id;
+        let account = self.get_account(FUNCTION, user_id).await?;
+        let (secret, uri) = self.totp_authenticator.generate_secret(user_id, &account)?;
+        self.totp_repository.enroll_totp(user_id, &secret).await?;
+        Ok(uri)
+    }
+
+    #[tracing::instrument(name = "user_service.confirm_totp", skip_all, fields(user_id = %principal.user_id))]
+    async fn confirm_totp(
+        &self,
+        principal: &Principal,
+        code: &str,
+    ) -> Result<Vec<String>, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.confirm_totp";
+        principal.check_session(FUNCTION)?;
+        let user_id = principal.user_id;
+        let Some(totp) = self.totp_repository.query_totp(user_id).await? else {
+            let account = self.get_account(FUNCTION, user_id).await?;
+            return Err(raise_error(FUNCTION, "PA014", &[&account]));
+        };
+        if totp.is_enabled {
+            let account = self.get_account(FUNCTION, user_id).await?;
+            return Err(raise_error(FUNCTION, "PA013", &[&account]));
         }
+        // Only a TOTP code proves the authenticator app has the secret.
+        let step = if is_totp_code(code) {
+            self.totp_authenticator
+                .verify_code(user_id, &totp.secret, code)?
+        } else {
+            None
+        };
+        let Some(step) = step else {
+            return Err(raise_error(FUNCTION, "PA016", &[]));
+        };
+
+        let recovery_codes = generate_recovery_codes();
+        let recovery_code_hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| get_recovery_code_hash(code))
+            .collect();
+        self.totp_repository
+            .confirm_totp(user_id, step, &recovery_code_hashes)
+            .await?;
+        Ok(recovery_codes)
+    }
+
+    #[tracing::instrument(name = "user_service.disable_totp", skip_all, fields(user_id = %principal.user_id))]
+    async fn disable_totp(
+        &self,
+        principal: &Principal,
+        password: &str,
+        code: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.disable_totp";
+        principal.check_session(FUNCTION)?;
+        self.check_password_or_recent_login(FUNCTION, principal, password)
+            .await?;
+        self.check_second_factor(FUNCTION, principal.user_id, code)
+            .await?;
+        self.totp_repository.disable_totp(principal.user_id).await
+    }
+
+    #[tracing::instrument(name = "user_service.generate_recovery_codes", skip_all, fields(user_id = %principal.user_id))]
+    async fn generate_recovery_codes(
+        &self,
+        principal: &Principal,
+        code: &str,
+    ) -> Result<Vec<String>, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.generate_recovery_codes";
+        principal.check_session(FUNCTION)?;
+        self.check_second_factor(FUNCTION, principal.user_id, code)
+            .await?;
+        let recovery_codes = generate_recovery_codes();
+        let recovery_code_hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| get_recovery_code_hash(code))
+            .collect();
+        self.totp_repository
+            .replace_recovery_codes(principal.user_id, &recovery_code_hashes)
+            .await?;
+        Ok(recovery_codes)
     }
 
+    #[tracing::instrument(name = "user_service.query_user_by_id", skip_all, fields(id = %id))]
+    async fn query_user_by_id(&self, id: Uuid) -> Result<Option<User>, ArcaneVaultError> {
+        self.user_repository.query_user_by_id(id).await
+    }
+
+    #[tracing::instrument(name = "user_service.query_user_by_email_account", skip_all, fields(user_id = tracing::field::Empty))]
     async fn query_user_by_email_account(
         &self,
         email_account: &str,
     ) -> Result<Option<User>, ArcaneVaultError> {
-        let sql_statement = r#"
-            SELECT * FROM func_query_user_by_email_account(
-                $1
-            )
-        "#;
-        let row = self
-            .db_context
-            .get_repository()
+        let user = self
+            .user_repository
+            .query_user_by_account(email_account)
+            .await?;
+        if let Some(user) = &user {
+            tracing::Span::current().record("user_id", tracing::field::display(&user.id));
+        }
+        Ok(user)
+    }
+
+    #[tracing::instrument(name = "user_service.update_user_profile", skip_all, fields(user_id = %principal.user_id))]
+    async fn update_user_profile(
+        &self,
+        principal: &Principal,
+        firstname: &str,
+        lastname: &str,
+        gender: i32,
+        locale: i32,
+        context: &RequestContext,
+    ) -> Result<User, ArcaneVaultError> {
+        principal.check_session("user_service.update_user_profile")?;
+        let (user, changes) = self
+            .user_repository
+            .update_profile(principal.user_id, firstname, lastname, gender, locale)
+            .await?;
+        if changes.is_empty() {
+            return Ok(user);
+        }
+        let event = NewAuditEvent {
+            event_type: AUDIT_EVENT_PROFILE_CHANGED,
+            user_id: Some(principal.user_id),
+            actor_id: Some(principal.user_id),
+            details: changes.join(", "),
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
+        Ok(user)
+    }
+
+    #[tracing::instrument(name = "user_service.set_timezone", skip_all, fields(user_id = %principal.user_id, timezone = %timezone))]
+    async fn set_timezone(
+        &self,
+        principal: &Principal,
+        timezone: &str,
+        context: &RequestContext,
+    ) -> Result<(), ArcaneVaultError> {
+        principal.check_session("user_service.set_timezone")?;
+        self.user_repository
+            .set_timezone(principal.user_id, timezone)
+            .await?;
+        let event = NewAuditEvent {
+            event_type: AUDIT_EVENT_PROFILE_CHANGED,
+            user_id: Some(principal.user_id),
+            actor_id: Some(principal.user_id),
+            details: "timezone".into(),
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "user_service.upload_avatar", skip_all, fields(user_id = %principal.user_id))]
+    async fn upload_avatar(
+        &self,
+        principal: &Principal,
+        mut chunks: BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>,
+        context: &RequestContext,
+    ) -> Result<Vec<String>, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.upload_avatar";
+        principal.check_session(FUNCTION)?;
+        let mut image = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            // Checked as the chunks come in, so an endless upload is cut off early.
+            if image.len() + chunk.len() > AVATAR_MAX_SIZE {
+                return Err(raise_error(
+                    FUNCTION,
+                    "PA034",
+                    &[&AVATAR_MAX_SIZE.to_string()],
+                ));
+            }
+            image.extend_from_slice(&chunk);
+        }
+        let thumbnails = spawn_blocking(move || get_avatar_thumbnails(FUNCTION, &image)).await?;
+        let old_avatar = self
+            .user_repository
+            .query_user_by_id(principal.user_id)
+            .await?
+            .map(|user| user.avatar)
+            .unwrap_or_default();
+        let urls = self
+            .avatar_store
+            .add_avatar(principal.user_id, thumbnails)
+            .await?;
+        let avatar = urls.last().cloned().unwrap_or_default();
+        if let Err(err) = self
+            .user_repository
+            .set_avatar(principal.user_id, &avatar)
             .await
-            .query_one_row(sql_statement, &[&email_account])
-            .await;
-        match row {
-            Ok(row) => Ok(Some(get_user_from_row(&row))),
-            Err(e) if e.code == Some(ArcaneVaultErrorCode::NoData) => Ok(None),
-            Err(e) => Err(e.into()),
+        {
+            let _ = self
+                .avatar_store
+                .delete_avatar(principal.user_id, &avatar)
+                .await;
+            return Err(err);
         }
+        // The avatar is already replaced, so thumbnails left behind only take up space.
+        if let Err(err) = self
+            .avatar_store
+            .delete_avatar(principal.user_id, &old_avatar)
+            .await
+        {
+            tracing::warn!(user_id = %principal.user_id, error = %err.message, "old avatar not deleted");
+        }
+        let event = NewAuditEvent {
+            event_type: AUDIT_EVENT_PROFILE_CHANGED,
+            user_id: Some(principal.user_id),
+            actor_id: Some(principal.user_id),
+            details: "avatar".into(),
+            ..Default::default()
+        };
+        self.add_audit_event(context, event).await;
+        Ok(urls)
     }
-}
 
-fn get_verification_code_from_row(row: &tokio_postgres::Row) -> String {
-    row.get("verification_code")
-}
+    async fn get_avatar(&self, path: &str) -> Result<Option<Vec<u8>>, ArcaneVaultError> {
+        self.avatar_store.get_avatar(path).await
+    }
 
-fn get_user_id_from_row(row: &tokio_postgres::Row) -> Uuid {
-    row.get("user_id")
-}
+    #[tracing::instrument(name = "user_service.query_users", skip_all, fields(user_id = %principal.user_id, offset = offset, limit = limit))]
+    async fn query_users(
+        &self,
+        principal: &Principal,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<User>, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.query_users";
+        principal.check_session(FUNCTION)?;
+        self.check_admin(FUNCTION, principal).await?;
+        self.user_repository.query_users(offset, limit).await
+    }
 
-fn get_user_from_row(row: &tokio_postgres::Row) -> User {
-    let created_at: std::time::SystemTime = row.get("created_at");
-    let updated_at: std::time::SystemTime = row.get("updated_at");
-    let last_login_at: Option<std::time::SystemTime> = row.get("last_login_at");
-    User {
-        id: crate::infrastructure::utility::get_string_from_uuid(row.get("id")),
-        email_account: row.get("email_account"),
-        created_at: Some(prost_types::Timestamp::from(created_at)),
-        updated_at: Some(prost_types::Timestamp::from(updated_at)),
-        last_login_at: match last_login_at {
-            Some(last_login_at) => Some(prost_types::Timestamp::from(last_login_at)),
-            None => None,
-        },
-        status: row.get("status"),
-        role: row.get("role"),
-        firstname: row.get("firstname"),
-        lastname: row.get("lastname"),
-        gender: row.get("gender"),
-        locale: row.get("locale"),
-        avatar: row.get("avatar"),
-        signature: row.get("signature"),
+    #[tracing::instrument(name = "user_service.export_users", skip_all, fields(user_id = %principal.user_id))]
+    async fn export_users(
+        &self,
+        principal: &Principal,
+    ) -> Result<BoxStream<'static, Result<User, ArcaneVaultError>>, ArcaneVaultError> {
+        const FUNCTION: &str = "user_service.export_users";
+        principal.check_session(FUNCTION)?;
+        self.check_admin(FUNCTION, principal).await?;
+        self.user_repository.export_users().await
     }
 }
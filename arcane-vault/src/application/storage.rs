@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use crate::{
+    domain::{
+        error::ArcaneVaultError,
+        repository::{
+            AttachmentRepository, AuditRepository, CollectionRepository, NoteRepository,
+            NoteRevisionRepository, OidcRepository, PersonalAccessTokenRepository,
+            ReferenceDataRepository, SessionRepository, TotpRepository, UserRepository,
+        },
+    },
+    infrastructure::{
+        memory::{
+            MemoryAttachmentRepository, MemoryAuditRepository, MemoryCollectionRepository,
+            MemoryNoteRepository, MemoryNoteRevisionRepository, MemoryOidcRepository,
+            MemoryPersonalAccessTokenRepository, MemoryReferenceDataRepository,
+            MemorySessionRepository, MemoryStore, MemoryTotpRepository, MemoryUserRepository,
+        },
+        repository::{
+            DbContext, PostgresAttachmentRepository, PostgresAuditRepository,
+            PostgresCollectionRepository, PostgresNoteRepository, PostgresNoteRevisionRepository,
+            PostgresOidcRepository, PostgresPersonalAccessTokenRepository,
+            PostgresReferenceDataRepository, PostgresSessionRepository, PostgresTotpRepository,
+            PostgresUserRepository,
+        },
+    },
+};
+
+/// Where arcane-vault keeps its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Storage {
+    Postgres,
+    /// In process memory, for local development and tests; nothing outlives the process.
+    Memory,
+    /// A local SQLite file at `arcane-vault[0].sqlite_path`, for single-user deployments.
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl std::str::FromStr for Storage {
+    type Err = ArcaneVaultError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "postgres" => Ok(Self::Postgres),
+            "memory" => Ok(Self::Memory),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(Self::Sqlite),
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => Err(ArcaneVaultError {
+                message: "storage `sqlite` needs arcane-vault built with the `sqlite` feature".into(),
+                code: None,
+                metadata: HashMap::new(),
+            }),
+            _ => Err(ArcaneVaultError {
+                message: format!(
+                    "unknown storage `{}`, expected `postgres`, `memory` or `sqlite`",
+                    value
+                ),
+                code: None,
+                metadata: HashMap::new(),
+            }),
+        }
+    }
+}
+
+/// The repositories of one storage, sharing its connection pool or store.
+pub struct Repositories {
+    pub users: Box<dyn UserRepository>,
+    pub totp: Box<dyn TotpRepository>,
+    pub sessions: Box<dyn SessionRepository>,
+    pub personal_access_tokens: Box<dyn PersonalAccessTokenRepository>,
+    pub oidc: Box<dyn OidcRepository>,
+    pub audit_events: Box<dyn AuditRepository>,
+    pub collections: Box<dyn CollectionRepository>,
+    pub notes: Box<dyn NoteRepository>,
+    pub attachments: Box<dyn AttachmentRepository>,
+    pub note_revisions: Box<dyn NoteRevisionRepository>,
+    pub reference_data: Box<dyn ReferenceDataRepository>,
+}
+
+impl Storage {
+    /// Fails with [`crate::domain::error::ArcaneVaultErrorCode::SchemaMismatch`] when the
+    /// PostgreSQL schema does not match, see [`crate::check_schema`].
+    pub async fn create_repositories(self) -> Result<Repositories, ArcaneVaultError> {
+        match self {
+            Storage::Postgres => {
+                let db_context = DbContext::new().await?;
+                db_context.check_schema().await?;
+                Ok(Repositories {
+                    users: Box::new(PostgresUserRepository::new(db_context.clone())),
+                    totp: Box::new(PostgresTotpRepository::new(db_context.clone())),
+                    sessions: Box::new(PostgresSessionRepository::new(db_context.clone())),
+                    personal_access_tokens: Box::new(PostgresPersonalAccessTokenRepository::new(
+                        db_context.clone(),
+                    )),
+                    oidc: Box::new(PostgresOidcRepository::new(db_context.clone())),
+                    audit_events: Box::new(PostgresAuditRepository::new(db_context.clone())),
+                    collections: Box::new(PostgresCollectionRepository::new(db_context.clone())),
+                    notes: Box::new(PostgresNoteRepository::new(db_context.clone())),
+                    attachments: Box::new(PostgresAttachmentRepository::new(db_context.clone())),
+                    note_revisions: Box::new(PostgresNoteRevisionRepository::new(
+                        db_context.clone(),
+                    )),
+                    reference_data: Box::new(PostgresReferenceDataRepository::new(db_context)),
+                })
+            }
+            Storage::Memory => {
+                let store = MemoryStore::new();
+                Ok(Repositories {
+                    users: Box::new(MemoryUserRepository::new(store.clone())),
+                    totp: Box::new(MemoryTotpRepository::new(store.clone())),
+                    sessions: Box::new(MemorySessionRepository::new(store.clone())),
+                    personal_access_tokens: Box::new(MemoryPersonalAccessTokenRepository::new(
+                        store.clone(),
+                    )),
+                    oidc: Box::new(MemoryOidcRepository::new(store.clone())),
+                    audit_events: Box::new(MemoryAuditRepository::new(store.clone())),
+                    collections: Box::new(MemoryCollectionRepository::new(store.clone())),
+                    notes: Box::new(MemoryNoteRepository::new(store.clone())),
+                    attachments: Box::new(MemoryAttachmentRepository::new(store.clone())),
+                    note_revisions: Box::new(MemoryNoteRevisionRepository::new(store)),
+                    reference_data: Box::new(MemoryReferenceDataRepository),
+                })
+            }
+            #[cfg(feature = "sqlite")]
+            Storage::Sqlite => {
+                use crate::infrastructure::sqlite::{
+                    SqliteAttachmentRepository, SqliteAuditRepository, SqliteCollectionRepository,
+                    SqliteContext, SqliteNoteRepository, SqliteNoteRevisionRepository,
+                    SqliteOidcRepository, SqlitePersonalAccessTokenRepository,
+                    SqliteReferenceDataRepository, SqliteSessionRepository, SqliteTotpRepository,
+                    SqliteUserRepository,
+                };
+
+                let sqlite_context = SqliteContext::new().await?;
+                Ok(Repositories {
+                    users: Box::new(SqliteUserRepository::new(sqlite_context.clone())),
+                    totp: Box::new(SqliteTotpRepository::new(sqlite_context.clone())),
+                    sessions: Box::new(SqliteSessionRepository::new(sqlite_context.clone())),
+                    personal_access_tokens: Box::new(SqlitePersonalAccessTokenRepository::new(
+                        sqlite_context.clone(),
+                    )),
+                    oidc: Box::new(SqliteOidcRepository::new(sqlite_context.clone())),
+                    audit_events: Box::new(SqliteAuditRepository::new(sqlite_context.clone())),
+                    collections: Box::new(SqliteCollectionRepository::new(sqlite_context.clone())),
+                    notes: Box::new(SqliteNoteRepository::new(sqlite_context.clone())),
+                    attachments: Box::new(SqliteAttachmentRepository::new(sqlite_context.clone())),
+                    note_revisions: Box::new(SqliteNoteRevisionRepository::new(sqlite_context.clone())),
+                    reference_data: Box::new(SqliteReferenceDataRepository::new(sqlite_context)),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::SystemTime;
+
+use ethereal_core::proto::Attachment;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+/// Row returned by `func_add_attachment`, `func_query_attachments` and `func_query_attachment`.
+#[derive(Debug, Clone, FromRow)]
+pub struct AttachmentEntity {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub blob_id: Uuid,
+    pub sha256: String,
+    pub size: i64,
+    pub file_name: String,
+    pub content_type: String,
+    pub created_at: SystemTime,
+    /// Whether the file of the blob is in the blob store yet.
+    pub is_stored: bool,
+}
+
+impl From<AttachmentEntity> for Attachment {
+    fn from(entity: AttachmentEntity) -> Self {
+        Attachment {
+            id: entity.id.to_string(),
+            note_id: entity.note_id.to_string(),
+            sha256: entity.sha256,
+            size: entity.size,
+            file_name: entity.file_name,
+            content_type: entity.content_type,
+            created_at: Some(entity.created_at.into()),
+        }
+    }
+}
+
+/// Row returned by `func_collect_attachment_blobs`.
+#[derive(Debug, Clone, FromRow)]
+pub struct AttachmentBlobEntity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub sha256: String,
+}
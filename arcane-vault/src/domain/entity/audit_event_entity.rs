@@ -0,0 +1,61 @@
+use ethereal_core::proto::AuditEvent;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+// Rows of `audit_event_types`.
+pub const AUDIT_EVENT_REGISTERED: i32 = 1;
+pub const AUDIT_EVENT_VERIFIED: i32 = 2;
+pub const AUDIT_EVENT_LOGIN_SUCCEEDED: i32 = 3;
+pub const AUDIT_EVENT_LOGIN_FAILED: i32 = 4;
+pub const AUDIT_EVENT_PASSWORD_SET: i32 = 5;
+pub const AUDIT_EVENT_PASSWORD_RESET: i32 = 6;
+pub const AUDIT_EVENT_PROFILE_CHANGED: i32 = 7;
+pub const AUDIT_EVENT_EMAIL_CHANGED: i32 = 8;
+
+/// A security event to record. `user_id` is unset for an account that is no user, and
+/// `actor_id` when nobody was signed in.
+#[derive(Debug, Clone, Default)]
+pub struct NewAuditEvent {
+    pub event_type: i32,
+    pub user_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub account: String,
+    pub details: String,
+    pub ip_address: String,
+    pub user_agent: String,
+    pub request_id: String,
+}
+
+/// Row returned by `func_query_audit_events`.
+#[derive(Debug, FromRow)]
+pub struct AuditEventEntity {
+    pub id: i64,
+    #[from_row(uuid)]
+    pub user_id: Option<String>,
+    #[from_row(uuid)]
+    pub actor_id: Option<String>,
+    pub event_type: i32,
+    pub account: String,
+    pub details: String,
+    pub ip_address: String,
+    pub user_agent: String,
+    pub request_id: String,
+    pub created_at: prost_types::Timestamp,
+}
+
+impl From<AuditEventEntity> for AuditEvent {
+    fn from(entity: AuditEventEntity) -> Self {
+        AuditEvent {
+            id: entity.id,
+            user_id: entity.user_id.unwrap_or_default(),
+            actor_id: entity.actor_id.unwrap_or_default(),
+            event_type: entity.event_type,
+            account: entity.account,
+            details: entity.details,
+            ip_address: entity.ip_address,
+            user_agent: entity.user_agent,
+            request_id: entity.request_id,
+            created_at: Some(entity.created_at),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use uuid::Uuid;
+
+/// Row of `collections`.
+#[derive(Debug, Clone)]
+pub struct CollectionEntity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub is_active: bool,
+    pub meta: serde_json::Value,
+}
@@ -0,0 +1,34 @@
+use ethereal_core::proto::ExternalIdentity;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+/// Row returned by `func_link_user_identity` and `func_query_user_identities`.
+#[derive(Debug, FromRow)]
+pub struct ExternalIdentityEntity {
+    pub provider: String,
+    pub subject: String,
+    pub email: String,
+    pub created_at: prost_types::Timestamp,
+    pub last_login_at: Option<prost_types::Timestamp>,
+}
+
+impl From<ExternalIdentityEntity> for ExternalIdentity {
+    fn from(entity: ExternalIdentityEntity) -> Self {
+        ExternalIdentity {
+            provider: entity.provider,
+            subject: entity.subject,
+            email: entity.email,
+            created_at: Some(entity.created_at),
+            last_login_at: entity.last_login_at,
+        }
+    }
+}
+
+/// Row returned by `func_use_oidc_login_attempt`. `user_id` is set for a link rather than a login.
+#[derive(Debug, FromRow)]
+pub struct OidcLoginAttemptEntity {
+    pub provider: String,
+    pub pkce_verifier: String,
+    pub nonce: String,
+    pub user_id: Option<Uuid>,
+}
@@ -0,0 +1,23 @@
+mod attachment_entity;
+mod audit_event_entity;
+mod collection_entity;
+mod external_identity_entity;
+mod note_entity;
+mod personal_access_token_entity;
+mod principal_entity;
+mod reference_data_entity;
+mod session_entity;
+mod totp_entity;
+mod user_entity;
+
+pub use attachment_entity::*;
+pub use audit_event_entity::*;
+pub use collection_entity::*;
+pub use external_identity_entity::*;
+pub use note_entity::*;
+pub use personal_access_token_entity::*;
+pub use principal_entity::*;
+pub use reference_data_entity::*;
+pub use session_entity::*;
+pub use totp_entity::*;
+pub use user_entity::*;
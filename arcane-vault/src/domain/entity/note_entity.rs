@@ -0,0 +1,47 @@
+use std::time::SystemTime;
+
+use ethereal_core::proto::NoteRevision;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+// `DiffLine.operation`.
+pub const DIFF_LINE_UNCHANGED: i32 = 1;
+pub const DIFF_LINE_INSERTED: i32 = 2;
+pub const DIFF_LINE_DELETED: i32 = 3;
+
+/// Row of `notes`.
+#[derive(Debug, Clone)]
+pub struct NoteEntity {
+    pub id: Uuid,
+    pub collection_id: Uuid,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+    pub content: String,
+    pub source_type: i32,
+    pub meta: serde_json::Value,
+    pub revision: i32,
+}
+
+/// Row returned by `func_query_note_revisions` and `func_query_note_revision`: an earlier version
+/// of a note, or the note itself as its latest revision.
+#[derive(Debug, Clone, FromRow)]
+pub struct NoteRevisionEntity {
+    pub note_id: Uuid,
+    pub revision: i32,
+    pub content: String,
+    pub meta: serde_json::Value,
+    /// When the note was changed to this version.
+    pub created_at: SystemTime,
+}
+
+impl From<NoteRevisionEntity> for NoteRevision {
+    fn from(entity: NoteRevisionEntity) -> Self {
+        NoteRevision {
+            note_id: entity.note_id.to_string(),
+            revision: entity.revision,
+            content: entity.content,
+            meta: entity.meta.to_string(),
+            created_at: Some(entity.created_at.into()),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use ethereal_core::proto::PersonalAccessToken;
+use sorcerers_kit::FromRow;
+
+/// Row returned by `func_add_personal_access_token` and `func_query_personal_access_tokens`.
+#[derive(Debug, FromRow)]
+pub struct PersonalAccessTokenEntity {
+    #[from_row(uuid)]
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: prost_types::Timestamp,
+    pub expires_at: Option<prost_types::Timestamp>,
+    pub last_used_at: Option<prost_types::Timestamp>,
+}
+
+impl From<PersonalAccessTokenEntity> for PersonalAccessToken {
+    fn from(entity: PersonalAccessTokenEntity) -> Self {
+        PersonalAccessToken {
+            id: entity.id,
+            name: entity.name,
+            scopes: entity.scopes,
+            created_at: Some(entity.created_at),
+            expires_at: entity.expires_at,
+            last_used_at: entity.last_used_at,
+        }
+    }
+}
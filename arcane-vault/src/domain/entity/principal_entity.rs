@@ -0,0 +1,138 @@
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{domain::error::ArcaneVaultError, infrastructure::error_code::raise_error};
+
+pub const NOTES_READ_SCOPE: &str = "notes:read";
+pub const NOTES_WRITE_SCOPE: &str = "notes:write";
+/// The scopes a personal access token can be given.
+pub const PERSONAL_ACCESS_TOKEN_SCOPES: &[&str] = &[NOTES_READ_SCOPE, NOTES_WRITE_SCOPE];
+
+/// Who a request is made by, as told by its access token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+    pub user_id: Uuid,
+    pub credential: Credential,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credential {
+    /// A session access token, which can do anything the user can.
+    Session(Uuid),
+    PersonalAccessToken {
+        token_id: Uuid,
+        scopes: Vec<String>,
+    },
+}
+
+impl Principal {
+    /// Raises `PA021` when a personal access token was not given `scope`.
+    pub fn check_scope(&self, scope: &str) -> Result<(), ArcaneVaultError> {
+        match &self.credential {
+            Credential::PersonalAccessToken { scopes, .. }
+                if !scopes.iter().any(|s| s == scope) =>
+            {
+                Err(raise_error("principal.check_scope", "PA021", &[scope]))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Raises `PA024` unless this is a session, for what a personal access token must not do.
+    pub fn check_session(&self, function: &str) -> Result<(), ArcaneVaultError> {
+        match self.credential {
+            Credential::Session(_) => Ok(()),
+            Credential::PersonalAccessToken { .. } => Err(raise_error(function, "PA024", &[])),
+        }
+    }
+}
+
+/// Row returned by `func_query_session_by_access_token`.
+#[derive(Debug, FromRow)]
+pub struct SessionPrincipalEntity {
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+}
+
+impl From<SessionPrincipalEntity> for Principal {
+    fn from(entity: SessionPrincipalEntity) -> Self {
+        Principal {
+            user_id: entity.user_id,
+            credential: Credential::Session(entity.session_id),
+        }
+    }
+}
+
+/// Row returned by `func_use_personal_access_token`.
+#[derive(Debug, FromRow)]
+pub struct PersonalAccessTokenPrincipalEntity {
+    pub user_id: Uuid,
+    pub token_id: Uuid,
+    pub scopes: Vec<String>,
+}
+
+impl From<PersonalAccessTokenPrincipalEntity> for Principal {
+    fn from(entity: PersonalAccessTokenPrincipalEntity) -> Self {
+        Principal {
+            user_id: entity.user_id,
+            credential: Credential::PersonalAccessToken {
+                token_id: entity.token_id,
+                scopes: entity.scopes,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_error_code(result: Result<(), ArcaneVaultError>) -> Option<String> {
+        result
+            .unwrap_err()
+            .code
+            .and_then(|code| code.get_error_code().map(str::to_string))
+    }
+
+    fn get_token_principal(scopes: &[&str]) -> Principal {
+        Principal {
+            user_id: Uuid::new_v4(),
+            credential: Credential::PersonalAccessToken {
+                token_id: Uuid::new_v4(),
+                scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn limits_tokens_to_their_scopes() {
+        let principal = get_token_principal(&[NOTES_READ_SCOPE]);
+
+        assert!(principal.check_scope(NOTES_READ_SCOPE).is_ok());
+        assert_eq!(
+            get_error_code(principal.check_scope(NOTES_WRITE_SCOPE)).as_deref(),
+            Some("PA021")
+        );
+    }
+
+    #[test]
+    fn keeps_tokens_from_what_needs_a_session() {
+        let principal = get_token_principal(PERSONAL_ACCESS_TOKEN_SCOPES);
+
+        assert_eq!(
+            get_error_code(principal.check_session("user_service.set_password")).as_deref(),
+            Some("PA024")
+        );
+    }
+
+    #[test]
+    fn lets_sessions_do_anything() {
+        let principal = Principal {
+            user_id: Uuid::new_v4(),
+            credential: Credential::Session(Uuid::new_v4()),
+        };
+
+        assert!(principal.check_scope(NOTES_WRITE_SCOPE).is_ok());
+        assert!(principal.check_session("user_service.set_password").is_ok());
+    }
+}
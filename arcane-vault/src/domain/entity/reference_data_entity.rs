@@ -0,0 +1,57 @@
+use ethereal_core::proto::{Locale, ReferenceEntry};
+use sorcerers_kit::FromRow;
+
+/// Row of `genders`, `roles`, `user_statuses` or `note_source_types`.
+#[derive(Debug, FromRow)]
+pub struct ReferenceEntity {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl From<ReferenceEntity> for ReferenceEntry {
+    fn from(entity: ReferenceEntity) -> Self {
+        ReferenceEntry {
+            id: entity.id,
+            name: entity.name,
+            description: entity.description.unwrap_or_default(),
+        }
+    }
+}
+
+/// Row of `locales`.
+#[derive(Debug, FromRow)]
+pub struct LocaleEntity {
+    pub id: i32,
+    pub language_code: String,
+    pub locale_code: String,
+    pub name_en: String,
+    pub native_name: String,
+    pub is_rtl: bool,
+    pub enabled: bool,
+}
+
+impl From<LocaleEntity> for Locale {
+    fn from(entity: LocaleEntity) -> Self {
+        Locale {
+            id: entity.id,
+            language_code: entity.language_code,
+            locale_code: entity.locale_code,
+            name_en: entity.name_en,
+            native_name: entity.native_name,
+            is_rtl: entity.is_rtl,
+            enabled: entity.enabled,
+        }
+    }
+}
+
+/// Every lookup table, ordered by id (timezones by name).
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceData {
+    pub genders: Vec<ReferenceEntry>,
+    pub locales: Vec<Locale>,
+    pub timezones: Vec<String>,
+    pub roles: Vec<ReferenceEntry>,
+    pub user_statuses: Vec<ReferenceEntry>,
+    pub note_source_types: Vec<ReferenceEntry>,
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use ethereal_core::proto::Session;
+use sorcerers_kit::FromRow;
+
+/// Row returned by `func_query_sessions`.
+#[derive(Debug, FromRow)]
+pub struct SessionEntity {
+    #[from_row(uuid)]
+    pub id: String,
+    pub device_label: String,
+    pub user_agent: String,
+    pub created_at: prost_types::Timestamp,
+    pub last_used_at: prost_types::Timestamp,
+    pub expires_at: prost_types::Timestamp,
+}
+
+impl From<SessionEntity> for Session {
+    fn from(entity: SessionEntity) -> Self {
+        Session {
+            id: entity.id,
+            device_label: entity.device_label,
+            user_agent: entity.user_agent,
+            created_at: Some(entity.created_at),
+            last_used_at: Some(entity.last_used_at),
+            expires_at: Some(entity.expires_at),
+        }
+    }
+}
+
+/// The hashes of the tokens a login or refresh hands out, and how long they are valid.
+#[derive(Debug, Clone)]
+pub struct SessionTokenHashes {
+    pub access_token_hash: String,
+    pub access_token_ttl: Duration,
+    pub refresh_token_hash: String,
+    pub refresh_token_ttl: Duration,
+}
@@ -0,0 +1,9 @@
+use sorcerers_kit::FromRow;
+
+/// Row of `func_query_totp`. The secret is encrypted and only enabled once confirmed.
+#[derive(Debug, FromRow)]
+pub struct TotpEntity {
+    pub secret: Vec<u8>,
+    pub last_used_step: i64,
+    pub is_enabled: bool,
+}
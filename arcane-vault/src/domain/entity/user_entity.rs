@@ -0,0 +1,63 @@
+use ethereal_core::proto::User;
+use sorcerers_kit::FromRow;
+
+/// The account and profile of a user to register.
+#[derive(Debug, Clone, Default)]
+pub struct NewUser {
+    pub account: String,
+    pub firstname: String,
+    pub lastname: String,
+    pub gender: i32,
+    pub locale: i32,
+    pub signature: String,
+    pub timezone: String,
+}
+
+/// Row returned by the `func_query_user*` functions.
+#[derive(Debug, FromRow)]
+pub struct UserEntity {
+    #[from_row(uuid)]
+    pub id: String,
+    #[from_row(rename = "account")]
+    pub email_account: String,
+    pub created_at: prost_types::Timestamp,
+    pub updated_at: prost_types::Timestamp,
+    pub last_login_at: Option<prost_types::Timestamp>,
+    pub status: i32,
+    pub role: i32,
+    pub firstname: String,
+    pub lastname: String,
+    pub gender: i32,
+    pub locale: i32,
+    pub timezone: String,
+    pub avatar: Option<String>,
+    pub signature: Option<String>,
+}
+
+impl From<UserEntity> for User {
+    fn from(entity: UserEntity) -> Self {
+        User {
+            id: entity.id,
+            email_account: entity.email_account,
+            created_at: Some(entity.created_at),
+            updated_at: Some(entity.updated_at),
+            last_login_at: entity.last_login_at,
+            status: entity.status,
+            role: entity.role,
+            firstname: entity.firstname,
+            lastname: entity.lastname,
+            gender: entity.gender,
+            locale: entity.locale,
+            timezone: entity.timezone,
+            avatar: entity.avatar.unwrap_or_default(),
+            signature: entity.signature.unwrap_or_default(),
+        }
+    }
+}
+
+/// Row returned by `func_confirm_email_change`.
+#[derive(Debug, FromRow)]
+pub struct EmailChangeEntity {
+    pub old_account: String,
+    pub new_account: String,
+}
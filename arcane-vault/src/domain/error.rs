@@ -1,16 +1,70 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
 use deadpool_postgres::CreatePoolError;
+use ethereal_core::proto::google::rpc::{ErrorInfo, LocalizedMessage, RetryInfo, Status};
+use prost::Message;
 use tokio_postgres::error::SqlState;
 
+use crate::infrastructure::error_code::get_error_message;
+pub use crate::infrastructure::error_code::{DEFAULT_ERROR_LOCALE, get_error_locale};
+use crate::infrastructure::rule::{TOTP_LOCKOUT, VERIFICATION_CODE_COOLDOWN};
+
+/// `ErrorInfo.domain` of every error arcane-vault returns.
+pub const ERROR_DOMAIN: &str = "arcane-vault";
+const TRANSIENT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Debug, PartialEq)]
 pub enum ArcaneVaultErrorCode {
     NoData,
+    ConnectionLost,
+    PoolTimeout,
+    SerializationFailure,
+    DeadlockDetected,
+    SchemaMismatch,
     InnerError(String),
 }
 
+impl ArcaneVaultErrorCode {
+    /// Transient errors may succeed when the operation is run again, permanent ones never will.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ArcaneVaultErrorCode::ConnectionLost
+                | ArcaneVaultErrorCode::PoolTimeout
+                | ArcaneVaultErrorCode::SerializationFailure
+                | ArcaneVaultErrorCode::DeadlockDetected
+        )
+    }
+
+    /// Whether the server rolled the statement back, so running it again cannot apply it twice.
+    pub fn is_rolled_back(&self) -> bool {
+        matches!(
+            self,
+            ArcaneVaultErrorCode::SerializationFailure | ArcaneVaultErrorCode::DeadlockDetected
+        )
+    }
+
+    /// The PA/PN code of an error raised by `util_raise_error` or `raise_error`.
+    pub fn get_error_code(&self) -> Option<&str> {
+        match self {
+            ArcaneVaultErrorCode::InnerError(code)
+                if code.len() == 5 && (code.starts_with("PA") || code.starts_with("PN")) =>
+            {
+                Some(code)
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ArcaneVaultError {
+    /// Full internal message, only for the server logs.
     pub message: String,
     pub code: Option<ArcaneVaultErrorCode>,
+    /// Arguments of a PA/PN error by parameter name, e.g. `account`.
+    pub metadata: HashMap<String, String>,
 }
 
 impl std::fmt::Display for ArcaneVaultError {
@@ -21,22 +75,199 @@ impl std::fmt::Display for ArcaneVaultError {
 
 impl std::error::Error for ArcaneVaultError {}
 
+impl ArcaneVaultError {
+    pub fn is_transient(&self) -> bool {
+        self.code.as_ref().is_some_and(ArcaneVaultErrorCode::is_transient)
+    }
+}
+
 impl From<tokio_postgres::error::Error> for ArcaneVaultError {
     fn from(err: tokio_postgres::Error) -> Self {
+        let hint = err.as_db_error().and_then(|db_error| db_error.hint().map(str::to_string));
+        // `util_raise_error` puts the arguments into the detail, as a JSON object by name.
+        let metadata = err
+            .as_db_error()
+            .and_then(|db_error| db_error.detail())
+            .filter(|_| hint.is_some())
+            .and_then(|detail| serde_json::from_str(detail).ok())
+            .unwrap_or_default();
         ArcaneVaultError {
             message: format!("{}", err),
             code: match err.code() {
                 Some(code) if code == &SqlState::NO_DATA => Some(ArcaneVaultErrorCode::NoData),
+                Some(code) if code == &SqlState::T_R_SERIALIZATION_FAILURE => {
+                    Some(ArcaneVaultErrorCode::SerializationFailure)
+                }
+                Some(code) if code == &SqlState::T_R_DEADLOCK_DETECTED => {
+                    Some(ArcaneVaultErrorCode::DeadlockDetected)
+                }
+                // `util_raise_error` puts the PA/PN error code into the hint.
+                Some(code) if code == &SqlState::RAISE_EXCEPTION && hint.is_some() => {
+                    hint.map(ArcaneVaultErrorCode::InnerError)
+                }
                 Some(code) => Some(ArcaneVaultErrorCode::InnerError(format!("{:?}", code))),
+                None if err.is_closed() || is_io_error(&err) => Some(ArcaneVaultErrorCode::ConnectionLost),
                 None => Some(ArcaneVaultErrorCode::InnerError("tokio postgres error".into())),
             },
+            metadata,
         }
     }
 }
 
 impl Into<tonic::Status> for ArcaneVaultError {
     fn into(self) -> tonic::Status {
-        tonic::Status::new(tonic::Code::Internal, format!("{:?}", self))
+        self.into_localized_status(&[])
+    }
+}
+
+impl ArcaneVaultError {
+    /// Clients get a sanitized message with `google.rpc.ErrorInfo`, `google.rpc.RetryInfo`
+    /// when trying again can help, and a `google.rpc.LocalizedMessage` in the first of
+    /// `locales` with translations; the full error only goes to the server log.
+    pub fn into_localized_status(self, locales: &[String]) -> tonic::Status {
+        let (code, reason, message) = self.get_client_error();
+        let reason = reason.to_string();
+        match code {
+            tonic::Code::Internal | tonic::Code::Unavailable | tonic::Code::Aborted => {
+                tracing::error!(reason, "{}", self)
+            }
+            _ => tracing::info!(reason, "{}", self),
+        }
+
+        let locale = locales
+            .iter()
+            .find_map(|locale| get_error_locale(locale))
+            .unwrap_or(DEFAULT_ERROR_LOCALE);
+        let localized_message = self
+            .code
+            .as_ref()
+            .and_then(ArcaneVaultErrorCode::get_error_code)
+            .and_then(|error_code| get_error_message(error_code, locale, &self.metadata));
+        let retry_delay = self.code.as_ref().and_then(get_retry_delay);
+
+        let error_info = ErrorInfo {
+            reason,
+            domain: ERROR_DOMAIN.to_string(),
+            metadata: self.metadata,
+        };
+        let mut details = vec![prost_types::Any {
+            type_url: "type.googleapis.com/google.rpc.ErrorInfo".to_string(),
+            value: error_info.encode_to_vec(),
+        }];
+        if let Some(retry_delay) = retry_delay {
+            let retry_info = RetryInfo {
+                retry_delay: prost_types::Duration::try_from(retry_delay).ok(),
+            };
+            details.push(prost_types::Any {
+                type_url: "type.googleapis.com/google.rpc.RetryInfo".to_string(),
+                value: retry_info.encode_to_vec(),
+            });
+        }
+        if let Some(localized_message) = localized_message {
+            let localized_message = LocalizedMessage {
+                locale: locale.to_string(),
+                message: localized_message,
+            };
+            details.push(prost_types::Any {
+                type_url: "type.googleapis.com/google.rpc.LocalizedMessage".to_string(),
+                value: localized_message.encode_to_vec(),
+            });
+        }
+
+        let status = Status {
+            code: code as i32,
+            message: message.clone(),
+            details,
+        };
+        tonic::Status::with_details(code, message, status.encode_to_vec().into())
+    }
+
+    // The gRPC code, `ErrorInfo.reason` and message a client may see.
+    fn get_client_error(&self) -> (tonic::Code, &str, String) {
+        if let Some(error_code) = self.code.as_ref().and_then(ArcaneVaultErrorCode::get_error_code) {
+            let message = get_error_message(error_code, DEFAULT_ERROR_LOCALE, &self.metadata)
+                .unwrap_or_default();
+            return (get_error_code_status(error_code), error_code, message);
+        }
+
+        let (code, reason, message) = match &self.code {
+            Some(ArcaneVaultErrorCode::NoData) => {
+                (tonic::Code::NotFound, "NO_DATA", "The requested data was not found.")
+            }
+            Some(ArcaneVaultErrorCode::ConnectionLost) => (
+                tonic::Code::Unavailable,
+                "CONNECTION_LOST",
+                "The service is temporarily unavailable.",
+            ),
+            Some(ArcaneVaultErrorCode::PoolTimeout) => (
+                tonic::Code::Unavailable,
+                "POOL_TIMEOUT",
+                "The service is temporarily unavailable.",
+            ),
+            Some(ArcaneVaultErrorCode::SerializationFailure) => (
+                tonic::Code::Aborted,
+                "SERIALIZATION_FAILURE",
+                "The request conflicted with a concurrent request.",
+            ),
+            Some(ArcaneVaultErrorCode::DeadlockDetected) => (
+                tonic::Code::Aborted,
+                "DEADLOCK_DETECTED",
+                "The request conflicted with a concurrent request.",
+            ),
+            Some(ArcaneVaultErrorCode::InnerError(state))
+                if *state == format!("{:?}", SqlState::FOREIGN_KEY_VIOLATION) =>
+            {
+                (
+                    tonic::Code::InvalidArgument,
+                    "FOREIGN_KEY_VIOLATION",
+                    "The request refers to a value that does not exist.",
+                )
+            }
+            Some(ArcaneVaultErrorCode::InnerError(state))
+                if *state == format!("{:?}", SqlState::UNIQUE_VIOLATION) =>
+            {
+                (tonic::Code::AlreadyExists, "UNIQUE_VIOLATION", "The value already exists.")
+            }
+            Some(ArcaneVaultErrorCode::InnerError(state))
+                if *state == format!("{:?}", SqlState::STRING_DATA_RIGHT_TRUNCATION) =>
+            {
+                (
+                    tonic::Code::InvalidArgument,
+                    "STRING_DATA_RIGHT_TRUNCATION",
+                    "A value in the request is too long.",
+                )
+            }
+            _ => (tonic::Code::Internal, "INTERNAL", "Internal error."),
+        };
+        (code, reason, message.to_string())
+    }
+}
+
+fn get_error_code_status(error_code: &str) -> tonic::Code {
+    match error_code {
+        "PA001" | "PA028" | "PN001" => tonic::Code::AlreadyExists,
+        "PA002" | "PA003" | "PA006" | "PA007" | "PA011" | "PA019" | "PA023" | "PA030"
+        | "PA032" | "PN005" | "PN006" | "PN008" => tonic::Code::NotFound,
+        "PA004" | "PA010" | "PA013" | "PA014" | "PA015" | "PA029" | "PA031" | "PN003" => {
+            tonic::Code::FailedPrecondition
+        }
+        "PA005" | "PA016" | "PA017" | "PA018" | "PA020" | "PA027" | "PA037" => {
+            tonic::Code::Unauthenticated
+        }
+        "PA008" | "PA036" => tonic::Code::ResourceExhausted,
+        "PA009" | "PA012" | "PA022" | "PA025" | "PA026" | "PA034" | "PA035" | "PN004"
+        | "PN007" => tonic::Code::InvalidArgument,
+        "PA021" | "PA024" | "PA033" | "PN002" => tonic::Code::PermissionDenied,
+        _ => tonic::Code::Internal,
+    }
+}
+
+fn get_retry_delay(code: &ArcaneVaultErrorCode) -> Option<Duration> {
+    match code {
+        code if code.is_transient() => Some(TRANSIENT_RETRY_DELAY),
+        code if code.get_error_code() == Some("PA008") => Some(VERIFICATION_CODE_COOLDOWN),
+        code if code.get_error_code() == Some("PA036") => Some(TOTP_LOCKOUT),
+        _ => None,
     }
 }
 
@@ -45,15 +276,160 @@ impl From<CreatePoolError> for ArcaneVaultError {
         Self {
             message: error.to_string(),
             code: Some(ArcaneVaultErrorCode::InnerError("create pool error".into())),
+            metadata: HashMap::new(),
         }
     }
 }
 
 impl From<deadpool_postgres::PoolError> for ArcaneVaultError {
     fn from(error: deadpool_postgres::PoolError) -> Self {
+        match error {
+            deadpool_postgres::PoolError::Backend(error) => error.into(),
+            deadpool_postgres::PoolError::Timeout(_) => Self {
+                message: error.to_string(),
+                code: Some(ArcaneVaultErrorCode::PoolTimeout),
+                metadata: HashMap::new(),
+            },
+            _ => Self {
+                message: error.to_string(),
+                code: Some(ArcaneVaultErrorCode::InnerError("deadpool postgres error".into())),
+                metadata: HashMap::new(),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for ArcaneVaultError {
+    fn from(error: rusqlite::Error) -> Self {
         Self {
             message: error.to_string(),
-            code: Some(ArcaneVaultErrorCode::InnerError("deadpool postgres error".into())),
+            code: match (&error, error.sqlite_error_code()) {
+                (rusqlite::Error::QueryReturnedNoRows, _) => Some(ArcaneVaultErrorCode::NoData),
+                (_, Some(code)) => Some(ArcaneVaultErrorCode::InnerError(format!("{:?}", code))),
+                (_, None) => Some(ArcaneVaultErrorCode::InnerError("sqlite error".into())),
+            },
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+fn is_io_error(err: &tokio_postgres::Error) -> bool {
+    std::error::Error::source(err).is_some_and(|source| source.is::<std::io::Error>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::error_code::raise_error;
+
+    fn get_error(code: ArcaneVaultErrorCode, message: &str) -> ArcaneVaultError {
+        ArcaneVaultError {
+            message: message.to_string(),
+            code: Some(code),
+            metadata: HashMap::new(),
         }
     }
+
+    fn get_details(status: &tonic::Status) -> Vec<prost_types::Any> {
+        Status::decode(status.details()).unwrap().details
+    }
+
+    fn get_error_info(status: &tonic::Status) -> ErrorInfo {
+        let details = get_details(status);
+        assert_eq!(
+            details[0].type_url,
+            "type.googleapis.com/google.rpc.ErrorInfo"
+        );
+        ErrorInfo::decode(details[0].value.as_slice()).unwrap()
+    }
+
+    fn get_retry_delay(status: &tonic::Status) -> Option<prost_types::Duration> {
+        get_details(status)
+            .iter()
+            .find(|detail| detail.type_url == "type.googleapis.com/google.rpc.RetryInfo")
+            .map(|detail| {
+                RetryInfo::decode(detail.value.as_slice())
+                    .unwrap()
+                    .retry_delay
+                    .unwrap()
+            })
+    }
+
+    #[test]
+    fn keeps_internal_messages_from_clients() {
+        let error = get_error(
+            ArcaneVaultErrorCode::InnerError("tokio postgres error".into()),
+            "db error: relation \"users\" does not exist",
+        );
+
+        let status = error.into_localized_status(&[]);
+
+        assert_eq!(status.code(), tonic::Code::Internal);
+        assert_eq!(status.message(), "Internal error.");
+        let error_info = get_error_info(&status);
+        assert_eq!(
+            (error_info.reason.as_str(), error_info.domain.as_str()),
+            ("INTERNAL", ERROR_DOMAIN)
+        );
+        assert_eq!(get_details(&status).len(), 1);
+    }
+
+    #[test]
+    fn sets_the_reason_and_metadata_of_error_codes() {
+        let error = raise_error("func_register_user", "PA001", &["ada@example.com"]);
+
+        let status = error.into_localized_status(&[]);
+
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+        assert_eq!(status.message(), "Account ada@example.com is unavailable.");
+        let error_info = get_error_info(&status);
+        assert_eq!(error_info.reason, "PA001");
+        assert_eq!(
+            error_info.metadata,
+            HashMap::from([("account".to_string(), "ada@example.com".to_string())])
+        );
+        assert_eq!(get_retry_delay(&status), None);
+    }
+
+    #[test]
+    fn localizes_the_message_for_the_first_locale_with_translations() {
+        let error = raise_error("func_register_user", "PA001", &["ada@example.com"]);
+
+        let status = error.into_localized_status(&["fr-FR".into(), "zh".into()]);
+
+        // The status message stays in the default locale for clients matching on it.
+        assert_eq!(status.message(), "Account ada@example.com is unavailable.");
+        let details = get_details(&status);
+        let detail = details
+            .iter()
+            .find(|detail| detail.type_url == "type.googleapis.com/google.rpc.LocalizedMessage")
+            .unwrap();
+        let localized_message = LocalizedMessage::decode(detail.value.as_slice()).unwrap();
+        assert_eq!(localized_message.locale, "zh-CN");
+        assert_eq!(localized_message.message, "账户 ada@example.com 不可用。");
+    }
+
+    #[test]
+    fn tells_when_to_retry() {
+        let status = get_error(ArcaneVaultErrorCode::ConnectionLost, "connection reset")
+            .into_localized_status(&[]);
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert_eq!(
+            get_retry_delay(&status),
+            prost_types::Duration::try_from(TRANSIENT_RETRY_DELAY).ok()
+        );
+
+        let status = raise_error(
+            "func_regenerate_verification_code",
+            "PA008",
+            &["ada@example.com"],
+        )
+        .into_localized_status(&[]);
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(
+            get_retry_delay(&status),
+            prost_types::Duration::try_from(VERIFICATION_CODE_COOLDOWN).ok()
+        );
+    }
 }
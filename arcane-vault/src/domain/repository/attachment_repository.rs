@@ -0,0 +1,56 @@
+use std::time::SystemTime;
+
+use uuid::Uuid;
+
+use crate::domain::{
+    entity::{AttachmentBlobEntity, AttachmentEntity},
+    error::ArcaneVaultError,
+};
+
+/// Storage for note attachments, mirroring the attachment functions of `script/easynote_note.sql`.
+/// Attachments of the same content share one blob per user, which counts them and is orphaned
+/// when the last one goes away, also along with its note. Notes of other users raise `PN005` and
+/// their attachments `PN006`.
+#[async_trait::async_trait]
+pub trait AttachmentRepository: Sync + Send {
+    /// Attaches the file of `sha256` to a note, adding its blob unless the user already has one.
+    /// The file still has to be stored when the returned `is_stored` is false.
+    async fn add_attachment(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        sha256: &str,
+        size: i64,
+        file_name: &str,
+        content_type: &str,
+    ) -> Result<AttachmentEntity, ArcaneVaultError>;
+
+    async fn set_attachment_blob_stored(&self, blob_id: Uuid) -> Result<(), ArcaneVaultError>;
+
+    /// Oldest first.
+    async fn query_attachments(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+    ) -> Result<Vec<AttachmentEntity>, ArcaneVaultError>;
+
+    async fn query_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<AttachmentEntity, ArcaneVaultError>;
+
+    async fn delete_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Deletes up to `limit` blobs orphaned before `orphaned_before` and returns them, for their
+    /// files to be deleted next. Blobs attached again meanwhile are kept.
+    async fn collect_attachment_blobs(
+        &self,
+        orphaned_before: SystemTime,
+        limit: i32,
+    ) -> Result<Vec<AttachmentBlobEntity>, ArcaneVaultError>;
+}
@@ -0,0 +1,20 @@
+use ethereal_core::proto::AuditEvent;
+use uuid::Uuid;
+
+use crate::domain::{entity::NewAuditEvent, error::ArcaneVaultError};
+
+/// Storage for the security audit log, mirroring the audit functions of
+/// `script/easynote_common.sql`. Events are only ever added.
+#[async_trait::async_trait]
+pub trait AuditRepository: Sync + Send {
+    async fn add_audit_event(&self, event: &NewAuditEvent) -> Result<(), ArcaneVaultError>;
+
+    /// Newest first, with the events recorded for the current account of the user before it
+    /// became one, e.g. its registration. Raises `PA007` when the user does not exist.
+    async fn query_audit_events(
+        &self,
+        user_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<AuditEvent>, ArcaneVaultError>;
+}
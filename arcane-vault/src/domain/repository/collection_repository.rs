@@ -0,0 +1,27 @@
+use uuid::Uuid;
+
+use crate::domain::error::ArcaneVaultError;
+
+/// Storage for note collections, mirroring the collection functions of `script/easynote_note.sql`.
+/// Every user has at most one default (active) collection.
+#[async_trait::async_trait]
+pub trait CollectionRepository: Sync + Send {
+    /// Adds a collection and returns its id. It becomes the default when `is_active` is set or when
+    /// the user has no default yet.
+    async fn add_collection(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        description: &str,
+        is_active: bool,
+    ) -> Result<Uuid, ArcaneVaultError>;
+
+    async fn change_default_collection(
+        &self,
+        user_id: Uuid,
+        collection_id: Uuid,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Returns the default collection, creating one named `Default` when the user has none.
+    async fn get_default_collection(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError>;
+}
@@ -0,0 +1,23 @@
+mod attachment_repository;
+mod audit_repository;
+mod collection_repository;
+mod note_repository;
+mod note_revision_repository;
+mod oidc_repository;
+mod personal_access_token_repository;
+mod reference_data_repository;
+mod session_repository;
+mod totp_repository;
+mod user_repository;
+
+pub use attachment_repository::*;
+pub use audit_repository::*;
+pub use collection_repository::*;
+pub use note_repository::*;
+pub use note_revision_repository::*;
+pub use oidc_repository::*;
+pub use personal_access_token_repository::*;
+pub use reference_data_repository::*;
+pub use session_repository::*;
+pub use totp_repository::*;
+pub use user_repository::*;
@@ -0,0 +1,24 @@
+use uuid::Uuid;
+
+use crate::domain::error::ArcaneVaultError;
+
+/// Storage for notes, mirroring the note functions of `script/easynote_note.sql`.
+#[async_trait::async_trait]
+pub trait NoteRepository: Sync + Send {
+    /// Adds a note to a collection and returns its id. `meta` defaults to `{}`.
+    async fn add_note(
+        &self,
+        content: &str,
+        collection_id: Uuid,
+        source_type: i32,
+        meta: Option<serde_json::Value>,
+    ) -> Result<Uuid, ArcaneVaultError>;
+
+    async fn update_note_meta(
+        &self,
+        note_id: Uuid,
+        meta: serde_json::Value,
+    ) -> Result<(), ArcaneVaultError>;
+
+    async fn delete_note(&self, note_id: Uuid) -> Result<(), ArcaneVaultError>;
+}
@@ -0,0 +1,46 @@
+use std::time::SystemTime;
+
+use uuid::Uuid;
+
+use crate::domain::{entity::NoteRevisionEntity, error::ArcaneVaultError};
+
+/// Storage for revisions of notes, mirroring the revision functions of
+/// `script/easynote_note.sql`. A note is its own latest revision, and every change of its content
+/// or meta keeps the version before. Notes of other users raise `PN005` and missing revisions
+/// `PN008`.
+#[async_trait::async_trait]
+pub trait NoteRevisionRepository: Sync + Send {
+    /// Newest first, starting with the note itself.
+    async fn query_note_revisions(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<NoteRevisionEntity>, ArcaneVaultError>;
+
+    async fn query_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<NoteRevisionEntity, ArcaneVaultError>;
+
+    /// Sets the content and meta of the note back to `revision` and returns the revision the
+    /// note is at afterwards.
+    async fn restore_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<i32, ArcaneVaultError>;
+
+    /// Deletes the revisions neither among the `keep_count` latest of their note nor replaced
+    /// after `keep_after` and returns how many. `None` leaves a rule out; with both `None`
+    /// every revision is kept.
+    async fn prune_note_revisions(
+        &self,
+        keep_count: Option<i32>,
+        keep_after: Option<SystemTime>,
+    ) -> Result<i32, ArcaneVaultError>;
+}
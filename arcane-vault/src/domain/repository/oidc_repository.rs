@@ -0,0 +1,72 @@
+use ethereal_core::proto::ExternalIdentity;
+use uuid::Uuid;
+
+use crate::domain::{entity::OidcLoginAttemptEntity, error::ArcaneVaultError};
+
+/// Storage for OpenID Connect logins and the identities they sign in with, mirroring the OIDC
+/// functions of `script/easynote_common.sql`. States are only ever given as their hashes.
+#[async_trait::async_trait]
+pub trait OidcRepository: Sync + Send {
+    /// Stores a login waiting for the provider, or a link to `user_id` when it is set.
+    async fn add_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+        provider: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Removes the attempt of a state. `None` when the state is unknown or its attempt expired.
+    async fn use_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+    ) -> Result<Option<OidcLoginAttemptEntity>, ArcaneVaultError>;
+
+    /// Records a login and returns the user of the identity, `None` when no user has it.
+    async fn login_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<Option<Uuid>, ArcaneVaultError>;
+
+    /// Creates an active user without a password for the first login of an identity, and
+    /// returns its id. `locale` is a BCP 47 tag; unknown locales and timezones get the defaults.
+    /// Raises `PA001` when the email is taken.
+    #[allow(clippy::too_many_arguments)]
+    async fn register_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+        firstname: &str,
+        lastname: &str,
+        locale: &str,
+        timezone: &str,
+    ) -> Result<Uuid, ArcaneVaultError>;
+
+    /// Raises `PA028` when another user has the identity.
+    async fn link_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<ExternalIdentity, ArcaneVaultError>;
+
+    /// Oldest first.
+    async fn query_user_identities(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ExternalIdentity>, ArcaneVaultError>;
+
+    /// Raises `PA030` when the user does not have the identity, and `PA031` when it is the only
+    /// way left for the user to sign in.
+    async fn unlink_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ArcaneVaultError>;
+}
@@ -0,0 +1,40 @@
+use std::time::SystemTime;
+
+use ethereal_core::proto::PersonalAccessToken;
+use uuid::Uuid;
+
+use crate::domain::{entity::Principal, error::ArcaneVaultError};
+
+/// Storage for personal access tokens, mirroring the personal access token functions of
+/// `script/easynote_common.sql`. Tokens are only ever given as their hashes.
+#[async_trait::async_trait]
+pub trait PersonalAccessTokenRepository: Sync + Send {
+    /// `expires_at` of `None` never expires.
+    async fn add_personal_access_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        token_hash: &str,
+        scopes: &[String],
+        expires_at: Option<SystemTime>,
+    ) -> Result<PersonalAccessToken, ArcaneVaultError>;
+
+    /// The tokens that are neither expired nor revoked, newest first.
+    async fn query_personal_access_tokens(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<PersonalAccessToken>, ArcaneVaultError>;
+
+    /// Raises `PA023` when the user has no such active token.
+    async fn revoke_personal_access_token(
+        &self,
+        user_id: Uuid,
+        token_id: Uuid,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Records the use of an active token. `None` when the token is unknown, expired or revoked.
+    async fn use_personal_access_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError>;
+}
@@ -0,0 +1,7 @@
+use crate::domain::{entity::ReferenceData, error::ArcaneVaultError};
+
+/// Read access to the lookup tables the other tables reference by foreign key.
+#[async_trait::async_trait]
+pub trait ReferenceDataRepository: Sync + Send {
+    async fn query_reference_data(&self) -> Result<ReferenceData, ArcaneVaultError>;
+}
@@ -0,0 +1,47 @@
+use ethereal_core::proto::Session;
+use uuid::Uuid;
+
+use crate::domain::{
+    entity::{Principal, SessionTokenHashes},
+    error::ArcaneVaultError,
+};
+
+/// Storage for login sessions, mirroring the session functions of `script/easynote_common.sql`.
+/// Each session keeps every refresh token it was given, so a reused one can be told apart from
+/// an unknown one.
+#[async_trait::async_trait]
+pub trait SessionRepository: Sync + Send {
+    /// Returns the session id.
+    async fn add_session(
+        &self,
+        user_id: Uuid,
+        device_label: &str,
+        user_agent: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Uuid, ArcaneVaultError>;
+
+    /// Swaps `refresh_token_hash` for `tokens` and returns the session id. `None` when the
+    /// token is unknown or its session is over; a token that was already used also revokes its
+    /// session.
+    async fn refresh_session(
+        &self,
+        refresh_token_hash: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Option<Uuid>, ArcaneVaultError>;
+
+    /// The user and session of an access token that has not expired, if any.
+    async fn query_session_by_access_token(
+        &self,
+        access_token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError>;
+
+    /// The sessions that are neither expired nor revoked, most recently used first.
+    async fn query_sessions(&self, user_id: Uuid) -> Result<Vec<Session>, ArcaneVaultError>;
+
+    /// Raises `PA019` when the user has no such active session.
+    async fn revoke_session(&self, user_id: Uuid, session_id: Uuid)
+    -> Result<(), ArcaneVaultError>;
+
+    /// Returns how many sessions were revoked.
+    async fn revoke_sessions(&self, user_id: Uuid) -> Result<i32, ArcaneVaultError>;
+}
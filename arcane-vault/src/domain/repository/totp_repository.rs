@@ -0,0 +1,61 @@
+use uuid::Uuid;
+
+use crate::domain::{entity::TotpEntity, error::ArcaneVaultError};
+
+/// Storage for TOTP two-factor authentication, mirroring the TOTP functions of
+/// `script/easynote_common.sql`. Secrets arrive encrypted and recovery codes as SHA-256 hashes.
+#[async_trait::async_trait]
+pub trait TotpRepository: Sync + Send {
+    /// Stores a secret to confirm, replacing an unconfirmed one. Raises `PA013` when 2FA is
+    /// already enabled.
+    async fn enroll_totp(&self, user_id: Uuid, secret: &[u8]) -> Result<(), ArcaneVaultError>;
+
+    async fn query_totp(&self, user_id: Uuid) -> Result<Option<TotpEntity>, ArcaneVaultError>;
+
+    /// Enables 2FA with the step of the code that confirmed it, replacing the recovery codes.
+    async fn confirm_totp(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Removes the secret, recovery codes and pending challenges. Raises `PA015` when 2FA is not
+    /// enabled.
+    async fn disable_totp(&self, user_id: Uuid) -> Result<(), ArcaneVaultError>;
+
+    /// Returns false when `step` or a later one was already used.
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool, ArcaneVaultError>;
+
+    /// Returns false when the code does not exist or was already used.
+    async fn use_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+    ) -> Result<bool, ArcaneVaultError>;
+
+    /// Counts an attempt at the second factor, or returns false while the last
+    /// `TOTP_MAX_ATTEMPTS` attempts failed within `TOTP_LOCKOUT` of each other and of now.
+    async fn use_totp_attempt(&self, user_id: Uuid) -> Result<bool, ArcaneVaultError>;
+
+    /// Clears the attempts once a code was right.
+    async fn reset_totp_attempts(&self, user_id: Uuid) -> Result<(), ArcaneVaultError>;
+
+    async fn replace_recovery_codes(
+        &self,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Starts the second step of a login and returns the challenge id.
+    async fn add_totp_challenge(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError>;
+
+    /// Counts an attempt on the challenge and returns its user, `None` once it expired or ran out
+    /// of attempts.
+    async fn use_totp_challenge(
+        &self,
+        challenge_id: Uuid,
+    ) -> Result<Option<Uuid>, ArcaneVaultError>;
+
+    async fn delete_totp_challenge(&self, challenge_id: Uuid) -> Result<(), ArcaneVaultError>;
+}
@@ -0,0 +1,105 @@
+use ethereal_core::proto::User;
+use futures_util::stream::BoxStream;
+use uuid::Uuid;
+
+use crate::domain::{
+    entity::{EmailChangeEntity, NewUser},
+    error::ArcaneVaultError,
+};
+
+/// Storage for accounts and profiles. Every method mirrors the `func_*` function of the same name
+/// in `script/easynote_common.sql`, including the `PA` error codes it raises. Passwords arrive
+/// hashed by [`crate::domain::service::PasswordHasher`] and are stored as they are.
+#[async_trait::async_trait]
+pub trait UserRepository: Sync + Send {
+    /// Adds a pending user and returns its verification code.
+    async fn register_user(
+        &self,
+        user: &NewUser,
+        password_hash: &str,
+    ) -> Result<String, ArcaneVaultError>;
+
+    /// Turns a pending user into a user and returns its id. `password_hash` has to be the stored
+    /// one, so a hash the caller did not verify fails like a wrong password.
+    async fn verify_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+        verification_code: &str,
+    ) -> Result<Uuid, ArcaneVaultError>;
+
+    async fn regenerate_verification_code(&self, account: &str) -> Result<String, ArcaneVaultError>;
+
+    /// Same check of `password_hash` as [`Self::verify_user`].
+    async fn login_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+    ) -> Result<Uuid, ArcaneVaultError>;
+
+    async fn request_reset_password(&self, account: &str) -> Result<String, ArcaneVaultError>;
+
+    async fn reset_password(
+        &self,
+        account: &str,
+        verification_code: &str,
+        new_password_hash: &str,
+    ) -> Result<(), ArcaneVaultError>;
+
+    async fn set_password(&self, id: Uuid, password_hash: &str) -> Result<(), ArcaneVaultError>;
+
+    /// Keeps `account` as the pending new address of the user and returns its verification code.
+    async fn request_email_change(
+        &self,
+        id: Uuid,
+        account: &str,
+    ) -> Result<String, ArcaneVaultError>;
+
+    /// Counts an attempt at the code of the pending email change, or returns false once
+    /// `EMAIL_CHANGE_MAX_ATTEMPTS` were made. True when there is no pending change.
+    async fn use_email_change_attempt(&self, id: Uuid) -> Result<bool, ArcaneVaultError>;
+
+    /// Replaces the address of the user with the pending one, when it is still available.
+    async fn confirm_email_change(
+        &self,
+        id: Uuid,
+        verification_code: &str,
+    ) -> Result<EmailChangeEntity, ArcaneVaultError>;
+
+    /// The stored password hash of a user, to verify a login against.
+    async fn query_password_hash(&self, account: &str) -> Result<Option<String>, ArcaneVaultError>;
+
+    /// The stored password hash of a pending user, to verify a verification against.
+    async fn query_pending_password_hash(
+        &self,
+        account: &str,
+    ) -> Result<Option<String>, ArcaneVaultError>;
+
+    /// Sets name, gender and locale at once, returning the updated user and which of `name`,
+    /// `gender` and `locale` changed; nothing is changed when any of them fails.
+    async fn update_profile(
+        &self,
+        id: Uuid,
+        firstname: &str,
+        lastname: &str,
+        gender: i32,
+        locale: i32,
+    ) -> Result<(User, Vec<&'static str>), ArcaneVaultError>;
+
+    async fn set_avatar(&self, id: Uuid, avatar: &str) -> Result<(), ArcaneVaultError>;
+
+    async fn set_signature(&self, id: Uuid, signature: &str) -> Result<(), ArcaneVaultError>;
+
+    /// Raises `PA012` unless `timezone` is in `timezones`.
+    async fn set_timezone(&self, id: Uuid, timezone: &str) -> Result<(), ArcaneVaultError>;
+
+    async fn query_user_by_id(&self, id: Uuid) -> Result<Option<User>, ArcaneVaultError>;
+
+    async fn query_user_by_account(&self, account: &str) -> Result<Option<User>, ArcaneVaultError>;
+
+    async fn query_users(&self, offset: i32, limit: i32) -> Result<Vec<User>, ArcaneVaultError>;
+
+    async fn export_users(
+        &self,
+    ) -> Result<BoxStream<'static, Result<User, ArcaneVaultError>>, ArcaneVaultError>;
+}
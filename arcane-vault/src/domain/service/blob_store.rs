@@ -0,0 +1,14 @@
+use crate::domain::error::ArcaneVaultError;
+
+/// Stores uploaded files, e.g. avatar thumbnails, under `/`-separated keys.
+#[async_trait::async_trait]
+pub trait BlobStore: Sync + Send {
+    /// Stores `data` under `key`, replacing what was stored there.
+    async fn put_blob(&self, key: &str, data: Vec<u8>) -> Result<(), ArcaneVaultError>;
+
+    /// `None` when nothing is stored under `key`.
+    async fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>, ArcaneVaultError>;
+
+    /// Does nothing when nothing is stored under `key`.
+    async fn delete_blob(&self, key: &str) -> Result<(), ArcaneVaultError>;
+}
@@ -0,0 +1,8 @@
+use crate::domain::error::ArcaneVaultError;
+
+/// Mails users about their account, e.g. verification codes and security alerts.
+#[async_trait::async_trait]
+pub trait Mailer: Sync + Send {
+    /// Sends a plain text mail to `to`.
+    async fn send_mail(&self, to: &str, subject: &str, body: &str) -> Result<(), ArcaneVaultError>;
+}
@@ -1,3 +1,13 @@
+mod blob_store;
+mod mailer;
+mod note_service;
+mod password_hasher;
+mod reference_data_service;
 mod user_service;
 
+pub use blob_store::*;
+pub use mailer::*;
+pub use note_service::*;
+pub use password_hasher::*;
+pub use reference_data_service::*;
 pub use user_service::*;
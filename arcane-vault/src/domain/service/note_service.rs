@@ -0,0 +1,87 @@
+use ethereal_core::proto::{Attachment, DiffLine, NoteRevision};
+use futures_util::stream::BoxStream;
+use uuid::Uuid;
+
+use crate::domain::{entity::Principal, error::ArcaneVaultError};
+
+/// Notes of the user of a principal; personal access tokens need the `notes:read` scope to read
+/// and `notes:write` to change them.
+#[async_trait::async_trait]
+pub trait NoteService: Sync + Send {
+    /// Attaches the file in `chunks` to a note, raising `PN007` when it is too large. A file the
+    /// user already has is not stored again.
+    async fn upload_attachment(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        file_name: &str,
+        content_type: &str,
+        chunks: BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>,
+    ) -> Result<Attachment, ArcaneVaultError>;
+
+    /// Oldest first.
+    async fn list_attachments(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+    ) -> Result<Vec<Attachment>, ArcaneVaultError>;
+
+    /// The attachment and its file in chunks.
+    async fn download_attachment(
+        &self,
+        principal: &Principal,
+        attachment_id: Uuid,
+    ) -> Result<
+        (
+            Attachment,
+            BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>,
+        ),
+        ArcaneVaultError,
+    >;
+
+    async fn delete_attachment(
+        &self,
+        principal: &Principal,
+        attachment_id: Uuid,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Deletes the blobs no attachment has referred to for the grace period, returning how many.
+    async fn collect_attachment_blobs(&self) -> Result<usize, ArcaneVaultError>;
+
+    /// Newest first, starting with the note itself.
+    async fn list_note_revisions(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<NoteRevision>, ArcaneVaultError>;
+
+    async fn get_note_revision(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<NoteRevision, ArcaneVaultError>;
+
+    /// The line-by-line diffs of the content and of the pretty-printed meta from one revision to
+    /// another.
+    async fn diff_note_revisions(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        from_revision: i32,
+        to_revision: i32,
+    ) -> Result<(Vec<DiffLine>, Vec<DiffLine>), ArcaneVaultError>;
+
+    /// Changes the note back to a revision and returns the revision it is at now.
+    async fn restore_note_revision(
+        &self,
+        principal: &Principal,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<i32, ArcaneVaultError>;
+
+    /// Deletes the revisions the retention settings no longer keep, returning how many.
+    async fn prune_note_revisions(&self) -> Result<usize, ArcaneVaultError>;
+}
@@ -0,0 +1,16 @@
+use crate::domain::error::ArcaneVaultError;
+
+/// Hashes passwords before they reach a repository, so storage only ever sees hashes.
+pub trait PasswordHasher: Sync + Send {
+    fn hash_password(&self, password: &str) -> Result<String, ArcaneVaultError>;
+
+    /// Also accepts hashes of algorithms or parameters this hasher no longer creates.
+    fn verify_password(
+        &self,
+        password: &str,
+        password_hash: &str,
+    ) -> Result<bool, ArcaneVaultError>;
+
+    /// Whether `password_hash` should be replaced once its password is known again.
+    fn needs_rehash(&self, password_hash: &str) -> bool;
+}
@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use crate::domain::{entity::ReferenceData, error::ArcaneVaultError};
+
+#[async_trait::async_trait]
+pub trait ReferenceDataService: Sync + Send {
+    /// The lookup tables, possibly as cached a while ago.
+    async fn get_reference_data(&self) -> Result<Arc<ReferenceData>, ArcaneVaultError>;
+}
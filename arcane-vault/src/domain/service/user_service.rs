@@ -1,28 +1,225 @@
-use ethereal_core::proto::User;
+use ethereal_core::proto::{
+    AuditEvent, ExternalIdentity, PersonalAccessToken, Session, SessionTokens, User,
+};
+use futures_util::stream::BoxStream;
 use uuid::Uuid;
 
-use crate::domain::error::ArcaneVaultError;
+use crate::domain::{
+    entity::{NewUser, Principal},
+    error::ArcaneVaultError,
+};
+
+/// The client a session is started for, as it describes itself.
+#[derive(Debug, Clone, Default)]
+pub struct SessionDevice {
+    pub device_label: String,
+    pub user_agent: String,
+}
+
+/// Where a request comes from, as recorded with the audit events it causes.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    pub ip_address: String,
+    pub user_agent: String,
+    pub request_id: String,
+}
+
+/// What a login with the right password leads to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoginOutcome {
+    /// The user id and the tokens of the new session.
+    LoggedIn(Uuid, SessionTokens),
+    /// 2FA is enabled, so the login finishes with [`UserService::verify_totp_challenge`].
+    TotpRequired(Uuid),
+}
 
 #[async_trait::async_trait]
 pub trait UserService: Sync + Send {
+    /// Registers `user` with `password` and returns the verification code. An empty timezone
+    /// stands for UTC.
     async fn create_user(
         &self,
-        email: &str,
+        user: &NewUser,
         password: &str,
-        firstname: &str,
-        lastname: &str,
-        gender: i32,
-        locale: i32,
-        avatar: &str,
-        signature: &str,
+        context: &RequestContext,
     ) -> Result<String, ArcaneVaultError>;
     async fn verify_user(
         &self,
         email: &str,
         password: &str,
         verification_code: &str,
+        context: &RequestContext,
     ) -> Result<Uuid, ArcaneVaultError>;
 
+    /// Also upgrades the stored password hash when [`super::PasswordHasher::needs_rehash`] says so.
+    async fn login_user(
+        &self,
+        email: &str,
+        password: &str,
+        device: &SessionDevice,
+        context: &RequestContext,
+    ) -> Result<LoginOutcome, ArcaneVaultError>;
+
+    /// Finishes a login with a TOTP or recovery code and returns the user id and session tokens.
+    async fn verify_totp_challenge(
+        &self,
+        challenge_id: Uuid,
+        code: &str,
+        device: &SessionDevice,
+        context: &RequestContext,
+    ) -> Result<(Uuid, SessionTokens), ArcaneVaultError>;
+
+    /// Replaces the tokens of the session `refresh_token` belongs to. Reusing a refresh token
+    /// revokes its session.
+    async fn refresh_session(&self, refresh_token: &str)
+    -> Result<SessionTokens, ArcaneVaultError>;
+
+    /// Like the other session methods, it acts for the user of `principal`, which must be a
+    /// session.
+    async fn list_sessions(&self, principal: &Principal) -> Result<Vec<Session>, ArcaneVaultError>;
+
+    async fn revoke_session(
+        &self,
+        principal: &Principal,
+        session_id: Uuid,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Revokes every session of the user, the one of `principal` too, and returns how many there
+    /// were.
+    async fn logout_everywhere(&self, principal: &Principal) -> Result<i32, ArcaneVaultError>;
+
+    /// Who a bearer token, a session access token or a personal access token, was issued to.
+    /// Raises `PA020` for any other token.
+    async fn authenticate(&self, token: &str) -> Result<Principal, ArcaneVaultError>;
+
+    /// Returns the new token with its details; only its hash is stored. Like the other personal
+    /// access token methods, it acts for the user of `principal`, which must be a session.
+    async fn create_personal_access_token(
+        &self,
+        principal: &Principal,
+        name: &str,
+        scopes: &[String],
+        expires_at: Option<prost_types::Timestamp>,
+    ) -> Result<(PersonalAccessToken, String), ArcaneVaultError>;
+
+    async fn list_personal_access_tokens(
+        &self,
+        principal: &Principal,
+    ) -> Result<Vec<PersonalAccessToken>, ArcaneVaultError>;
+
+    async fn revoke_personal_access_token(
+        &self,
+        principal: &Principal,
+        token_id: Uuid,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Starts a login with an OpenID Connect provider and returns the URL to send the user to.
+    /// Raises `PA026` for a provider that is not configured.
+    async fn start_oidc_login(&self, provider: &str) -> Result<String, ArcaneVaultError>;
+
+    /// Finishes a login with the `state` and `code` the provider sent the user back with. The
+    /// first login of an identity registers a user, when its email is verified and not taken.
+    /// Raises `PA027` for an unknown or expired `state`, or a rejected `code`.
+    async fn finish_oidc_login(
+        &self,
+        state: &str,
+        code: &str,
+        device: &SessionDevice,
+        context: &RequestContext,
+    ) -> Result<LoginOutcome, ArcaneVaultError>;
+
+    /// Like [`UserService::start_oidc_login`], to link the identity to the user of `principal`
+    /// instead. Like the other external identity methods, `principal` must be a session.
+    async fn start_oidc_link(
+        &self,
+        principal: &Principal,
+        provider: &str,
+    ) -> Result<String, ArcaneVaultError>;
+
+    async fn finish_oidc_link(
+        &self,
+        principal: &Principal,
+        state: &str,
+        code: &str,
+    ) -> Result<ExternalIdentity, ArcaneVaultError>;
+
+    async fn list_external_identities(
+        &self,
+        principal: &Principal,
+    ) -> Result<Vec<ExternalIdentity>, ArcaneVaultError>;
+
+    async fn unlink_external_identity(
+        &self,
+        principal: &Principal,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Mails a verification code to `new_email`, which must not be taken. Like
+    /// [`UserService::confirm_email_change`], it acts for the user of `principal`, which must be a
+    /// session. Raises `PA005` for a wrong `password`, and `PA037` for an empty one unless the
+    /// session started within the last 5 minutes.
+    async fn request_email_change(
+        &self,
+        principal: &Principal,
+        password: &str,
+        new_email: &str,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Changes the email address to the requested one and returns it. The old address is alerted.
+    /// Raises `PA009` for a wrong code, and for any code once 5 were tried, until another one is
+    /// requested.
+    async fn confirm_email_change(
+        &self,
+        principal: &Principal,
+        verification_code: &str,
+        context: &RequestContext,
+    ) -> Result<String, ArcaneVaultError>;
+
+    /// Security events of `user_id`, the user of `principal` when unset, newest first. Other users
+    /// are only for admins, and `principal` must be a session.
+    async fn list_audit_events(
+        &self,
+        principal: &Principal,
+        user_id: Option<Uuid>,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<AuditEvent>, ArcaneVaultError>;
+
+    /// Starts a TOTP enrollment and returns the `otpauth://` URI for authenticator apps. Like the
+    /// other TOTP methods, it acts for the user of `principal`, which must be a session. Raises
+    /// `PA005` for a wrong `password`, and `PA037` for an empty one unless the session started
+    /// within the last 5 minutes.
+    async fn enroll_totp(
+        &self,
+        principal: &Principal,
+        password: &str,
+    ) -> Result<String, ArcaneVaultError>;
+
+    /// Enables 2FA with a first TOTP code and returns the recovery codes.
+    async fn confirm_totp(
+        &self,
+        principal: &Principal,
+        code: &str,
+    ) -> Result<Vec<String>, ArcaneVaultError>;
+
+    /// Disables 2FA after checking the password like [`UserService::enroll_totp`], and a TOTP or
+    /// recovery code.
+    async fn disable_totp(
+        &self,
+        principal: &Principal,
+        password: &str,
+        code: &str,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Replaces the recovery codes after checking a TOTP or recovery code. The second factor of a
+    /// user raises `PA036` after 5 wrong codes in a row, for 15 minutes.
+    async fn generate_recovery_codes(
+        &self,
+        principal: &Principal,
+        code: &str,
+    ) -> Result<Vec<String>, ArcaneVaultError>;
+
     async fn query_user_by_id(
         &self,
         id: Uuid,
@@ -33,4 +230,51 @@ pub trait UserService: Sync + Send {
         email_account: &str,
     ) -> Result<Option<User>, ArcaneVaultError>;
 
+    /// Sets the name, gender and locale of the user of `principal`, which must be a session.
+    async fn update_user_profile(
+        &self,
+        principal: &Principal,
+        firstname: &str,
+        lastname: &str,
+        gender: i32,
+        locale: i32,
+        context: &RequestContext,
+    ) -> Result<User, ArcaneVaultError>;
+
+    /// Sets the timezone of the user of `principal`, which must be a session.
+    async fn set_timezone(
+        &self,
+        principal: &Principal,
+        timezone: &str,
+        context: &RequestContext,
+    ) -> Result<(), ArcaneVaultError>;
+
+    /// Makes thumbnails of the image in `chunks` the avatar of the user of the session, raising
+    /// `PA034` when it is too large and `PA035` when it is no supported image. Returns the URLs of
+    /// the thumbnails, smallest first; the largest is the new `avatar`.
+    async fn upload_avatar(
+        &self,
+        principal: &Principal,
+        chunks: BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>,
+        context: &RequestContext,
+    ) -> Result<Vec<String>, ArcaneVaultError>;
+
+    /// The PNG thumbnail at `path` under the avatar base URL, `None` when there is none.
+    async fn get_avatar(&self, path: &str) -> Result<Option<Vec<u8>>, ArcaneVaultError>;
+
+    /// Raises `PA033` unless `principal` is an admin's session.
+    async fn query_users(
+        &self,
+        principal: &Principal,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<User>, ArcaneVaultError>;
+
+    /// Streams every user, reading from the database only as fast as the consumer polls. Raises
+    /// `PA033` unless `principal` is an admin's session.
+    async fn export_users(
+        &self,
+        principal: &Principal,
+    ) -> Result<BoxStream<'static, Result<User, ArcaneVaultError>>, ArcaneVaultError>;
+
 }
\ No newline at end of file
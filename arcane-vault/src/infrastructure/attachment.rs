@@ -0,0 +1,84 @@
+use std::{sync::Arc, time::Duration};
+
+use uuid::Uuid;
+
+use crate::{
+    domain::{error::ArcaneVaultError, service::BlobStore},
+    infrastructure::blob_store::create_blob_store,
+};
+
+/// Keeps the file of every row of `attachment_blobs` in the blob store under
+/// `attachments/<user id>/<sha256>.<blob id>`, so a blob collected and uploaded again gets a new
+/// file.
+pub struct AttachmentStore {
+    blob_store: Arc<dyn BlobStore>,
+    max_size: usize,
+    gc_grace_period: Duration,
+}
+
+impl AttachmentStore {
+    pub fn new() -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        Ok(Self {
+            blob_store: create_blob_store()?,
+            max_size: config
+                .get::<usize>("arcane-vault[0].attachment_max_size")
+                .unwrap_or(25 * 1024 * 1024),
+            gc_grace_period: Duration::from_secs(
+                config
+                    .get::<u64>("arcane-vault[0].attachment_gc_grace_secs")
+                    .unwrap_or(24 * 60 * 60),
+            ),
+        })
+    }
+
+    /// Largest file accepted as an attachment, in bytes.
+    pub fn get_max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// How long a blob no attachment refers to is kept, e.g. for an attachment deleted by mistake
+    /// to be uploaded again without storing the file twice.
+    pub fn get_gc_grace_period(&self) -> Duration {
+        self.gc_grace_period
+    }
+
+    pub async fn put_blob(
+        &self,
+        user_id: Uuid,
+        sha256: &str,
+        blob_id: Uuid,
+        data: Vec<u8>,
+    ) -> Result<(), ArcaneVaultError> {
+        self.blob_store
+            .put_blob(&get_attachment_key(user_id, sha256, blob_id), data)
+            .await
+    }
+
+    pub async fn get_blob(
+        &self,
+        user_id: Uuid,
+        sha256: &str,
+        blob_id: Uuid,
+    ) -> Result<Option<Vec<u8>>, ArcaneVaultError> {
+        self.blob_store
+            .get_blob(&get_attachment_key(user_id, sha256, blob_id))
+            .await
+    }
+
+    pub async fn delete_blob(
+        &self,
+        user_id: Uuid,
+        sha256: &str,
+        blob_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        self.blob_store
+            .delete_blob(&get_attachment_key(user_id, sha256, blob_id))
+            .await
+    }
+}
+
+fn get_attachment_key(user_id: Uuid, sha256: &str, blob_id: Uuid) -> String {
+    format!("attachments/{}/{}.{}", user_id, sha256, blob_id)
+}
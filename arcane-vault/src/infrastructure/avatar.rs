@@ -0,0 +1,146 @@
+use std::{collections::HashMap, io::Cursor, sync::Arc};
+
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits, imageops::FilterType};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        error::{ArcaneVaultError, ArcaneVaultErrorCode},
+        service::BlobStore,
+    },
+    infrastructure::{blob_store::create_blob_store, error_code::raise_error},
+};
+
+/// Sides in pixels of the square PNG thumbnails an avatar is stored as, smallest first.
+pub const AVATAR_SIZES: [u32; 3] = [64, 128, 256];
+/// Largest image accepted as an avatar, in bytes.
+pub const AVATAR_MAX_SIZE: usize = 5 * 1024 * 1024;
+// Checked before decoding, so a small file cannot take gigabytes once decoded.
+const AVATAR_MAX_DIMENSION: u32 = 8192;
+
+/// Keeps avatar thumbnails in the blob store under `avatars/<user id>/<version>/<size>.png`, served
+/// under `arcane-vault[0].avatar_base_url` with the same path. Every upload is a new version, so
+/// the URLs can be cached for good.
+pub struct AvatarStore {
+    blob_store: Arc<dyn BlobStore>,
+    base_url: String,
+}
+
+impl AvatarStore {
+    pub fn new() -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        let base_url = config
+            .get::<String>("arcane-vault[0].avatar_base_url")
+            .unwrap_or_else(|_| "http://localhost:8080/v1/avatars".to_string());
+        Ok(Self {
+            blob_store: create_blob_store()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Stores `thumbnails`, in the order of [`AVATAR_SIZES`], as a new version of the avatar of
+    /// `user_id` and returns their URLs.
+    pub async fn add_avatar(
+        &self,
+        user_id: Uuid,
+        thumbnails: Vec<Vec<u8>>,
+    ) -> Result<Vec<String>, ArcaneVaultError> {
+        let version = Uuid::new_v4();
+        let mut urls = Vec::with_capacity(thumbnails.len());
+        for (size, thumbnail) in AVATAR_SIZES.into_iter().zip(thumbnails) {
+            let path = format!("{}/{}/{}.png", user_id, version, size);
+            self.blob_store
+                .put_blob(&format!("avatars/{}", path), thumbnail)
+                .await?;
+            urls.push(format!("{}/{}", self.base_url, path));
+        }
+        Ok(urls)
+    }
+
+    /// Deletes the thumbnails of the version `avatar` is a URL of, when it is an avatar of
+    /// `user_id`; other URLs are left alone.
+    pub async fn delete_avatar(&self, user_id: Uuid, avatar: &str) -> Result<(), ArcaneVaultError> {
+        let Some((_, version, _)) = avatar
+            .strip_prefix(&self.base_url)
+            .and_then(|path| path.strip_prefix('/'))
+            .and_then(parse_avatar_path)
+            .filter(|(avatar_user_id, ..)| *avatar_user_id == user_id)
+        else {
+            return Ok(());
+        };
+        for size in AVATAR_SIZES {
+            self.blob_store
+                .delete_blob(&format!("avatars/{}/{}/{}.png", user_id, version, size))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// The PNG thumbnail at `path` under the base URL, `None` for paths of no thumbnail.
+    pub async fn get_avatar(&self, path: &str) -> Result<Option<Vec<u8>>, ArcaneVaultError> {
+        let Some((user_id, version, size)) = parse_avatar_path(path) else {
+            return Ok(None);
+        };
+        self.blob_store
+            .get_blob(&format!("avatars/{}/{}/{}.png", user_id, version, size))
+            .await
+    }
+}
+
+/// The thumbnails of `image` in the order of [`AVATAR_SIZES`], cropped to squares. Raises `PA035`
+/// unless `image` is a PNG, JPEG, GIF or WebP file, told by its content, of at most 8192 pixels
+/// per side. Takes a while, so it is for a blocking task.
+pub fn get_avatar_thumbnails(
+    function: &str,
+    image: &[u8],
+) -> Result<Vec<Vec<u8>>, ArcaneVaultError> {
+    let unsupported = || raise_error(function, "PA035", &[&AVATAR_MAX_DIMENSION.to_string()]);
+    let mut reader = ImageReader::new(Cursor::new(image))
+        .with_guessed_format()
+        .map_err(|_| unsupported())?;
+    if !matches!(
+        reader.format(),
+        Some(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP)
+    ) {
+        return Err(unsupported());
+    }
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(AVATAR_MAX_DIMENSION);
+    limits.max_image_height = Some(AVATAR_MAX_DIMENSION);
+    reader.limits(limits);
+    let mut decoder = reader.into_decoder().map_err(|_| unsupported())?;
+    let orientation = decoder.orientation().map_err(|_| unsupported())?;
+    // Only the pixels are encoded again, which drops EXIF data such as where a photo was taken;
+    // its orientation is applied first, so photos are not shown on their side.
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|_| unsupported())?;
+    image.apply_orientation(orientation);
+    AVATAR_SIZES
+        .into_iter()
+        .map(|size| {
+            let mut thumbnail = Cursor::new(Vec::new());
+            image
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .write_to(&mut thumbnail, ImageFormat::Png)
+                .map_err(|e| get_image_error(e.to_string()))?;
+            Ok(thumbnail.into_inner())
+        })
+        .collect()
+}
+
+// `<user id>/<version>/<size>.png`.
+fn parse_avatar_path(path: &str) -> Option<(Uuid, Uuid, u32)> {
+    let mut parts = path.split('/');
+    let user_id = Uuid::parse_str(parts.next()?).ok()?;
+    let version = Uuid::parse_str(parts.next()?).ok()?;
+    let size = parts.next()?.strip_suffix(".png")?.parse::<u32>().ok()?;
+    (parts.next().is_none() && AVATAR_SIZES.contains(&size)).then_some((user_id, version, size))
+}
+
+fn get_image_error(message: String) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message,
+        code: Some(ArcaneVaultErrorCode::InnerError("image error".into())),
+        metadata: HashMap::new(),
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use tokio_postgres::error::SqlState;
+
+use crate::domain::error::{ArcaneVaultError, ArcaneVaultErrorCode};
+
+// Rows of `error_codes` in `script/easynote_common.sql`, for the backends without the SQL functions:
+// code, parameter names and message template.
+const ERROR_CODES: &[(&str, &[&str], &str)] = &[
+    ("PA001", &["account"], "Account %s is unavailable."),
+    ("PA002", &["account"], "Account %s is not registered."),
+    ("PA003", &["account"], "Account %s was not found."),
+    ("PA004", &["account"], "Account %s is pending activation."),
+    ("PA005", &["account"], "Invalid password for account %s."),
+    ("PA006", &["account"], "User profile for account %s was not found."),
+    ("PA007", &["user_id"], "User ID %s not found."),
+    ("PA008", &["account"], "Verification code for account %s was recently generated. Please wait before requesting again."),
+    ("PA009", &[], "Invalid verification code."),
+    ("PA010", &[], "Verification code expired."),
+    ("PA011", &["account"], "No password reset request found for account %s."),
+    ("PA012", &["timezone"], "Timezone %s is invalid."),
+    ("PA013", &["account"], "Two-factor authentication is already enabled for account %s."),
+    ("PA014", &["account"], "Account %s has no two-factor enrollment to confirm."),
+    ("PA015", &["account"], "Two-factor authentication is not enabled for account %s."),
+    ("PA016", &[], "Invalid two-factor authentication code."),
+    ("PA017", &[], "The two-factor challenge is invalid or has expired."),
+    ("PA018", &[], "The refresh token is invalid or has expired."),
+    ("PA019", &["session_id"], "Session %s was not found."),
+    ("PA020", &[], "The access token is missing, invalid or has expired."),
+    ("PA021", &["scope"], "The access token does not grant the %s scope."),
+    ("PA022", &["scopes"], "Scopes \"%s\" are invalid."),
+    ("PA023", &["token_id"], "Personal access token %s was not found."),
+    ("PA024", &[], "This can only be done with a session access token."),
+    ("PA025", &[], "The expiry time must be in the future."),
+    ("PA026", &["provider"], "OpenID Connect provider %s is not configured."),
+    ("PA027", &[], "The OpenID Connect login is invalid or has expired."),
+    ("PA028", &["provider"], "The %s identity is already linked to another account."),
+    ("PA029", &["provider"], "The %s identity has no verified email address."),
+    ("PA030", &["provider", "subject"], "The %s identity %s is not linked to this account."),
+    ("PA031", &[], "The last sign-in method of an account without a password cannot be removed."),
+    ("PA032", &["account"], "No email address change request found for account %s."),
+    ("PA033", &[], "This can only be done by an administrator."),
+    ("PA034", &["max_size"], "The avatar must not be larger than %s bytes."),
+    ("PA035", &["max_dimension"], "The avatar must be a PNG, JPEG, GIF or WebP image of at most %s pixels per side."),
+    ("PA036", &[], "Too many invalid two-factor authentication codes. Please try again later."),
+    ("PA037", &[], "Please enter your password, or log in again."),
+    ("PN001", &["collection_name", "account"], "The category name %s\" already exists for user %s."),
+    ("PN002", &["collection_id", "account"], "Category %s does not belong to user %s."),
+    ("PN003", &["collection_name", "account"], "Collection %s of user %s cannot be deleted because it still contains notes."),
+    ("PN004", &["source_type"], "Source type %s is invalid."),
+    ("PN005", &["note_id"], "Note %s does not exist."),
+    ("PN006", &["attachment_id"], "Attachment %s does not exist."),
+    ("PN007", &["max_size"], "The attachment must not be larger than %s bytes."),
+    ("PN008", &["revision", "note_id"], "Revision %s of note %s does not exist."),
+];
+
+/// Locale of the `ERROR_CODES` templates, used when no requested locale has translations.
+pub const DEFAULT_ERROR_LOCALE: &str = "en-US";
+
+// Rows of `error_code_translations`: code, locale and message template.
+const ERROR_CODE_TRANSLATIONS: &[(&str, &str, &str)] = &[
+    ("PA001", "zh-CN", "账户 %s 不可用。"),
+    ("PA002", "zh-CN", "账户 %s 尚未注册。"),
+    ("PA003", "zh-CN", "未找到账户 %s。"),
+    ("PA004", "zh-CN", "账户 %s 正在等待激活。"),
+    ("PA005", "zh-CN", "账户 %s 的密码错误。"),
+    ("PA006", "zh-CN", "未找到账户 %s 的用户资料。"),
+    ("PA007", "zh-CN", "未找到用户 ID %s。"),
+    ("PA008", "zh-CN", "账户 %s 的验证码刚刚生成，请稍后再试。"),
+    ("PA009", "zh-CN", "验证码无效。"),
+    ("PA010", "zh-CN", "验证码已过期。"),
+    ("PA011", "zh-CN", "未找到账户 %s 的密码重置请求。"),
+    ("PA012", "zh-CN", "时区 %s 无效。"),
+    ("PA013", "zh-CN", "账户 %s 已启用两步验证。"),
+    ("PA014", "zh-CN", "账户 %s 没有待确认的两步验证。"),
+    ("PA015", "zh-CN", "账户 %s 未启用两步验证。"),
+    ("PA016", "zh-CN", "两步验证码无效。"),
+    ("PA017", "zh-CN", "两步验证请求无效或已过期。"),
+    ("PA018", "zh-CN", "刷新令牌无效或已过期。"),
+    ("PA019", "zh-CN", "未找到会话 %s。"),
+    ("PA020", "zh-CN", "访问令牌缺失、无效或已过期。"),
+    ("PA021", "zh-CN", "访问令牌未授予 %s 权限范围。"),
+    ("PA022", "zh-CN", "权限范围 \"%s\" 无效。"),
+    ("PA023", "zh-CN", "未找到个人访问令牌 %s。"),
+    ("PA024", "zh-CN", "只能使用会话访问令牌执行此操作。"),
+    ("PA025", "zh-CN", "过期时间必须晚于当前时间。"),
+    ("PA026", "zh-CN", "未配置 OpenID Connect 提供方 %s。"),
+    ("PA027", "zh-CN", "OpenID Connect 登录无效或已过期。"),
+    ("PA028", "zh-CN", "该 %s 身份已关联到其他账户。"),
+    ("PA029", "zh-CN", "该 %s 身份没有已验证的电子邮件地址。"),
+    ("PA030", "zh-CN", "%s 身份 %s 未关联到此账户。"),
+    ("PA031", "zh-CN", "无法移除没有密码的账户的最后一种登录方式。"),
+    ("PA032", "zh-CN", "未找到账户 %s 的邮箱地址变更请求。"),
+    ("PA033", "zh-CN", "只有管理员才能执行此操作。"),
+    ("PA034", "zh-CN", "头像不能大于 %s 字节。"),
+    ("PA035", "zh-CN", "头像必须是每边不超过 %s 像素的 PNG、JPEG、GIF 或 WebP 图片。"),
+    ("PA036", "zh-CN", "两步验证码错误次数过多，请稍后再试。"),
+    ("PA037", "zh-CN", "请输入密码，或重新登录。"),
+    ("PN001", "zh-CN", "分类名称 %s 已被用户 %s 使用。"),
+    ("PN002", "zh-CN", "分类 %s 不属于用户 %s。"),
+    ("PN003", "zh-CN", "集合 %s（用户 %s）仍包含笔记，无法删除。"),
+    ("PN004", "zh-CN", "来源类型 %s 无效。"),
+    ("PN005", "zh-CN", "笔记 %s 不存在。"),
+    ("PN006", "zh-CN", "附件 %s 不存在。"),
+    ("PN007", "zh-CN", "附件不能大于 %s 字节。"),
+    ("PN008", "zh-CN", "版本 %s（笔记 %s）不存在。"),
+];
+
+/// Same error as `util_raise_error`: the formatted message, with the code as error code and
+/// the arguments by parameter name.
+pub(crate) fn raise_error(function: &str, code: &str, args: &[&str]) -> ArcaneVaultError {
+    let (param_names, template) = get_error_code(code).unwrap_or_default();
+    let metadata = param_names
+        .iter()
+        .zip(args)
+        .map(|(name, arg)| (name.to_string(), arg.to_string()))
+        .collect();
+    ArcaneVaultError {
+        message: format!("Error in function {}: {}", function, format_template(template, args)),
+        code: Some(ArcaneVaultErrorCode::InnerError(code.to_string())),
+        metadata,
+    }
+}
+
+/// The message of a PA/PN error in `locale` without the function name, from the arguments
+/// in `metadata`.
+pub(crate) fn get_error_message(
+    code: &str,
+    locale: &str,
+    metadata: &HashMap<String, String>,
+) -> Option<String> {
+    let (param_names, template) = get_error_code(code)?;
+    let template = ERROR_CODE_TRANSLATIONS
+        .iter()
+        .find(|(errcode, translation_locale, _)| *errcode == code && *translation_locale == locale)
+        .map_or(template, |(.., translation)| *translation);
+    let args: Vec<&str> = param_names
+        .iter()
+        .map(|name| metadata.get(*name).map_or("", String::as_str))
+        .collect();
+    Some(format_template(template, &args))
+}
+
+/// The locale with error messages for `language_range`, e.g. `zh-CN` for `zh-CN`, `zh` or
+/// `zh-TW`.
+pub fn get_error_locale(language_range: &str) -> Option<&'static str> {
+    let locales = || {
+        std::iter::once(DEFAULT_ERROR_LOCALE)
+            .chain(ERROR_CODE_TRANSLATIONS.iter().map(|(_, locale, _)| *locale))
+    };
+    let get_language = |tag: &str| tag.split('-').next().unwrap_or_default().to_ascii_lowercase();
+    locales()
+        .find(|locale| locale.eq_ignore_ascii_case(language_range))
+        .or_else(|| locales().find(|locale| get_language(locale) == get_language(language_range)))
+}
+
+fn get_error_code(code: &str) -> Option<(&'static [&'static str], &'static str)> {
+    ERROR_CODES
+        .iter()
+        .find(|(errcode, ..)| *errcode == code)
+        .map(|(_, param_names, template)| (*param_names, *template))
+}
+
+fn format_template(template: &str, args: &[&str]) -> String {
+    let mut message = template.to_string();
+    for arg in args {
+        message = message.replacen("%s", arg, 1);
+    }
+    message
+}
+
+/// Same error code as Postgres raises for `state`, e.g. on a foreign key violation.
+pub(crate) fn get_sql_error(state: SqlState, message: &str) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message: message.to_string(),
+        code: Some(ArcaneVaultErrorCode::InnerError(format!("{:?}", state))),
+        metadata: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = include_str!("../../../script/easynote_common.sql");
+
+    // The rows of the first `INSERT INTO <table>` of the script, one per line, as their values.
+    fn get_seed_rows(table: &str) -> Vec<Vec<String>> {
+        let insert = format!("INSERT INTO {} ", table);
+        let mut rows = Vec::new();
+        for line in SCRIPT
+            .lines()
+            .map(str::trim_end)
+            .skip_while(|line| !line.starts_with(&insert))
+            .skip(1)
+            .filter(|line| !line.is_empty() && !line.starts_with("--"))
+        {
+            let values = line
+                .strip_prefix("('")
+                .and_then(|line| {
+                    line.strip_suffix("'),")
+                        .or_else(|| line.strip_suffix("');"))
+                })
+                .unwrap_or_else(|| panic!("unexpected row of {}: {}", table, line));
+            rows.push(values.split("', '").map(str::to_string).collect());
+            if line.ends_with(';') {
+                break;
+            }
+        }
+        rows
+    }
+
+    fn get_metadata(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn matches_the_error_codes_of_the_script() {
+        let rows: Vec<Vec<String>> = ERROR_CODES
+            .iter()
+            .map(|(code, param_names, template)| {
+                vec![
+                    code.to_string(),
+                    format!("{{{}}}", param_names.join(",")),
+                    template.to_string(),
+                ]
+            })
+            .collect();
+        assert_eq!(rows, get_seed_rows("error_codes"));
+    }
+
+    #[test]
+    fn matches_the_translations_of_the_script() {
+        let rows: Vec<Vec<String>> = ERROR_CODE_TRANSLATIONS
+            .iter()
+            .map(|(code, locale, template)| {
+                vec![code.to_string(), locale.to_string(), template.to_string()]
+            })
+            .collect();
+        assert_eq!(rows, get_seed_rows("error_code_translations"));
+    }
+
+    #[test]
+    fn fills_in_the_template_of_the_locale() {
+        let metadata = get_metadata(&[("revision", "3"), ("note_id", "n-1")]);
+        let account = get_metadata(&[("account", "ada@example.com")]);
+
+        assert_eq!(
+            get_error_message("PN008", "en-US", &metadata).as_deref(),
+            Some("Revision 3 of note n-1 does not exist.")
+        );
+        assert_eq!(
+            get_error_message("PA001", "zh-CN", &account).as_deref(),
+            Some("账户 ada@example.com 不可用。")
+        );
+        // Locales without translations get the default template.
+        assert_eq!(
+            get_error_message("PA001", "fr-FR", &account).as_deref(),
+            Some("Account ada@example.com is unavailable.")
+        );
+        assert_eq!(get_error_message("PX999", "en-US", &metadata), None);
+    }
+
+    #[test]
+    fn leaves_missing_arguments_empty() {
+        assert_eq!(
+            get_error_message("PA001", "en-US", &HashMap::new()).as_deref(),
+            Some("Account  is unavailable.")
+        );
+    }
+
+    #[test]
+    fn picks_the_locale_of_a_language_range() {
+        assert_eq!(get_error_locale("zh-CN"), Some("zh-CN"));
+        assert_eq!(get_error_locale("zh-cn"), Some("zh-CN"));
+        assert_eq!(get_error_locale("zh"), Some("zh-CN"));
+        assert_eq!(get_error_locale("zh-TW"), Some("zh-CN"));
+        assert_eq!(get_error_locale("en-GB"), Some("en-US"));
+        assert_eq!(get_error_locale("fr"), None);
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{AttachmentBlobEntity, AttachmentEntity},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        memory::store::{MemoryStore, StoredAttachment, StoredAttachmentBlob},
+    },
+};
+
+/// [`crate::domain::repository::AttachmentRepository`] kept in process memory, for tests and demos.
+pub struct MemoryAttachmentRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryAttachmentRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::AttachmentRepository for MemoryAttachmentRepository {
+    async fn add_attachment(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        sha256: &str,
+        size: i64,
+        file_name: &str,
+        content_type: &str,
+    ) -> Result<AttachmentEntity, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.check_note_owner("func_add_attachment", user_id, note_id)?;
+
+        let existing_blob_id = store
+            .attachment_blobs
+            .iter()
+            .find(|(_, blob)| blob.user_id == user_id && blob.sha256 == sha256)
+            .map(|(blob_id, _)| *blob_id);
+        let blob_id = match existing_blob_id {
+            Some(blob_id) => blob_id,
+            None => {
+                let blob_id = Uuid::new_v4();
+                store.attachment_blobs.insert(
+                    blob_id,
+                    StoredAttachmentBlob {
+                        user_id,
+                        sha256: sha256.to_string(),
+                        size,
+                        ref_count: 0,
+                        is_stored: false,
+                        orphaned_at: None,
+                    },
+                );
+                blob_id
+            }
+        };
+        let blob = store.attachment_blobs.get_mut(&blob_id).unwrap();
+        blob.ref_count += 1;
+        blob.orphaned_at = None;
+
+        let id = Uuid::new_v4();
+        store.note_attachments.insert(
+            id,
+            StoredAttachment {
+                note_id,
+                blob_id,
+                file_name: file_name.to_string(),
+                content_type: content_type.to_string(),
+                created_at: SystemTime::now(),
+            },
+        );
+        Ok(get_attachment_entity(&store, id).unwrap())
+    }
+
+    async fn set_attachment_blob_stored(&self, blob_id: Uuid) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        if let Some(blob) = store.attachment_blobs.get_mut(&blob_id) {
+            blob.is_stored = true;
+        }
+        Ok(())
+    }
+
+    async fn query_attachments(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+    ) -> Result<Vec<AttachmentEntity>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        store.check_note_owner("func_query_attachments", user_id, note_id)?;
+        let mut attachments = store
+            .note_attachments
+            .iter()
+            .filter(|(_, attachment)| attachment.note_id == note_id)
+            .filter_map(|(id, _)| get_attachment_entity(&store, *id))
+            .collect::<Vec<_>>();
+        attachments.sort_by_key(|attachment| (attachment.created_at, attachment.id));
+        Ok(attachments)
+    }
+
+    async fn query_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<AttachmentEntity, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        get_user_attachment_entity(&store, "func_query_attachment", user_id, attachment_id)
+    }
+
+    async fn delete_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        get_user_attachment_entity(&store, "func_delete_attachment", user_id, attachment_id)?;
+        store.remove_attachments(|id, _| id == attachment_id);
+        Ok(())
+    }
+
+    async fn collect_attachment_blobs(
+        &self,
+        orphaned_before: SystemTime,
+        limit: i32,
+    ) -> Result<Vec<AttachmentBlobEntity>, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let blob_ids = store
+            .attachment_blobs
+            .iter()
+            .filter(|(_, blob)| {
+                blob.ref_count == 0
+                    && blob
+                        .orphaned_at
+                        .is_some_and(|orphaned_at| orphaned_at < orphaned_before)
+            })
+            .map(|(blob_id, _)| *blob_id)
+            .take(limit.max(0) as usize)
+            .collect::<Vec<_>>();
+        Ok(blob_ids
+            .into_iter()
+            .filter_map(|blob_id| {
+                let blob = store.attachment_blobs.remove(&blob_id)?;
+                Some(AttachmentBlobEntity {
+                    id: blob_id,
+                    user_id: blob.user_id,
+                    sha256: blob.sha256,
+                })
+            })
+            .collect())
+    }
+}
+
+fn get_attachment_entity(store: &MemoryStore, id: Uuid) -> Option<AttachmentEntity> {
+    let attachment = store.note_attachments.get(&id)?;
+    let blob = store.attachment_blobs.get(&attachment.blob_id)?;
+    Some(AttachmentEntity {
+        id,
+        note_id: attachment.note_id,
+        blob_id: attachment.blob_id,
+        sha256: blob.sha256.clone(),
+        size: blob.size,
+        file_name: attachment.file_name.clone(),
+        content_type: attachment.content_type.clone(),
+        created_at: attachment.created_at,
+        is_stored: blob.is_stored,
+    })
+}
+
+// Attachments of other users raise `PN006` as if they did not exist.
+fn get_user_attachment_entity(
+    store: &MemoryStore,
+    function: &str,
+    user_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<AttachmentEntity, ArcaneVaultError> {
+    get_attachment_entity(store, attachment_id)
+        .filter(|attachment| {
+            store
+                .attachment_blobs
+                .get(&attachment.blob_id)
+                .is_some_and(|blob| blob.user_id == user_id)
+        })
+        .ok_or_else(|| raise_error(function, "PN006", &[&attachment_id.to_string()]))
+}
@@ -0,0 +1,72 @@
+use std::sync::{Arc, Mutex};
+
+use ethereal_core::proto::AuditEvent;
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::NewAuditEvent, error::ArcaneVaultError},
+    infrastructure::{
+        memory::store::{MemoryStore, StoredAuditEvent},
+        rule::check_page,
+    },
+};
+
+/// [`crate::domain::repository::AuditRepository`] kept in process memory, following the audit
+/// functions of the SQL script.
+pub struct MemoryAuditRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryAuditRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::AuditRepository for MemoryAuditRepository {
+    async fn add_audit_event(&self, event: &NewAuditEvent) -> Result<(), ArcaneVaultError> {
+        self.store.lock().unwrap().add_audit_event(event);
+        Ok(())
+    }
+
+    async fn query_audit_events(
+        &self,
+        user_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<AuditEvent>, ArcaneVaultError> {
+        check_page(offset, limit)?;
+        let store = self.store.lock().unwrap();
+        let account = store.get_account("func_query_audit_events", user_id)?;
+        Ok(store
+            .audit_events
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, stored)| match stored.event.user_id {
+                Some(id) => id == user_id,
+                None => stored.event.account == account,
+            })
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|(index, stored)| get_audit_event(index as i64 + 1, stored))
+            .collect())
+    }
+}
+
+fn get_audit_event(id: i64, stored: &StoredAuditEvent) -> AuditEvent {
+    let event = &stored.event;
+    AuditEvent {
+        id,
+        user_id: event.user_id.map(|id| id.to_string()).unwrap_or_default(),
+        actor_id: event.actor_id.map(|id| id.to_string()).unwrap_or_default(),
+        event_type: event.event_type,
+        account: event.account.clone(),
+        details: event.details.clone(),
+        ip_address: event.ip_address.clone(),
+        user_agent: event.user_agent.clone(),
+        request_id: event.request_id.clone(),
+        created_at: Some(stored.created_at.into()),
+    }
+}
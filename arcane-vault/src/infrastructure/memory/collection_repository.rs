@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::CollectionEntity, error::ArcaneVaultError},
+    infrastructure::{
+        error_code::raise_error,
+        memory::store::MemoryStore,
+        rule::{DEFAULT_COLLECTION_DESCRIPTION, DEFAULT_COLLECTION_NAME},
+    },
+};
+
+/// [`crate::domain::repository::CollectionRepository`] kept in process memory, for tests and demos.
+pub struct MemoryCollectionRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryCollectionRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::CollectionRepository for MemoryCollectionRepository {
+    async fn add_collection(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        description: &str,
+        is_active: bool,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        add_collection(&mut store, user_id, name, description, is_active)
+    }
+
+    async fn change_default_collection(
+        &self,
+        user_id: Uuid,
+        collection_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_change_default_collection";
+        let mut store = self.store.lock().unwrap();
+        let account = store.get_account(FUNCTION, user_id)?;
+        if store
+            .collections
+            .get(&collection_id)
+            .is_none_or(|collection| collection.user_id != user_id)
+        {
+            return Err(raise_error(
+                FUNCTION,
+                "PN002",
+                &[&collection_id.to_string(), &account],
+            ));
+        }
+
+        for collection in store
+            .collections
+            .values_mut()
+            .filter(|collection| collection.user_id == user_id)
+        {
+            collection.is_active = collection.id == collection_id;
+        }
+        Ok(())
+    }
+
+    async fn get_default_collection(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.get_account("func_get_default_collection", user_id)?;
+        if let Some(collection) = store
+            .collections
+            .values()
+            .find(|collection| collection.user_id == user_id && collection.is_active)
+        {
+            return Ok(collection.id);
+        }
+        add_collection(
+            &mut store,
+            user_id,
+            DEFAULT_COLLECTION_NAME,
+            DEFAULT_COLLECTION_DESCRIPTION,
+            true,
+        )
+    }
+}
+
+fn add_collection(
+    store: &mut MemoryStore,
+    user_id: Uuid,
+    name: &str,
+    description: &str,
+    is_active: bool,
+) -> Result<Uuid, ArcaneVaultError> {
+    const FUNCTION: &str = "func_add_collection";
+    let account = store.get_account(FUNCTION, user_id)?;
+    let user_collections = || {
+        store
+            .collections
+            .values()
+            .filter(|collection| collection.user_id == user_id)
+    };
+    if user_collections().any(|collection| collection.name == name) {
+        return Err(raise_error(FUNCTION, "PN001", &[name, &account]));
+    }
+    // The first collection of a user always becomes the default.
+    let is_active = is_active || !user_collections().any(|collection| collection.is_active);
+
+    if is_active {
+        for collection in store
+            .collections
+            .values_mut()
+            .filter(|collection| collection.user_id == user_id)
+        {
+            collection.is_active = false;
+        }
+    }
+    let id = Uuid::new_v4();
+    store.collections.insert(
+        id,
+        CollectionEntity {
+            id,
+            user_id,
+            name: name.to_string(),
+            description: description.to_string(),
+            is_active,
+            meta: serde_json::json!({}),
+        },
+    );
+    Ok(id)
+}
@@ -0,0 +1,92 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::NoteEntity, error::ArcaneVaultError},
+    infrastructure::{
+        error_code::{get_sql_error, raise_error},
+        memory::store::{MemoryStore, NOTE_SOURCE_TYPES},
+    },
+};
+
+/// [`crate::domain::repository::NoteRepository`] kept in process memory, for tests and demos.
+pub struct MemoryNoteRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryNoteRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::NoteRepository for MemoryNoteRepository {
+    async fn add_note(
+        &self,
+        content: &str,
+        collection_id: Uuid,
+        source_type: i32,
+        meta: Option<serde_json::Value>,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        if !NOTE_SOURCE_TYPES.iter().any(|(id, ..)| *id == source_type) {
+            return Err(raise_error("func_add_note", "PN004", &[&source_type.to_string()]));
+        }
+        if !store.collections.contains_key(&collection_id) {
+            return Err(get_sql_error(
+                SqlState::FOREIGN_KEY_VIOLATION,
+                &format!("collection {} is not present in table \"collections\"", collection_id),
+            ));
+        }
+
+        let id = Uuid::new_v4();
+        let now = SystemTime::now();
+        store.notes.insert(
+            id,
+            NoteEntity {
+                id,
+                collection_id,
+                created_at: now,
+                updated_at: now,
+                content: content.to_string(),
+                source_type,
+                meta: meta.unwrap_or_else(|| serde_json::json!({})),
+                revision: 1,
+            },
+        );
+        Ok(id)
+    }
+
+    async fn update_note_meta(
+        &self,
+        note_id: Uuid,
+        meta: serde_json::Value,
+    ) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let content = store
+            .notes
+            .get(&note_id)
+            .map(|note| note.content.clone())
+            .ok_or_else(|| raise_error("func_update_note_meta", "PN005", &[&note_id.to_string()]))?;
+        store.update_note(note_id, content, meta);
+        Ok(())
+    }
+
+    async fn delete_note(&self, note_id: Uuid) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store
+            .notes
+            .remove(&note_id)
+            .ok_or_else(|| raise_error("func_delete_note", "PN005", &[&note_id.to_string()]))?;
+        // `ON DELETE CASCADE` of `note_attachments` and `note_revisions`.
+        store.remove_attachments(|_, attachment| attachment.note_id == note_id);
+        store.note_revisions.retain(|(id, _), _| *id != note_id);
+        Ok(())
+    }
+}
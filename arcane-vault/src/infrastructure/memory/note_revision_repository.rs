@@ -0,0 +1,146 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::NoteRevisionEntity, error::ArcaneVaultError},
+    infrastructure::{error_code::raise_error, memory::store::MemoryStore},
+};
+
+/// [`crate::domain::repository::NoteRevisionRepository`] kept in process memory, for tests and
+/// demos.
+pub struct MemoryNoteRevisionRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryNoteRevisionRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::NoteRevisionRepository for MemoryNoteRevisionRepository {
+    async fn query_note_revisions(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<NoteRevisionEntity>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        store.check_note_owner("func_query_note_revisions", user_id, note_id)?;
+        Ok(get_note_revision_entities(&store, note_id)
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn query_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<NoteRevisionEntity, ArcaneVaultError> {
+        const FUNCTION: &str = "func_query_note_revision";
+        let store = self.store.lock().unwrap();
+        store.check_note_owner(FUNCTION, user_id, note_id)?;
+        get_note_revision_entities(&store, note_id)
+            .into_iter()
+            .find(|entity| entity.revision == revision)
+            .ok_or_else(|| {
+                raise_error(
+                    FUNCTION,
+                    "PN008",
+                    &[&revision.to_string(), &note_id.to_string()],
+                )
+            })
+    }
+
+    async fn restore_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<i32, ArcaneVaultError> {
+        const FUNCTION: &str = "func_restore_note_revision";
+        let mut store = self.store.lock().unwrap();
+        store.check_note_owner(FUNCTION, user_id, note_id)?;
+        let Some(stored) = store.note_revisions.get(&(note_id, revision)) else {
+            // Restoring the latest revision changes nothing.
+            return match store.notes.get(&note_id) {
+                Some(note) if note.revision == revision => Ok(revision),
+                _ => Err(raise_error(
+                    FUNCTION,
+                    "PN008",
+                    &[&revision.to_string(), &note_id.to_string()],
+                )),
+            };
+        };
+        let (content, meta) = (stored.content.clone(), stored.meta.clone());
+        store
+            .update_note(note_id, content, meta)
+            .ok_or_else(|| raise_error(FUNCTION, "PN005", &[&note_id.to_string()]))
+    }
+
+    async fn prune_note_revisions(
+        &self,
+        keep_count: Option<i32>,
+        keep_after: Option<SystemTime>,
+    ) -> Result<i32, ArcaneVaultError> {
+        if keep_count.is_none() && keep_after.is_none() {
+            return Ok(0);
+        }
+        let mut store = self.store.lock().unwrap();
+        let mut pruned = Vec::new();
+        let mut last_note_id = None;
+        let mut rank = 0;
+        // Newest first per note, like `row_number()` in `func_prune_note_revisions`.
+        for (&(note_id, revision), stored) in store.note_revisions.iter().rev() {
+            if last_note_id != Some(note_id) {
+                last_note_id = Some(note_id);
+                rank = 0;
+            }
+            rank += 1;
+            if keep_count.is_none_or(|keep_count| rank > keep_count)
+                && keep_after.is_none_or(|keep_after| stored.replaced_at < keep_after)
+            {
+                pruned.push((note_id, revision));
+            }
+        }
+        for key in &pruned {
+            store.note_revisions.remove(key);
+        }
+        Ok(pruned.len() as i32)
+    }
+}
+
+// Newest first, starting with the note itself.
+fn get_note_revision_entities(store: &MemoryStore, note_id: Uuid) -> Vec<NoteRevisionEntity> {
+    let Some(note) = store.notes.get(&note_id) else {
+        return Vec::new();
+    };
+    let current = NoteRevisionEntity {
+        note_id,
+        revision: note.revision,
+        content: note.content.clone(),
+        meta: note.meta.clone(),
+        created_at: note.updated_at,
+    };
+    let earlier = store
+        .note_revisions
+        .range((note_id, i32::MIN)..=(note_id, i32::MAX))
+        .rev()
+        .map(|(&(note_id, revision), stored)| NoteRevisionEntity {
+            note_id,
+            revision,
+            content: stored.content.clone(),
+            meta: stored.meta.clone(),
+            created_at: stored.created_at,
+        });
+    std::iter::once(current).chain(earlier).collect()
+}
@@ -0,0 +1,255 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use ethereal_core::proto::ExternalIdentity;
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::OidcLoginAttemptEntity, error::ArcaneVaultError},
+    infrastructure::{
+        error_code::raise_error,
+        memory::store::{
+            LOCALES, MemoryStore, OidcLoginAttempt, StoredUser, StoredUserIdentity, TIMEZONES,
+            is_within,
+        },
+        rule::{
+            DEFAULT_TIMEZONE, GENDER_UNKNOWN, NO_PASSWORD, OIDC_LOGIN_ATTEMPT_EXPIRY,
+            USER_ROLE_USER, USER_STATUS_ACTIVE, find_locale,
+        },
+    },
+};
+
+/// [`crate::domain::repository::OidcRepository`] kept in process memory, following the OIDC
+/// functions of the SQL script.
+pub struct MemoryOidcRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryOidcRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::OidcRepository for MemoryOidcRepository {
+    async fn add_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+        provider: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        if let Some(user_id) = user_id {
+            store.get_account("func_add_oidc_login_attempt", user_id)?;
+        }
+
+        // What the SQL cleanup job does for `oidc_login_attempts`, as soon as they expire.
+        store
+            .oidc_login_attempts
+            .retain(|_, attempt| is_within(attempt.created_at, OIDC_LOGIN_ATTEMPT_EXPIRY));
+        store.oidc_login_attempts.insert(
+            state_hash.to_string(),
+            OidcLoginAttempt {
+                provider: provider.to_string(),
+                pkce_verifier: pkce_verifier.to_string(),
+                nonce: nonce.to_string(),
+                user_id,
+                created_at: SystemTime::now(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn use_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+    ) -> Result<Option<OidcLoginAttemptEntity>, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        Ok(store
+            .oidc_login_attempts
+            .remove(state_hash)
+            .filter(|attempt| is_within(attempt.created_at, OIDC_LOGIN_ATTEMPT_EXPIRY))
+            .map(|attempt| OidcLoginAttemptEntity {
+                provider: attempt.provider,
+                pkce_verifier: attempt.pkce_verifier,
+                nonce: attempt.nonce,
+                user_id: attempt.user_id,
+            }))
+    }
+
+    async fn login_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let now = SystemTime::now();
+        let key = (provider.to_string(), subject.to_string());
+        let Some(identity) = store.user_identities.get_mut(&key) else {
+            return Ok(None);
+        };
+        identity.email = email.to_string();
+        identity.last_login_at = Some(now);
+        let user_id = identity.user_id;
+        if let Some(user) = store.users.get_mut(&user_id) {
+            user.last_login_at = Some(now);
+        }
+        Ok(Some(user_id))
+    }
+
+    async fn register_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+        firstname: &str,
+        lastname: &str,
+        locale: &str,
+        timezone: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        if store.pending_users.contains_key(email) || store.find_user_id(email).is_some() {
+            return Err(raise_error(
+                "func_register_user_identity",
+                "PA001",
+                &[email],
+            ));
+        }
+
+        let id = Uuid::new_v4();
+        let now = SystemTime::now();
+        let locales = LOCALES
+            .iter()
+            .map(|(id, language_code, locale_code, ..)| (*id, *language_code, *locale_code));
+        let timezone = if TIMEZONES.contains(&timezone) {
+            timezone
+        } else {
+            DEFAULT_TIMEZONE
+        };
+        store.users.insert(
+            id,
+            StoredUser {
+                id,
+                account: email.to_string(),
+                password_hash: NO_PASSWORD.to_string(),
+                created_at: now,
+                updated_at: now,
+                last_login_at: Some(now),
+                status: USER_STATUS_ACTIVE,
+                role: USER_ROLE_USER,
+                firstname: firstname.to_string(),
+                lastname: lastname.to_string(),
+                gender: GENDER_UNKNOWN,
+                locale: find_locale(locales, locale),
+                timezone: timezone.to_string(),
+                avatar: String::new(),
+                signature: String::new(),
+            },
+        );
+        store.user_identities.insert(
+            (provider.to_string(), subject.to_string()),
+            StoredUserIdentity {
+                user_id: id,
+                email: email.to_string(),
+                created_at: now,
+                last_login_at: Some(now),
+            },
+        );
+        Ok(id)
+    }
+
+    async fn link_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<ExternalIdentity, ArcaneVaultError> {
+        const FUNCTION: &str = "func_link_user_identity";
+        let mut store = self.store.lock().unwrap();
+        store.get_account(FUNCTION, user_id)?;
+        let key = (provider.to_string(), subject.to_string());
+        let identity = store
+            .user_identities
+            .entry(key)
+            .or_insert_with(|| StoredUserIdentity {
+                user_id,
+                email: String::new(),
+                created_at: SystemTime::now(),
+                last_login_at: None,
+            });
+        if identity.user_id != user_id {
+            return Err(raise_error(FUNCTION, "PA028", &[provider]));
+        }
+        identity.email = email.to_string();
+        Ok(get_external_identity(provider, subject, identity))
+    }
+
+    async fn query_user_identities(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ExternalIdentity>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        let mut identities: Vec<_> = store
+            .user_identities
+            .iter()
+            .filter(|(_, identity)| identity.user_id == user_id)
+            .collect();
+        identities.sort_by_key(|(_, identity)| identity.created_at);
+        Ok(identities
+            .into_iter()
+            .map(|((provider, subject), identity)| {
+                get_external_identity(provider, subject, identity)
+            })
+            .collect())
+    }
+
+    async fn unlink_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_unlink_user_identity";
+        let mut store = self.store.lock().unwrap();
+        let has_password = store.get_user_mut(FUNCTION, user_id)?.password_hash != NO_PASSWORD;
+        let key = (provider.to_string(), subject.to_string());
+        if store
+            .user_identities
+            .get(&key)
+            .is_none_or(|identity| identity.user_id != user_id)
+        {
+            return Err(raise_error(FUNCTION, "PA030", &[provider, subject]));
+        }
+        let identity_count = store
+            .user_identities
+            .values()
+            .filter(|identity| identity.user_id == user_id)
+            .count();
+        if !has_password && identity_count == 1 {
+            return Err(raise_error(FUNCTION, "PA031", &[]));
+        }
+        store.user_identities.remove(&key);
+        Ok(())
+    }
+}
+
+fn get_external_identity(
+    provider: &str,
+    subject: &str,
+    identity: &StoredUserIdentity,
+) -> ExternalIdentity {
+    ExternalIdentity {
+        provider: provider.to_string(),
+        subject: subject.to_string(),
+        email: identity.email.clone(),
+        created_at: Some(identity.created_at.into()),
+        last_login_at: identity.last_login_at.map(Into::into),
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use ethereal_core::proto::PersonalAccessToken;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{Credential, Principal},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        memory::store::{MemoryStore, StoredPersonalAccessToken, is_within},
+        rule::ENDED_PERSONAL_ACCESS_TOKEN_RETENTION,
+    },
+};
+
+/// [`crate::domain::repository::PersonalAccessTokenRepository`] kept in process memory,
+/// following the personal access token functions of the SQL script.
+pub struct MemoryPersonalAccessTokenRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryPersonalAccessTokenRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::PersonalAccessTokenRepository
+    for MemoryPersonalAccessTokenRepository
+{
+    async fn add_personal_access_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        token_hash: &str,
+        scopes: &[String],
+        expires_at: Option<SystemTime>,
+    ) -> Result<PersonalAccessToken, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.get_account("func_add_personal_access_token", user_id)?;
+
+        // What the SQL cleanup job does for `personal_access_tokens`.
+        let now = SystemTime::now();
+        store.personal_access_tokens.retain(|_, token| {
+            token
+                .revoked_at
+                .or(token.expires_at)
+                .is_none_or(|ended_at| {
+                    ended_at > now || is_within(ended_at, ENDED_PERSONAL_ACCESS_TOKEN_RETENTION)
+                })
+        });
+
+        let id = Uuid::new_v4();
+        let token = StoredPersonalAccessToken {
+            user_id,
+            name: name.to_string(),
+            token_hash: token_hash.to_string(),
+            scopes: scopes.to_vec(),
+            created_at: now,
+            expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        };
+        let personal_access_token = get_personal_access_token(id, &token);
+        store.personal_access_tokens.insert(id, token);
+        Ok(personal_access_token)
+    }
+
+    async fn query_personal_access_tokens(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<PersonalAccessToken>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        let now = SystemTime::now();
+        let mut tokens: Vec<_> = store
+            .personal_access_tokens
+            .iter()
+            .filter(|(_, token)| token.user_id == user_id && token.is_active(now))
+            .collect();
+        tokens.sort_by_key(|(_, token)| std::cmp::Reverse(token.created_at));
+        Ok(tokens
+            .into_iter()
+            .map(|(id, token)| get_personal_access_token(*id, token))
+            .collect())
+    }
+
+    async fn revoke_personal_access_token(
+        &self,
+        user_id: Uuid,
+        token_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_revoke_personal_access_token";
+        let mut store = self.store.lock().unwrap();
+        store.get_account(FUNCTION, user_id)?;
+        let now = SystemTime::now();
+        match store.personal_access_tokens.get_mut(&token_id) {
+            Some(token) if token.user_id == user_id && token.is_active(now) => {
+                token.revoked_at = Some(now);
+                Ok(())
+            }
+            _ => Err(raise_error(FUNCTION, "PA023", &[&token_id.to_string()])),
+        }
+    }
+
+    async fn use_personal_access_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let now = SystemTime::now();
+        Ok(store
+            .personal_access_tokens
+            .iter_mut()
+            .find(|(_, token)| token.token_hash == token_hash && token.is_active(now))
+            .map(|(id, token)| {
+                token.last_used_at = Some(now);
+                Principal {
+                    user_id: token.user_id,
+                    credential: Credential::PersonalAccessToken {
+                        token_id: *id,
+                        scopes: token.scopes.clone(),
+                    },
+                }
+            }))
+    }
+}
+
+fn get_personal_access_token(id: Uuid, token: &StoredPersonalAccessToken) -> PersonalAccessToken {
+    PersonalAccessToken {
+        id: id.to_string(),
+        name: token.name.clone(),
+        scopes: token.scopes.clone(),
+        created_at: Some(token.created_at.into()),
+        expires_at: token.expires_at.map(Into::into),
+        last_used_at: token.last_used_at.map(Into::into),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        domain::{
+            entity::{NOTES_READ_SCOPE, NewUser},
+            repository::{PersonalAccessTokenRepository, UserRepository},
+        },
+        infrastructure::memory::MemoryUserRepository,
+    };
+
+    async fn create_repository() -> (MemoryPersonalAccessTokenRepository, Uuid) {
+        let store = MemoryStore::new();
+        let users = MemoryUserRepository::new(store.clone());
+        let user = NewUser {
+            account: "ada@example.com".into(),
+            locale: 1,
+            timezone: "UTC".into(),
+            ..Default::default()
+        };
+        let verification_code = users.register_user(&user, "hash").await.unwrap();
+        let user_id = users
+            .verify_user(&user.account, "hash", &verification_code)
+            .await
+            .unwrap();
+        (MemoryPersonalAccessTokenRepository::new(store), user_id)
+    }
+
+    #[tokio::test]
+    async fn authenticates_tokens_with_their_scopes() {
+        let (repository, user_id) = create_repository().await;
+        let scopes = vec![NOTES_READ_SCOPE.to_string()];
+        let token = repository
+            .add_personal_access_token(user_id, "cli", "hash", &scopes, None)
+            .await
+            .unwrap();
+
+        let principal = repository
+            .use_personal_access_token("hash")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(principal.user_id, user_id);
+        assert_eq!(
+            principal.credential,
+            Credential::PersonalAccessToken {
+                token_id: token.id.parse().unwrap(),
+                scopes,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_expired_and_revoked_tokens() {
+        let (repository, user_id) = create_repository().await;
+        let scopes = vec![NOTES_READ_SCOPE.to_string()];
+        let expires_at = SystemTime::now() + Duration::from_millis(50);
+        repository
+            .add_personal_access_token(user_id, "expiring", "expiring", &scopes, Some(expires_at))
+            .await
+            .unwrap();
+        let token = repository
+            .add_personal_access_token(user_id, "revoked", "revoked", &scopes, None)
+            .await
+            .unwrap();
+        assert!(
+            repository
+                .use_personal_access_token("expiring")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        repository
+            .revoke_personal_access_token(user_id, token.id.parse().unwrap())
+            .await
+            .unwrap();
+
+        for token_hash in ["expiring", "revoked"] {
+            assert!(
+                repository
+                    .use_personal_access_token(token_hash)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+        assert!(
+            repository
+                .query_personal_access_tokens(user_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use ethereal_core::proto::{Locale, ReferenceEntry};
+
+use crate::{
+    domain::{entity::ReferenceData, error::ArcaneVaultError},
+    infrastructure::memory::store::{
+        GENDERS, LOCALES, NOTE_SOURCE_TYPES, ROLES, TIMEZONES, USER_STATUSES,
+    },
+};
+
+/// [`crate::domain::repository::ReferenceDataRepository`] serving the seeded rows of the
+/// reference tables, which never change in memory.
+pub struct MemoryReferenceDataRepository;
+
+#[async_trait::async_trait]
+impl crate::domain::repository::ReferenceDataRepository for MemoryReferenceDataRepository {
+    async fn query_reference_data(&self) -> Result<ReferenceData, ArcaneVaultError> {
+        Ok(ReferenceData {
+            genders: get_reference_entries(GENDERS),
+            locales: LOCALES
+                .iter()
+                .map(
+                    |&(id, language_code, locale_code, name_en, native_name, is_rtl)| Locale {
+                        id,
+                        language_code: language_code.to_string(),
+                        locale_code: locale_code.to_string(),
+                        name_en: name_en.to_string(),
+                        native_name: native_name.to_string(),
+                        is_rtl,
+                        enabled: true,
+                    },
+                )
+                .collect(),
+            timezones: TIMEZONES.iter().map(|name| name.to_string()).collect(),
+            roles: get_reference_entries(ROLES),
+            user_statuses: get_reference_entries(USER_STATUSES),
+            note_source_types: get_reference_entries(NOTE_SOURCE_TYPES),
+        })
+    }
+}
+
+fn get_reference_entries(rows: &[(i32, &str, &str)]) -> Vec<ReferenceEntry> {
+    rows.iter()
+        .map(|&(id, name, description)| ReferenceEntry {
+            id,
+            name: name.to_string(),
+            description: description.to_string(),
+        })
+        .collect()
+}
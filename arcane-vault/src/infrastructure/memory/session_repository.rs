@@ -0,0 +1,196 @@
+use std::{
+    cmp::Reverse,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use ethereal_core::proto::Session;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{Credential, Principal, SessionTokenHashes},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        memory::store::{MemoryStore, SessionRefreshToken, StoredSession, is_within},
+        rule::ENDED_SESSION_RETENTION,
+    },
+};
+
+/// [`crate::domain::repository::SessionRepository`] kept in process memory, following the
+/// session functions of the SQL script.
+pub struct MemorySessionRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemorySessionRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::SessionRepository for MemorySessionRepository {
+    async fn add_session(
+        &self,
+        user_id: Uuid,
+        device_label: &str,
+        user_agent: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.get_account("func_add_session", user_id)?;
+
+        // What the SQL cleanup job does for `sessions`.
+        let now = SystemTime::now();
+        store.sessions.retain(|_, session| {
+            let ended_at = session.revoked_at.unwrap_or(session.expires_at);
+            ended_at > now || is_within(ended_at, ENDED_SESSION_RETENTION)
+        });
+        let MemoryStore {
+            sessions,
+            session_refresh_tokens,
+            ..
+        } = &mut *store;
+        session_refresh_tokens.retain(|_, token| sessions.contains_key(&token.session_id));
+
+        let session_id = Uuid::new_v4();
+        store.sessions.insert(
+            session_id,
+            StoredSession {
+                user_id,
+                device_label: device_label.to_string(),
+                user_agent: user_agent.to_string(),
+                access_token_hash: tokens.access_token_hash.clone(),
+                access_token_expires_at: now + tokens.access_token_ttl,
+                created_at: now,
+                last_used_at: now,
+                expires_at: now + tokens.refresh_token_ttl,
+                revoked_at: None,
+            },
+        );
+        store.session_refresh_tokens.insert(
+            tokens.refresh_token_hash.clone(),
+            SessionRefreshToken {
+                session_id,
+                used_at: None,
+            },
+        );
+        Ok(session_id)
+    }
+
+    async fn refresh_session(
+        &self,
+        refresh_token_hash: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let now = SystemTime::now();
+        let Some(refresh_token) = store.session_refresh_tokens.get(refresh_token_hash) else {
+            return Ok(None);
+        };
+        let (session_id, is_used) = (refresh_token.session_id, refresh_token.used_at.is_some());
+        let Some(session) = store.sessions.get_mut(&session_id) else {
+            return Ok(None);
+        };
+        if is_used {
+            session.revoked_at.get_or_insert(now);
+            return Ok(None);
+        }
+        if !session.is_active(now) {
+            return Ok(None);
+        }
+
+        session.access_token_hash = tokens.access_token_hash.clone();
+        session.access_token_expires_at = now + tokens.access_token_ttl;
+        session.last_used_at = now;
+        session.expires_at = now + tokens.refresh_token_ttl;
+        if let Some(refresh_token) = store.session_refresh_tokens.get_mut(refresh_token_hash) {
+            refresh_token.used_at = Some(now);
+        }
+        store.session_refresh_tokens.insert(
+            tokens.refresh_token_hash.clone(),
+            SessionRefreshToken {
+                session_id,
+                used_at: None,
+            },
+        );
+        Ok(Some(session_id))
+    }
+
+    async fn query_session_by_access_token(
+        &self,
+        access_token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        let now = SystemTime::now();
+        Ok(store
+            .sessions
+            .iter()
+            .find(|(_, session)| {
+                session.access_token_hash == access_token_hash
+                    && session.access_token_expires_at > now
+                    && session.is_active(now)
+            })
+            .map(|(id, session)| Principal {
+                user_id: session.user_id,
+                credential: Credential::Session(*id),
+            }))
+    }
+
+    async fn query_sessions(&self, user_id: Uuid) -> Result<Vec<Session>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        let now = SystemTime::now();
+        let mut sessions: Vec<_> = store
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.user_id == user_id && session.is_active(now))
+            .collect();
+        sessions.sort_by_key(|(_, session)| Reverse(session.last_used_at));
+        Ok(sessions
+            .into_iter()
+            .map(|(id, session)| Session {
+                id: id.to_string(),
+                device_label: session.device_label.clone(),
+                user_agent: session.user_agent.clone(),
+                created_at: Some(session.created_at.into()),
+                last_used_at: Some(session.last_used_at.into()),
+                expires_at: Some(session.expires_at.into()),
+            })
+            .collect())
+    }
+
+    async fn revoke_session(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_revoke_session";
+        let mut store = self.store.lock().unwrap();
+        store.get_account(FUNCTION, user_id)?;
+        let now = SystemTime::now();
+        match store.sessions.get_mut(&session_id) {
+            Some(session) if session.user_id == user_id && session.is_active(now) => {
+                session.revoked_at = Some(now);
+                Ok(())
+            }
+            _ => Err(raise_error(FUNCTION, "PA019", &[&session_id.to_string()])),
+        }
+    }
+
+    async fn revoke_sessions(&self, user_id: Uuid) -> Result<i32, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.get_account("func_revoke_sessions", user_id)?;
+        let now = SystemTime::now();
+        let mut revoked_sessions = 0;
+        for session in store.sessions.values_mut() {
+            if session.user_id == user_id && session.is_active(now) {
+                session.revoked_at = Some(now);
+                revoked_sessions += 1;
+            }
+        }
+        Ok(revoked_sessions)
+    }
+}
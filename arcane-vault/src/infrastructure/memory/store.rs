@@ -0,0 +1,406 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use ethereal_core::proto::User;
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{CollectionEntity, NewAuditEvent, NoteEntity},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::{get_sql_error, raise_error},
+        rule::get_stored_audit_event,
+    },
+};
+
+// Seeded rows of the reference tables, which the SQL tables reference by foreign key.
+// (id, name, description)
+pub(super) const GENDERS: &[(i32, &str, &str)] = &[
+    (0, "Unknown", "Gender not specified or unknown"),
+    (1, "Male", "Male gender"),
+    (2, "Female", "Female gender"),
+    (3, "Other", "Other or non-binary gender"),
+];
+pub(super) const ROLES: &[(i32, &str, &str)] = &[
+    (1, "Admin", "Administrator with full permissions"),
+    (2, "User", "Regular user with limited permissions"),
+    (3, "Guest", "Guest user with minimal permissions"),
+];
+pub(super) const USER_STATUSES: &[(i32, &str, &str)] = &[
+    (0, "Inactive", "User account is inactive"),
+    (1, "Active", "User account is active"),
+    (2, "Suspended", "User account is suspended"),
+    (3, "Deleted", "User account is deleted"),
+];
+pub(super) const NOTE_SOURCE_TYPES: &[(i32, &str, &str)] = &[
+    (0, "Unknown", "Unknown source"),
+    (1, "Web", "Collected from website"),
+    (2, "Manual", "Manually added by user"),
+];
+// (id, language_code, locale_code, name_en, native_name, is_rtl)
+pub(super) const LOCALES: &[(i32, &str, &str, &str, &str, bool)] = &[
+    (1, "en", "en-US", "English (United States)", "English", false),
+    (2, "zh", "zh-CN", "Chinese (Simplified)", "简体中文", false),
+];
+// Postgres fills `timezones` from `pg_timezone_names`; these are the common zones of that list.
+pub(super) const TIMEZONES: &[&str] = &[
+    "Africa/Cairo",
+    "Africa/Johannesburg",
+    "Africa/Lagos",
+    "America/Chicago",
+    "America/Denver",
+    "America/Los_Angeles",
+    "America/Mexico_City",
+    "America/New_York",
+    "America/Sao_Paulo",
+    "America/Toronto",
+    "Asia/Dubai",
+    "Asia/Hong_Kong",
+    "Asia/Jakarta",
+    "Asia/Kolkata",
+    "Asia/Seoul",
+    "Asia/Shanghai",
+    "Asia/Singapore",
+    "Asia/Taipei",
+    "Asia/Tokyo",
+    "Australia/Melbourne",
+    "Australia/Sydney",
+    "Europe/Berlin",
+    "Europe/Istanbul",
+    "Europe/London",
+    "Europe/Madrid",
+    "Europe/Moscow",
+    "Europe/Paris",
+    "Europe/Rome",
+    "Pacific/Auckland",
+    "UTC",
+];
+
+/// Tables shared by the in-memory repositories.
+#[derive(Default)]
+pub struct MemoryStore {
+    pub(super) pending_users: HashMap<String, PendingUser>,
+    pub(super) users: HashMap<Uuid, StoredUser>,
+    pub(super) pending_reset_passwords: HashMap<Uuid, PendingResetPassword>,
+    pub(super) pending_email_changes: HashMap<Uuid, PendingEmailChange>,
+    pub(super) collections: HashMap<Uuid, CollectionEntity>,
+    pub(super) notes: HashMap<Uuid, NoteEntity>,
+    /// Keyed by note and revision.
+    pub(super) note_revisions: BTreeMap<(Uuid, i32), StoredNoteRevision>,
+    pub(super) attachment_blobs: HashMap<Uuid, StoredAttachmentBlob>,
+    pub(super) note_attachments: HashMap<Uuid, StoredAttachment>,
+    pub(super) totps: HashMap<Uuid, StoredTotp>,
+    pub(super) totp_challenges: HashMap<Uuid, TotpChallenge>,
+    pub(super) sessions: HashMap<Uuid, StoredSession>,
+    /// Keyed by token hash.
+    pub(super) session_refresh_tokens: HashMap<String, SessionRefreshToken>,
+    pub(super) personal_access_tokens: HashMap<Uuid, StoredPersonalAccessToken>,
+    /// Keyed by state hash.
+    pub(super) oidc_login_attempts: HashMap<String, OidcLoginAttempt>,
+    /// Keyed by provider and subject.
+    pub(super) user_identities: HashMap<(String, String), StoredUserIdentity>,
+    /// In the order they were added; the id of an event is its position plus one.
+    pub(super) audit_events: Vec<StoredAuditEvent>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    pub(super) fn find_user_id(&self, account: &str) -> Option<Uuid> {
+        self.users
+            .values()
+            .find(|user| user.account == account)
+            .map(|user| user.id)
+    }
+
+    pub(super) fn get_user_mut(
+        &mut self,
+        function: &str,
+        id: Uuid,
+    ) -> Result<&mut StoredUser, ArcaneVaultError> {
+        self.users
+            .get_mut(&id)
+            .ok_or_else(|| raise_error(function, "PA007", &[&id.to_string()]))
+    }
+
+    pub(super) fn get_account(&self, function: &str, id: Uuid) -> Result<String, ArcaneVaultError> {
+        self.users
+            .get(&id)
+            .map(|user| user.account.clone())
+            .ok_or_else(|| raise_error(function, "PA007", &[&id.to_string()]))
+    }
+
+    /// Same as `func_add_audit_event`.
+    pub(super) fn add_audit_event(&mut self, event: &NewAuditEvent) {
+        self.audit_events.push(StoredAuditEvent {
+            event: get_stored_audit_event(event),
+            created_at: SystemTime::now(),
+        });
+    }
+
+    /// Same as `util_check_note_owner`, raising `PN005` for notes of other users.
+    pub(super) fn check_note_owner(
+        &self,
+        function: &str,
+        user_id: Uuid,
+        note_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        let is_owner = self
+            .notes
+            .get(&note_id)
+            .and_then(|note| self.collections.get(&note.collection_id))
+            .is_some_and(|collection| collection.user_id == user_id);
+        if !is_owner {
+            return Err(raise_error(function, "PN005", &[&note_id.to_string()]));
+        }
+        Ok(())
+    }
+
+    /// Changes the content and meta of a note, keeping the version before as a revision like the
+    /// update trigger of `notes`, and returns the revision of the note afterwards.
+    pub(super) fn update_note(
+        &mut self,
+        note_id: Uuid,
+        content: String,
+        meta: serde_json::Value,
+    ) -> Option<i32> {
+        let now = SystemTime::now();
+        let note = self.notes.get_mut(&note_id)?;
+        if note.content != content || note.meta != meta {
+            self.note_revisions.insert(
+                (note_id, note.revision),
+                StoredNoteRevision {
+                    content: std::mem::replace(&mut note.content, content),
+                    meta: std::mem::replace(&mut note.meta, meta),
+                    created_at: note.updated_at,
+                    replaced_at: now,
+                },
+            );
+            note.revision += 1;
+        }
+        note.updated_at = now;
+        Some(note.revision)
+    }
+
+    /// Removes the attachments `is_removed` picks, counting them off their blobs like the
+    /// triggers of `note_attachments`.
+    pub(super) fn remove_attachments(
+        &mut self,
+        is_removed: impl Fn(Uuid, &StoredAttachment) -> bool,
+    ) {
+        let now = SystemTime::now();
+        let attachment_blobs = &mut self.attachment_blobs;
+        self.note_attachments.retain(|id, attachment| {
+            if !is_removed(*id, attachment) {
+                return true;
+            }
+            if let Some(blob) = attachment_blobs.get_mut(&attachment.blob_id) {
+                blob.ref_count -= 1;
+                if blob.ref_count == 0 {
+                    blob.orphaned_at = Some(now);
+                }
+            }
+            false
+        });
+    }
+}
+
+pub(super) struct StoredNoteRevision {
+    pub(super) content: String,
+    pub(super) meta: serde_json::Value,
+    pub(super) created_at: SystemTime,
+    pub(super) replaced_at: SystemTime,
+}
+
+pub(super) struct PendingUser {
+    pub(super) password_hash: String,
+    pub(super) verification_code: String,
+    pub(super) created_at: SystemTime,
+    pub(super) updated_at: SystemTime,
+    pub(super) firstname: String,
+    pub(super) lastname: String,
+    pub(super) gender: i32,
+    pub(super) locale: i32,
+    pub(super) timezone: String,
+    pub(super) signature: String,
+}
+
+pub(super) struct StoredUser {
+    pub(super) id: Uuid,
+    pub(super) account: String,
+    pub(super) password_hash: String,
+    pub(super) created_at: SystemTime,
+    pub(super) updated_at: SystemTime,
+    pub(super) last_login_at: Option<SystemTime>,
+    pub(super) status: i32,
+    pub(super) role: i32,
+    pub(super) firstname: String,
+    pub(super) lastname: String,
+    pub(super) gender: i32,
+    pub(super) locale: i32,
+    pub(super) timezone: String,
+    pub(super) avatar: String,
+    pub(super) signature: String,
+}
+
+impl From<&StoredUser> for User {
+    fn from(user: &StoredUser) -> Self {
+        User {
+            id: user.id.to_string(),
+            email_account: user.account.clone(),
+            created_at: Some(user.created_at.into()),
+            updated_at: Some(user.updated_at.into()),
+            last_login_at: user.last_login_at.map(prost_types::Timestamp::from),
+            status: user.status,
+            role: user.role,
+            firstname: user.firstname.clone(),
+            lastname: user.lastname.clone(),
+            gender: user.gender,
+            locale: user.locale,
+            timezone: user.timezone.clone(),
+            avatar: user.avatar.clone(),
+            signature: user.signature.clone(),
+        }
+    }
+}
+
+pub(super) struct PendingResetPassword {
+    pub(super) verification_code: String,
+    pub(super) updated_at: SystemTime,
+}
+
+pub(super) struct PendingEmailChange {
+    pub(super) account: String,
+    pub(super) verification_code: String,
+    pub(super) attempts: i32,
+    pub(super) updated_at: SystemTime,
+}
+
+pub(super) struct StoredTotp {
+    pub(super) secret: Vec<u8>,
+    pub(super) last_used_step: i64,
+    /// Codes tried since the last right one, and when the latest was.
+    pub(super) attempts: i32,
+    pub(super) last_attempt_at: Option<SystemTime>,
+    pub(super) enabled_at: Option<SystemTime>,
+    /// Hash of each recovery code and when it was used.
+    pub(super) recovery_codes: HashMap<String, Option<SystemTime>>,
+}
+
+pub(super) struct TotpChallenge {
+    pub(super) user_id: Uuid,
+    pub(super) attempts: i32,
+    pub(super) created_at: SystemTime,
+}
+
+pub(super) struct StoredSession {
+    pub(super) user_id: Uuid,
+    pub(super) device_label: String,
+    pub(super) user_agent: String,
+    pub(super) access_token_hash: String,
+    pub(super) access_token_expires_at: SystemTime,
+    pub(super) created_at: SystemTime,
+    pub(super) last_used_at: SystemTime,
+    pub(super) expires_at: SystemTime,
+    pub(super) revoked_at: Option<SystemTime>,
+}
+
+impl StoredSession {
+    pub(super) fn is_active(&self, now: SystemTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+pub(super) struct SessionRefreshToken {
+    pub(super) session_id: Uuid,
+    pub(super) used_at: Option<SystemTime>,
+}
+
+pub(super) struct StoredPersonalAccessToken {
+    pub(super) user_id: Uuid,
+    pub(super) name: String,
+    pub(super) token_hash: String,
+    pub(super) scopes: Vec<String>,
+    pub(super) created_at: SystemTime,
+    pub(super) expires_at: Option<SystemTime>,
+    pub(super) last_used_at: Option<SystemTime>,
+    pub(super) revoked_at: Option<SystemTime>,
+}
+
+impl StoredPersonalAccessToken {
+    pub(super) fn is_active(&self, now: SystemTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+pub(super) struct OidcLoginAttempt {
+    pub(super) provider: String,
+    pub(super) pkce_verifier: String,
+    pub(super) nonce: String,
+    pub(super) user_id: Option<Uuid>,
+    pub(super) created_at: SystemTime,
+}
+
+pub(super) struct StoredUserIdentity {
+    pub(super) user_id: Uuid,
+    pub(super) email: String,
+    pub(super) created_at: SystemTime,
+    pub(super) last_login_at: Option<SystemTime>,
+}
+
+pub(super) struct StoredAttachmentBlob {
+    pub(super) user_id: Uuid,
+    pub(super) sha256: String,
+    pub(super) size: i64,
+    pub(super) ref_count: i32,
+    pub(super) is_stored: bool,
+    pub(super) orphaned_at: Option<SystemTime>,
+}
+
+pub(super) struct StoredAttachment {
+    pub(super) note_id: Uuid,
+    pub(super) blob_id: Uuid,
+    pub(super) file_name: String,
+    pub(super) content_type: String,
+    pub(super) created_at: SystemTime,
+}
+
+pub(super) struct StoredAuditEvent {
+    pub(super) event: NewAuditEvent,
+    pub(super) created_at: SystemTime,
+}
+
+pub(super) fn check_profile_references(gender: i32, locale: i32) -> Result<(), ArcaneVaultError> {
+    if !GENDERS.iter().any(|(id, ..)| *id == gender) {
+        return Err(get_sql_error(
+            SqlState::FOREIGN_KEY_VIOLATION,
+            &format!("gender {} is not present in table \"genders\"", gender),
+        ));
+    }
+    if !LOCALES.iter().any(|(id, ..)| *id == locale) {
+        return Err(get_sql_error(
+            SqlState::FOREIGN_KEY_VIOLATION,
+            &format!("locale {} is not present in table \"locales\"", locale),
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn check_timezone(function: &str, timezone: &str) -> Result<(), ArcaneVaultError> {
+    if !TIMEZONES.contains(&timezone) {
+        return Err(raise_error(function, "PA012", &[timezone]));
+    }
+    Ok(())
+}
+
+pub(super) fn is_within(time: SystemTime, duration: Duration) -> bool {
+    time.elapsed().is_ok_and(|elapsed| elapsed < duration)
+}
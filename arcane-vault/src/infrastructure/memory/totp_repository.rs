@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::TotpEntity, error::ArcaneVaultError},
+    infrastructure::{
+        error_code::raise_error,
+        memory::store::{MemoryStore, StoredTotp, TotpChallenge, is_within},
+        rule::{
+            TOTP_CHALLENGE_EXPIRY, TOTP_CHALLENGE_MAX_ATTEMPTS, TOTP_LOCKOUT, TOTP_MAX_ATTEMPTS,
+        },
+    },
+};
+
+/// [`crate::domain::repository::TotpRepository`] kept in process memory, following the TOTP
+/// functions of the SQL script.
+pub struct MemoryTotpRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryTotpRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::TotpRepository for MemoryTotpRepository {
+    async fn enroll_totp(&self, user_id: Uuid, secret: &[u8]) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_enroll_totp";
+        let mut store = self.store.lock().unwrap();
+        let account = store.get_account(FUNCTION, user_id)?;
+        if store
+            .totps
+            .get(&user_id)
+            .is_some_and(|totp| totp.enabled_at.is_some())
+        {
+            return Err(raise_error(FUNCTION, "PA013", &[&account]));
+        }
+
+        store.totps.insert(
+            user_id,
+            StoredTotp {
+                secret: secret.to_vec(),
+                last_used_step: 0,
+                attempts: 0,
+                last_attempt_at: None,
+                enabled_at: None,
+                recovery_codes: HashMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn query_totp(&self, user_id: Uuid) -> Result<Option<TotpEntity>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        Ok(store.totps.get(&user_id).map(|totp| TotpEntity {
+            secret: totp.secret.clone(),
+            last_used_step: totp.last_used_step,
+            is_enabled: totp.enabled_at.is_some(),
+        }))
+    }
+
+    async fn confirm_totp(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_confirm_totp";
+        let mut store = self.store.lock().unwrap();
+        let account = store.get_account(FUNCTION, user_id)?;
+        let Some(totp) = store.totps.get_mut(&user_id) else {
+            return Err(raise_error(FUNCTION, "PA014", &[&account]));
+        };
+        if totp.enabled_at.is_some() {
+            return Err(raise_error(FUNCTION, "PA013", &[&account]));
+        }
+
+        totp.enabled_at = Some(SystemTime::now());
+        totp.last_used_step = step;
+        totp.recovery_codes = get_recovery_codes(recovery_code_hashes);
+        Ok(())
+    }
+
+    async fn disable_totp(&self, user_id: Uuid) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_disable_totp";
+        let mut store = self.store.lock().unwrap();
+        let account = store.get_account(FUNCTION, user_id)?;
+        if store
+            .totps
+            .get(&user_id)
+            .is_none_or(|totp| totp.enabled_at.is_none())
+        {
+            return Err(raise_error(FUNCTION, "PA015", &[&account]));
+        }
+
+        store.totps.remove(&user_id);
+        store
+            .totp_challenges
+            .retain(|_, challenge| challenge.user_id != user_id);
+        Ok(())
+    }
+
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        match store.totps.get_mut(&user_id) {
+            Some(totp) if totp.enabled_at.is_some() && totp.last_used_step < step => {
+                totp.last_used_step = step;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+    ) -> Result<bool, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let used_at = store
+            .totps
+            .get_mut(&user_id)
+            .and_then(|totp| totp.recovery_codes.get_mut(code_hash));
+        match used_at {
+            Some(used_at) if used_at.is_none() => {
+                *used_at = Some(SystemTime::now());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn use_totp_attempt(&self, user_id: Uuid) -> Result<bool, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let Some(totp) = store.totps.get_mut(&user_id) else {
+            return Ok(false);
+        };
+        let is_recent = totp
+            .last_attempt_at
+            .is_some_and(|last_attempt_at| is_within(last_attempt_at, TOTP_LOCKOUT));
+        if is_recent && totp.attempts >= TOTP_MAX_ATTEMPTS {
+            return Ok(false);
+        }
+
+        totp.attempts = if is_recent { totp.attempts + 1 } else { 1 };
+        totp.last_attempt_at = Some(SystemTime::now());
+        Ok(true)
+    }
+
+    async fn reset_totp_attempts(&self, user_id: Uuid) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        if let Some(totp) = store.totps.get_mut(&user_id) {
+            totp.attempts = 0;
+        }
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(
+        &self,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_replace_recovery_codes";
+        let mut store = self.store.lock().unwrap();
+        let account = store.get_account(FUNCTION, user_id)?;
+        match store.totps.get_mut(&user_id) {
+            Some(totp) if totp.enabled_at.is_some() => {
+                totp.recovery_codes = get_recovery_codes(recovery_code_hashes);
+                Ok(())
+            }
+            _ => Err(raise_error(FUNCTION, "PA015", &[&account])),
+        }
+    }
+
+    async fn add_totp_challenge(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        // What the SQL cleanup job does for `totp_challenges`.
+        store
+            .totp_challenges
+            .retain(|_, challenge| is_within(challenge.created_at, TOTP_CHALLENGE_EXPIRY));
+
+        let challenge_id = Uuid::new_v4();
+        store.totp_challenges.insert(
+            challenge_id,
+            TotpChallenge {
+                user_id,
+                attempts: 0,
+                created_at: SystemTime::now(),
+            },
+        );
+        Ok(challenge_id)
+    }
+
+    async fn use_totp_challenge(
+        &self,
+        challenge_id: Uuid,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        match store.totp_challenges.get_mut(&challenge_id) {
+            Some(challenge)
+                if challenge.attempts < TOTP_CHALLENGE_MAX_ATTEMPTS
+                    && is_within(challenge.created_at, TOTP_CHALLENGE_EXPIRY) =>
+            {
+                challenge.attempts += 1;
+                Ok(Some(challenge.user_id))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete_totp_challenge(&self, challenge_id: Uuid) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.totp_challenges.remove(&challenge_id);
+        Ok(())
+    }
+}
+
+fn get_recovery_codes(recovery_code_hashes: &[String]) -> HashMap<String, Option<SystemTime>> {
+    recovery_code_hashes
+        .iter()
+        .map(|code_hash| (code_hash.clone(), None))
+        .collect()
+}
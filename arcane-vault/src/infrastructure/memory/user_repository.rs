@@ -0,0 +1,475 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use ethereal_core::proto::User;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{
+            AUDIT_EVENT_PASSWORD_RESET, AUDIT_EVENT_PASSWORD_SET, EmailChangeEntity, NewAuditEvent,
+            NewUser,
+        },
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        memory::store::{
+            MemoryStore, PendingEmailChange, PendingResetPassword, PendingUser, StoredUser,
+            check_profile_references, check_timezone, is_within,
+        },
+        rule::{
+            EMAIL_CHANGE_EXPIRY, EMAIL_CHANGE_MAX_ATTEMPTS, RESET_PASSWORD_EXPIRY, USER_ROLE_USER,
+            USER_STATUS_ACTIVE, VERIFICATION_CODE_COOLDOWN, check_page, generate_verification_code,
+            get_profile_changes,
+        },
+    },
+};
+
+/// [`crate::domain::repository::UserRepository`] kept in process memory, for tests and demos.
+/// Follows the SQL functions closely: pending users, verification codes and their cooldowns, and
+/// the `PA` error codes. Everything is lost when the process exits.
+pub struct MemoryUserRepository {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryUserRepository {
+    pub fn new(store: Arc<Mutex<MemoryStore>>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::UserRepository for MemoryUserRepository {
+    async fn register_user(
+        &self,
+        user: &NewUser,
+        password_hash: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let account = user.account.as_str();
+        if store.pending_users.contains_key(account) || store.find_user_id(account).is_some() {
+            return Err(raise_error("func_register_user", "PA001", &[account]));
+        }
+        check_profile_references(user.gender, user.locale)?;
+        check_timezone("func_register_user", &user.timezone)?;
+
+        let verification_code = generate_verification_code();
+        let now = SystemTime::now();
+        store.pending_users.insert(
+            account.to_string(),
+            PendingUser {
+                password_hash: password_hash.to_string(),
+                verification_code: verification_code.clone(),
+                created_at: now,
+                updated_at: now,
+                firstname: user.firstname.clone(),
+                lastname: user.lastname.clone(),
+                gender: user.gender,
+                locale: user.locale,
+                timezone: user.timezone.clone(),
+                signature: user.signature.clone(),
+            },
+        );
+        Ok(verification_code)
+    }
+
+    async fn verify_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+        verification_code: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let is_matched = store
+            .pending_users
+            .get(account)
+            .is_some_and(|pending_user| {
+                pending_user.password_hash == password_hash
+                    && pending_user.verification_code == verification_code.to_uppercase()
+            });
+        if !is_matched {
+            return Err(raise_error("func_verify_user", "PA002", &[account]));
+        }
+
+        let pending_user = store.pending_users.remove(account).unwrap();
+        let id = Uuid::new_v4();
+        let now = SystemTime::now();
+        store.users.insert(
+            id,
+            StoredUser {
+                id,
+                account: account.to_string(),
+                password_hash: pending_user.password_hash,
+                created_at: pending_user.created_at,
+                updated_at: now,
+                last_login_at: Some(now),
+                status: USER_STATUS_ACTIVE,
+                role: USER_ROLE_USER,
+                firstname: pending_user.firstname,
+                lastname: pending_user.lastname,
+                gender: pending_user.gender,
+                locale: pending_user.locale,
+                timezone: pending_user.timezone,
+                avatar: String::new(),
+                signature: pending_user.signature,
+            },
+        );
+        Ok(id)
+    }
+
+    async fn regenerate_verification_code(&self, account: &str) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "func_regenerate_verification_code";
+        let mut store = self.store.lock().unwrap();
+        let pending_user = store
+            .pending_users
+            .get_mut(account)
+            .ok_or_else(|| raise_error(FUNCTION, "PA002", &[account]))?;
+        if is_within(pending_user.updated_at, VERIFICATION_CODE_COOLDOWN) {
+            return Err(raise_error(FUNCTION, "PA008", &[account]));
+        }
+
+        let verification_code = generate_verification_code();
+        pending_user.verification_code = verification_code.clone();
+        pending_user.updated_at = SystemTime::now();
+        Ok(verification_code)
+    }
+
+    async fn login_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        const FUNCTION: &str = "func_login_user";
+        let mut store = self.store.lock().unwrap();
+        if let Some(user) = store
+            .users
+            .values_mut()
+            .find(|user| user.account == account && user.password_hash == password_hash)
+        {
+            user.last_login_at = Some(SystemTime::now());
+            return Ok(user.id);
+        }
+
+        if store.pending_users.contains_key(account) {
+            return Err(raise_error(FUNCTION, "PA004", &[account]));
+        }
+        if store.find_user_id(account).is_some() {
+            return Err(raise_error(FUNCTION, "PA005", &[account]));
+        }
+        Err(raise_error(FUNCTION, "PA003", &[account]))
+    }
+
+    async fn request_reset_password(&self, account: &str) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "func_request_reset_password";
+        let mut store = self.store.lock().unwrap();
+        let id = store
+            .find_user_id(account)
+            .ok_or_else(|| raise_error(FUNCTION, "PA003", &[account]))?;
+        if store
+            .pending_reset_passwords
+            .get(&id)
+            .is_some_and(|pending| is_within(pending.updated_at, VERIFICATION_CODE_COOLDOWN))
+        {
+            return Err(raise_error(FUNCTION, "PA008", &[account]));
+        }
+
+        let verification_code = generate_verification_code();
+        store.pending_reset_passwords.insert(
+            id,
+            PendingResetPassword {
+                verification_code: verification_code.clone(),
+                updated_at: SystemTime::now(),
+            },
+        );
+        Ok(verification_code)
+    }
+
+    async fn reset_password(
+        &self,
+        account: &str,
+        verification_code: &str,
+        new_password_hash: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_reset_password";
+        let mut store = self.store.lock().unwrap();
+        let id = store
+            .find_user_id(account)
+            .ok_or_else(|| raise_error(FUNCTION, "PA003", &[account]))?;
+        let pending = store
+            .pending_reset_passwords
+            .get(&id)
+            .ok_or_else(|| raise_error(FUNCTION, "PA011", &[account]))?;
+        if pending.verification_code != verification_code {
+            return Err(raise_error(FUNCTION, "PA009", &[]));
+        }
+        if !is_within(pending.updated_at, RESET_PASSWORD_EXPIRY) {
+            return Err(raise_error(FUNCTION, "PA010", &[]));
+        }
+
+        let user = store.get_user_mut(FUNCTION, id)?;
+        user.password_hash = new_password_hash.to_string();
+        user.updated_at = SystemTime::now();
+        store.pending_reset_passwords.remove(&id);
+        store.add_audit_event(&NewAuditEvent {
+            event_type: AUDIT_EVENT_PASSWORD_RESET,
+            user_id: Some(id),
+            account: account.to_string(),
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    async fn set_password(&self, id: Uuid, password_hash: &str) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let user = store.get_user_mut("func_set_password", id)?;
+        user.password_hash = password_hash.to_string();
+        user.updated_at = SystemTime::now();
+        let account = user.account.clone();
+        store.add_audit_event(&NewAuditEvent {
+            event_type: AUDIT_EVENT_PASSWORD_SET,
+            user_id: Some(id),
+            actor_id: Some(id),
+            account,
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    async fn request_email_change(
+        &self,
+        id: Uuid,
+        account: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "func_request_email_change";
+        let mut store = self.store.lock().unwrap();
+        let old_account = store.get_account(FUNCTION, id)?;
+        if store.pending_users.contains_key(account) || store.find_user_id(account).is_some() {
+            return Err(raise_error(FUNCTION, "PA001", &[account]));
+        }
+        if store
+            .pending_email_changes
+            .get(&id)
+            .is_some_and(|pending| is_within(pending.updated_at, VERIFICATION_CODE_COOLDOWN))
+        {
+            return Err(raise_error(FUNCTION, "PA008", &[&old_account]));
+        }
+
+        let verification_code = generate_verification_code();
+        store.pending_email_changes.insert(
+            id,
+            PendingEmailChange {
+                account: account.to_string(),
+                verification_code: verification_code.clone(),
+                attempts: 0,
+                updated_at: SystemTime::now(),
+            },
+        );
+        Ok(verification_code)
+    }
+
+    async fn use_email_change_attempt(&self, id: Uuid) -> Result<bool, ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        let Some(pending) = store.pending_email_changes.get_mut(&id) else {
+            return Ok(true);
+        };
+        if pending.attempts >= EMAIL_CHANGE_MAX_ATTEMPTS {
+            return Ok(false);
+        }
+
+        pending.attempts += 1;
+        Ok(true)
+    }
+
+    async fn confirm_email_change(
+        &self,
+        id: Uuid,
+        verification_code: &str,
+    ) -> Result<EmailChangeEntity, ArcaneVaultError> {
+        const FUNCTION: &str = "func_confirm_email_change";
+        let mut store = self.store.lock().unwrap();
+        let old_account = store.get_account(FUNCTION, id)?;
+        let pending = store
+            .pending_email_changes
+            .get(&id)
+            .ok_or_else(|| raise_error(FUNCTION, "PA032", &[&old_account]))?;
+        if pending.verification_code != verification_code.to_uppercase() {
+            return Err(raise_error(FUNCTION, "PA009", &[]));
+        }
+        if !is_within(pending.updated_at, EMAIL_CHANGE_EXPIRY) {
+            return Err(raise_error(FUNCTION, "PA010", &[]));
+        }
+        let new_account = pending.account.clone();
+        if store.pending_users.contains_key(&new_account)
+            || store.find_user_id(&new_account).is_some()
+        {
+            return Err(raise_error(FUNCTION, "PA001", &[&new_account]));
+        }
+
+        let user = store.get_user_mut(FUNCTION, id)?;
+        user.account = new_account.clone();
+        user.updated_at = SystemTime::now();
+        store.pending_email_changes.remove(&id);
+        Ok(EmailChangeEntity {
+            old_account,
+            new_account,
+        })
+    }
+
+    async fn query_password_hash(&self, account: &str) -> Result<Option<String>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        Ok(store
+            .users
+            .values()
+            .find(|user| user.account == account)
+            .map(|user| user.password_hash.clone()))
+    }
+
+    async fn query_pending_password_hash(
+        &self,
+        account: &str,
+    ) -> Result<Option<String>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        Ok(store
+            .pending_users
+            .get(account)
+            .map(|pending_user| pending_user.password_hash.clone()))
+    }
+
+    async fn update_profile(
+        &self,
+        id: Uuid,
+        firstname: &str,
+        lastname: &str,
+        gender: i32,
+        locale: i32,
+    ) -> Result<(User, Vec<&'static str>), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.get_user_mut("func_set_name", id)?;
+        check_profile_references(gender, locale)?;
+
+        // Profiles live in `user_profiles`, so `users.updated_at` is left alone.
+        let user = store.get_user_mut("func_set_name", id)?;
+        let changes = get_profile_changes(
+            (&user.firstname, &user.lastname, user.gender, user.locale),
+            (firstname, lastname, gender, locale),
+        );
+        user.firstname = firstname.to_string();
+        user.lastname = lastname.to_string();
+        user.gender = gender;
+        user.locale = locale;
+        Ok((User::from(&*user), changes))
+    }
+
+    async fn set_avatar(&self, id: Uuid, avatar: &str) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.get_user_mut("func_set_avatar", id)?.avatar = avatar.to_string();
+        Ok(())
+    }
+
+    async fn set_signature(&self, id: Uuid, signature: &str) -> Result<(), ArcaneVaultError> {
+        let mut store = self.store.lock().unwrap();
+        store.get_user_mut("func_set_signature", id)?.signature = signature.to_string();
+        Ok(())
+    }
+
+    async fn set_timezone(&self, id: Uuid, timezone: &str) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_set_timezone";
+        let mut store = self.store.lock().unwrap();
+        store.get_user_mut(FUNCTION, id)?;
+        check_timezone(FUNCTION, timezone)?;
+        store.get_user_mut(FUNCTION, id)?.timezone = timezone.to_string();
+        Ok(())
+    }
+
+    async fn query_user_by_id(&self, id: Uuid) -> Result<Option<User>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        Ok(store.users.get(&id).map(User::from))
+    }
+
+    async fn query_user_by_account(&self, account: &str) -> Result<Option<User>, ArcaneVaultError> {
+        let store = self.store.lock().unwrap();
+        Ok(store
+            .users
+            .values()
+            .find(|user| user.account == account)
+            .map(User::from))
+    }
+
+    async fn query_users(&self, offset: i32, limit: i32) -> Result<Vec<User>, ArcaneVaultError> {
+        check_page(offset, limit)?;
+        Ok(self
+            .get_sorted_users()
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn export_users(
+        &self,
+    ) -> Result<BoxStream<'static, Result<User, ArcaneVaultError>>, ArcaneVaultError> {
+        Ok(stream::iter(self.get_sorted_users().into_iter().map(Ok)).boxed())
+    }
+}
+
+impl MemoryUserRepository {
+    // Same order as `func_query_users`.
+    fn get_sorted_users(&self) -> Vec<User> {
+        let store = self.store.lock().unwrap();
+        let mut users = store.users.values().collect::<Vec<_>>();
+        users.sort_by_key(|user| (user.created_at, user.id));
+        users.into_iter().map(User::from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::repository::UserRepository;
+
+    fn get_error_code(err: &ArcaneVaultError) -> Option<&str> {
+        err.code.as_ref().and_then(|code| code.get_error_code())
+    }
+
+    fn get_new_user(timezone: &str) -> NewUser {
+        NewUser {
+            account: "ada@example.com".into(),
+            locale: 1,
+            timezone: timezone.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_timezones_with_pa012() {
+        let repository = MemoryUserRepository::new(MemoryStore::new());
+
+        let err = repository
+            .register_user(&get_new_user("Mars/Olympus_Mons"), "hash")
+            .await
+            .unwrap_err();
+        assert_eq!(get_error_code(&err), Some("PA012"));
+
+        let verification_code = repository
+            .register_user(&get_new_user("Europe/London"), "hash")
+            .await
+            .unwrap();
+        let id = repository
+            .verify_user("ada@example.com", "hash", &verification_code)
+            .await
+            .unwrap();
+        let err = repository
+            .set_timezone(id, "Europe/Atlantis")
+            .await
+            .unwrap_err();
+        assert_eq!(get_error_code(&err), Some("PA012"));
+        repository.set_timezone(id, "UTC").await.unwrap();
+
+        let user = repository.query_user_by_id(id).await.unwrap().unwrap();
+        assert_eq!(user.timezone, "UTC");
+    }
+}
@@ -0,0 +1,66 @@
+use lazy_static::lazy_static;
+use prometheus::{IntCounter, IntCounterVec, IntGauge};
+
+use crate::domain::error::{ArcaneVaultError, ArcaneVaultErrorCode};
+
+lazy_static! {
+    static ref REPOSITORY_ATTEMPTS: IntCounter = prometheus::register_int_counter!(
+        "arcane_vault_repository_attempts_total",
+        "Number of attempts made by Repository::with_retry."
+    )
+    .unwrap();
+    static ref REPOSITORY_RETRIES_EXHAUSTED: IntCounter = prometheus::register_int_counter!(
+        "arcane_vault_repository_retries_exhausted_total",
+        "Number of operations that still failed after the last retry."
+    )
+    .unwrap();
+    static ref ERRORS: IntCounterVec = prometheus::register_int_counter_vec!(
+        "arcane_vault_errors_total",
+        "Number of errors returned by the repository, by error code.",
+        &["code"]
+    )
+    .unwrap();
+    static ref POOL_SIZE: IntGauge = prometheus::register_int_gauge!(
+        "arcane_vault_pool_size",
+        "Current number of connections in the pool."
+    )
+    .unwrap();
+    static ref POOL_AVAILABLE: IntGauge = prometheus::register_int_gauge!(
+        "arcane_vault_pool_available",
+        "Number of idle connections in the pool."
+    )
+    .unwrap();
+    static ref POOL_WAITING: IntGauge = prometheus::register_int_gauge!(
+        "arcane_vault_pool_waiting",
+        "Number of callers waiting for a connection."
+    )
+    .unwrap();
+}
+
+pub(crate) fn observe_attempt() {
+    REPOSITORY_ATTEMPTS.inc();
+}
+
+pub(crate) fn observe_retries_exhausted() {
+    REPOSITORY_RETRIES_EXHAUSTED.inc();
+}
+
+pub(crate) fn observe_error(error: &ArcaneVaultError) {
+    let code = get_error_code_label(&error.code);
+    ERRORS.with_label_values(&[code.as_str()]).inc();
+}
+
+pub(crate) fn observe_pool_status(status: deadpool_postgres::Status) {
+    POOL_SIZE.set(status.size as i64);
+    // deadpool reports waiting callers as a negative number of available objects.
+    POOL_AVAILABLE.set(status.available.max(0) as i64);
+    POOL_WAITING.set((-status.available).max(0) as i64);
+}
+
+fn get_error_code_label(code: &Option<ArcaneVaultErrorCode>) -> String {
+    match code {
+        Some(ArcaneVaultErrorCode::InnerError(code)) => code.clone(),
+        Some(code) => format!("{:?}", code),
+        None => "Unknown".into(),
+    }
+}
@@ -1,2 +1,15 @@
+pub mod attachment;
+pub mod avatar;
+pub mod blob_store;
+pub mod error_code;
+pub mod mailer;
+pub mod memory;
+pub mod metrics;
+pub mod oidc;
+pub mod password_hasher;
 pub mod repository;
-pub mod utility;
\ No newline at end of file
+pub mod rule;
+pub mod token;
+pub mod totp;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use openidconnect::{
+    AccessTokenHash, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EndpointMaybeSet,
+    EndpointNotSet, EndpointSet, IssuerUrl, Nonce, OAuth2TokenResponse, PkceCodeChallenge,
+    PkceCodeVerifier, RedirectUrl, Scope, TokenResponse,
+    core::{CoreAuthenticationFlow, CoreClient, CoreProviderMetadata},
+    reqwest,
+};
+use serde::Deserialize;
+
+use crate::{
+    domain::error::{ArcaneVaultError, ArcaneVaultErrorCode},
+    infrastructure::error_code::raise_error,
+};
+
+/// Longest provider name, VARCHAR(64) in `user_identities`.
+const PROVIDER_NAME_MAX_LEN: usize = 64;
+/// How long discovery documents and their signing keys are used before being fetched again, so
+/// rotated keys are picked up.
+const PROVIDER_METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_SCOPES: &[&str] = &["email", "profile"];
+
+/// A client of discovered endpoints, where the token endpoint is only checked for on use.
+type DiscoveredClient = CoreClient<
+    EndpointSet,
+    EndpointNotSet,
+    EndpointNotSet,
+    EndpointNotSet,
+    EndpointMaybeSet,
+    EndpointMaybeSet,
+>;
+
+/// One `[[arcane-vault.oidc_providers]]` of the configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcProviderConfig {
+    /// What clients pass as `provider`, and what identities are stored with.
+    pub name: String,
+    /// Where `/.well-known/openid-configuration` is found, e.g. a local mock IdP for testing.
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Leave out for public clients, which only rely on PKCE.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Where the provider sends the user back to with `state` and `code`.
+    pub redirect_url: String,
+    /// Requested with `openid`; `email` and `profile` when left out.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+/// An authorization request to send the user to, with what finishing it takes.
+pub struct OidcAuthorization {
+    pub url: String,
+    pub state: String,
+    pub pkce_verifier: String,
+    pub nonce: String,
+}
+
+/// What a verified ID token says about who signed in.
+pub struct OidcClaims {
+    pub subject: String,
+    pub email: Option<String>,
+    pub is_email_verified: bool,
+    pub firstname: String,
+    pub lastname: String,
+    /// BCP 47 tag, e.g. `en-US`.
+    pub locale: String,
+    /// IANA name, e.g. `Europe/Berlin`.
+    pub timezone: String,
+}
+
+/// Logs users in with the OpenID Connect providers of `arcane-vault[0].oidc_providers`, using the
+/// authorization code flow with PKCE. ID tokens are checked against the keys of the provider's
+/// discovery document, along with their issuer, audience, expiry and nonce.
+pub struct OidcAuthenticator {
+    providers: HashMap<String, OidcProviderConfig>,
+    http_client: reqwest::Client,
+    provider_metadata: Mutex<HashMap<String, (CoreProviderMetadata, Instant)>>,
+}
+
+impl OidcAuthenticator {
+    pub fn new() -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        let provider_configs = config
+            .get::<Vec<OidcProviderConfig>>("arcane-vault[0].oidc_providers")
+            .unwrap_or_default();
+
+        let mut providers = HashMap::new();
+        for provider in provider_configs {
+            let is_valid = !provider.name.is_empty()
+                && provider.name.len() <= PROVIDER_NAME_MAX_LEN
+                && IssuerUrl::new(provider.issuer_url.clone()).is_ok()
+                && RedirectUrl::new(provider.redirect_url.clone()).is_ok();
+            if !is_valid || providers.contains_key(&provider.name) {
+                return Err(get_config_error(format!(
+                    "arcane-vault[0].oidc_providers has an invalid or duplicate provider `{}`",
+                    provider.name
+                )));
+            }
+            providers.insert(provider.name.clone(), provider);
+        }
+
+        let http_client = reqwest::ClientBuilder::new()
+            // Following redirects would let a provider make the server request other URLs.
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .map_err(|e| get_oidc_error(e.to_string()))?;
+        Ok(Self {
+            providers,
+            http_client,
+            provider_metadata: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Starts a login with `provider`. Raises `PA026` for a provider that is not configured.
+    pub async fn get_authorization(
+        &self,
+        function: &str,
+        provider: &str,
+    ) -> Result<OidcAuthorization, ArcaneVaultError> {
+        let (config, client) = self.get_client(function, provider).await?;
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+        let mut request = client.authorize_url(
+            CoreAuthenticationFlow::AuthorizationCode,
+            CsrfToken::new_random,
+            Nonce::new_random,
+        );
+        if config.scopes.is_empty() {
+            for scope in DEFAULT_SCOPES {
+                request = request.add_scope(Scope::new(scope.to_string()));
+            }
+        } else {
+            for scope in &config.scopes {
+                request = request.add_scope(Scope::new(scope.clone()));
+            }
+        }
+        let (url, state, nonce) = request.set_pkce_challenge(pkce_challenge).url();
+        Ok(OidcAuthorization {
+            url: url.to_string(),
+            state: state.into_secret(),
+            pkce_verifier: pkce_verifier.into_secret(),
+            nonce: nonce.secret().clone(),
+        })
+    }
+
+    /// Redeems the code the provider sent the user back with and verifies the ID token. Raises
+    /// `PA027` when the provider rejects the code or the ID token does not check out.
+    pub async fn exchange_code(
+        &self,
+        function: &str,
+        provider: &str,
+        code: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+    ) -> Result<OidcClaims, ArcaneVaultError> {
+        let (_, client) = self.get_client(function, provider).await?;
+        let get_login_error = |reason: String| {
+            tracing::warn!(provider, %reason, "OpenID Connect login rejected");
+            raise_error(function, "PA027", &[])
+        };
+
+        let token_response = client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .map_err(|e| get_oidc_error(e.to_string()))?
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(&self.http_client)
+            .await
+            .map_err(|e| get_login_error(e.to_string()))?;
+        let id_token = token_response
+            .id_token()
+            .ok_or_else(|| get_login_error("no ID token was returned".into()))?;
+        let verifier = client.id_token_verifier();
+        let claims = id_token
+            .claims(&verifier, &Nonce::new(nonce.to_string()))
+            .map_err(|e| get_login_error(e.to_string()))?;
+
+        // The access token is not used afterwards, but a substituted one means a tampered
+        // response.
+        if let Some(expected_hash) = claims.access_token_hash() {
+            let hash = id_token
+                .signing_alg()
+                .and_then(|alg| Ok((alg, id_token.signing_key(&verifier)?)))
+                .map_err(|e| get_login_error(e.to_string()))
+                .and_then(|(alg, key)| {
+                    AccessTokenHash::from_token(token_response.access_token(), alg, key)
+                        .map_err(|e| get_login_error(e.to_string()))
+                })?;
+            if hash != *expected_hash {
+                return Err(get_login_error(
+                    "the access token hash does not match".into(),
+                ));
+            }
+        }
+
+        Ok(OidcClaims {
+            subject: claims.subject().to_string(),
+            email: claims.email().map(|email| email.to_string()),
+            is_email_verified: claims.email_verified().unwrap_or(false),
+            firstname: claims
+                .given_name()
+                .and_then(|name| name.get(None))
+                .map(|name| name.to_string())
+                .unwrap_or_default(),
+            lastname: claims
+                .family_name()
+                .and_then(|name| name.get(None))
+                .map(|name| name.to_string())
+                .unwrap_or_default(),
+            locale: claims
+                .locale()
+                .map(|locale| locale.to_string())
+                .unwrap_or_default(),
+            timezone: claims
+                .zoneinfo()
+                .map(|timezone| timezone.to_string())
+                .unwrap_or_default(),
+        })
+    }
+
+    async fn get_client(
+        &self,
+        function: &str,
+        provider: &str,
+    ) -> Result<(&OidcProviderConfig, DiscoveredClient), ArcaneVaultError> {
+        let config = self
+            .providers
+            .get(provider)
+            .ok_or_else(|| raise_error(function, "PA026", &[provider]))?;
+        let metadata = self.get_provider_metadata(config).await?;
+        let client = CoreClient::from_provider_metadata(
+            metadata,
+            ClientId::new(config.client_id.clone()),
+            config.client_secret.clone().map(ClientSecret::new),
+        )
+        .set_redirect_uri(
+            RedirectUrl::new(config.redirect_url.clone())
+                .map_err(|e| get_oidc_error(e.to_string()))?,
+        );
+        Ok((config, client))
+    }
+
+    // Providers are discovered on first use rather than on startup, so one being down does not
+    // keep the others from working.
+    async fn get_provider_metadata(
+        &self,
+        config: &OidcProviderConfig,
+    ) -> Result<CoreProviderMetadata, ArcaneVaultError> {
+        if let Some((metadata, fetched_at)) =
+            self.provider_metadata.lock().unwrap().get(&config.name)
+            && fetched_at.elapsed() < PROVIDER_METADATA_TTL
+        {
+            return Ok(metadata.clone());
+        }
+
+        let issuer_url =
+            IssuerUrl::new(config.issuer_url.clone()).map_err(|e| get_oidc_error(e.to_string()))?;
+        let metadata = CoreProviderMetadata::discover_async(issuer_url, &self.http_client)
+            .await
+            .map_err(|e| get_oidc_error(format!("discovery of `{}` failed: {}", config.name, e)))?;
+        self.provider_metadata
+            .lock()
+            .unwrap()
+            .insert(config.name.clone(), (metadata.clone(), Instant::now()));
+        Ok(metadata)
+    }
+}
+
+fn get_config_error(message: String) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message,
+        code: None,
+        metadata: HashMap::new(),
+    }
+}
+
+fn get_oidc_error(message: String) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message,
+        code: Some(ArcaneVaultErrorCode::InnerError("oidc error".into())),
+        metadata: HashMap::new(),
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        self, PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString,
+        rand_core::OsRng,
+    },
+};
+
+use crate::domain::{
+    error::{ArcaneVaultError, ArcaneVaultErrorCode},
+    service::PasswordHasher,
+};
+
+// `crypt(..., gen_salt('bf'))` of the Postgres schema wrote `$2a$`, the bcrypt crate `$2b$`.
+const BCRYPT_PREFIXES: &[&str] = &["$2a$", "$2b$", "$2x$", "$2y$"];
+
+/// Argon2id with `arcane-vault[0].argon2_memory_kib`, `argon2_iterations` and
+/// `argon2_parallelism`, by default the 19 MiB, 2 iterations and 1 lane OWASP recommends.
+/// Verifies bcrypt hashes and Argon2 hashes of other parameters too, and flags them for rehashing.
+pub struct Argon2PasswordHasher {
+    argon2: Argon2<'static>,
+}
+
+impl Argon2PasswordHasher {
+    pub fn new() -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        let params = Params::new(
+            config
+                .get::<u32>("arcane-vault[0].argon2_memory_kib")
+                .unwrap_or(Params::DEFAULT_M_COST),
+            config
+                .get::<u32>("arcane-vault[0].argon2_iterations")
+                .unwrap_or(Params::DEFAULT_T_COST),
+            config
+                .get::<u32>("arcane-vault[0].argon2_parallelism")
+                .unwrap_or(Params::DEFAULT_P_COST),
+            None,
+        )
+        .map_err(|e| ArcaneVaultError {
+            message: format!("invalid argon2 parameters in arcane-vault[0]: {}", e),
+            code: None,
+            metadata: HashMap::new(),
+        })?;
+        Ok(Self {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+        })
+    }
+}
+
+impl PasswordHasher for Argon2PasswordHasher {
+    fn hash_password(&self, password: &str) -> Result<String, ArcaneVaultError> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2
+            .hash_password(password.as_bytes(), &salt)
+            .map(|password_hash| password_hash.to_string())
+            .map_err(get_argon2_error)
+    }
+
+    fn verify_password(
+        &self,
+        password: &str,
+        password_hash: &str,
+    ) -> Result<bool, ArcaneVaultError> {
+        if is_bcrypt_hash(password_hash) {
+            return bcrypt::verify(password, password_hash).map_err(get_bcrypt_error);
+        }
+
+        // Algorithm, version and parameters are taken from the hash, not from `self.argon2`.
+        let password_hash = PasswordHash::new(password_hash).map_err(get_argon2_error)?;
+        match self
+            .argon2
+            .verify_password(password.as_bytes(), &password_hash)
+        {
+            Ok(()) => Ok(true),
+            Err(password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(get_argon2_error(e)),
+        }
+    }
+
+    fn needs_rehash(&self, password_hash: &str) -> bool {
+        if is_bcrypt_hash(password_hash) {
+            return true;
+        }
+        let Ok(password_hash) = PasswordHash::new(password_hash) else {
+            return true;
+        };
+
+        let params = self.argon2.params();
+        password_hash.algorithm != Algorithm::Argon2id.ident()
+            || password_hash.version != Some(Version::V0x13.into())
+            || Params::try_from(&password_hash).is_ok_and(|hash_params| {
+                hash_params.m_cost() != params.m_cost()
+                    || hash_params.t_cost() != params.t_cost()
+                    || hash_params.p_cost() != params.p_cost()
+            })
+    }
+}
+
+fn is_bcrypt_hash(password_hash: &str) -> bool {
+    BCRYPT_PREFIXES
+        .iter()
+        .any(|prefix| password_hash.starts_with(prefix))
+}
+
+fn get_argon2_error(error: password_hash::Error) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message: error.to_string(),
+        code: Some(ArcaneVaultErrorCode::InnerError("argon2 error".into())),
+        metadata: HashMap::new(),
+    }
+}
+
+fn get_bcrypt_error(error: bcrypt::BcryptError) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message: error.to_string(),
+        code: Some(ArcaneVaultErrorCode::InnerError("bcrypt error".into())),
+        metadata: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_hasher(memory_kib: u32, iterations: u32) -> Argon2PasswordHasher {
+        let params = Params::new(memory_kib, iterations, 1, None).unwrap();
+        Argon2PasswordHasher {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+        }
+    }
+
+    #[test]
+    fn verifies_pgcrypto_bcrypt_hashes() {
+        // A `$2a$` hash as `crypt('U*U', gen_salt('bf', 5))` writes it.
+        let password_hash = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+        let hasher = create_hasher(1024, 1);
+
+        assert!(hasher.verify_password("U*U", password_hash).unwrap());
+        assert!(!hasher.verify_password("U*V", password_hash).unwrap());
+        assert!(hasher.needs_rehash(password_hash));
+    }
+
+    #[test]
+    fn flags_argon2_hashes_of_outdated_parameters() {
+        let password_hash = create_hasher(1024, 1).hash_password("password1").unwrap();
+        let hasher = create_hasher(2048, 2);
+
+        assert!(hasher.verify_password("password1", &password_hash).unwrap());
+        assert!(hasher.needs_rehash(&password_hash));
+        assert!(!hasher.needs_rehash(&hasher.hash_password("password1").unwrap()));
+    }
+
+    #[test]
+    fn rejects_a_wrong_password() {
+        let hasher = create_hasher(1024, 1);
+        let password_hash = hasher.hash_password("password1").unwrap();
+
+        assert!(!hasher.verify_password("password2", &password_hash).unwrap());
+        assert!(!hasher.verify_password("", &password_hash).unwrap());
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::SystemTime;
+
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{AttachmentBlobEntity, AttachmentEntity},
+        error::ArcaneVaultError,
+    },
+    infrastructure::repository::{
+        ADD_ATTACHMENT, COLLECT_ATTACHMENT_BLOBS, DELETE_ATTACHMENT, DbContext, QUERY_ATTACHMENT,
+        QUERY_ATTACHMENTS, SET_ATTACHMENT_BLOB_STORED,
+    },
+};
+
+/// [`crate::domain::repository::AttachmentRepository`] backed by the functions of
+/// `script/easynote_note.sql`.
+pub struct PostgresAttachmentRepository {
+    db_context: DbContext,
+}
+
+impl PostgresAttachmentRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::AttachmentRepository for PostgresAttachmentRepository {
+    async fn add_attachment(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        sha256: &str,
+        size: i64,
+        file_name: &str,
+        content_type: &str,
+    ) -> Result<AttachmentEntity, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                ADD_ATTACHMENT.sql,
+                &[
+                    &user_id,
+                    &note_id,
+                    &sha256,
+                    &size,
+                    &file_name,
+                    &content_type,
+                ],
+                AttachmentEntity::from_row,
+            )
+            .await
+    }
+
+    async fn set_attachment_blob_stored(&self, blob_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(SET_ATTACHMENT_BLOB_STORED.sql, &[&blob_id])
+            .await?;
+        Ok(())
+    }
+
+    async fn query_attachments(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+    ) -> Result<Vec<AttachmentEntity>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_many(
+                QUERY_ATTACHMENTS.sql,
+                &[&user_id, &note_id],
+                AttachmentEntity::from_row,
+            )
+            .await
+    }
+
+    async fn query_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<AttachmentEntity, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                QUERY_ATTACHMENT.sql,
+                &[&user_id, &attachment_id],
+                AttachmentEntity::from_row,
+            )
+            .await
+    }
+
+    async fn delete_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(DELETE_ATTACHMENT.sql, &[&user_id, &attachment_id])
+            .await?;
+        Ok(())
+    }
+
+    async fn collect_attachment_blobs(
+        &self,
+        orphaned_before: SystemTime,
+        limit: i32,
+    ) -> Result<Vec<AttachmentBlobEntity>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_many(
+                COLLECT_ATTACHMENT_BLOBS.sql,
+                &[&orphaned_before, &limit],
+                AttachmentBlobEntity::from_row,
+            )
+            .await
+    }
+}
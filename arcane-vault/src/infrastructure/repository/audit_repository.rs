@@ -0,0 +1,71 @@
+use ethereal_core::proto::AuditEvent;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{AuditEventEntity, NewAuditEvent},
+        error::ArcaneVaultError,
+    },
+    infrastructure::repository::{ADD_AUDIT_EVENT, DbContext, QUERY_AUDIT_EVENTS},
+};
+
+/// [`crate::domain::repository::AuditRepository`] backed by the functions of
+/// `script/easynote_common.sql`.
+pub struct PostgresAuditRepository {
+    db_context: DbContext,
+}
+
+impl PostgresAuditRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::AuditRepository for PostgresAuditRepository {
+    async fn add_audit_event(&self, event: &NewAuditEvent) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(
+                ADD_AUDIT_EVENT.sql,
+                &[
+                    &event.event_type,
+                    &event.user_id,
+                    &event.actor_id,
+                    &event.account,
+                    &event.details,
+                    &event.ip_address,
+                    &event.user_agent,
+                    &event.request_id,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn query_audit_events(
+        &self,
+        user_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<AuditEvent>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_many(
+                QUERY_AUDIT_EVENTS.sql,
+                &[&user_id, &offset, &limit],
+                get_audit_event_from_row,
+            )
+            .await
+    }
+}
+
+fn get_audit_event_from_row(
+    row: &tokio_postgres::Row,
+) -> Result<AuditEvent, tokio_postgres::Error> {
+    AuditEventEntity::from_row(row).map(AuditEvent::from)
+}
@@ -0,0 +1,70 @@
+use uuid::Uuid;
+
+use crate::{
+    domain::error::ArcaneVaultError,
+    infrastructure::repository::{
+        ADD_COLLECTION, CHANGE_DEFAULT_COLLECTION, DbContext, GET_DEFAULT_COLLECTION,
+    },
+};
+
+/// [`crate::domain::repository::CollectionRepository`] backed by the functions of `script/easynote_note.sql`.
+pub struct PostgresCollectionRepository {
+    db_context: DbContext,
+}
+
+impl PostgresCollectionRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::CollectionRepository for PostgresCollectionRepository {
+    async fn add_collection(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        description: &str,
+        is_active: bool,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                ADD_COLLECTION.sql,
+                &[&user_id, &name, &description, &is_active],
+                get_collection_id_from_row,
+            )
+            .await
+    }
+
+    async fn change_default_collection(
+        &self,
+        user_id: Uuid,
+        collection_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(CHANGE_DEFAULT_COLLECTION.sql, &[&user_id, &collection_id])
+            .await?;
+        Ok(())
+    }
+
+    async fn get_default_collection(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                GET_DEFAULT_COLLECTION.sql,
+                &[&user_id],
+                get_collection_id_from_row,
+            )
+            .await
+    }
+}
+
+fn get_collection_id_from_row(row: &tokio_postgres::Row) -> Result<Uuid, tokio_postgres::Error> {
+    row.try_get("collection_id")
+}
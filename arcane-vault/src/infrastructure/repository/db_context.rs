@@ -1,22 +1,109 @@
+use std::{collections::HashMap, time::Duration};
+
 use deadpool_postgres::{Pool, Runtime};
 use tokio_postgres::NoTls;
 
-use crate::domain::error::ArcaneVaultError;
-use crate::infrastructure::repository::Repository;
+use crate::domain::error::{ArcaneVaultError, ArcaneVaultErrorCode};
+use crate::infrastructure::metrics;
+use crate::infrastructure::repository::{Repository, RetryPolicy, SQL_STATEMENTS};
+
+pub const POOL_METRICS_INTERVAL_SECS: u64 = 15;
 
 #[derive(Debug, Clone)]
 pub struct DbContext {
     pool: Pool,
+    retry_policy: RetryPolicy,
 }
 
 impl DbContext {
     pub async fn new() -> Result<Self, ArcaneVaultError> {
-        let pool = create_db_pool().await?;
-        Ok(Self { pool })
+        let db_context = Self::new_unobserved().await?;
+        spawn_pool_status_observer(db_context.pool.clone());
+        Ok(db_context)
+    }
+
+    /// Like [`DbContext::new`], but the pool is left out of the pool gauges, for short-lived uses
+    /// that must not overwrite those of the service's pool.
+    pub async fn new_unobserved() -> Result<Self, ArcaneVaultError> {
+        Ok(Self {
+            pool: create_db_pool().await?,
+            retry_policy: get_retry_policy(),
+        })
+    }
+
+    /// Closes the pool, dropping its idle connections; later requests fail.
+    pub fn close(&self) {
+        self.pool.close();
     }
 
     pub async fn get_repository(&self) -> Repository {
-        Repository::new(self.pool.clone())
+        Repository::new(self.pool.clone(), self.retry_policy.clone())
+    }
+
+    /// Prepares every statement in [`SQL_STATEMENTS`] and checks the columns it returns, so a
+    /// schema that does not match the code is reported at once instead of on the first request.
+    pub async fn check_schema(&self) -> Result<(), ArcaneVaultError> {
+        let client = self.pool.get().await?;
+        let mut report = Vec::new();
+        for statement in SQL_STATEMENTS {
+            let problems = match client.prepare(statement.sql).await {
+                Ok(prepared) => (statement.check_columns)(prepared.columns()),
+                Err(e) => vec![match e.as_db_error() {
+                    Some(db_error) => db_error.message().to_string(),
+                    None => return Err(e.into()),
+                }],
+            };
+            report.extend(
+                problems
+                    .into_iter()
+                    .map(|problem| format!("  {}: {}", statement.name, problem)),
+            );
+        }
+
+        if report.is_empty() {
+            return Ok(());
+        }
+        Err(ArcaneVaultError {
+            message: format!(
+                "database schema does not match arcane-vault:\n{}",
+                report.join("\n")
+            ),
+            code: Some(ArcaneVaultErrorCode::SchemaMismatch),
+            metadata: HashMap::new(),
+        })
+    }
+}
+
+// Requests refresh the pool gauges too, but an idle service makes none.
+fn spawn_pool_status_observer(pool: Pool) {
+    let config = ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+    let interval = config
+        .get::<u64>("arcane-vault[0].pool_metrics_interval_secs")
+        .unwrap_or(POOL_METRICS_INTERVAL_SECS);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval.max(1)));
+        while !pool.is_closed() {
+            interval.tick().await;
+            metrics::observe_pool_status(pool.status());
+        }
+    });
+}
+
+fn get_retry_policy() -> RetryPolicy {
+    let config = ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+    let default = RetryPolicy::default();
+
+    RetryPolicy {
+        max_retries: config
+            .get::<u32>("arcane-vault[0].max_retries")
+            .unwrap_or(default.max_retries),
+        base_delay_ms: config
+            .get::<u64>("arcane-vault[0].base_delay_ms")
+            .unwrap_or(default.base_delay_ms),
+        max_delay_ms: config
+            .get::<u64>("arcane-vault[0].max_delay_ms")
+            .unwrap_or(default.max_delay_ms),
     }
 }
 
@@ -37,3 +124,80 @@ async fn create_db_pool() -> Result<Pool, ArcaneVaultError> {
     cfg.create_pool(Some(Runtime::Tokio1), NoTls)
         .map_err(ArcaneVaultError::from)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_pool_config(options: Option<String>) -> deadpool_postgres::Config {
+        let mut config = deadpool_postgres::Config::new();
+        config.host = Some("localhost".to_string());
+        config.user = Some("easynote".to_string());
+        config.password = Some("easynote_password".to_string());
+        config.dbname = Some("easynote".to_string());
+        config.options = options;
+        config
+    }
+
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL with the easynote database on localhost:5432"]
+    async fn reports_the_statements_the_schema_does_not_match() {
+        // A function of the same signature earlier on the search path shadows the real one.
+        let schema = format!("schema_test_{}", uuid::Uuid::new_v4().simple());
+        let (client, connection) = get_pool_config(None)
+            .get_pg_config()
+            .unwrap()
+            .connect(NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        client
+            .batch_execute(&format!(
+                "CREATE SCHEMA {schema};
+                CREATE FUNCTION {schema}.func_query_totp(p_user_id UUID)
+                RETURNS TABLE (secret TEXT, last_used_step BIGINT)
+                AS $$ SELECT NULL::TEXT, NULL::BIGINT $$ LANGUAGE sql;"
+            ))
+            .await
+            .unwrap();
+        let db_context = DbContext {
+            pool: get_pool_config(Some(format!("-c search_path={schema},public")))
+                .create_pool(Some(Runtime::Tokio1), NoTls)
+                .unwrap(),
+            retry_policy: RetryPolicy::default(),
+        };
+
+        let result = db_context.check_schema().await;
+        db_context.close();
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let err = result.unwrap_err();
+        assert_eq!(err.code, Some(ArcaneVaultErrorCode::SchemaMismatch));
+        assert_eq!(
+            err.message.lines().collect::<Vec<_>>(),
+            [
+                "database schema does not match arcane-vault:",
+                "  query_totp: column `secret` is text but alloc::vec::Vec<u8> is expected",
+                "  query_totp: missing column `is_enabled`",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL with the easynote database on localhost:5432"]
+    async fn accepts_the_schema_of_the_scripts() {
+        let db_context = DbContext {
+            pool: get_pool_config(None)
+                .create_pool(Some(Runtime::Tokio1), NoTls)
+                .unwrap(),
+            retry_policy: RetryPolicy::default(),
+        };
+
+        let result = db_context.check_schema().await;
+        db_context.close();
+        result.unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use uuid::Uuid;
+
+use crate::{
+    domain::error::ArcaneVaultError,
+    infrastructure::repository::{ADD_NOTE, DELETE_NOTE, DbContext, UPDATE_NOTE_META},
+};
+
+/// [`crate::domain::repository::NoteRepository`] backed by the functions of `script/easynote_note.sql`.
+pub struct PostgresNoteRepository {
+    db_context: DbContext,
+}
+
+impl PostgresNoteRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::NoteRepository for PostgresNoteRepository {
+    async fn add_note(
+        &self,
+        content: &str,
+        collection_id: Uuid,
+        source_type: i32,
+        meta: Option<serde_json::Value>,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                ADD_NOTE.sql,
+                &[&content, &collection_id, &source_type, &meta],
+                get_note_id_from_row,
+            )
+            .await
+    }
+
+    async fn update_note_meta(
+        &self,
+        note_id: Uuid,
+        meta: serde_json::Value,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(UPDATE_NOTE_META.sql, &[&note_id, &meta])
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_note(&self, note_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(DELETE_NOTE.sql, &[&note_id])
+            .await?;
+        Ok(())
+    }
+}
+
+fn get_note_id_from_row(row: &tokio_postgres::Row) -> Result<Uuid, tokio_postgres::Error> {
+    row.try_get("note_id")
+}
@@ -0,0 +1,103 @@
+use std::time::SystemTime;
+
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::NoteRevisionEntity, error::ArcaneVaultError},
+    infrastructure::repository::{
+        DbContext, PRUNE_NOTE_REVISIONS, QUERY_NOTE_REVISION, QUERY_NOTE_REVISIONS,
+        RESTORE_NOTE_REVISION,
+    },
+};
+
+/// [`crate::domain::repository::NoteRevisionRepository`] backed by the functions of
+/// `script/easynote_note.sql`.
+pub struct PostgresNoteRevisionRepository {
+    db_context: DbContext,
+}
+
+impl PostgresNoteRevisionRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::NoteRevisionRepository for PostgresNoteRevisionRepository {
+    async fn query_note_revisions(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<NoteRevisionEntity>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_many(
+                QUERY_NOTE_REVISIONS.sql,
+                &[&user_id, &note_id, &offset, &limit],
+                NoteRevisionEntity::from_row,
+            )
+            .await
+    }
+
+    async fn query_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<NoteRevisionEntity, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                QUERY_NOTE_REVISION.sql,
+                &[&user_id, &note_id, &revision],
+                NoteRevisionEntity::from_row,
+            )
+            .await
+    }
+
+    async fn restore_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<i32, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                RESTORE_NOTE_REVISION.sql,
+                &[&user_id, &note_id, &revision],
+                get_revision_from_row,
+            )
+            .await
+    }
+
+    async fn prune_note_revisions(
+        &self,
+        keep_count: Option<i32>,
+        keep_after: Option<SystemTime>,
+    ) -> Result<i32, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                PRUNE_NOTE_REVISIONS.sql,
+                &[&keep_count, &keep_after],
+                get_pruned_from_row,
+            )
+            .await
+    }
+}
+
+fn get_revision_from_row(row: &tokio_postgres::Row) -> Result<i32, tokio_postgres::Error> {
+    row.try_get("revision")
+}
+
+fn get_pruned_from_row(row: &tokio_postgres::Row) -> Result<i32, tokio_postgres::Error> {
+    row.try_get("pruned")
+}
@@ -0,0 +1,173 @@
+use ethereal_core::proto::ExternalIdentity;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{ExternalIdentityEntity, OidcLoginAttemptEntity},
+        error::ArcaneVaultError,
+    },
+    infrastructure::repository::{
+        ADD_OIDC_LOGIN_ATTEMPT, DbContext, LINK_USER_IDENTITY, LOGIN_USER_IDENTITY,
+        QUERY_USER_IDENTITIES, REGISTER_USER_IDENTITY, UNLINK_USER_IDENTITY,
+        USE_OIDC_LOGIN_ATTEMPT,
+    },
+};
+
+/// [`crate::domain::repository::OidcRepository`] backed by the functions of
+/// `script/easynote_common.sql`.
+pub struct PostgresOidcRepository {
+    db_context: DbContext,
+}
+
+impl PostgresOidcRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::OidcRepository for PostgresOidcRepository {
+    async fn add_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+        provider: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(
+                ADD_OIDC_LOGIN_ATTEMPT.sql,
+                &[&state_hash, &provider, &pkce_verifier, &nonce, &user_id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn use_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+    ) -> Result<Option<OidcLoginAttemptEntity>, ArcaneVaultError> {
+        let attempts = self
+            .db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_many(
+                USE_OIDC_LOGIN_ATTEMPT.sql,
+                &[&state_hash],
+                OidcLoginAttemptEntity::from_row,
+            )
+            .await?;
+        Ok(attempts.into_iter().next())
+    }
+
+    async fn login_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                LOGIN_USER_IDENTITY.sql,
+                &[&provider, &subject, &email],
+                get_optional_user_id_from_row,
+            )
+            .await
+    }
+
+    async fn register_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+        firstname: &str,
+        lastname: &str,
+        locale: &str,
+        timezone: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                REGISTER_USER_IDENTITY.sql,
+                &[
+                    &provider, &subject, &email, &firstname, &lastname, &locale, &timezone,
+                ],
+                get_user_id_from_row,
+            )
+            .await
+    }
+
+    async fn link_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<ExternalIdentity, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                LINK_USER_IDENTITY.sql,
+                &[&user_id, &provider, &subject, &email],
+                get_external_identity_from_row,
+            )
+            .await
+    }
+
+    async fn query_user_identities(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ExternalIdentity>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_many(
+                QUERY_USER_IDENTITIES.sql,
+                &[&user_id],
+                get_external_identity_from_row,
+            )
+            .await
+    }
+
+    async fn unlink_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(UNLINK_USER_IDENTITY.sql, &[&user_id, &provider, &subject])
+            .await?;
+        Ok(())
+    }
+}
+
+fn get_external_identity_from_row(
+    row: &tokio_postgres::Row,
+) -> Result<ExternalIdentity, tokio_postgres::Error> {
+    ExternalIdentityEntity::from_row(row).map(ExternalIdentity::from)
+}
+
+fn get_user_id_from_row(row: &tokio_postgres::Row) -> Result<Uuid, tokio_postgres::Error> {
+    row.try_get("user_id")
+}
+
+fn get_optional_user_id_from_row(
+    row: &tokio_postgres::Row,
+) -> Result<Option<Uuid>, tokio_postgres::Error> {
+    row.try_get("user_id")
+}
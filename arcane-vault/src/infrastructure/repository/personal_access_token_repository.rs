@@ -0,0 +1,108 @@
+use std::time::SystemTime;
+
+use ethereal_core::proto::PersonalAccessToken;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{PersonalAccessTokenEntity, PersonalAccessTokenPrincipalEntity, Principal},
+        error::ArcaneVaultError,
+    },
+    infrastructure::repository::{
+        ADD_PERSONAL_ACCESS_TOKEN, DbContext, QUERY_PERSONAL_ACCESS_TOKENS,
+        REVOKE_PERSONAL_ACCESS_TOKEN, USE_PERSONAL_ACCESS_TOKEN,
+    },
+};
+
+/// [`crate::domain::repository::PersonalAccessTokenRepository`] backed by the functions of
+/// `script/easynote_common.sql`.
+pub struct PostgresPersonalAccessTokenRepository {
+    db_context: DbContext,
+}
+
+impl PostgresPersonalAccessTokenRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::PersonalAccessTokenRepository
+    for PostgresPersonalAccessTokenRepository
+{
+    async fn add_personal_access_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        token_hash: &str,
+        scopes: &[String],
+        expires_at: Option<SystemTime>,
+    ) -> Result<PersonalAccessToken, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                ADD_PERSONAL_ACCESS_TOKEN.sql,
+                &[&user_id, &name, &token_hash, &scopes, &expires_at],
+                get_personal_access_token_from_row,
+            )
+            .await
+    }
+
+    async fn query_personal_access_tokens(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<PersonalAccessToken>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_many(
+                QUERY_PERSONAL_ACCESS_TOKENS.sql,
+                &[&user_id],
+                get_personal_access_token_from_row,
+            )
+            .await
+    }
+
+    async fn revoke_personal_access_token(
+        &self,
+        user_id: Uuid,
+        token_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(REVOKE_PERSONAL_ACCESS_TOKEN.sql, &[&user_id, &token_id])
+            .await?;
+        Ok(())
+    }
+
+    async fn use_personal_access_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError> {
+        let principals = self
+            .db_context
+            .get_repository()
+            .await
+            .query_many(
+                USE_PERSONAL_ACCESS_TOKEN.sql,
+                &[&token_hash],
+                get_principal_from_row,
+            )
+            .await?;
+        Ok(principals.into_iter().next())
+    }
+}
+
+fn get_personal_access_token_from_row(
+    row: &tokio_postgres::Row,
+) -> Result<PersonalAccessToken, tokio_postgres::Error> {
+    PersonalAccessTokenEntity::from_row(row).map(PersonalAccessToken::from)
+}
+
+fn get_principal_from_row(row: &tokio_postgres::Row) -> Result<Principal, tokio_postgres::Error> {
+    PersonalAccessTokenPrincipalEntity::from_row(row).map(Principal::from)
+}
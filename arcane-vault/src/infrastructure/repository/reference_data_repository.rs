@@ -0,0 +1,70 @@
+use ethereal_core::proto::{Locale, ReferenceEntry};
+use sorcerers_kit::FromRow;
+
+use crate::{
+    domain::{
+        entity::{LocaleEntity, ReferenceData, ReferenceEntity},
+        error::ArcaneVaultError,
+    },
+    infrastructure::repository::{
+        DbContext, QUERY_GENDERS, QUERY_LOCALES, QUERY_NOTE_SOURCE_TYPES, QUERY_ROLES,
+        QUERY_TIMEZONES, QUERY_USER_STATUSES,
+    },
+};
+
+/// [`crate::domain::repository::ReferenceDataRepository`] reading the lookup tables of
+/// `script/easynote_common.sql` and `script/easynote_note.sql`.
+pub struct PostgresReferenceDataRepository {
+    db_context: DbContext,
+}
+
+impl PostgresReferenceDataRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::ReferenceDataRepository for PostgresReferenceDataRepository {
+    async fn query_reference_data(&self) -> Result<ReferenceData, ArcaneVaultError> {
+        let repository = self.db_context.get_repository().await;
+        Ok(ReferenceData {
+            genders: repository
+                .query_many(QUERY_GENDERS.sql, &[], get_reference_entry_from_row)
+                .await?,
+            locales: repository
+                .query_many(QUERY_LOCALES.sql, &[], get_locale_from_row)
+                .await?,
+            timezones: repository
+                .query_many(QUERY_TIMEZONES.sql, &[], get_timezone_from_row)
+                .await?,
+            roles: repository
+                .query_many(QUERY_ROLES.sql, &[], get_reference_entry_from_row)
+                .await?,
+            user_statuses: repository
+                .query_many(QUERY_USER_STATUSES.sql, &[], get_reference_entry_from_row)
+                .await?,
+            note_source_types: repository
+                .query_many(
+                    QUERY_NOTE_SOURCE_TYPES.sql,
+                    &[],
+                    get_reference_entry_from_row,
+                )
+                .await?,
+        })
+    }
+}
+
+fn get_reference_entry_from_row(
+    row: &tokio_postgres::Row,
+) -> Result<ReferenceEntry, tokio_postgres::Error> {
+    ReferenceEntity::from_row(row).map(ReferenceEntry::from)
+}
+
+fn get_locale_from_row(row: &tokio_postgres::Row) -> Result<Locale, tokio_postgres::Error> {
+    LocaleEntity::from_row(row).map(Locale::from)
+}
+
+fn get_timezone_from_row(row: &tokio_postgres::Row) -> Result<String, tokio_postgres::Error> {
+    row.try_get("name")
+}
@@ -1,23 +1,69 @@
 use std::time::Duration;
 use deadpool_postgres::Pool;
+use futures_util::{Stream, StreamExt};
 use tokio::time::sleep;
 use tokio_postgres::Row;
-use crate::domain::error::ArcaneVaultError;
+pub use tokio_postgres::IsolationLevel;
+use crate::domain::error::{ArcaneVaultError, ArcaneVaultErrorCode};
+use crate::infrastructure::metrics;
 
-const MAX_RETRIES: u32 = 3;
-const BASE_DELAY_MS: u64 = 100;
-const MAX_DELAY_MS: u64 = 1000;
+pub const MAX_RETRIES: u32 = 3;
+pub const BASE_DELAY_MS: u64 = 100;
+pub const MAX_DELAY_MS: u64 = 1000;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: MAX_RETRIES,
+            base_delay_ms: BASE_DELAY_MS,
+            max_delay_ms: MAX_DELAY_MS,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Exponential backoff with equal jitter: half of the delay is fixed, the other half is random.
+    fn get_delay(&self, attempts: u32) -> Duration {
+        let delay = self
+            .base_delay_ms
+            .saturating_mul(1 << (attempts - 1).min(16))
+            .min(self.max_delay_ms);
+        let half = delay / 2;
+        Duration::from_millis(half + fastrand::u64(0..=delay - half))
+    }
+}
 
 #[derive(Debug)]
 pub struct Repository {
     pool: Pool,
+    retry_policy: RetryPolicy,
+    is_idempotent: bool,
 }
 
 impl Repository {
-    pub fn new(pool: Pool) -> Self {
-        Self { pool }
+    pub fn new(pool: Pool, retry_policy: RetryPolicy) -> Self {
+        Self {
+            pool,
+            retry_policy,
+            is_idempotent: true,
+        }
     }
 
+    /// Marks the operations run through this repository as unsafe to repeat. They are then only
+    /// retried when the statement is known not to have been applied.
+    pub fn non_idempotent(mut self) -> Self {
+        self.is_idempotent = false;
+        self
+    }
+
+    #[tracing::instrument(name = "repository.query_one_row", skip_all, fields(db.system = "postgresql", db.statement = statement))]
     pub async fn query_one_row(
         &self,
         statement: &str,
@@ -33,18 +79,111 @@ impl Repository {
         .await
     }
 
+    #[tracing::instrument(name = "repository.query_one", skip_all, fields(db.system = "postgresql", db.statement = statement))]
     pub async fn query_one<T>(
         &self,
         statement: &str,
         params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
-        get_instance_func: fn(&tokio_postgres::row::Row) -> T
+        get_instance_func: fn(&tokio_postgres::row::Row) -> Result<T, tokio_postgres::Error>
     ) -> Result<T, ArcaneVaultError> {
         self.with_retry(|client| async move {
             let row = client
                 .query_one(statement, params)
                 .await
                 .map_err(ArcaneVaultError::from)?;
-            Ok(get_instance_func(&row))
+            get_instance_func(&row).map_err(ArcaneVaultError::from)
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "repository.query_many", skip_all, fields(db.system = "postgresql", db.statement = statement))]
+    pub async fn query_many<T>(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+        get_instance_func: fn(&tokio_postgres::row::Row) -> Result<T, tokio_postgres::Error>
+    ) -> Result<Vec<T>, ArcaneVaultError> {
+        self.with_retry(|client| async move {
+            let rows = client
+                .query(statement, params)
+                .await
+                .map_err(ArcaneVaultError::from)?;
+            rows.iter()
+                .map(|row| get_instance_func(row).map_err(ArcaneVaultError::from))
+                .collect()
+        })
+        .await
+    }
+
+    /// Like [`Repository::query_many`], but rows are read from the connection only as the stream
+    /// is polled, so large results are never buffered in memory. Starting the query is retried as
+    /// usual; errors hit while reading are yielded by the stream. The pooled connection is held
+    /// until the stream is dropped.
+    #[tracing::instrument(name = "repository.query_stream", skip_all, fields(db.system = "postgresql", db.statement = statement))]
+    pub async fn query_stream<T>(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+        get_instance_func: fn(&tokio_postgres::row::Row) -> Result<T, tokio_postgres::Error>
+    ) -> Result<impl Stream<Item = Result<T, ArcaneVaultError>> + Send + 'static, ArcaneVaultError>
+    where
+        T: Send + 'static,
+    {
+        let (client, rows) = self
+            .with_retry(|client| async move {
+                let rows = client
+                    .query_raw(statement, params.iter().copied())
+                    .await
+                    .map_err(ArcaneVaultError::from)?;
+                Ok((client, rows))
+            })
+            .await?;
+
+        Ok(futures_util::stream::unfold(
+            (client, Box::pin(rows)),
+            move |(client, mut rows)| async move {
+                let item = match rows.next().await?.and_then(|row| get_instance_func(&row)) {
+                    Ok(instance) => Ok(instance),
+                    Err(e) => {
+                        let error = ArcaneVaultError::from(e);
+                        metrics::observe_error(&error);
+                        Err(error)
+                    }
+                };
+                Some((item, (client, rows)))
+            },
+        ))
+    }
+
+    /// Runs `f` inside a single transaction, committing when it returns `Ok` and rolling back
+    /// otherwise. The whole closure is run again on serialization failures and deadlocks, which
+    /// roll the transaction back, but never when the connection is lost: a lost `COMMIT` may have
+    /// been applied.
+    #[tracing::instrument(name = "repository.transaction", skip_all, fields(db.system = "postgresql", isolation_level = ?isolation_level))]
+    pub async fn transaction<T, F>(
+        &self,
+        isolation_level: IsolationLevel,
+        f: F,
+    ) -> Result<T, ArcaneVaultError>
+    where
+        F: for<'a> Fn(&'a UnitOfWork<'a>) -> UnitOfWorkFuture<'a, T>,
+    {
+        let f = &f;
+        self.with_retry_if(false, |mut client| async move {
+            let transaction = client
+                .build_transaction()
+                .isolation_level(isolation_level)
+                .start()
+                .await
+                .map_err(ArcaneVaultError::from)?;
+            let unit_of_work = UnitOfWork { transaction };
+            let result = f(&unit_of_work).await?;
+            unit_of_work
+                .transaction
+                .commit()
+                .await
+                .map_err(ArcaneVaultError::from)?;
+            Ok(result)
         })
         .await
     }
@@ -54,41 +193,277 @@ impl Repository {
         F: Fn(deadpool_postgres::Client) -> Fut,
         Fut: std::future::Future<Output = Result<T, ArcaneVaultError>>,
     {
+        self.with_retry_if(self.is_idempotent, f).await
+    }
+
+    /// Like [`Repository::with_retry`], but the caller tells whether `f` may run again after an
+    /// error that leaves open whether the server applied it.
+    async fn with_retry_if<F, Fut, T>(
+        &self,
+        is_idempotent: bool,
+        f: F,
+    ) -> Result<T, ArcaneVaultError>
+    where
+        F: Fn(deadpool_postgres::Client) -> Fut,
+        Fut: std::future::Future<Output = Result<T, ArcaneVaultError>>,
+    {
+        let max_retries = self.retry_policy.max_retries.max(1);
         let mut attempts = 0;
-        let mut last_error = None;
 
-        while attempts < MAX_RETRIES {
+        loop {
             attempts += 1;
-            
-            match self.pool.get().await {
-                Ok(client) => {
-                    match f(client).await {
-                        Ok(result) => return Ok(result),
-                        Err(e) => {
-                            last_error = Some(e);
-                            if attempts < MAX_RETRIES {
-                                let delay = (BASE_DELAY_MS * (1 << (attempts - 1))).min(MAX_DELAY_MS);
-                                sleep(Duration::from_millis(delay)).await;
-                                continue;
-                            }
-                        }
+            metrics::observe_attempt();
+            metrics::observe_pool_status(self.pool.status());
+
+            let (error, is_retryable) = match self.pool.get().await {
+                Ok(client) => match f(client).await {
+                    Ok(result) => return Ok(result),
+                    Err(e) => {
+                        let is_retryable = is_retryable(is_idempotent, &e);
+                        (e, is_retryable)
                     }
-                }
+                },
+                // Nothing was sent to the server yet, so this is safe to retry either way.
                 Err(e) => {
-                    last_error = Some(e.into());
-                    if attempts < MAX_RETRIES {
-                        let delay = (BASE_DELAY_MS * (1 << (attempts - 1))).min(MAX_DELAY_MS);
-                        sleep(Duration::from_millis(delay)).await;
-                        continue;
-                    }
+                    let e = ArcaneVaultError::from(e);
+                    let is_retryable = e.is_transient();
+                    (e, is_retryable)
+                }
+            };
+
+            if is_retryable {
+                if attempts < max_retries {
+                    sleep(self.retry_policy.get_delay(attempts)).await;
+                    continue;
                 }
+                metrics::observe_retries_exhausted();
             }
+            metrics::observe_error(&error);
+            return Err(error);
         }
+    }
+}
+
+// Whether an operation that failed with `error` may be run again.
+fn is_retryable(is_idempotent: bool, error: &ArcaneVaultError) -> bool {
+    let is_rolled_back = error
+        .code
+        .as_ref()
+        .is_some_and(ArcaneVaultErrorCode::is_rolled_back);
+    error.is_transient() && (is_idempotent || is_rolled_back)
+}
+
+pub type UnitOfWorkFuture<'a, T> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, ArcaneVaultError>> + Send + 'a>>;
+
+/// Transactional handle passed to the closure of [`Repository::transaction`].
+pub struct UnitOfWork<'a> {
+    transaction: deadpool_postgres::Transaction<'a>,
+}
+
+impl UnitOfWork<'_> {
+    #[tracing::instrument(name = "unit_of_work.execute", skip_all, fields(db.system = "postgresql", db.statement = statement))]
+    pub async fn execute(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)]
+    ) -> Result<u64, ArcaneVaultError> {
+        self.transaction
+            .execute(statement, params)
+            .await
+            .map_err(ArcaneVaultError::from)
+    }
 
-        Err(last_error.unwrap_or_else(|| ArcaneVaultError {
-            message: format!("Failed after {} attempts", MAX_RETRIES),
-            code: None
+    #[tracing::instrument(name = "unit_of_work.query_one", skip_all, fields(db.system = "postgresql", db.statement = statement))]
+    pub async fn query_one<T>(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+        get_instance_func: fn(&tokio_postgres::row::Row) -> Result<T, tokio_postgres::Error>,
+    ) -> Result<T, ArcaneVaultError> {
+        let row = self
+            .transaction
+            .query_one(statement, params)
+            .await
+            .map_err(ArcaneVaultError::from)?;
+        get_instance_func(&row).map_err(ArcaneVaultError::from)
+    }
+
+    #[tracing::instrument(name = "unit_of_work.query_many", skip_all, fields(db.system = "postgresql", db.statement = statement))]
+    pub async fn query_many<T>(
+        &self,
+        statement: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+        get_instance_func: fn(&tokio_postgres::row::Row) -> Result<T, tokio_postgres::Error>,
+    ) -> Result<Vec<T>, ArcaneVaultError> {
+        let rows = self
+            .transaction
+            .query(statement, params)
+            .await
+            .map_err(ArcaneVaultError::from)?;
+        rows.iter()
+            .map(|row| get_instance_func(row).map_err(ArcaneVaultError::from))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn get_error(code: ArcaneVaultErrorCode) -> ArcaneVaultError {
+        ArcaneVaultError {
+            message: String::new(),
+            code: Some(code),
+            metadata: HashMap::new(),
         }
-        ))
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn retries_transient_errors_of_idempotent_operations() {
+        for code in [
+            ArcaneVaultErrorCode::ConnectionLost,
+            ArcaneVaultErrorCode::PoolTimeout,
+            ArcaneVaultErrorCode::SerializationFailure,
+            ArcaneVaultErrorCode::DeadlockDetected,
+        ] {
+            assert!(is_retryable(true, &get_error(code)));
+        }
+    }
+
+    #[test]
+    fn retries_non_idempotent_operations_only_when_rolled_back() {
+        assert!(is_retryable(
+            false,
+            &get_error(ArcaneVaultErrorCode::SerializationFailure)
+        ));
+        assert!(is_retryable(
+            false,
+            &get_error(ArcaneVaultErrorCode::DeadlockDetected)
+        ));
+        // A lost connection may have lost the reply to an applied statement.
+        assert!(!is_retryable(
+            false,
+            &get_error(ArcaneVaultErrorCode::ConnectionLost)
+        ));
+        assert!(!is_retryable(
+            false,
+            &get_error(ArcaneVaultErrorCode::PoolTimeout)
+        ));
+    }
+
+    #[test]
+    fn never_retries_permanent_errors() {
+        for is_idempotent in [true, false] {
+            for code in [
+                ArcaneVaultErrorCode::NoData,
+                ArcaneVaultErrorCode::SchemaMismatch,
+                ArcaneVaultErrorCode::InnerError("PA002".into()),
+                ArcaneVaultErrorCode::InnerError("23505".into()),
+            ] {
+                assert!(!is_retryable(is_idempotent, &get_error(code)));
+            }
+            let error = ArcaneVaultError {
+                message: String::new(),
+                code: None,
+                metadata: HashMap::new(),
+            };
+            assert!(!is_retryable(is_idempotent, &error));
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs PostgreSQL with the easynote database on localhost:5432"]
+    async fn runs_the_whole_transaction_again_after_a_serialization_failure() {
+        let mut config = deadpool_postgres::Config::new();
+        config.host = Some("localhost".to_string());
+        config.user = Some("easynote".to_string());
+        config.password = Some("easynote_password".to_string());
+        config.dbname = Some("easynote".to_string());
+        let pool = config
+            .create_pool(
+                Some(deadpool_postgres::Runtime::Tokio1),
+                tokio_postgres::NoTls,
+            )
+            .unwrap();
+        let table = format!("retry_test_{}", uuid::Uuid::new_v4().simple());
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(&format!(
+                "CREATE TABLE {table} (value INTEGER NOT NULL); INSERT INTO {table} VALUES (0)"
+            ))
+            .await
+            .unwrap();
+        let repository = Repository::new(
+            pool.clone(),
+            RetryPolicy {
+                max_retries: 3,
+                base_delay_ms: 1,
+                max_delay_ms: 1,
+            },
+        )
+        .non_idempotent();
+        let attempts = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let (select, update) = (
+            format!("SELECT value FROM {table}"),
+            format!("UPDATE {table} SET value = $1"),
+        );
+
+        let result = repository
+            .transaction(IsolationLevel::RepeatableRead, |unit_of_work| {
+                let (pool, attempts) = (pool.clone(), attempts.clone());
+                let (select, update) = (select.clone(), update.clone());
+                Box::pin(async move {
+                    let value: i32 = unit_of_work
+                        .query_one(&select, &[], |row| row.try_get(0))
+                        .await?;
+                    // Another connection changes the row after this transaction read it, the first
+                    // time only.
+                    if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                        pool.get().await?.execute(&update, &[&100]).await?;
+                    }
+                    unit_of_work.execute(&update, &[&(value + 1)]).await?;
+                    Ok(value)
+                })
+            })
+            .await;
+
+        let value: i32 = client
+            .query_one(&format!("SELECT value FROM {table}"), &[])
+            .await
+            .unwrap()
+            .get(0);
+        client
+            .batch_execute(&format!("DROP TABLE {table}"))
+            .await
+            .unwrap();
+        assert_eq!(result.unwrap(), 100);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(value, 101);
+    }
+
+    #[test]
+    fn backs_off_exponentially_up_to_the_maximum_delay() {
+        let retry_policy = RetryPolicy {
+            max_retries: 10,
+            base_delay_ms: 100,
+            max_delay_ms: 1000,
+        };
+        for (attempts, delay_ms) in [
+            (1, 100),
+            (2, 200),
+            (3, 400),
+            (4, 800),
+            (5, 1000),
+            (40, 1000),
+        ] {
+            for _ in 0..100 {
+                let delay = retry_policy.get_delay(attempts);
+                assert!(delay >= Duration::from_millis(delay_ms / 2), "{:?}", delay);
+                assert!(delay <= Duration::from_millis(delay_ms), "{:?}", delay);
+            }
+        }
+    }
+}
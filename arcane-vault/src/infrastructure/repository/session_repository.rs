@@ -0,0 +1,156 @@
+use ethereal_core::proto::Session;
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{Principal, SessionEntity, SessionPrincipalEntity, SessionTokenHashes},
+        error::ArcaneVaultError,
+    },
+    infrastructure::repository::{
+        ADD_SESSION, DbContext, QUERY_SESSION_BY_ACCESS_TOKEN, QUERY_SESSIONS, REFRESH_SESSION,
+        REVOKE_SESSION, REVOKE_SESSIONS,
+    },
+};
+
+/// [`crate::domain::repository::SessionRepository`] backed by the functions of `script/easynote_common.sql`.
+pub struct PostgresSessionRepository {
+    db_context: DbContext,
+}
+
+impl PostgresSessionRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::SessionRepository for PostgresSessionRepository {
+    async fn add_session(
+        &self,
+        user_id: Uuid,
+        device_label: &str,
+        user_agent: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                ADD_SESSION.sql,
+                &[
+                    &user_id,
+                    &device_label,
+                    &user_agent,
+                    &tokens.access_token_hash,
+                    &tokens.refresh_token_hash,
+                    &get_ttl_secs(tokens.access_token_ttl),
+                    &get_ttl_secs(tokens.refresh_token_ttl),
+                ],
+                get_session_id_from_row,
+            )
+            .await
+    }
+
+    async fn refresh_session(
+        &self,
+        refresh_token_hash: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                REFRESH_SESSION.sql,
+                &[
+                    &refresh_token_hash,
+                    &tokens.access_token_hash,
+                    &tokens.refresh_token_hash,
+                    &get_ttl_secs(tokens.access_token_ttl),
+                    &get_ttl_secs(tokens.refresh_token_ttl),
+                ],
+                get_refreshed_session_id_from_row,
+            )
+            .await
+    }
+
+    async fn query_session_by_access_token(
+        &self,
+        access_token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError> {
+        let principals = self
+            .db_context
+            .get_repository()
+            .await
+            .query_many(
+                QUERY_SESSION_BY_ACCESS_TOKEN.sql,
+                &[&access_token_hash],
+                get_principal_from_row,
+            )
+            .await?;
+        Ok(principals.into_iter().next())
+    }
+
+    async fn query_sessions(&self, user_id: Uuid) -> Result<Vec<Session>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_many(QUERY_SESSIONS.sql, &[&user_id], get_session_from_row)
+            .await
+    }
+
+    async fn revoke_session(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(REVOKE_SESSION.sql, &[&user_id, &session_id])
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke_sessions(&self, user_id: Uuid) -> Result<i32, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                REVOKE_SESSIONS.sql,
+                &[&user_id],
+                get_revoked_sessions_from_row,
+            )
+            .await
+    }
+}
+
+// `SessionTokenIssuer` keeps the lifetimes within INTEGER.
+fn get_ttl_secs(ttl: std::time::Duration) -> i32 {
+    ttl.as_secs().try_into().unwrap_or(i32::MAX)
+}
+
+fn get_session_id_from_row(row: &tokio_postgres::Row) -> Result<Uuid, tokio_postgres::Error> {
+    row.try_get("session_id")
+}
+
+fn get_refreshed_session_id_from_row(
+    row: &tokio_postgres::Row,
+) -> Result<Option<Uuid>, tokio_postgres::Error> {
+    row.try_get("session_id")
+}
+
+fn get_revoked_sessions_from_row(row: &tokio_postgres::Row) -> Result<i32, tokio_postgres::Error> {
+    row.try_get("revoked_sessions")
+}
+
+fn get_session_from_row(row: &tokio_postgres::Row) -> Result<Session, tokio_postgres::Error> {
+    SessionEntity::from_row(row).map(Session::from)
+}
+
+fn get_principal_from_row(row: &tokio_postgres::Row) -> Result<Principal, tokio_postgres::Error> {
+    SessionPrincipalEntity::from_row(row).map(Principal::from)
+}
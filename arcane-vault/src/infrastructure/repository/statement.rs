@@ -0,0 +1,596 @@
+use sorcerers_kit::FromRow;
+use tokio_postgres::Column;
+use tokio_postgres::types::FromSql;
+
+use crate::domain::entity::{
+    AttachmentBlobEntity, AttachmentEntity, AuditEventEntity, EmailChangeEntity,
+    ExternalIdentityEntity, LocaleEntity, NoteRevisionEntity, OidcLoginAttemptEntity,
+    PersonalAccessTokenEntity, PersonalAccessTokenPrincipalEntity, ReferenceEntity, SessionEntity,
+    SessionPrincipalEntity, TotpEntity, UserEntity,
+};
+
+/// A SQL statement arcane-vault runs, with the check for the columns its row mapper reads.
+#[derive(Debug)]
+pub struct SqlStatement {
+    pub name: &'static str,
+    pub sql: &'static str,
+    pub check_columns: fn(&[Column]) -> Vec<String>,
+}
+
+pub const REGISTER_USER: SqlStatement = SqlStatement {
+    name: "register_user",
+    sql: r#"
+            SELECT func_register_user(
+                $1, $2, $3, $4, $5, $6, $7, $8
+            ) AS verification_code
+        "#,
+    check_columns: |columns| check_column::<String>(columns, "verification_code"),
+};
+
+pub const VERIFY_USER: SqlStatement = SqlStatement {
+    name: "verify_user",
+    sql: r#"
+            SELECT func_verify_user(
+                $1, $2, $3
+            ) AS user_id
+        "#,
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "user_id"),
+};
+
+pub const REGENERATE_VERIFICATION_CODE: SqlStatement = SqlStatement {
+    name: "regenerate_verification_code",
+    sql: "SELECT func_regenerate_verification_code($1) AS verification_code",
+    check_columns: |columns| check_column::<String>(columns, "verification_code"),
+};
+
+pub const LOGIN_USER: SqlStatement = SqlStatement {
+    name: "login_user",
+    sql: "SELECT func_login_user($1, $2) AS user_id",
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "user_id"),
+};
+
+pub const REQUEST_RESET_PASSWORD: SqlStatement = SqlStatement {
+    name: "request_reset_password",
+    sql: "SELECT func_request_reset_password($1) AS verification_code",
+    check_columns: |columns| check_column::<String>(columns, "verification_code"),
+};
+
+pub const RESET_PASSWORD: SqlStatement = SqlStatement {
+    name: "reset_password",
+    sql: "SELECT func_reset_password($1, $2, $3)",
+    check_columns: check_no_columns,
+};
+
+pub const SET_PASSWORD: SqlStatement = SqlStatement {
+    name: "set_password",
+    sql: "SELECT func_set_password($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const REQUEST_EMAIL_CHANGE: SqlStatement = SqlStatement {
+    name: "request_email_change",
+    sql: "SELECT func_request_email_change($1, $2) AS verification_code",
+    check_columns: |columns| check_column::<String>(columns, "verification_code"),
+};
+
+pub const USE_EMAIL_CHANGE_ATTEMPT: SqlStatement = SqlStatement {
+    name: "use_email_change_attempt",
+    sql: "SELECT func_use_email_change_attempt($1) AS is_used",
+    check_columns: |columns| check_column::<bool>(columns, "is_used"),
+};
+
+pub const CONFIRM_EMAIL_CHANGE: SqlStatement = SqlStatement {
+    name: "confirm_email_change",
+    sql: "SELECT * FROM func_confirm_email_change($1, $2)",
+    check_columns: EmailChangeEntity::check_columns,
+};
+
+pub const QUERY_PASSWORD_HASH: SqlStatement = SqlStatement {
+    name: "query_password_hash",
+    sql: "SELECT password AS password_hash FROM users WHERE account = $1",
+    check_columns: |columns| check_column::<String>(columns, "password_hash"),
+};
+
+pub const QUERY_PENDING_PASSWORD_HASH: SqlStatement = SqlStatement {
+    name: "query_pending_password_hash",
+    sql: "SELECT password AS password_hash FROM pending_users WHERE account = $1",
+    check_columns: |columns| check_column::<String>(columns, "password_hash"),
+};
+
+pub const QUERY_USER_BY_ID: SqlStatement = SqlStatement {
+    name: "query_user_by_id",
+    sql: r#"
+            SELECT * FROM func_query_user_by_id(
+                $1
+            )
+        "#,
+    check_columns: UserEntity::check_columns,
+};
+
+pub const QUERY_USER_BY_ACCOUNT: SqlStatement = SqlStatement {
+    name: "query_user_by_account",
+    sql: r#"
+            SELECT * FROM func_query_user_by_account(
+                $1
+            )
+        "#,
+    check_columns: UserEntity::check_columns,
+};
+
+pub const QUERY_USERS: SqlStatement = SqlStatement {
+    name: "query_users",
+    sql: r#"
+            SELECT * FROM func_query_users(
+                $1, $2
+            )
+        "#,
+    check_columns: UserEntity::check_columns,
+};
+
+pub const EXPORT_USERS: SqlStatement = SqlStatement {
+    name: "export_users",
+    sql: r#"
+            SELECT * FROM func_query_users(
+                0, NULL
+            )
+        "#,
+    check_columns: UserEntity::check_columns,
+};
+
+pub const SET_NAME: SqlStatement = SqlStatement {
+    name: "set_name",
+    sql: "SELECT func_set_name($1, $2, $3)",
+    check_columns: check_no_columns,
+};
+
+pub const SET_GENDER: SqlStatement = SqlStatement {
+    name: "set_gender",
+    sql: "SELECT func_set_gender($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const SET_LOCALE: SqlStatement = SqlStatement {
+    name: "set_locale",
+    sql: "SELECT func_set_locale($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const SET_AVATAR: SqlStatement = SqlStatement {
+    name: "set_avatar",
+    sql: "SELECT func_set_avatar($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const SET_SIGNATURE: SqlStatement = SqlStatement {
+    name: "set_signature",
+    sql: "SELECT func_set_signature($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const SET_TIMEZONE: SqlStatement = SqlStatement {
+    name: "set_timezone",
+    sql: "SELECT func_set_timezone($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const ENROLL_TOTP: SqlStatement = SqlStatement {
+    name: "enroll_totp",
+    sql: "SELECT func_enroll_totp($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const QUERY_TOTP: SqlStatement = SqlStatement {
+    name: "query_totp",
+    sql: "SELECT * FROM func_query_totp($1)",
+    check_columns: TotpEntity::check_columns,
+};
+
+pub const CONFIRM_TOTP: SqlStatement = SqlStatement {
+    name: "confirm_totp",
+    sql: "SELECT func_confirm_totp($1, $2, $3)",
+    check_columns: check_no_columns,
+};
+
+pub const DISABLE_TOTP: SqlStatement = SqlStatement {
+    name: "disable_totp",
+    sql: "SELECT func_disable_totp($1)",
+    check_columns: check_no_columns,
+};
+
+pub const USE_TOTP_STEP: SqlStatement = SqlStatement {
+    name: "use_totp_step",
+    sql: "SELECT func_use_totp_step($1, $2) AS is_used",
+    check_columns: |columns| check_column::<bool>(columns, "is_used"),
+};
+
+pub const USE_RECOVERY_CODE: SqlStatement = SqlStatement {
+    name: "use_recovery_code",
+    sql: "SELECT func_use_recovery_code($1, $2) AS is_used",
+    check_columns: |columns| check_column::<bool>(columns, "is_used"),
+};
+
+pub const USE_TOTP_ATTEMPT: SqlStatement = SqlStatement {
+    name: "use_totp_attempt",
+    sql: "SELECT func_use_totp_attempt($1) AS is_used",
+    check_columns: |columns| check_column::<bool>(columns, "is_used"),
+};
+
+pub const RESET_TOTP_ATTEMPTS: SqlStatement = SqlStatement {
+    name: "reset_totp_attempts",
+    sql: "SELECT func_reset_totp_attempts($1)",
+    check_columns: check_no_columns,
+};
+
+pub const REPLACE_RECOVERY_CODES: SqlStatement = SqlStatement {
+    name: "replace_recovery_codes",
+    sql: "SELECT func_replace_recovery_codes($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const ADD_TOTP_CHALLENGE: SqlStatement = SqlStatement {
+    name: "add_totp_challenge",
+    sql: "SELECT func_add_totp_challenge($1) AS challenge_id",
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "challenge_id"),
+};
+
+pub const USE_TOTP_CHALLENGE: SqlStatement = SqlStatement {
+    name: "use_totp_challenge",
+    sql: "SELECT func_use_totp_challenge($1) AS user_id",
+    check_columns: |columns| check_column::<Option<uuid::Uuid>>(columns, "user_id"),
+};
+
+pub const DELETE_TOTP_CHALLENGE: SqlStatement = SqlStatement {
+    name: "delete_totp_challenge",
+    sql: "SELECT func_delete_totp_challenge($1)",
+    check_columns: check_no_columns,
+};
+
+pub const ADD_SESSION: SqlStatement = SqlStatement {
+    name: "add_session",
+    sql: "SELECT func_add_session($1, $2, $3, $4, $5, $6, $7) AS session_id",
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "session_id"),
+};
+
+pub const REFRESH_SESSION: SqlStatement = SqlStatement {
+    name: "refresh_session",
+    sql: "SELECT func_refresh_session($1, $2, $3, $4, $5) AS session_id",
+    check_columns: |columns| check_column::<Option<uuid::Uuid>>(columns, "session_id"),
+};
+
+pub const QUERY_SESSIONS: SqlStatement = SqlStatement {
+    name: "query_sessions",
+    sql: "SELECT * FROM func_query_sessions($1)",
+    check_columns: SessionEntity::check_columns,
+};
+
+pub const REVOKE_SESSION: SqlStatement = SqlStatement {
+    name: "revoke_session",
+    sql: "SELECT func_revoke_session($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const REVOKE_SESSIONS: SqlStatement = SqlStatement {
+    name: "revoke_sessions",
+    sql: "SELECT func_revoke_sessions($1) AS revoked_sessions",
+    check_columns: |columns| check_column::<i32>(columns, "revoked_sessions"),
+};
+
+pub const QUERY_SESSION_BY_ACCESS_TOKEN: SqlStatement = SqlStatement {
+    name: "query_session_by_access_token",
+    sql: "SELECT * FROM func_query_session_by_access_token($1)",
+    check_columns: SessionPrincipalEntity::check_columns,
+};
+
+pub const ADD_PERSONAL_ACCESS_TOKEN: SqlStatement = SqlStatement {
+    name: "add_personal_access_token",
+    sql: "SELECT * FROM func_add_personal_access_token($1, $2, $3, $4, $5)",
+    check_columns: PersonalAccessTokenEntity::check_columns,
+};
+
+pub const QUERY_PERSONAL_ACCESS_TOKENS: SqlStatement = SqlStatement {
+    name: "query_personal_access_tokens",
+    sql: "SELECT * FROM func_query_personal_access_tokens($1)",
+    check_columns: PersonalAccessTokenEntity::check_columns,
+};
+
+pub const REVOKE_PERSONAL_ACCESS_TOKEN: SqlStatement = SqlStatement {
+    name: "revoke_personal_access_token",
+    sql: "SELECT func_revoke_personal_access_token($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const USE_PERSONAL_ACCESS_TOKEN: SqlStatement = SqlStatement {
+    name: "use_personal_access_token",
+    sql: "SELECT * FROM func_use_personal_access_token($1)",
+    check_columns: PersonalAccessTokenPrincipalEntity::check_columns,
+};
+
+pub const ADD_OIDC_LOGIN_ATTEMPT: SqlStatement = SqlStatement {
+    name: "add_oidc_login_attempt",
+    sql: "SELECT func_add_oidc_login_attempt($1, $2, $3, $4, $5)",
+    check_columns: check_no_columns,
+};
+
+pub const USE_OIDC_LOGIN_ATTEMPT: SqlStatement = SqlStatement {
+    name: "use_oidc_login_attempt",
+    sql: "SELECT * FROM func_use_oidc_login_attempt($1)",
+    check_columns: OidcLoginAttemptEntity::check_columns,
+};
+
+pub const LOGIN_USER_IDENTITY: SqlStatement = SqlStatement {
+    name: "login_user_identity",
+    sql: "SELECT func_login_user_identity($1, $2, $3) AS user_id",
+    check_columns: |columns| check_column::<Option<uuid::Uuid>>(columns, "user_id"),
+};
+
+pub const REGISTER_USER_IDENTITY: SqlStatement = SqlStatement {
+    name: "register_user_identity",
+    sql: "SELECT func_register_user_identity($1, $2, $3, $4, $5, $6, $7) AS user_id",
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "user_id"),
+};
+
+pub const LINK_USER_IDENTITY: SqlStatement = SqlStatement {
+    name: "link_user_identity",
+    sql: "SELECT * FROM func_link_user_identity($1, $2, $3, $4)",
+    check_columns: ExternalIdentityEntity::check_columns,
+};
+
+pub const QUERY_USER_IDENTITIES: SqlStatement = SqlStatement {
+    name: "query_user_identities",
+    sql: "SELECT * FROM func_query_user_identities($1)",
+    check_columns: ExternalIdentityEntity::check_columns,
+};
+
+pub const UNLINK_USER_IDENTITY: SqlStatement = SqlStatement {
+    name: "unlink_user_identity",
+    sql: "SELECT func_unlink_user_identity($1, $2, $3)",
+    check_columns: check_no_columns,
+};
+
+pub const ADD_AUDIT_EVENT: SqlStatement = SqlStatement {
+    name: "add_audit_event",
+    sql: "SELECT func_add_audit_event($1, $2, $3, $4, $5, $6, $7, $8)",
+    check_columns: check_no_columns,
+};
+
+pub const QUERY_AUDIT_EVENTS: SqlStatement = SqlStatement {
+    name: "query_audit_events",
+    sql: "SELECT * FROM func_query_audit_events($1, $2, $3)",
+    check_columns: AuditEventEntity::check_columns,
+};
+
+pub const ADD_COLLECTION: SqlStatement = SqlStatement {
+    name: "add_collection",
+    sql: "SELECT func_add_collection($1, $2, $3, $4) AS collection_id",
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "collection_id"),
+};
+
+pub const CHANGE_DEFAULT_COLLECTION: SqlStatement = SqlStatement {
+    name: "change_default_collection",
+    sql: "SELECT func_change_default_collection($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const GET_DEFAULT_COLLECTION: SqlStatement = SqlStatement {
+    name: "get_default_collection",
+    sql: "SELECT func_get_default_collection($1) AS collection_id",
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "collection_id"),
+};
+
+pub const ADD_NOTE: SqlStatement = SqlStatement {
+    name: "add_note",
+    sql: "SELECT func_add_note($1, $2, $3, $4) AS note_id",
+    check_columns: |columns| check_column::<uuid::Uuid>(columns, "note_id"),
+};
+
+pub const UPDATE_NOTE_META: SqlStatement = SqlStatement {
+    name: "update_note_meta",
+    sql: "SELECT func_update_note_meta($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const DELETE_NOTE: SqlStatement = SqlStatement {
+    name: "delete_note",
+    sql: "SELECT func_delete_note($1)",
+    check_columns: check_no_columns,
+};
+
+pub const ADD_ATTACHMENT: SqlStatement = SqlStatement {
+    name: "add_attachment",
+    sql: "SELECT * FROM func_add_attachment($1, $2, $3, $4, $5, $6)",
+    check_columns: AttachmentEntity::check_columns,
+};
+
+pub const SET_ATTACHMENT_BLOB_STORED: SqlStatement = SqlStatement {
+    name: "set_attachment_blob_stored",
+    sql: "SELECT func_set_attachment_blob_stored($1)",
+    check_columns: check_no_columns,
+};
+
+pub const QUERY_ATTACHMENTS: SqlStatement = SqlStatement {
+    name: "query_attachments",
+    sql: "SELECT * FROM func_query_attachments($1, $2)",
+    check_columns: AttachmentEntity::check_columns,
+};
+
+pub const QUERY_ATTACHMENT: SqlStatement = SqlStatement {
+    name: "query_attachment",
+    sql: "SELECT * FROM func_query_attachment($1, $2)",
+    check_columns: AttachmentEntity::check_columns,
+};
+
+pub const DELETE_ATTACHMENT: SqlStatement = SqlStatement {
+    name: "delete_attachment",
+    sql: "SELECT func_delete_attachment($1, $2)",
+    check_columns: check_no_columns,
+};
+
+pub const COLLECT_ATTACHMENT_BLOBS: SqlStatement = SqlStatement {
+    name: "collect_attachment_blobs",
+    sql: "SELECT * FROM func_collect_attachment_blobs($1, $2)",
+    check_columns: AttachmentBlobEntity::check_columns,
+};
+
+pub const QUERY_NOTE_REVISIONS: SqlStatement = SqlStatement {
+    name: "query_note_revisions",
+    sql: "SELECT * FROM func_query_note_revisions($1, $2, $3, $4)",
+    check_columns: NoteRevisionEntity::check_columns,
+};
+
+pub const QUERY_NOTE_REVISION: SqlStatement = SqlStatement {
+    name: "query_note_revision",
+    sql: "SELECT * FROM func_query_note_revision($1, $2, $3)",
+    check_columns: NoteRevisionEntity::check_columns,
+};
+
+pub const RESTORE_NOTE_REVISION: SqlStatement = SqlStatement {
+    name: "restore_note_revision",
+    sql: "SELECT func_restore_note_revision($1, $2, $3) AS revision",
+    check_columns: |columns| check_column::<i32>(columns, "revision"),
+};
+
+pub const PRUNE_NOTE_REVISIONS: SqlStatement = SqlStatement {
+    name: "prune_note_revisions",
+    sql: "SELECT func_prune_note_revisions($1, $2) AS pruned",
+    check_columns: |columns| check_column::<i32>(columns, "pruned"),
+};
+
+pub const QUERY_GENDERS: SqlStatement = SqlStatement {
+    name: "query_genders",
+    sql: "SELECT id, name, description FROM genders ORDER BY id",
+    check_columns: ReferenceEntity::check_columns,
+};
+
+pub const QUERY_LOCALES: SqlStatement = SqlStatement {
+    name: "query_locales",
+    sql: r#"
+            SELECT id, language_code, locale_code, name_en, native_name, is_rtl, enabled
+            FROM locales
+            ORDER BY id
+        "#,
+    check_columns: LocaleEntity::check_columns,
+};
+
+pub const QUERY_TIMEZONES: SqlStatement = SqlStatement {
+    name: "query_timezones",
+    sql: "SELECT name FROM timezones ORDER BY name",
+    check_columns: |columns| check_column::<String>(columns, "name"),
+};
+
+pub const QUERY_ROLES: SqlStatement = SqlStatement {
+    name: "query_roles",
+    sql: "SELECT id, name, description FROM roles ORDER BY id",
+    check_columns: ReferenceEntity::check_columns,
+};
+
+pub const QUERY_USER_STATUSES: SqlStatement = SqlStatement {
+    name: "query_user_statuses",
+    sql: "SELECT id, name, description FROM user_statuses ORDER BY id",
+    check_columns: ReferenceEntity::check_columns,
+};
+
+pub const QUERY_NOTE_SOURCE_TYPES: SqlStatement = SqlStatement {
+    name: "query_note_source_types",
+    sql: "SELECT id, name, description FROM note_source_types ORDER BY id",
+    check_columns: ReferenceEntity::check_columns,
+};
+
+/// Every statement arcane-vault depends on, checked against the database on startup.
+pub const SQL_STATEMENTS: &[SqlStatement] = &[
+    REGISTER_USER,
+    VERIFY_USER,
+    REGENERATE_VERIFICATION_CODE,
+    LOGIN_USER,
+    REQUEST_RESET_PASSWORD,
+    RESET_PASSWORD,
+    SET_PASSWORD,
+    REQUEST_EMAIL_CHANGE,
+    USE_EMAIL_CHANGE_ATTEMPT,
+    CONFIRM_EMAIL_CHANGE,
+    QUERY_PASSWORD_HASH,
+    QUERY_PENDING_PASSWORD_HASH,
+    QUERY_USER_BY_ID,
+    QUERY_USER_BY_ACCOUNT,
+    QUERY_USERS,
+    EXPORT_USERS,
+    SET_NAME,
+    SET_GENDER,
+    SET_LOCALE,
+    SET_AVATAR,
+    SET_SIGNATURE,
+    SET_TIMEZONE,
+    ENROLL_TOTP,
+    QUERY_TOTP,
+    CONFIRM_TOTP,
+    DISABLE_TOTP,
+    USE_TOTP_STEP,
+    USE_RECOVERY_CODE,
+    USE_TOTP_ATTEMPT,
+    RESET_TOTP_ATTEMPTS,
+    REPLACE_RECOVERY_CODES,
+    ADD_TOTP_CHALLENGE,
+    USE_TOTP_CHALLENGE,
+    DELETE_TOTP_CHALLENGE,
+    ADD_SESSION,
+    REFRESH_SESSION,
+    QUERY_SESSIONS,
+    REVOKE_SESSION,
+    REVOKE_SESSIONS,
+    QUERY_SESSION_BY_ACCESS_TOKEN,
+    ADD_PERSONAL_ACCESS_TOKEN,
+    QUERY_PERSONAL_ACCESS_TOKENS,
+    REVOKE_PERSONAL_ACCESS_TOKEN,
+    USE_PERSONAL_ACCESS_TOKEN,
+    ADD_OIDC_LOGIN_ATTEMPT,
+    USE_OIDC_LOGIN_ATTEMPT,
+    LOGIN_USER_IDENTITY,
+    REGISTER_USER_IDENTITY,
+    LINK_USER_IDENTITY,
+    QUERY_USER_IDENTITIES,
+    UNLINK_USER_IDENTITY,
+    ADD_AUDIT_EVENT,
+    QUERY_AUDIT_EVENTS,
+    ADD_COLLECTION,
+    CHANGE_DEFAULT_COLLECTION,
+    GET_DEFAULT_COLLECTION,
+    ADD_NOTE,
+    UPDATE_NOTE_META,
+    DELETE_NOTE,
+    ADD_ATTACHMENT,
+    SET_ATTACHMENT_BLOB_STORED,
+    QUERY_ATTACHMENTS,
+    QUERY_ATTACHMENT,
+    DELETE_ATTACHMENT,
+    COLLECT_ATTACHMENT_BLOBS,
+    QUERY_NOTE_REVISIONS,
+    QUERY_NOTE_REVISION,
+    RESTORE_NOTE_REVISION,
+    PRUNE_NOTE_REVISIONS,
+    QUERY_GENDERS,
+    QUERY_LOCALES,
+    QUERY_TIMEZONES,
+    QUERY_ROLES,
+    QUERY_USER_STATUSES,
+    QUERY_NOTE_SOURCE_TYPES,
+];
+
+fn check_column<T>(columns: &[Column], name: &str) -> Vec<String>
+where
+    T: for<'a> FromSql<'a>,
+{
+    match columns.iter().find(|column| column.name() == name) {
+        Some(column) if T::accepts(column.type_()) => Vec::new(),
+        Some(column) => vec![format!(
+            "column `{}` is {} but {} is expected",
+            name,
+            column.type_(),
+            std::any::type_name::<T>()
+        )],
+        None => vec![format!("missing column `{}`", name)],
+    }
+}
+
+// Executed only for their side effects, whatever they return is ignored.
+fn check_no_columns(_: &[Column]) -> Vec<String> {
+    Vec::new()
+}
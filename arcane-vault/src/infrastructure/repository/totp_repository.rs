@@ -0,0 +1,182 @@
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::TotpEntity,
+        error::ArcaneVaultError,
+    },
+    infrastructure::repository::{
+        ADD_TOTP_CHALLENGE, CONFIRM_TOTP, DELETE_TOTP_CHALLENGE, DISABLE_TOTP, DbContext,
+        ENROLL_TOTP, QUERY_TOTP, REPLACE_RECOVERY_CODES, RESET_TOTP_ATTEMPTS, USE_RECOVERY_CODE,
+        USE_TOTP_ATTEMPT, USE_TOTP_CHALLENGE, USE_TOTP_STEP,
+    },
+};
+
+/// [`crate::domain::repository::TotpRepository`] backed by the functions of `script/easynote_common.sql`.
+pub struct PostgresTotpRepository {
+    db_context: DbContext,
+}
+
+impl PostgresTotpRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::TotpRepository for PostgresTotpRepository {
+    async fn enroll_totp(&self, user_id: Uuid, secret: &[u8]) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(ENROLL_TOTP.sql, &[&user_id, &secret])
+            .await?;
+        Ok(())
+    }
+
+    async fn query_totp(&self, user_id: Uuid) -> Result<Option<TotpEntity>, ArcaneVaultError> {
+        let totps = self
+            .db_context
+            .get_repository()
+            .await
+            .query_many(QUERY_TOTP.sql, &[&user_id], TotpEntity::from_row)
+            .await?;
+        Ok(totps.into_iter().next())
+    }
+
+    async fn confirm_totp(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(CONFIRM_TOTP.sql, &[&user_id, &step, &recovery_code_hashes])
+            .await?;
+        Ok(())
+    }
+
+    async fn disable_totp(&self, user_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(DISABLE_TOTP.sql, &[&user_id])
+            .await?;
+        Ok(())
+    }
+
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(USE_TOTP_STEP.sql, &[&user_id, &step], get_is_used_from_row)
+            .await
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+    ) -> Result<bool, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                USE_RECOVERY_CODE.sql,
+                &[&user_id, &code_hash],
+                get_is_used_from_row,
+            )
+            .await
+    }
+
+    async fn use_totp_attempt(&self, user_id: Uuid) -> Result<bool, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(USE_TOTP_ATTEMPT.sql, &[&user_id], get_is_used_from_row)
+            .await
+    }
+
+    async fn reset_totp_attempts(&self, user_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(RESET_TOTP_ATTEMPTS.sql, &[&user_id])
+            .await?;
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(
+        &self,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(
+                REPLACE_RECOVERY_CODES.sql,
+                &[&user_id, &recovery_code_hashes],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn add_totp_challenge(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                ADD_TOTP_CHALLENGE.sql,
+                &[&user_id],
+                get_challenge_id_from_row,
+            )
+            .await
+    }
+
+    async fn use_totp_challenge(
+        &self,
+        challenge_id: Uuid,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                USE_TOTP_CHALLENGE.sql,
+                &[&challenge_id],
+                get_user_id_from_row,
+            )
+            .await
+    }
+
+    async fn delete_totp_challenge(&self, challenge_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(DELETE_TOTP_CHALLENGE.sql, &[&challenge_id])
+            .await?;
+        Ok(())
+    }
+}
+
+fn get_is_used_from_row(row: &tokio_postgres::Row) -> Result<bool, tokio_postgres::Error> {
+    row.try_get("is_used")
+}
+
+fn get_challenge_id_from_row(row: &tokio_postgres::Row) -> Result<Uuid, tokio_postgres::Error> {
+    row.try_get("challenge_id")
+}
+
+fn get_user_id_from_row(row: &tokio_postgres::Row) -> Result<Option<Uuid>, tokio_postgres::Error> {
+    row.try_get("user_id")
+}
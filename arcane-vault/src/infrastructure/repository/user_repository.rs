@@ -0,0 +1,350 @@
+use ethereal_core::proto::User;
+use futures_util::{StreamExt, stream::BoxStream};
+use sorcerers_kit::FromRow;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{EmailChangeEntity, NewUser, UserEntity},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        repository::{
+            CONFIRM_EMAIL_CHANGE, DbContext, EXPORT_USERS, IsolationLevel, LOGIN_USER,
+            QUERY_PASSWORD_HASH, QUERY_PENDING_PASSWORD_HASH, QUERY_USER_BY_ACCOUNT,
+            QUERY_USER_BY_ID, QUERY_USERS, REGENERATE_VERIFICATION_CODE, REGISTER_USER,
+            REQUEST_EMAIL_CHANGE, REQUEST_RESET_PASSWORD, RESET_PASSWORD, SET_AVATAR, SET_GENDER,
+            SET_LOCALE, SET_NAME, SET_PASSWORD, SET_SIGNATURE, SET_TIMEZONE, SqlStatement,
+            USE_EMAIL_CHANGE_ATTEMPT, VERIFY_USER,
+        },
+        rule::get_profile_changes,
+    },
+};
+
+/// [`crate::domain::repository::UserRepository`] backed by the functions of `script/easynote_common.sql`.
+pub struct PostgresUserRepository {
+    db_context: DbContext,
+}
+
+impl PostgresUserRepository {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context }
+    }
+
+    // For the `func_set_*` functions, which can safely be run again.
+    async fn execute(
+        &self,
+        statement: &SqlStatement,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one_row(statement.sql, params)
+            .await?;
+        Ok(())
+    }
+
+    async fn query_user(
+        &self,
+        statement: &SqlStatement,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    ) -> Result<Option<User>, ArcaneVaultError> {
+        let users = self
+            .db_context
+            .get_repository()
+            .await
+            .query_many(statement.sql, params, get_user_from_row)
+            .await?;
+        Ok(users.into_iter().next())
+    }
+
+    async fn query_password_hash_by(
+        &self,
+        statement: &SqlStatement,
+        account: &str,
+    ) -> Result<Option<String>, ArcaneVaultError> {
+        let password_hashes = self
+            .db_context
+            .get_repository()
+            .await
+            .query_many(statement.sql, &[&account], get_password_hash_from_row)
+            .await?;
+        Ok(password_hashes.into_iter().next())
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::UserRepository for PostgresUserRepository {
+    async fn register_user(
+        &self,
+        user: &NewUser,
+        password_hash: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                REGISTER_USER.sql,
+                &[
+                    &user.account,
+                    &password_hash,
+                    &user.firstname,
+                    &user.lastname,
+                    &user.gender,
+                    &user.locale,
+                    &user.signature,
+                    &user.timezone,
+                ],
+                get_verification_code_from_row,
+            )
+            .await
+    }
+
+    async fn verify_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+        verification_code: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                VERIFY_USER.sql,
+                &[&account, &password_hash, &verification_code],
+                get_user_id_from_row,
+            )
+            .await
+    }
+
+    async fn regenerate_verification_code(&self, account: &str) -> Result<String, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                REGENERATE_VERIFICATION_CODE.sql,
+                &[&account],
+                get_verification_code_from_row,
+            )
+            .await
+    }
+
+    async fn login_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_one(
+                LOGIN_USER.sql,
+                &[&account, &password_hash],
+                get_user_id_from_row,
+            )
+            .await
+    }
+
+    async fn request_reset_password(&self, account: &str) -> Result<String, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                REQUEST_RESET_PASSWORD.sql,
+                &[&account],
+                get_verification_code_from_row,
+            )
+            .await
+    }
+
+    async fn reset_password(
+        &self,
+        account: &str,
+        verification_code: &str,
+        new_password_hash: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one_row(RESET_PASSWORD.sql, &[&account, &verification_code, &new_password_hash])
+            .await?;
+        Ok(())
+    }
+
+    async fn set_password(&self, id: Uuid, password_hash: &str) -> Result<(), ArcaneVaultError> {
+        self.execute(&SET_PASSWORD, &[&id, &password_hash]).await
+    }
+
+    async fn request_email_change(
+        &self,
+        id: Uuid,
+        account: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                REQUEST_EMAIL_CHANGE.sql,
+                &[&id, &account],
+                get_verification_code_from_row,
+            )
+            .await
+    }
+
+    async fn use_email_change_attempt(&self, id: Uuid) -> Result<bool, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(USE_EMAIL_CHANGE_ATTEMPT.sql, &[&id], get_is_used_from_row)
+            .await
+    }
+
+    async fn confirm_email_change(
+        &self,
+        id: Uuid,
+        verification_code: &str,
+    ) -> Result<EmailChangeEntity, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .non_idempotent()
+            .query_one(
+                CONFIRM_EMAIL_CHANGE.sql,
+                &[&id, &verification_code],
+                EmailChangeEntity::from_row,
+            )
+            .await
+    }
+
+    async fn query_password_hash(&self, account: &str) -> Result<Option<String>, ArcaneVaultError> {
+        self.query_password_hash_by(&QUERY_PASSWORD_HASH, account).await
+    }
+
+    async fn query_pending_password_hash(
+        &self,
+        account: &str,
+    ) -> Result<Option<String>, ArcaneVaultError> {
+        self.query_password_hash_by(&QUERY_PENDING_PASSWORD_HASH, account)
+            .await
+    }
+
+    async fn update_profile(
+        &self,
+        id: Uuid,
+        firstname: &str,
+        lastname: &str,
+        gender: i32,
+        locale: i32,
+    ) -> Result<(User, Vec<&'static str>), ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            // A profile changed by another request after it was read fails the update, which is
+            // then run again, so the changes and the user reported are those that were made.
+            .transaction(IsolationLevel::RepeatableRead, |unit_of_work| {
+                let (firstname, lastname) = (firstname.to_string(), lastname.to_string());
+                Box::pin(async move {
+                    let Some(user) = unit_of_work
+                        .query_many(QUERY_USER_BY_ID.sql, &[&id], get_user_from_row)
+                        .await?
+                        .pop()
+                    else {
+                        return Err(raise_error("func_set_name", "PA007", &[&id.to_string()]));
+                    };
+                    let changes = get_profile_changes(
+                        (&user.firstname, &user.lastname, user.gender, user.locale),
+                        (&firstname, &lastname, gender, locale),
+                    );
+                    if changes.contains(&"name") {
+                        unit_of_work
+                            .execute(SET_NAME.sql, &[&id, &firstname, &lastname])
+                            .await?;
+                    }
+                    if changes.contains(&"gender") {
+                        unit_of_work
+                            .execute(SET_GENDER.sql, &[&id, &gender])
+                            .await?;
+                    }
+                    if changes.contains(&"locale") {
+                        unit_of_work
+                            .execute(SET_LOCALE.sql, &[&id, &locale])
+                            .await?;
+                    }
+                    let user = unit_of_work
+                        .query_one(QUERY_USER_BY_ID.sql, &[&id], get_user_from_row)
+                        .await?;
+                    Ok((user, changes))
+                })
+            })
+            .await
+    }
+
+    async fn set_avatar(&self, id: Uuid, avatar: &str) -> Result<(), ArcaneVaultError> {
+        self.execute(&SET_AVATAR, &[&id, &avatar]).await
+    }
+
+    async fn set_signature(&self, id: Uuid, signature: &str) -> Result<(), ArcaneVaultError> {
+        self.execute(&SET_SIGNATURE, &[&id, &signature]).await
+    }
+
+    async fn set_timezone(&self, id: Uuid, timezone: &str) -> Result<(), ArcaneVaultError> {
+        self.execute(&SET_TIMEZONE, &[&id, &timezone]).await
+    }
+
+    async fn query_user_by_id(&self, id: Uuid) -> Result<Option<User>, ArcaneVaultError> {
+        self.query_user(&QUERY_USER_BY_ID, &[&id]).await
+    }
+
+    async fn query_user_by_account(&self, account: &str) -> Result<Option<User>, ArcaneVaultError> {
+        self.query_user(&QUERY_USER_BY_ACCOUNT, &[&account]).await
+    }
+
+    async fn query_users(&self, offset: i32, limit: i32) -> Result<Vec<User>, ArcaneVaultError> {
+        self.db_context
+            .get_repository()
+            .await
+            .query_many(QUERY_USERS.sql, &[&offset, &limit], get_user_from_row)
+            .await
+    }
+
+    async fn export_users(
+        &self,
+    ) -> Result<BoxStream<'static, Result<User, ArcaneVaultError>>, ArcaneVaultError> {
+        let users = self
+            .db_context
+            .get_repository()
+            .await
+            .query_stream(EXPORT_USERS.sql, &[], get_user_from_row)
+            .await?;
+        Ok(users.boxed())
+    }
+}
+
+fn get_verification_code_from_row(row: &tokio_postgres::Row) -> Result<String, tokio_postgres::Error> {
+    row.try_get("verification_code")
+}
+
+fn get_is_used_from_row(row: &tokio_postgres::Row) -> Result<bool, tokio_postgres::Error> {
+    row.try_get("is_used")
+}
+
+fn get_password_hash_from_row(row: &tokio_postgres::Row) -> Result<String, tokio_postgres::Error> {
+    row.try_get("password_hash")
+}
+
+fn get_user_id_from_row(row: &tokio_postgres::Row) -> Result<Uuid, tokio_postgres::Error> {
+    row.try_get("user_id")
+}
+
+fn get_user_from_row(row: &tokio_postgres::Row) -> Result<User, tokio_postgres::Error> {
+    UserEntity::from_row(row).map(User::from)
+}
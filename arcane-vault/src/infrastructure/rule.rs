@@ -0,0 +1,126 @@
+//! Constants and helpers of the SQL functions, for the backends that implement them in Rust.
+
+use std::time::Duration;
+
+use tokio_postgres::error::SqlState;
+
+use crate::{
+    domain::{entity::NewAuditEvent, error::ArcaneVaultError},
+    infrastructure::error_code::get_sql_error,
+};
+
+pub(crate) const USER_STATUS_ACTIVE: i32 = 1;
+pub(crate) const USER_ROLE_ADMIN: i32 = 1;
+pub(crate) const USER_ROLE_USER: i32 = 2;
+pub(crate) const GENDER_UNKNOWN: i32 = 0;
+/// en-US, the locale of users whose locale is unknown.
+pub(crate) const DEFAULT_LOCALE: i32 = 1;
+/// Password of the users created by an OpenID Connect login, which no password hash equals.
+pub(crate) const NO_PASSWORD: &str = "!";
+/// Timezone of users that did not choose one, same as the `user_profiles.timezone` default.
+pub(crate) const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// How long a verification code blocks generating another one for the same account.
+pub(crate) const VERIFICATION_CODE_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+/// How long a password reset code stays valid.
+pub(crate) const RESET_PASSWORD_EXPIRY: Duration = Duration::from_secs(15 * 60);
+/// How long the code sent to a new email address stays valid.
+pub(crate) const EMAIL_CHANGE_EXPIRY: Duration = Duration::from_secs(15 * 60);
+/// How many codes may be tried against one email change.
+pub(crate) const EMAIL_CHANGE_MAX_ATTEMPTS: i32 = 5;
+/// How long the second step of a login may take.
+pub(crate) const TOTP_CHALLENGE_EXPIRY: Duration = Duration::from_secs(5 * 60);
+/// How many codes may be tried against one login challenge.
+pub(crate) const TOTP_CHALLENGE_MAX_ATTEMPTS: i32 = 5;
+/// How many wrong TOTP or recovery codes in a row lock the second factor of a user.
+pub(crate) const TOTP_MAX_ATTEMPTS: i32 = 5;
+/// How long the second factor stays locked after the last of those attempts.
+pub(crate) const TOTP_LOCKOUT: Duration = Duration::from_secs(15 * 60);
+/// How recently a session must have been started to stand in for the password.
+pub(crate) const RECENT_LOGIN_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// How long expired and revoked sessions are kept, so their refresh tokens still count as reused.
+pub(crate) const ENDED_SESSION_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+/// How long expired and revoked personal access tokens are kept, same as the cleanup job.
+pub(crate) const ENDED_PERSONAL_ACCESS_TOKEN_RETENTION: Duration =
+    Duration::from_secs(24 * 60 * 60);
+
+/// How long an OpenID Connect login may take at the provider.
+pub(crate) const OIDC_LOGIN_ATTEMPT_EXPIRY: Duration = Duration::from_secs(10 * 60);
+
+pub(crate) const DEFAULT_COLLECTION_NAME: &str = "Default";
+pub(crate) const DEFAULT_COLLECTION_DESCRIPTION: &str = "Default collection for user";
+
+// Same as `util_generate_verification_code`: 'I', 'O', '1' and '0' are left out to avoid ambiguity.
+const VERIFICATION_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const VERIFICATION_CODE_LENGTH: usize = 6;
+
+pub(crate) fn generate_verification_code() -> String {
+    (0..VERIFICATION_CODE_LENGTH)
+        .map(|_| VERIFICATION_CODE_CHARS[fastrand::usize(..VERIFICATION_CODE_CHARS.len())] as char)
+        .collect()
+}
+
+/// Same as `func_add_audit_event`: values are cut to the lengths of the `audit_events` columns.
+pub(crate) fn get_stored_audit_event(event: &NewAuditEvent) -> NewAuditEvent {
+    let cut = |value: &str, max_len: usize| value.chars().take(max_len).collect();
+    NewAuditEvent {
+        account: cut(&event.account, 255),
+        ip_address: cut(&event.ip_address, 45),
+        user_agent: cut(&event.user_agent, 512),
+        request_id: cut(&event.request_id, 128),
+        ..event.clone()
+    }
+}
+
+/// Same as `func_register_user_identity`: the enabled locale matching the BCP 47 `tag` best,
+/// from `(id, language_code, locale_code)`, or [`DEFAULT_LOCALE`].
+pub(crate) fn find_locale<'a>(
+    locales: impl IntoIterator<Item = (i32, &'a str, &'a str)>,
+    tag: &str,
+) -> i32 {
+    let language = tag.split('-').next().unwrap_or_default();
+    let mut locales: Vec<_> = locales
+        .into_iter()
+        .filter(|(_, language_code, locale_code)| {
+            locale_code.eq_ignore_ascii_case(tag) || language_code.eq_ignore_ascii_case(language)
+        })
+        .collect();
+    locales.sort_by_key(|(id, _, locale_code)| (!locale_code.eq_ignore_ascii_case(tag), *id));
+    locales.first().map_or(DEFAULT_LOCALE, |(id, ..)| *id)
+}
+
+/// Which of `name`, `gender` and `locale` an update of the stored `(firstname, lastname, gender,
+/// locale)` profile to `new` changes.
+pub(crate) fn get_profile_changes(
+    stored: (&str, &str, i32, i32),
+    new: (&str, &str, i32, i32),
+) -> Vec<&'static str> {
+    let mut changes = Vec::new();
+    if (stored.0, stored.1) != (new.0, new.1) {
+        changes.push("name");
+    }
+    if stored.2 != new.2 {
+        changes.push("gender");
+    }
+    if stored.3 != new.3 {
+        changes.push("locale");
+    }
+    changes
+}
+
+/// Rejects a negative OFFSET or LIMIT with the same errors as Postgres.
+pub(crate) fn check_page(offset: i32, limit: i32) -> Result<(), ArcaneVaultError> {
+    if offset < 0 {
+        return Err(get_sql_error(
+            SqlState::INVALID_ROW_COUNT_IN_RESULT_OFFSET_CLAUSE,
+            "OFFSET must not be negative",
+        ));
+    }
+    if limit < 0 {
+        return Err(get_sql_error(
+            SqlState::INVALID_ROW_COUNT_IN_LIMIT_CLAUSE,
+            "LIMIT must not be negative",
+        ));
+    }
+    Ok(())
+}
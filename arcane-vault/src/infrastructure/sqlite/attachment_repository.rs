@@ -0,0 +1,237 @@
+use std::time::SystemTime;
+
+use rusqlite::{OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{AttachmentBlobEntity, AttachmentEntity},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        sqlite::sqlite_context::{
+            SqliteContext, check_note_owner, get_micros, get_now, get_system_time,
+        },
+    },
+};
+
+const SELECT_ATTACHMENTS: &str = r#"
+    SELECT a.id, a.note_id, a.blob_id, b.sha256, b.size, a.file_name, a.content_type,
+        a.created_at, b.is_stored
+    FROM note_attachments a
+    JOIN attachment_blobs b ON b.id = a.blob_id
+"#;
+
+/// [`crate::domain::repository::AttachmentRepository`] backed by `script/easynote_sqlite.sql`,
+/// implementing the attachment functions of `script/easynote_note.sql` in Rust. The blobs count
+/// their attachments by trigger, as in Postgres.
+pub struct SqliteAttachmentRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteAttachmentRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::AttachmentRepository for SqliteAttachmentRepository {
+    async fn add_attachment(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        sha256: &str,
+        size: i64,
+        file_name: &str,
+        content_type: &str,
+    ) -> Result<AttachmentEntity, ArcaneVaultError> {
+        let (sha256, file_name, content_type) = (
+            sha256.to_string(),
+            file_name.to_string(),
+            content_type.to_string(),
+        );
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                check_note_owner(&transaction, "func_add_attachment", user_id, note_id)?;
+                let blob_id: Option<Uuid> = transaction
+                    .query_row(
+                        "SELECT id FROM attachment_blobs WHERE user_id = ?1 AND sha256 = ?2",
+                        params![user_id, sha256],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let blob_id = match blob_id {
+                    Some(blob_id) => blob_id,
+                    None => {
+                        let blob_id = Uuid::new_v4();
+                        transaction.execute(
+                            r#"
+                                INSERT INTO attachment_blobs (id, user_id, sha256, size, created_at)
+                                VALUES (?1, ?2, ?3, ?4, ?5)
+                            "#,
+                            params![blob_id, user_id, sha256, size, get_now()],
+                        )?;
+                        blob_id
+                    }
+                };
+
+                let id = Uuid::new_v4();
+                transaction.execute(
+                    r#"
+                        INSERT INTO note_attachments (
+                            id, note_id, blob_id, file_name, content_type, created_at
+                        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    "#,
+                    params![id, note_id, blob_id, file_name, content_type, get_now()],
+                )?;
+                let attachment = transaction.query_row(
+                    &format!("{} WHERE a.id = ?1", SELECT_ATTACHMENTS),
+                    params![id],
+                    get_attachment_from_row,
+                )?;
+                transaction.commit()?;
+                Ok(attachment)
+            })
+            .await
+    }
+
+    async fn set_attachment_blob_stored(&self, blob_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                connection.execute(
+                    "UPDATE attachment_blobs SET is_stored = 1 WHERE id = ?1",
+                    params![blob_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn query_attachments(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+    ) -> Result<Vec<AttachmentEntity>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                check_note_owner(connection, "func_query_attachments", user_id, note_id)?;
+                let mut statement = connection.prepare(&format!(
+                    "{} WHERE a.note_id = ?1 ORDER BY a.created_at, a.id",
+                    SELECT_ATTACHMENTS
+                ))?;
+                let attachments = statement
+                    .query_map(params![note_id], get_attachment_from_row)?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(attachments)
+            })
+            .await
+    }
+
+    async fn query_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<AttachmentEntity, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                connection
+                    .query_row(
+                        &format!("{} WHERE a.id = ?1 AND b.user_id = ?2", SELECT_ATTACHMENTS),
+                        params![attachment_id, user_id],
+                        get_attachment_from_row,
+                    )
+                    .optional()?
+                    .ok_or_else(|| {
+                        raise_error(
+                            "func_query_attachment",
+                            "PN006",
+                            &[&attachment_id.to_string()],
+                        )
+                    })
+            })
+            .await
+    }
+
+    async fn delete_attachment(
+        &self,
+        user_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let deleted = connection.execute(
+                    r#"
+                        DELETE FROM note_attachments
+                        WHERE id = ?1
+                            AND blob_id IN (SELECT id FROM attachment_blobs WHERE user_id = ?2)
+                    "#,
+                    params![attachment_id, user_id],
+                )?;
+                if deleted == 0 {
+                    return Err(raise_error(
+                        "func_delete_attachment",
+                        "PN006",
+                        &[&attachment_id.to_string()],
+                    ));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn collect_attachment_blobs(
+        &self,
+        orphaned_before: SystemTime,
+        limit: i32,
+    ) -> Result<Vec<AttachmentBlobEntity>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let blobs = {
+                    let mut statement = transaction.prepare(
+                        r#"
+                            SELECT id, user_id, sha256
+                            FROM attachment_blobs
+                            WHERE ref_count = 0 AND orphaned_at < ?1
+                            LIMIT ?2
+                        "#,
+                    )?;
+                    statement
+                        .query_map(params![get_micros(orphaned_before), limit], |row| {
+                            Ok(AttachmentBlobEntity {
+                                id: row.get("id")?,
+                                user_id: row.get("user_id")?,
+                                sha256: row.get("sha256")?,
+                            })
+                        })?
+                        .collect::<Result<Vec<_>, _>>()?
+                };
+                for blob in &blobs {
+                    transaction.execute(
+                        "DELETE FROM attachment_blobs WHERE id = ?1",
+                        params![blob.id],
+                    )?;
+                }
+                transaction.commit()?;
+                Ok(blobs)
+            })
+            .await
+    }
+}
+
+fn get_attachment_from_row(row: &rusqlite::Row) -> rusqlite::Result<AttachmentEntity> {
+    Ok(AttachmentEntity {
+        id: row.get("id")?,
+        note_id: row.get("note_id")?,
+        blob_id: row.get("blob_id")?,
+        sha256: row.get("sha256")?,
+        size: row.get("size")?,
+        file_name: row.get("file_name")?,
+        content_type: row.get("content_type")?,
+        created_at: get_system_time(row.get("created_at")?),
+        is_stored: row.get("is_stored")?,
+    })
+}
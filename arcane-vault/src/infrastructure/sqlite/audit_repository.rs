@@ -0,0 +1,113 @@
+use ethereal_core::proto::AuditEvent;
+use rusqlite::{Connection, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::NewAuditEvent, error::ArcaneVaultError},
+    infrastructure::{
+        rule::{check_page, get_stored_audit_event},
+        sqlite::sqlite_context::{SqliteContext, get_account, get_now, get_system_time},
+    },
+};
+
+/// [`crate::domain::repository::AuditRepository`] backed by `script/easynote_sqlite.sql`,
+/// implementing the audit functions of `script/easynote_common.sql` in Rust.
+pub struct SqliteAuditRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteAuditRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::AuditRepository for SqliteAuditRepository {
+    async fn add_audit_event(&self, event: &NewAuditEvent) -> Result<(), ArcaneVaultError> {
+        let event = event.clone();
+        self.sqlite_context
+            .run(move |connection| add_audit_event(connection, &event))
+            .await
+    }
+
+    async fn query_audit_events(
+        &self,
+        user_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<AuditEvent>, ArcaneVaultError> {
+        check_page(offset, limit)?;
+        self.sqlite_context
+            .run(move |connection| {
+                let account = get_account(connection, "func_query_audit_events", user_id)?;
+                let mut statement = connection.prepare(
+                    r#"
+                        SELECT id, user_id, actor_id, event_type, account, details, ip_address,
+                            user_agent, request_id, created_at
+                        FROM audit_events
+                        WHERE user_id = ?1 OR (user_id IS NULL AND account = ?2)
+                        ORDER BY id DESC
+                        LIMIT ?3 OFFSET ?4
+                    "#,
+                )?;
+                let events = statement
+                    .query_map(
+                        params![user_id, account, limit, offset],
+                        get_audit_event_from_row,
+                    )?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(events)
+            })
+            .await
+    }
+}
+
+/// Same as `func_add_audit_event`.
+pub(super) fn add_audit_event(
+    connection: &Connection,
+    event: &NewAuditEvent,
+) -> Result<(), ArcaneVaultError> {
+    let event = get_stored_audit_event(event);
+    connection.execute(
+        r#"
+            INSERT INTO audit_events (
+                user_id, actor_id, event_type, account, details, ip_address, user_agent,
+                request_id, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#,
+        params![
+            event.user_id,
+            event.actor_id,
+            event.event_type,
+            event.account,
+            event.details,
+            event.ip_address,
+            event.user_agent,
+            event.request_id,
+            get_now()
+        ],
+    )?;
+    Ok(())
+}
+
+fn get_audit_event_from_row(row: &rusqlite::Row) -> rusqlite::Result<AuditEvent> {
+    let get_id = |column: &str| -> rusqlite::Result<String> {
+        Ok(row
+            .get::<_, Option<Uuid>>(column)?
+            .map(|id| id.to_string())
+            .unwrap_or_default())
+    };
+    Ok(AuditEvent {
+        id: row.get("id")?,
+        user_id: get_id("user_id")?,
+        actor_id: get_id("actor_id")?,
+        event_type: row.get("event_type")?,
+        account: row.get("account")?,
+        details: row.get("details")?,
+        ip_address: row.get("ip_address")?,
+        user_agent: row.get("user_agent")?,
+        request_id: row.get("request_id")?,
+        created_at: Some(get_system_time(row.get("created_at")?).into()),
+    })
+}
@@ -0,0 +1,146 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::error::ArcaneVaultError,
+    infrastructure::{
+        error_code::raise_error,
+        rule::{DEFAULT_COLLECTION_DESCRIPTION, DEFAULT_COLLECTION_NAME},
+        sqlite::sqlite_context::{SqliteContext, get_account},
+    },
+};
+
+/// [`crate::domain::repository::CollectionRepository`] backed by `script/easynote_sqlite.sql`.
+pub struct SqliteCollectionRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteCollectionRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::CollectionRepository for SqliteCollectionRepository {
+    async fn add_collection(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        description: &str,
+        is_active: bool,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let (name, description) = (name.to_string(), description.to_string());
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let id = add_collection(&transaction, user_id, &name, &description, is_active)?;
+                transaction.commit()?;
+                Ok(id)
+            })
+            .await
+    }
+
+    async fn change_default_collection(
+        &self,
+        user_id: Uuid,
+        collection_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_change_default_collection";
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let account = get_account(&transaction, FUNCTION, user_id)?;
+                let is_owned: bool = transaction.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM collections WHERE id = ?1 AND user_id = ?2)",
+                    params![collection_id, user_id],
+                    |row| row.get(0),
+                )?;
+                if !is_owned {
+                    return Err(raise_error(
+                        FUNCTION,
+                        "PN002",
+                        &[&collection_id.to_string(), &account],
+                    ));
+                }
+
+                // Cleared first, so `idx_collections_user_default` holds after each statement.
+                transaction.execute(
+                    "UPDATE collections SET is_active = 0 WHERE user_id = ?1 AND id <> ?2",
+                    params![user_id, collection_id],
+                )?;
+                transaction.execute(
+                    "UPDATE collections SET is_active = 1 WHERE id = ?1",
+                    params![collection_id],
+                )?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_default_collection(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                get_account(&transaction, "func_get_default_collection", user_id)?;
+                let id: Option<Uuid> = transaction
+                    .query_row(
+                        "SELECT id FROM collections WHERE user_id = ?1 AND is_active = 1",
+                        params![user_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let id = match id {
+                    Some(id) => id,
+                    None => add_collection(
+                        &transaction,
+                        user_id,
+                        DEFAULT_COLLECTION_NAME,
+                        DEFAULT_COLLECTION_DESCRIPTION,
+                        true,
+                    )?,
+                };
+                transaction.commit()?;
+                Ok(id)
+            })
+            .await
+    }
+}
+
+fn add_collection(
+    connection: &Connection,
+    user_id: Uuid,
+    name: &str,
+    description: &str,
+    is_active: bool,
+) -> Result<Uuid, ArcaneVaultError> {
+    const FUNCTION: &str = "func_add_collection";
+    let account = get_account(connection, FUNCTION, user_id)?;
+    let (is_taken, has_default): (bool, bool) = connection.query_row(
+        r#"
+            SELECT EXISTS (SELECT 1 FROM collections WHERE user_id = ?1 AND name = ?2),
+                EXISTS (SELECT 1 FROM collections WHERE user_id = ?1 AND is_active = 1)
+        "#,
+        params![user_id, name],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    if is_taken {
+        return Err(raise_error(FUNCTION, "PN001", &[name, &account]));
+    }
+    // The first collection of a user always becomes the default.
+    let is_active = is_active || !has_default;
+
+    if is_active {
+        connection.execute(
+            "UPDATE collections SET is_active = 0 WHERE user_id = ?1",
+            params![user_id],
+        )?;
+    }
+    let id = Uuid::new_v4();
+    connection.execute(
+        "INSERT INTO collections (id, user_id, name, description, is_active) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, user_id, name, description, is_active],
+    )?;
+    Ok(id)
+}
@@ -0,0 +1,97 @@
+use rusqlite::params;
+use uuid::Uuid;
+
+use crate::{
+    domain::error::ArcaneVaultError,
+    infrastructure::{
+        error_code::raise_error,
+        sqlite::sqlite_context::{SqliteContext, get_now},
+    },
+};
+
+/// [`crate::domain::repository::NoteRepository`] backed by `script/easynote_sqlite.sql`.
+pub struct SqliteNoteRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteNoteRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::NoteRepository for SqliteNoteRepository {
+    async fn add_note(
+        &self,
+        content: &str,
+        collection_id: Uuid,
+        source_type: i32,
+        meta: Option<serde_json::Value>,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let content = content.to_string();
+        let meta = meta.unwrap_or_else(|| serde_json::json!({}));
+        self.sqlite_context
+            .run(move |connection| {
+                let is_valid: bool = connection.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM note_source_types WHERE id = ?1)",
+                    params![source_type],
+                    |row| row.get(0),
+                )?;
+                if !is_valid {
+                    return Err(raise_error("func_add_note", "PN004", &[&source_type.to_string()]));
+                }
+
+                let id = Uuid::new_v4();
+                connection.execute(
+                    r#"
+                        INSERT INTO notes (id, collection_id, created_at, updated_at, content, source_type, meta)
+                        VALUES (?1, ?2, ?3, ?3, ?4, ?5, ?6)
+                    "#,
+                    params![id, collection_id, get_now(), content, source_type, meta],
+                )?;
+                Ok(id)
+            })
+            .await
+    }
+
+    async fn update_note_meta(
+        &self,
+        note_id: Uuid,
+        meta: serde_json::Value,
+    ) -> Result<(), ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let updated = connection.execute(
+                    "UPDATE notes SET meta = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![meta, get_now(), note_id],
+                )?;
+                if updated == 0 {
+                    return Err(raise_error(
+                        "func_update_note_meta",
+                        "PN005",
+                        &[&note_id.to_string()],
+                    ));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn delete_note(&self, note_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let deleted =
+                    connection.execute("DELETE FROM notes WHERE id = ?1", params![note_id])?;
+                if deleted == 0 {
+                    return Err(raise_error(
+                        "func_delete_note",
+                        "PN005",
+                        &[&note_id.to_string()],
+                    ));
+                }
+                Ok(())
+            })
+            .await
+    }
+}
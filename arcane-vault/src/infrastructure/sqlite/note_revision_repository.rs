@@ -0,0 +1,191 @@
+use std::time::SystemTime;
+
+use rusqlite::{OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::NoteRevisionEntity, error::ArcaneVaultError},
+    infrastructure::{
+        error_code::raise_error,
+        sqlite::sqlite_context::{
+            SqliteContext, check_note_owner, get_micros, get_now, get_system_time,
+        },
+    },
+};
+
+// The note itself as its latest revision, then the earlier ones.
+const SELECT_NOTE_REVISIONS: &str = r#"
+    SELECT note_id, revision, content, meta, created_at
+    FROM (
+        SELECT id AS note_id, revision, content, meta, updated_at AS created_at
+        FROM notes
+        UNION ALL
+        SELECT note_id, revision, content, meta, created_at
+        FROM note_revisions
+    )
+"#;
+
+/// [`crate::domain::repository::NoteRevisionRepository`] backed by `script/easynote_sqlite.sql`,
+/// implementing the revision functions of `script/easynote_note.sql` in Rust. Updates of notes
+/// keep their revisions by trigger, as in Postgres.
+pub struct SqliteNoteRevisionRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteNoteRevisionRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::NoteRevisionRepository for SqliteNoteRevisionRepository {
+    async fn query_note_revisions(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        offset: i32,
+        limit: i32,
+    ) -> Result<Vec<NoteRevisionEntity>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                check_note_owner(connection, "func_query_note_revisions", user_id, note_id)?;
+                let mut statement = connection.prepare(&format!(
+                    "{} WHERE note_id = ?1 ORDER BY revision DESC LIMIT ?2 OFFSET ?3",
+                    SELECT_NOTE_REVISIONS
+                ))?;
+                let revisions = statement
+                    .query_map(params![note_id, limit, offset], get_note_revision_from_row)?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(revisions)
+            })
+            .await
+    }
+
+    async fn query_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<NoteRevisionEntity, ArcaneVaultError> {
+        const FUNCTION: &str = "func_query_note_revision";
+        self.sqlite_context
+            .run(move |connection| {
+                check_note_owner(connection, FUNCTION, user_id, note_id)?;
+                connection
+                    .query_row(
+                        &format!(
+                            "{} WHERE note_id = ?1 AND revision = ?2",
+                            SELECT_NOTE_REVISIONS
+                        ),
+                        params![note_id, revision],
+                        get_note_revision_from_row,
+                    )
+                    .optional()?
+                    .ok_or_else(|| {
+                        raise_error(
+                            FUNCTION,
+                            "PN008",
+                            &[&revision.to_string(), &note_id.to_string()],
+                        )
+                    })
+            })
+            .await
+    }
+
+    async fn restore_note_revision(
+        &self,
+        user_id: Uuid,
+        note_id: Uuid,
+        revision: i32,
+    ) -> Result<i32, ArcaneVaultError> {
+        const FUNCTION: &str = "func_restore_note_revision";
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                check_note_owner(&transaction, FUNCTION, user_id, note_id)?;
+                let stored: Option<(String, serde_json::Value)> = transaction
+                    .query_row(
+                        "SELECT content, meta FROM note_revisions WHERE note_id = ?1 AND revision = ?2",
+                        params![note_id, revision],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                match stored {
+                    Some((content, meta)) => {
+                        transaction.execute(
+                            "UPDATE notes SET content = ?1, meta = ?2, updated_at = ?3 WHERE id = ?4",
+                            params![content, meta, get_now(), note_id],
+                        )?;
+                    }
+                    None => {
+                        // Restoring the latest revision changes nothing.
+                        let is_latest: bool = transaction.query_row(
+                            "SELECT EXISTS (SELECT 1 FROM notes WHERE id = ?1 AND revision = ?2)",
+                            params![note_id, revision],
+                            |row| row.get(0),
+                        )?;
+                        if !is_latest {
+                            return Err(raise_error(
+                                FUNCTION,
+                                "PN008",
+                                &[&revision.to_string(), &note_id.to_string()],
+                            ));
+                        }
+                    }
+                }
+                let revision = transaction.query_row(
+                    "SELECT revision FROM notes WHERE id = ?1",
+                    params![note_id],
+                    |row| row.get(0),
+                )?;
+                transaction.commit()?;
+                Ok(revision)
+            })
+            .await
+    }
+
+    async fn prune_note_revisions(
+        &self,
+        keep_count: Option<i32>,
+        keep_after: Option<SystemTime>,
+    ) -> Result<i32, ArcaneVaultError> {
+        if keep_count.is_none() && keep_after.is_none() {
+            return Ok(0);
+        }
+        let keep_after = keep_after.map(get_micros);
+        self.sqlite_context
+            .run(move |connection| {
+                let pruned = connection.execute(
+                    r#"
+                        DELETE FROM note_revisions
+                        WHERE (note_id, revision) IN (
+                            SELECT note_id, revision
+                            FROM (
+                                SELECT note_id, revision, replaced_at,
+                                    row_number() OVER (
+                                        PARTITION BY note_id ORDER BY revision DESC
+                                    ) AS rank
+                                FROM note_revisions
+                            )
+                            WHERE (?1 IS NULL OR rank > ?1)
+                              AND (?2 IS NULL OR replaced_at < ?2)
+                        )
+                    "#,
+                    params![keep_count, keep_after],
+                )?;
+                Ok(pruned as i32)
+            })
+            .await
+    }
+}
+
+fn get_note_revision_from_row(row: &rusqlite::Row) -> rusqlite::Result<NoteRevisionEntity> {
+    Ok(NoteRevisionEntity {
+        note_id: row.get("note_id")?,
+        revision: row.get("revision")?,
+        content: row.get("content")?,
+        meta: row.get("meta")?,
+        created_at: get_system_time(row.get("created_at")?),
+    })
+}
@@ -0,0 +1,365 @@
+use ethereal_core::proto::ExternalIdentity;
+use rusqlite::{OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::OidcLoginAttemptEntity, error::ArcaneVaultError},
+    infrastructure::{
+        error_code::raise_error,
+        rule::{
+            DEFAULT_TIMEZONE, GENDER_UNKNOWN, NO_PASSWORD, OIDC_LOGIN_ATTEMPT_EXPIRY,
+            USER_ROLE_USER, USER_STATUS_ACTIVE, find_locale,
+        },
+        sqlite::sqlite_context::{SqliteContext, get_account, get_now, get_system_time},
+    },
+};
+
+/// [`crate::domain::repository::OidcRepository`] backed by `script/easynote_sqlite.sql`,
+/// implementing the OIDC functions of `script/easynote_common.sql` in Rust.
+pub struct SqliteOidcRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteOidcRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::OidcRepository for SqliteOidcRepository {
+    async fn add_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+        provider: &str,
+        pkce_verifier: &str,
+        nonce: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<(), ArcaneVaultError> {
+        let (state_hash, provider, pkce_verifier, nonce) = (
+            state_hash.to_string(),
+            provider.to_string(),
+            pkce_verifier.to_string(),
+            nonce.to_string(),
+        );
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                if let Some(user_id) = user_id {
+                    get_account(&transaction, "func_add_oidc_login_attempt", user_id)?;
+                }
+
+                let now = get_now();
+                // What the SQL cleanup job does for `oidc_login_attempts`, as soon as they expire.
+                transaction.execute(
+                    "DELETE FROM oidc_login_attempts WHERE created_at < ?1",
+                    params![now - OIDC_LOGIN_ATTEMPT_EXPIRY.as_micros() as i64],
+                )?;
+                transaction.execute(
+                    r#"
+                        INSERT INTO oidc_login_attempts (
+                            state_hash, provider, pkce_verifier, nonce, user_id, created_at
+                        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    "#,
+                    params![state_hash, provider, pkce_verifier, nonce, user_id, now],
+                )?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn use_oidc_login_attempt(
+        &self,
+        state_hash: &str,
+    ) -> Result<Option<OidcLoginAttemptEntity>, ArcaneVaultError> {
+        let state_hash = state_hash.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let attempt = connection
+                    .query_row(
+                        r#"
+                            DELETE FROM oidc_login_attempts
+                            WHERE state_hash = ?1
+                            RETURNING provider, pkce_verifier, nonce, user_id, created_at
+                        "#,
+                        params![state_hash],
+                        |row| {
+                            let created_at: i64 = row.get(4)?;
+                            Ok((
+                                OidcLoginAttemptEntity {
+                                    provider: row.get(0)?,
+                                    pkce_verifier: row.get(1)?,
+                                    nonce: row.get(2)?,
+                                    user_id: row.get(3)?,
+                                },
+                                created_at,
+                            ))
+                        },
+                    )
+                    .optional()?;
+                let expired_before = get_now() - OIDC_LOGIN_ATTEMPT_EXPIRY.as_micros() as i64;
+                Ok(attempt
+                    .filter(|(_, created_at)| *created_at > expired_before)
+                    .map(|(attempt, _)| attempt))
+            })
+            .await
+    }
+
+    async fn login_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        let (provider, subject, email) =
+            (provider.to_string(), subject.to_string(), email.to_string());
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let now = get_now();
+                let user_id: Option<Uuid> = transaction
+                    .query_row(
+                        r#"
+                            UPDATE user_identities
+                            SET email = ?3, last_login_at = ?4
+                            WHERE provider = ?1 AND subject = ?2
+                            RETURNING user_id
+                        "#,
+                        params![provider, subject, email, now],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(user_id) = user_id {
+                    transaction.execute(
+                        "UPDATE users SET last_login_at = ?2 WHERE id = ?1",
+                        params![user_id, now],
+                    )?;
+                }
+                transaction.commit()?;
+                Ok(user_id)
+            })
+            .await
+    }
+
+    async fn register_user_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        email: &str,
+        firstname: &str,
+        lastname: &str,
+        locale: &str,
+        timezone: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let (provider, subject, email, firstname, lastname, locale, timezone) = (
+            provider.to_string(),
+            subject.to_string(),
+            email.to_string(),
+            firstname.to_string(),
+            lastname.to_string(),
+            locale.to_string(),
+            timezone.to_string(),
+        );
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let is_taken: bool = transaction.query_row(
+                    r#"
+                        SELECT EXISTS (SELECT 1 FROM pending_users WHERE account = ?1)
+                            OR EXISTS (SELECT 1 FROM users WHERE account = ?1)
+                    "#,
+                    params![email],
+                    |row| row.get(0),
+                )?;
+                if is_taken {
+                    return Err(raise_error("func_register_user_identity", "PA001", &[&email]));
+                }
+
+                let locales = {
+                    let mut statement = transaction.prepare(
+                        "SELECT id, language_code, locale_code FROM locales WHERE enabled = 1",
+                    )?;
+                    statement
+                        .query_map(params![], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                        .collect::<Result<Vec<(i32, String, String)>, _>>()?
+                };
+                let locale = find_locale(
+                    locales
+                        .iter()
+                        .map(|(id, language_code, locale_code)| {
+                            (*id, language_code.as_str(), locale_code.as_str())
+                        }),
+                    &locale,
+                );
+                let is_timezone: bool = transaction.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM timezones WHERE name = ?1)",
+                    params![timezone],
+                    |row| row.get(0),
+                )?;
+                let timezone = if is_timezone {
+                    timezone.as_str()
+                } else {
+                    DEFAULT_TIMEZONE
+                };
+
+                let id = Uuid::new_v4();
+                let now = get_now();
+                transaction.execute(
+                    r#"
+                        INSERT INTO users (
+                            id, account, password, created_at, updated_at, last_login_at, status, role
+                        ) VALUES (?1, ?2, ?3, ?4, ?4, ?4, ?5, ?6)
+                    "#,
+                    params![
+                        id,
+                        email,
+                        NO_PASSWORD,
+                        now,
+                        USER_STATUS_ACTIVE,
+                        USER_ROLE_USER
+                    ],
+                )?;
+                transaction.execute(
+                    r#"
+                        INSERT INTO user_profiles (id, firstname, lastname, gender, locale, timezone)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    "#,
+                    params![id, firstname, lastname, GENDER_UNKNOWN, locale, timezone],
+                )?;
+                transaction.execute(
+                    r#"
+                        INSERT INTO user_identities (
+                            provider, subject, user_id, email, created_at, last_login_at
+                        ) VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+                    "#,
+                    params![provider, subject, id, email, now],
+                )?;
+                transaction.commit()?;
+                Ok(id)
+            })
+            .await
+    }
+
+    async fn link_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> Result<ExternalIdentity, ArcaneVaultError> {
+        const FUNCTION: &str = "func_link_user_identity";
+        let (provider, subject, email) =
+            (provider.to_string(), subject.to_string(), email.to_string());
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                get_account(&transaction, FUNCTION, user_id)?;
+                let is_taken: bool = transaction.query_row(
+                    r#"
+                        SELECT EXISTS (
+                            SELECT 1 FROM user_identities
+                            WHERE provider = ?1 AND subject = ?2 AND user_id <> ?3
+                        )
+                    "#,
+                    params![provider, subject, user_id],
+                    |row| row.get(0),
+                )?;
+                if is_taken {
+                    return Err(raise_error(FUNCTION, "PA028", &[&provider]));
+                }
+
+                let identity = transaction.query_row(
+                    r#"
+                        INSERT INTO user_identities (provider, subject, user_id, email, created_at)
+                        VALUES (?1, ?2, ?3, ?4, ?5)
+                        ON CONFLICT (provider, subject) DO UPDATE SET email = excluded.email
+                        RETURNING provider, subject, email, created_at, last_login_at
+                    "#,
+                    params![provider, subject, user_id, email, get_now()],
+                    get_external_identity_from_row,
+                )?;
+                transaction.commit()?;
+                Ok(identity)
+            })
+            .await
+    }
+
+    async fn query_user_identities(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ExternalIdentity>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let mut statement = connection.prepare(
+                    r#"
+                        SELECT provider, subject, email, created_at, last_login_at
+                        FROM user_identities
+                        WHERE user_id = ?1
+                        ORDER BY created_at
+                    "#,
+                )?;
+                let identities = statement
+                    .query_map(params![user_id], get_external_identity_from_row)?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(identities)
+            })
+            .await
+    }
+
+    async fn unlink_user_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_unlink_user_identity";
+        let (provider, subject) = (provider.to_string(), subject.to_string());
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let password: String = transaction
+                    .query_row(
+                        "SELECT password FROM users WHERE id = ?1",
+                        params![user_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?
+                    .ok_or_else(|| raise_error(FUNCTION, "PA007", &[&user_id.to_string()]))?;
+                let identity_count: i64 = transaction.query_row(
+                    "SELECT count(*) FROM user_identities WHERE user_id = ?1",
+                    params![user_id],
+                    |row| row.get(0),
+                )?;
+
+                let deleted = transaction.execute(
+                    r#"
+                        DELETE FROM user_identities
+                        WHERE user_id = ?1 AND provider = ?2 AND subject = ?3
+                    "#,
+                    params![user_id, provider, subject],
+                )?;
+                if deleted == 0 {
+                    return Err(raise_error(FUNCTION, "PA030", &[&provider, &subject]));
+                }
+                if password == NO_PASSWORD && identity_count == 1 {
+                    return Err(raise_error(FUNCTION, "PA031", &[]));
+                }
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+fn get_external_identity_from_row(row: &rusqlite::Row) -> rusqlite::Result<ExternalIdentity> {
+    Ok(ExternalIdentity {
+        provider: row.get("provider")?,
+        subject: row.get("subject")?,
+        email: row.get("email")?,
+        created_at: Some(get_system_time(row.get("created_at")?).into()),
+        last_login_at: row
+            .get::<_, Option<i64>>("last_login_at")?
+            .map(|micros| get_system_time(micros).into()),
+    })
+}
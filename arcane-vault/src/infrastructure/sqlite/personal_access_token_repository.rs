@@ -0,0 +1,196 @@
+use std::time::SystemTime;
+
+use ethereal_core::proto::PersonalAccessToken;
+use rusqlite::{OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{Credential, Principal},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        rule::ENDED_PERSONAL_ACCESS_TOKEN_RETENTION,
+        sqlite::sqlite_context::{
+            SqliteContext, get_account, get_micros, get_now, get_system_time,
+        },
+    },
+};
+
+/// [`crate::domain::repository::PersonalAccessTokenRepository`] backed by
+/// `script/easynote_sqlite.sql`, implementing the personal access token functions of
+/// `script/easynote_common.sql` in Rust.
+pub struct SqlitePersonalAccessTokenRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqlitePersonalAccessTokenRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::PersonalAccessTokenRepository
+    for SqlitePersonalAccessTokenRepository
+{
+    async fn add_personal_access_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        token_hash: &str,
+        scopes: &[String],
+        expires_at: Option<SystemTime>,
+    ) -> Result<PersonalAccessToken, ArcaneVaultError> {
+        let (name, token_hash, scopes) =
+            (name.to_string(), token_hash.to_string(), scopes.join(" "));
+        let expires_at = expires_at.map(get_micros);
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                get_account(&transaction, "func_add_personal_access_token", user_id)?;
+
+                let now = get_now();
+                // What the SQL cleanup job does for `personal_access_tokens`.
+                transaction.execute(
+                    "DELETE FROM personal_access_tokens WHERE expires_at < ?1 OR revoked_at < ?1",
+                    params![now - ENDED_PERSONAL_ACCESS_TOKEN_RETENTION.as_micros() as i64],
+                )?;
+
+                let id = Uuid::new_v4();
+                transaction.execute(
+                    r#"
+                        INSERT INTO personal_access_tokens (
+                            id, user_id, name, token_hash, scopes, created_at, expires_at
+                        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                    "#,
+                    params![id, user_id, name, token_hash, scopes, now, expires_at],
+                )?;
+                let personal_access_token = transaction.query_row(
+                    &format!("{} WHERE id = ?1", SELECT_PERSONAL_ACCESS_TOKENS),
+                    params![id],
+                    get_personal_access_token_from_row,
+                )?;
+                transaction.commit()?;
+                Ok(personal_access_token)
+            })
+            .await
+    }
+
+    async fn query_personal_access_tokens(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<PersonalAccessToken>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let mut statement = connection.prepare(&format!(
+                    r#"
+                        {}
+                        WHERE user_id = ?1
+                            AND revoked_at IS NULL
+                            AND (expires_at IS NULL OR expires_at > ?2)
+                        ORDER BY created_at DESC
+                    "#,
+                    SELECT_PERSONAL_ACCESS_TOKENS
+                ))?;
+                let tokens = statement
+                    .query_map(
+                        params![user_id, get_now()],
+                        get_personal_access_token_from_row,
+                    )?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(tokens)
+            })
+            .await
+    }
+
+    async fn revoke_personal_access_token(
+        &self,
+        user_id: Uuid,
+        token_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_revoke_personal_access_token";
+        self.sqlite_context
+            .run(move |connection| {
+                get_account(connection, FUNCTION, user_id)?;
+                let updated = connection.execute(
+                    r#"
+                        UPDATE personal_access_tokens
+                        SET revoked_at = ?3
+                        WHERE id = ?1
+                            AND user_id = ?2
+                            AND revoked_at IS NULL
+                            AND (expires_at IS NULL OR expires_at > ?3)
+                    "#,
+                    params![token_id, user_id, get_now()],
+                )?;
+                if updated == 0 {
+                    return Err(raise_error(FUNCTION, "PA023", &[&token_id.to_string()]));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn use_personal_access_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError> {
+        let token_hash = token_hash.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let principal = connection
+                    .query_row(
+                        r#"
+                            UPDATE personal_access_tokens
+                            SET last_used_at = ?2
+                            WHERE token_hash = ?1
+                                AND revoked_at IS NULL
+                                AND (expires_at IS NULL OR expires_at > ?2)
+                            RETURNING user_id, id, scopes
+                        "#,
+                        params![token_hash, get_now()],
+                        |row| {
+                            Ok(Principal {
+                                user_id: row.get(0)?,
+                                credential: Credential::PersonalAccessToken {
+                                    token_id: row.get(1)?,
+                                    scopes: get_scopes(&row.get::<_, String>(2)?),
+                                },
+                            })
+                        },
+                    )
+                    .optional()?;
+                Ok(principal)
+            })
+            .await
+    }
+}
+
+const SELECT_PERSONAL_ACCESS_TOKENS: &str = r#"
+    SELECT id, name, scopes, created_at, expires_at, last_used_at
+    FROM personal_access_tokens
+"#;
+
+// `scopes` are separated by spaces.
+fn get_scopes(scopes: &str) -> Vec<String> {
+    scopes.split_whitespace().map(str::to_string).collect()
+}
+
+fn get_personal_access_token_from_row(
+    row: &rusqlite::Row,
+) -> rusqlite::Result<PersonalAccessToken> {
+    Ok(PersonalAccessToken {
+        id: row.get::<_, Uuid>("id")?.to_string(),
+        name: row.get("name")?,
+        scopes: get_scopes(&row.get::<_, String>("scopes")?),
+        created_at: Some(get_system_time(row.get("created_at")?).into()),
+        expires_at: row
+            .get::<_, Option<i64>>("expires_at")?
+            .map(|micros| get_system_time(micros).into()),
+        last_used_at: row
+            .get::<_, Option<i64>>("last_used_at")?
+            .map(|micros| get_system_time(micros).into()),
+    })
+}
@@ -0,0 +1,90 @@
+use ethereal_core::proto::{Locale, ReferenceEntry};
+use rusqlite::{Connection, params};
+
+use crate::{
+    domain::{entity::ReferenceData, error::ArcaneVaultError},
+    infrastructure::sqlite::sqlite_context::SqliteContext,
+};
+
+/// [`crate::domain::repository::ReferenceDataRepository`] backed by `script/easynote_sqlite.sql`.
+pub struct SqliteReferenceDataRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteReferenceDataRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::ReferenceDataRepository for SqliteReferenceDataRepository {
+    async fn query_reference_data(&self) -> Result<ReferenceData, ArcaneVaultError> {
+        self.sqlite_context
+            .run(|connection| {
+                Ok(ReferenceData {
+                    genders: query_reference_entries(connection, "genders")?,
+                    locales: query_locales(connection)?,
+                    timezones: query_timezones(connection)?,
+                    roles: query_reference_entries(connection, "roles")?,
+                    user_statuses: query_reference_entries(connection, "user_statuses")?,
+                    note_source_types: query_reference_entries(connection, "note_source_types")?,
+                })
+            })
+            .await
+    }
+}
+
+fn query_reference_entries(
+    connection: &Connection,
+    table: &str,
+) -> Result<Vec<ReferenceEntry>, ArcaneVaultError> {
+    let mut statement = connection.prepare(&format!(
+        "SELECT id, name, description FROM {} ORDER BY id",
+        table
+    ))?;
+    let entries = statement
+        .query_map(params![], |row| {
+            Ok(ReferenceEntry {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                description: row
+                    .get::<_, Option<String>>("description")?
+                    .unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+fn query_locales(connection: &Connection) -> Result<Vec<Locale>, ArcaneVaultError> {
+    let mut statement = connection.prepare(
+        r#"
+            SELECT id, language_code, locale_code, name_en, native_name, is_rtl, enabled
+            FROM locales
+            ORDER BY id
+        "#,
+    )?;
+    let locales = statement
+        .query_map(params![], |row| {
+            Ok(Locale {
+                id: row.get("id")?,
+                language_code: row.get("language_code")?,
+                locale_code: row.get("locale_code")?,
+                name_en: row.get("name_en")?,
+                native_name: row.get("native_name")?,
+                is_rtl: row.get("is_rtl")?,
+                enabled: row.get("enabled")?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(locales)
+}
+
+fn query_timezones(connection: &Connection) -> Result<Vec<String>, ArcaneVaultError> {
+    let mut statement = connection.prepare("SELECT name FROM timezones ORDER BY name")?;
+    let timezones = statement
+        .query_map(params![], |row| row.get("name"))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(timezones)
+}
@@ -0,0 +1,258 @@
+use ethereal_core::proto::Session;
+use rusqlite::{OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{Credential, Principal, SessionTokenHashes},
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        rule::ENDED_SESSION_RETENTION,
+        sqlite::sqlite_context::{SqliteContext, get_account, get_now, get_system_time},
+    },
+};
+
+/// [`crate::domain::repository::SessionRepository`] backed by `script/easynote_sqlite.sql`,
+/// implementing the session functions of `script/easynote_common.sql` in Rust.
+pub struct SqliteSessionRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteSessionRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::SessionRepository for SqliteSessionRepository {
+    async fn add_session(
+        &self,
+        user_id: Uuid,
+        device_label: &str,
+        user_agent: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let (device_label, user_agent, tokens) = (
+            device_label.to_string(),
+            user_agent.to_string(),
+            tokens.clone(),
+        );
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                get_account(&transaction, "func_add_session", user_id)?;
+
+                let now = get_now();
+                // What the SQL cleanup job does for `sessions`.
+                transaction.execute(
+                    "DELETE FROM sessions WHERE expires_at < ?1 OR revoked_at < ?1",
+                    params![now - ENDED_SESSION_RETENTION.as_micros() as i64],
+                )?;
+
+                let session_id = Uuid::new_v4();
+                transaction.execute(
+                    r#"
+                        INSERT INTO sessions (
+                            id, user_id, device_label, user_agent, access_token_hash,
+                            access_token_expires_at, created_at, last_used_at, expires_at
+                        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, ?8)
+                    "#,
+                    params![
+                        session_id,
+                        user_id,
+                        device_label,
+                        user_agent,
+                        tokens.access_token_hash,
+                        now + tokens.access_token_ttl.as_micros() as i64,
+                        now,
+                        now + tokens.refresh_token_ttl.as_micros() as i64
+                    ],
+                )?;
+                transaction.execute(
+                    r#"
+                        INSERT INTO session_refresh_tokens (token_hash, session_id, created_at)
+                        VALUES (?1, ?2, ?3)
+                    "#,
+                    params![tokens.refresh_token_hash, session_id, now],
+                )?;
+                transaction.commit()?;
+                Ok(session_id)
+            })
+            .await
+    }
+
+    async fn refresh_session(
+        &self,
+        refresh_token_hash: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        let (refresh_token_hash, tokens) = (refresh_token_hash.to_string(), tokens.clone());
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let refresh_token: Option<(Uuid, Option<i64>)> = transaction
+                    .query_row(
+                        "SELECT session_id, used_at FROM session_refresh_tokens WHERE token_hash = ?1",
+                        params![refresh_token_hash],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                let Some((session_id, used_at)) = refresh_token else {
+                    return Ok(None);
+                };
+
+                let now = get_now();
+                if used_at.is_some() {
+                    transaction.execute(
+                        "UPDATE sessions SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL",
+                        params![session_id, now],
+                    )?;
+                    transaction.commit()?;
+                    return Ok(None);
+                }
+
+                let updated = transaction.execute(
+                    r#"
+                        UPDATE sessions
+                        SET access_token_hash = ?2,
+                            access_token_expires_at = ?3,
+                            last_used_at = ?4,
+                            expires_at = ?5
+                        WHERE id = ?1 AND revoked_at IS NULL AND expires_at > ?4
+                    "#,
+                    params![
+                        session_id,
+                        tokens.access_token_hash,
+                        now + tokens.access_token_ttl.as_micros() as i64,
+                        now,
+                        now + tokens.refresh_token_ttl.as_micros() as i64
+                    ],
+                )?;
+                if updated == 0 {
+                    return Ok(None);
+                }
+                transaction.execute(
+                    "UPDATE session_refresh_tokens SET used_at = ?2 WHERE token_hash = ?1",
+                    params![refresh_token_hash, now],
+                )?;
+                transaction.execute(
+                    r#"
+                        INSERT INTO session_refresh_tokens (token_hash, session_id, created_at)
+                        VALUES (?1, ?2, ?3)
+                    "#,
+                    params![tokens.refresh_token_hash, session_id, now],
+                )?;
+                transaction.commit()?;
+                Ok(Some(session_id))
+            })
+            .await
+    }
+
+    async fn query_session_by_access_token(
+        &self,
+        access_token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError> {
+        let access_token_hash = access_token_hash.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let principal = connection
+                    .query_row(
+                        r#"
+                            SELECT user_id, id
+                            FROM sessions
+                            WHERE access_token_hash = ?1
+                                AND access_token_expires_at > ?2
+                                AND revoked_at IS NULL
+                                AND expires_at > ?2
+                        "#,
+                        params![access_token_hash, get_now()],
+                        |row| {
+                            Ok(Principal {
+                                user_id: row.get(0)?,
+                                credential: Credential::Session(row.get(1)?),
+                            })
+                        },
+                    )
+                    .optional()?;
+                Ok(principal)
+            })
+            .await
+    }
+
+    async fn query_sessions(&self, user_id: Uuid) -> Result<Vec<Session>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let mut statement = connection.prepare(
+                    r#"
+                        SELECT id, device_label, user_agent, created_at, last_used_at, expires_at
+                        FROM sessions
+                        WHERE user_id = ?1 AND revoked_at IS NULL AND expires_at > ?2
+                        ORDER BY last_used_at DESC
+                    "#,
+                )?;
+                let sessions = statement
+                    .query_map(params![user_id, get_now()], get_session_from_row)?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(sessions)
+            })
+            .await
+    }
+
+    async fn revoke_session(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_revoke_session";
+        self.sqlite_context
+            .run(move |connection| {
+                get_account(connection, FUNCTION, user_id)?;
+                let now = get_now();
+                let updated = connection.execute(
+                    r#"
+                        UPDATE sessions
+                        SET revoked_at = ?3
+                        WHERE id = ?1 AND user_id = ?2 AND revoked_at IS NULL AND expires_at > ?3
+                    "#,
+                    params![session_id, user_id, now],
+                )?;
+                if updated == 0 {
+                    return Err(raise_error(FUNCTION, "PA019", &[&session_id.to_string()]));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn revoke_sessions(&self, user_id: Uuid) -> Result<i32, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                get_account(connection, "func_revoke_sessions", user_id)?;
+                let now = get_now();
+                let updated = connection.execute(
+                    r#"
+                        UPDATE sessions
+                        SET revoked_at = ?2
+                        WHERE user_id = ?1 AND revoked_at IS NULL AND expires_at > ?2
+                    "#,
+                    params![user_id, now],
+                )?;
+                Ok(updated as i32)
+            })
+            .await
+    }
+}
+
+fn get_session_from_row(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+    Ok(Session {
+        id: row.get::<_, Uuid>("id")?.to_string(),
+        device_label: row.get("device_label")?,
+        user_agent: row.get("user_agent")?,
+        created_at: Some(get_system_time(row.get("created_at")?).into()),
+        last_used_at: Some(get_system_time(row.get("last_used_at")?).into()),
+        expires_at: Some(get_system_time(row.get("expires_at")?).into()),
+    })
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{Connection, OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::error::{ArcaneVaultError, ArcaneVaultErrorCode},
+    infrastructure::error_code::raise_error,
+};
+
+const SCHEMA: &str = include_str!("../../../../script/easynote_sqlite.sql");
+
+/// One connection to the SQLite file at `arcane-vault[0].sqlite_path`, shared by the SQLite
+/// repositories. The schema is created when it is missing.
+#[derive(Clone)]
+pub struct SqliteContext {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteContext {
+    pub async fn new() -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        let path = config
+            .get::<String>("arcane-vault[0].sqlite_path")
+            .unwrap_or_else(|_| "easynote.db".into());
+
+        let connection = spawn_blocking(move || {
+            if let Some(parent) = std::path::Path::new(&path).parent() {
+                std::fs::create_dir_all(parent).map_err(|e| ArcaneVaultError {
+                    message: format!("cannot create {}: {}", parent.display(), e),
+                    code: None,
+                    metadata: HashMap::new(),
+                })?;
+            }
+            let connection = Connection::open(path)?;
+            connection.pragma_update(None, "foreign_keys", true)?;
+            connection.execute_batch(SCHEMA)?;
+            Ok(connection)
+        })
+        .await?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Runs `f` on the connection off the async runtime, SQLite calls being blocking.
+    pub(super) async fn run<T, F>(&self, f: F) -> Result<T, ArcaneVaultError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, ArcaneVaultError> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+        spawn_blocking(move || {
+            // A call that panicked poisons the lock, but the connection is still sound: its
+            // transaction was rolled back when dropped during the unwind.
+            let mut connection = connection.lock().unwrap_or_else(PoisonError::into_inner);
+            f(&mut connection)
+        })
+        .await
+    }
+}
+
+async fn spawn_blocking<T, F>(f: F) -> Result<T, ArcaneVaultError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ArcaneVaultError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| ArcaneVaultError {
+            message: e.to_string(),
+            code: Some(ArcaneVaultErrorCode::InnerError("sqlite task error".into())),
+            metadata: HashMap::new(),
+        })?
+}
+
+/// Same as `func_*` raising `PA007` when the user does not exist.
+pub(super) fn get_account(
+    connection: &Connection,
+    function: &str,
+    id: Uuid,
+) -> Result<String, ArcaneVaultError> {
+    connection
+        .query_row(
+            "SELECT account FROM users WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| raise_error(function, "PA007", &[&id.to_string()]))
+}
+
+/// Same as `util_check_note_owner`, raising `PN005` for notes of other users.
+pub(super) fn check_note_owner(
+    connection: &Connection,
+    function: &str,
+    user_id: Uuid,
+    note_id: Uuid,
+) -> Result<(), ArcaneVaultError> {
+    let is_owner: bool = connection.query_row(
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM notes n
+                JOIN collections c ON c.id = n.collection_id
+                WHERE n.id = ?1 AND c.user_id = ?2
+            )
+        "#,
+        params![note_id, user_id],
+        |row| row.get(0),
+    )?;
+    if !is_owner {
+        return Err(raise_error(function, "PN005", &[&note_id.to_string()]));
+    }
+    Ok(())
+}
+
+// Timestamps are stored as microseconds since the Unix epoch.
+pub(super) fn get_now() -> i64 {
+    get_micros(SystemTime::now())
+}
+
+pub(super) fn get_micros(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_micros() as i64)
+}
+
+pub(super) fn get_system_time(micros: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_micros(micros.max(0) as u64)
+}
+
+pub(super) fn is_within(micros: i64, duration: Duration) -> bool {
+    get_system_time(micros)
+        .elapsed()
+        .is_ok_and(|elapsed| elapsed < duration)
+}
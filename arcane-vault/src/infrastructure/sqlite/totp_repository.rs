@@ -0,0 +1,315 @@
+use rusqlite::{OptionalExtension, Transaction, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{entity::TotpEntity, error::ArcaneVaultError},
+    infrastructure::{
+        error_code::raise_error,
+        rule::{
+            TOTP_CHALLENGE_EXPIRY, TOTP_CHALLENGE_MAX_ATTEMPTS, TOTP_LOCKOUT, TOTP_MAX_ATTEMPTS,
+        },
+        sqlite::sqlite_context::{SqliteContext, get_account, get_now},
+    },
+};
+
+/// [`crate::domain::repository::TotpRepository`] backed by `script/easynote_sqlite.sql`,
+/// implementing the TOTP functions of `script/easynote_common.sql` in Rust.
+pub struct SqliteTotpRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteTotpRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::TotpRepository for SqliteTotpRepository {
+    async fn enroll_totp(&self, user_id: Uuid, secret: &[u8]) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_enroll_totp";
+        let secret = secret.to_vec();
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let account = get_account(&transaction, FUNCTION, user_id)?;
+                if is_totp_enabled(&transaction, user_id)? {
+                    return Err(raise_error(FUNCTION, "PA013", &[&account]));
+                }
+
+                transaction.execute(
+                    r#"
+                        INSERT INTO user_totp (id, secret, created_at)
+                        VALUES (?1, ?2, ?3)
+                        ON CONFLICT (id) DO UPDATE
+                        SET secret = excluded.secret,
+                            last_used_step = 0,
+                            created_at = excluded.created_at
+                    "#,
+                    params![user_id, secret, get_now()],
+                )?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn query_totp(&self, user_id: Uuid) -> Result<Option<TotpEntity>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                Ok(connection
+                    .query_row(
+                        r#"
+                            SELECT secret, last_used_step, enabled_at IS NOT NULL
+                            FROM user_totp
+                            WHERE id = ?1
+                        "#,
+                        params![user_id],
+                        |row| {
+                            Ok(TotpEntity {
+                                secret: row.get(0)?,
+                                last_used_step: row.get(1)?,
+                                is_enabled: row.get(2)?,
+                            })
+                        },
+                    )
+                    .optional()?)
+            })
+            .await
+    }
+
+    async fn confirm_totp(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_confirm_totp";
+        let recovery_code_hashes = recovery_code_hashes.to_vec();
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let account = get_account(&transaction, FUNCTION, user_id)?;
+                let enabled_at: Option<Option<i64>> = transaction
+                    .query_row(
+                        "SELECT enabled_at FROM user_totp WHERE id = ?1",
+                        params![user_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                match enabled_at {
+                    None => return Err(raise_error(FUNCTION, "PA014", &[&account])),
+                    Some(Some(_)) => return Err(raise_error(FUNCTION, "PA013", &[&account])),
+                    Some(None) => {}
+                }
+
+                transaction.execute(
+                    "UPDATE user_totp SET enabled_at = ?2, last_used_step = ?3 WHERE id = ?1",
+                    params![user_id, get_now(), step],
+                )?;
+                insert_recovery_codes(&transaction, user_id, &recovery_code_hashes)?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn disable_totp(&self, user_id: Uuid) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_disable_totp";
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let account = get_account(&transaction, FUNCTION, user_id)?;
+                let deleted = transaction.execute(
+                    "DELETE FROM user_totp WHERE id = ?1 AND enabled_at IS NOT NULL",
+                    params![user_id],
+                )?;
+                if deleted == 0 {
+                    return Err(raise_error(FUNCTION, "PA015", &[&account]));
+                }
+
+                transaction.execute(
+                    "DELETE FROM user_recovery_codes WHERE id = ?1",
+                    params![user_id],
+                )?;
+                transaction.execute(
+                    "DELETE FROM totp_challenges WHERE user_id = ?1",
+                    params![user_id],
+                )?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let updated = connection.execute(
+                    r#"
+                        UPDATE user_totp
+                        SET last_used_step = ?2
+                        WHERE id = ?1 AND enabled_at IS NOT NULL AND last_used_step < ?2
+                    "#,
+                    params![user_id, step],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: Uuid,
+        code_hash: &str,
+    ) -> Result<bool, ArcaneVaultError> {
+        let code_hash = code_hash.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let updated = connection.execute(
+                    r#"
+                        UPDATE user_recovery_codes
+                        SET used_at = ?3
+                        WHERE id = ?1 AND code_hash = ?2 AND used_at IS NULL
+                    "#,
+                    params![user_id, code_hash, get_now()],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    async fn use_totp_attempt(&self, user_id: Uuid) -> Result<bool, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let now = get_now();
+                let locked_after = now - TOTP_LOCKOUT.as_micros() as i64;
+                let updated = connection.execute(
+                    r#"
+                        UPDATE user_totp
+                        SET attempts = CASE WHEN last_attempt_at > ?3 THEN attempts + 1 ELSE 1 END,
+                            last_attempt_at = ?2
+                        WHERE id = ?1 AND (attempts < ?4 OR last_attempt_at <= ?3)
+                    "#,
+                    params![user_id, now, locked_after, TOTP_MAX_ATTEMPTS],
+                )?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    async fn reset_totp_attempts(&self, user_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                connection.execute(
+                    "UPDATE user_totp SET attempts = 0 WHERE id = ?1",
+                    params![user_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn replace_recovery_codes(
+        &self,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_replace_recovery_codes";
+        let recovery_code_hashes = recovery_code_hashes.to_vec();
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let account = get_account(&transaction, FUNCTION, user_id)?;
+                if !is_totp_enabled(&transaction, user_id)? {
+                    return Err(raise_error(FUNCTION, "PA015", &[&account]));
+                }
+
+                insert_recovery_codes(&transaction, user_id, &recovery_code_hashes)?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn add_totp_challenge(&self, user_id: Uuid) -> Result<Uuid, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let now = get_now();
+                // What the SQL cleanup job does for `totp_challenges`.
+                connection.execute(
+                    "DELETE FROM totp_challenges WHERE created_at <= ?1",
+                    params![now - TOTP_CHALLENGE_EXPIRY.as_micros() as i64],
+                )?;
+
+                let challenge_id = Uuid::new_v4();
+                connection.execute(
+                    "INSERT INTO totp_challenges (id, user_id, created_at) VALUES (?1, ?2, ?3)",
+                    params![challenge_id, user_id, now],
+                )?;
+                Ok(challenge_id)
+            })
+            .await
+    }
+
+    async fn use_totp_challenge(
+        &self,
+        challenge_id: Uuid,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let expired_at = get_now() - TOTP_CHALLENGE_EXPIRY.as_micros() as i64;
+                Ok(connection
+                    .query_row(
+                        r#"
+                            UPDATE totp_challenges
+                            SET attempts = attempts + 1
+                            WHERE id = ?1 AND attempts < ?2 AND created_at > ?3
+                            RETURNING user_id
+                        "#,
+                        params![challenge_id, TOTP_CHALLENGE_MAX_ATTEMPTS, expired_at],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })
+            .await
+    }
+
+    async fn delete_totp_challenge(&self, challenge_id: Uuid) -> Result<(), ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                connection.execute(
+                    "DELETE FROM totp_challenges WHERE id = ?1",
+                    params![challenge_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+fn is_totp_enabled(transaction: &Transaction, user_id: Uuid) -> Result<bool, ArcaneVaultError> {
+    Ok(transaction.query_row(
+        "SELECT EXISTS (SELECT 1 FROM user_totp WHERE id = ?1 AND enabled_at IS NOT NULL)",
+        params![user_id],
+        |row| row.get(0),
+    )?)
+}
+
+fn insert_recovery_codes(
+    transaction: &Transaction,
+    user_id: Uuid,
+    recovery_code_hashes: &[String],
+) -> Result<(), ArcaneVaultError> {
+    transaction.execute(
+        "DELETE FROM user_recovery_codes WHERE id = ?1",
+        params![user_id],
+    )?;
+    for code_hash in recovery_code_hashes {
+        transaction.execute(
+            "INSERT INTO user_recovery_codes (id, code_hash) VALUES (?1, ?2)",
+            params![user_id, code_hash],
+        )?;
+    }
+    Ok(())
+}
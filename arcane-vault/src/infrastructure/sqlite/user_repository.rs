@@ -0,0 +1,695 @@
+use ethereal_core::proto::User;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use rusqlite::{Connection, OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        entity::{
+            AUDIT_EVENT_PASSWORD_RESET, AUDIT_EVENT_PASSWORD_SET, EmailChangeEntity, NewAuditEvent,
+            NewUser,
+        },
+        error::ArcaneVaultError,
+    },
+    infrastructure::{
+        error_code::raise_error,
+        rule::{
+            EMAIL_CHANGE_EXPIRY, EMAIL_CHANGE_MAX_ATTEMPTS, RESET_PASSWORD_EXPIRY, USER_ROLE_USER,
+            USER_STATUS_ACTIVE, VERIFICATION_CODE_COOLDOWN, check_page, generate_verification_code,
+            get_profile_changes,
+        },
+        sqlite::{
+            audit_repository::add_audit_event,
+            sqlite_context::{SqliteContext, get_account, get_now, get_system_time, is_within},
+        },
+    },
+};
+
+const SELECT_USERS: &str = r#"
+    SELECT u.id, u.account, u.created_at, u.updated_at, u.last_login_at, u.status, u.role,
+        up.firstname, up.lastname, up.gender, up.locale, up.timezone, up.avatar, up.signature
+    FROM users u
+    JOIN user_profiles up ON u.id = up.id
+"#;
+
+/// [`crate::domain::repository::UserRepository`] backed by `script/easynote_sqlite.sql`.
+/// Implements the SQL functions of `script/easynote_common.sql` in Rust, with the same `PA`
+/// error codes.
+pub struct SqliteUserRepository {
+    sqlite_context: SqliteContext,
+}
+
+impl SqliteUserRepository {
+    pub fn new(sqlite_context: SqliteContext) -> Self {
+        Self { sqlite_context }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::domain::repository::UserRepository for SqliteUserRepository {
+    async fn register_user(
+        &self,
+        user: &NewUser,
+        password_hash: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        let (user, password_hash) = (user.clone(), password_hash.to_string());
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let is_taken: bool = transaction.query_row(
+                    r#"
+                        SELECT EXISTS (SELECT 1 FROM pending_users WHERE account = ?1)
+                            OR EXISTS (SELECT 1 FROM users WHERE account = ?1)
+                    "#,
+                    params![user.account],
+                    |row| row.get(0),
+                )?;
+                if is_taken {
+                    return Err(raise_error("func_register_user", "PA001", &[&user.account]));
+                }
+                check_timezone(&transaction, "func_register_user", &user.timezone)?;
+
+                let verification_code = generate_verification_code();
+                let now = get_now();
+                transaction.execute(
+                    r#"
+                        INSERT INTO pending_users (
+                            account, password, verification_code, created_at, updated_at,
+                            firstname, lastname, gender, locale, timezone, signature
+                        ) VALUES (?1, ?2, ?3, ?4, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                    "#,
+                    params![
+                        user.account,
+                        password_hash,
+                        verification_code,
+                        now,
+                        user.firstname,
+                        user.lastname,
+                        user.gender,
+                        user.locale,
+                        user.timezone,
+                        user.signature
+                    ],
+                )?;
+                transaction.commit()?;
+                Ok(verification_code)
+            })
+            .await
+    }
+
+    async fn verify_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+        verification_code: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        let (account, password_hash, verification_code) = (
+            account.to_string(),
+            password_hash.to_string(),
+            verification_code.to_uppercase(),
+        );
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let is_matched: bool = transaction.query_row(
+                    r#"
+                        SELECT EXISTS (
+                            SELECT 1 FROM pending_users
+                            WHERE account = ?1 AND password = ?2 AND verification_code = ?3
+                        )
+                    "#,
+                    params![account, password_hash, verification_code],
+                    |row| row.get(0),
+                )?;
+                if !is_matched {
+                    return Err(raise_error("func_verify_user", "PA002", &[&account]));
+                }
+
+                let id = Uuid::new_v4();
+                transaction.execute(
+                    r#"
+                        INSERT INTO users (
+                            id, account, password, created_at, updated_at, last_login_at, status, role
+                        )
+                        SELECT ?1, account, password, created_at, ?2, ?2, ?3, ?4
+                        FROM pending_users WHERE account = ?5
+                    "#,
+                    params![id, get_now(), USER_STATUS_ACTIVE, USER_ROLE_USER, account],
+                )?;
+                transaction.execute(
+                    r#"
+                        INSERT INTO user_profiles (
+                            id, firstname, lastname, gender, locale, timezone, signature
+                        )
+                        SELECT ?1, firstname, lastname, gender, locale, timezone, signature
+                        FROM pending_users WHERE account = ?2
+                    "#,
+                    params![id, account],
+                )?;
+                transaction.execute("DELETE FROM pending_users WHERE account = ?1", params![account])?;
+                transaction.commit()?;
+                Ok(id)
+            })
+            .await
+    }
+
+    async fn regenerate_verification_code(
+        &self,
+        account: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "func_regenerate_verification_code";
+        let account = account.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let updated_at: i64 = connection
+                    .query_row(
+                        "SELECT updated_at FROM pending_users WHERE account = ?1",
+                        params![account],
+                        |row| row.get(0),
+                    )
+                    .optional()?
+                    .ok_or_else(|| raise_error(FUNCTION, "PA002", &[&account]))?;
+                if is_within(updated_at, VERIFICATION_CODE_COOLDOWN) {
+                    return Err(raise_error(FUNCTION, "PA008", &[&account]));
+                }
+
+                let verification_code = generate_verification_code();
+                connection.execute(
+                    "UPDATE pending_users SET verification_code = ?1, updated_at = ?2 WHERE account = ?3",
+                    params![verification_code, get_now(), account],
+                )?;
+                Ok(verification_code)
+            })
+            .await
+    }
+
+    async fn login_user(
+        &self,
+        account: &str,
+        password_hash: &str,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        const FUNCTION: &str = "func_login_user";
+        let (account, password_hash) = (account.to_string(), password_hash.to_string());
+        self.sqlite_context
+            .run(move |connection| {
+                let user: Option<(Uuid, String)> = connection
+                    .query_row(
+                        "SELECT id, password FROM users WHERE account = ?1",
+                        params![account],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                if let Some((id, stored_password_hash)) = &user
+                    && *stored_password_hash == password_hash
+                {
+                    connection.execute(
+                        "UPDATE users SET last_login_at = ?1 WHERE id = ?2",
+                        params![get_now(), id],
+                    )?;
+                    return Ok(*id);
+                }
+
+                let is_pending: bool = connection.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM pending_users WHERE account = ?1)",
+                    params![account],
+                    |row| row.get(0),
+                )?;
+                if is_pending {
+                    return Err(raise_error(FUNCTION, "PA004", &[&account]));
+                }
+                if user.is_some() {
+                    return Err(raise_error(FUNCTION, "PA005", &[&account]));
+                }
+                Err(raise_error(FUNCTION, "PA003", &[&account]))
+            })
+            .await
+    }
+
+    async fn request_reset_password(&self, account: &str) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "func_request_reset_password";
+        let account = account.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let id = get_user_id(connection, FUNCTION, &account)?;
+                let updated_at: Option<i64> = connection
+                    .query_row(
+                        "SELECT updated_at FROM pending_reset_passwords WHERE id = ?1",
+                        params![id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if updated_at.is_some_and(|updated_at| is_within(updated_at, VERIFICATION_CODE_COOLDOWN)) {
+                    return Err(raise_error(FUNCTION, "PA008", &[&account]));
+                }
+
+                let verification_code = generate_verification_code();
+                connection.execute(
+                    r#"
+                        INSERT INTO pending_reset_passwords (id, verification_code, created_at, updated_at)
+                        VALUES (?1, ?2, ?3, ?3)
+                        ON CONFLICT (id) DO UPDATE SET
+                            verification_code = excluded.verification_code,
+                            updated_at = excluded.updated_at
+                    "#,
+                    params![id, verification_code, get_now()],
+                )?;
+                Ok(verification_code)
+            })
+            .await
+    }
+
+    async fn reset_password(
+        &self,
+        account: &str,
+        verification_code: &str,
+        new_password_hash: &str,
+    ) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_reset_password";
+        let (account, verification_code, new_password_hash) = (
+            account.to_string(),
+            verification_code.to_string(),
+            new_password_hash.to_string(),
+        );
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let id = get_user_id(&transaction, FUNCTION, &account)?;
+                let (code, updated_at): (String, i64) = transaction
+                    .query_row(
+                        "SELECT verification_code, updated_at FROM pending_reset_passwords WHERE id = ?1",
+                        params![id],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?
+                    .ok_or_else(|| raise_error(FUNCTION, "PA011", &[&account]))?;
+                if code != verification_code {
+                    return Err(raise_error(FUNCTION, "PA009", &[]));
+                }
+                if !is_within(updated_at, RESET_PASSWORD_EXPIRY) {
+                    return Err(raise_error(FUNCTION, "PA010", &[]));
+                }
+
+                transaction.execute(
+                    "UPDATE users SET password = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![new_password_hash, get_now(), id],
+                )?;
+                transaction.execute("DELETE FROM pending_reset_passwords WHERE id = ?1", params![id])?;
+                add_audit_event(
+                    &transaction,
+                    &NewAuditEvent {
+                        event_type: AUDIT_EVENT_PASSWORD_RESET,
+                        user_id: Some(id),
+                        account,
+                        ..Default::default()
+                    },
+                )?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn set_password(&self, id: Uuid, password_hash: &str) -> Result<(), ArcaneVaultError> {
+        let password_hash = password_hash.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let account = get_account(&transaction, "func_set_password", id)?;
+                transaction.execute(
+                    "UPDATE users SET password = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![password_hash, get_now(), id],
+                )?;
+                add_audit_event(
+                    &transaction,
+                    &NewAuditEvent {
+                        event_type: AUDIT_EVENT_PASSWORD_SET,
+                        user_id: Some(id),
+                        actor_id: Some(id),
+                        account,
+                        ..Default::default()
+                    },
+                )?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn request_email_change(
+        &self,
+        id: Uuid,
+        account: &str,
+    ) -> Result<String, ArcaneVaultError> {
+        const FUNCTION: &str = "func_request_email_change";
+        let account = account.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let old_account = get_account(&transaction, FUNCTION, id)?;
+                if !is_account_available(&transaction, &account)? {
+                    return Err(raise_error(FUNCTION, "PA001", &[&account]));
+                }
+                let updated_at: Option<i64> = transaction
+                    .query_row(
+                        "SELECT updated_at FROM pending_email_changes WHERE id = ?1",
+                        params![id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if updated_at
+                    .is_some_and(|updated_at| is_within(updated_at, VERIFICATION_CODE_COOLDOWN))
+                {
+                    return Err(raise_error(FUNCTION, "PA008", &[&old_account]));
+                }
+
+                let verification_code = generate_verification_code();
+                transaction.execute(
+                    r#"
+                        INSERT INTO pending_email_changes (
+                            id, account, verification_code, created_at, updated_at
+                        ) VALUES (?1, ?2, ?3, ?4, ?4)
+                        ON CONFLICT (id) DO UPDATE SET
+                            account = excluded.account,
+                            verification_code = excluded.verification_code,
+                            attempts = 0,
+                            updated_at = excluded.updated_at
+                    "#,
+                    params![id, account, verification_code, get_now()],
+                )?;
+                transaction.commit()?;
+                Ok(verification_code)
+            })
+            .await
+    }
+
+    async fn use_email_change_attempt(&self, id: Uuid) -> Result<bool, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| {
+                let updated = connection.execute(
+                    r#"
+                        UPDATE pending_email_changes
+                        SET attempts = attempts + 1
+                        WHERE id = ?1 AND attempts < ?2
+                    "#,
+                    params![id, EMAIL_CHANGE_MAX_ATTEMPTS],
+                )?;
+                if updated > 0 {
+                    return Ok(true);
+                }
+                let is_pending = connection
+                    .query_row(
+                        "SELECT 1 FROM pending_email_changes WHERE id = ?1",
+                        params![id],
+                        |_| Ok(()),
+                    )
+                    .optional()?
+                    .is_some();
+                Ok(!is_pending)
+            })
+            .await
+    }
+
+    async fn confirm_email_change(
+        &self,
+        id: Uuid,
+        verification_code: &str,
+    ) -> Result<EmailChangeEntity, ArcaneVaultError> {
+        const FUNCTION: &str = "func_confirm_email_change";
+        let verification_code = verification_code.to_uppercase();
+        self.sqlite_context
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let old_account = get_account(&transaction, FUNCTION, id)?;
+                let (new_account, code, updated_at): (String, String, i64) = transaction
+                    .query_row(
+                        r#"
+                            SELECT account, verification_code, updated_at
+                            FROM pending_email_changes
+                            WHERE id = ?1
+                        "#,
+                        params![id],
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                    )
+                    .optional()?
+                    .ok_or_else(|| raise_error(FUNCTION, "PA032", &[&old_account]))?;
+                if code != verification_code {
+                    return Err(raise_error(FUNCTION, "PA009", &[]));
+                }
+                if !is_within(updated_at, EMAIL_CHANGE_EXPIRY) {
+                    return Err(raise_error(FUNCTION, "PA010", &[]));
+                }
+                if !is_account_available(&transaction, &new_account)? {
+                    return Err(raise_error(FUNCTION, "PA001", &[&new_account]));
+                }
+
+                transaction.execute(
+                    "UPDATE users SET account = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![new_account, get_now(), id],
+                )?;
+                transaction.execute(
+                    "DELETE FROM pending_email_changes WHERE id = ?1",
+                    params![id],
+                )?;
+                transaction.commit()?;
+                Ok(EmailChangeEntity {
+                    old_account,
+                    new_account,
+                })
+            })
+            .await
+    }
+
+    async fn query_password_hash(&self, account: &str) -> Result<Option<String>, ArcaneVaultError> {
+        let account = account.to_string();
+        self.sqlite_context
+            .run(move |connection| query_password_hash(connection, "users", &account))
+            .await
+    }
+
+    async fn query_pending_password_hash(
+        &self,
+        account: &str,
+    ) -> Result<Option<String>, ArcaneVaultError> {
+        let account = account.to_string();
+        self.sqlite_context
+            .run(move |connection| query_password_hash(connection, "pending_users", &account))
+            .await
+    }
+
+    async fn update_profile(
+        &self,
+        id: Uuid,
+        firstname: &str,
+        lastname: &str,
+        gender: i32,
+        locale: i32,
+    ) -> Result<(User, Vec<&'static str>), ArcaneVaultError> {
+        let (firstname, lastname) = (firstname.to_string(), lastname.to_string());
+        self.sqlite_context
+            .run(move |connection| {
+                let stored = query_user(connection, "u.id = ?1", &id)?
+                    .ok_or_else(|| raise_error("func_set_name", "PA007", &[&id.to_string()]))?;
+                connection.execute(
+                    r#"
+                        UPDATE user_profiles SET firstname = ?1, lastname = ?2, gender = ?3, locale = ?4
+                        WHERE id = ?5
+                    "#,
+                    params![firstname, lastname, gender, locale, id],
+                )?;
+                let changes = get_profile_changes(
+                    (&stored.firstname, &stored.lastname, stored.gender, stored.locale),
+                    (&firstname, &lastname, gender, locale),
+                );
+                let user = query_user(connection, "u.id = ?1", &id)?
+                    .ok_or_else(|| raise_error("func_set_name", "PA007", &[&id.to_string()]))?;
+                Ok((user, changes))
+            })
+            .await
+    }
+
+    async fn set_avatar(&self, id: Uuid, avatar: &str) -> Result<(), ArcaneVaultError> {
+        let avatar = avatar.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                get_account(connection, "func_set_avatar", id)?;
+                connection.execute(
+                    "UPDATE user_profiles SET avatar = ?1 WHERE id = ?2",
+                    params![avatar, id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn set_signature(&self, id: Uuid, signature: &str) -> Result<(), ArcaneVaultError> {
+        let signature = signature.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                get_account(connection, "func_set_signature", id)?;
+                connection.execute(
+                    "UPDATE user_profiles SET signature = ?1 WHERE id = ?2",
+                    params![signature, id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn set_timezone(&self, id: Uuid, timezone: &str) -> Result<(), ArcaneVaultError> {
+        const FUNCTION: &str = "func_set_timezone";
+        let timezone = timezone.to_string();
+        self.sqlite_context
+            .run(move |connection| {
+                get_account(connection, FUNCTION, id)?;
+                check_timezone(connection, FUNCTION, &timezone)?;
+                connection.execute(
+                    "UPDATE user_profiles SET timezone = ?1 WHERE id = ?2",
+                    params![timezone, id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn query_user_by_id(&self, id: Uuid) -> Result<Option<User>, ArcaneVaultError> {
+        self.sqlite_context
+            .run(move |connection| query_user(connection, "u.id = ?1", &id))
+            .await
+    }
+
+    async fn query_user_by_account(&self, account: &str) -> Result<Option<User>, ArcaneVaultError> {
+        let account = account.to_string();
+        self.sqlite_context
+            .run(move |connection| query_user(connection, "u.account = ?1", &account))
+            .await
+    }
+
+    async fn query_users(&self, offset: i32, limit: i32) -> Result<Vec<User>, ArcaneVaultError> {
+        check_page(offset, limit)?;
+        self.sqlite_context
+            .run(move |connection| query_users(connection, offset, limit))
+            .await
+    }
+
+    async fn export_users(
+        &self,
+    ) -> Result<BoxStream<'static, Result<User, ArcaneVaultError>>, ArcaneVaultError> {
+        // A statement cannot outlive the lock on the connection, so the rows are read up front.
+        let users = self
+            .sqlite_context
+            .run(|connection| query_users(connection, 0, -1))
+            .await?;
+        Ok(stream::iter(users.into_iter().map(Ok)).boxed())
+    }
+}
+
+fn query_password_hash(
+    connection: &Connection,
+    table: &str,
+    account: &str,
+) -> Result<Option<String>, ArcaneVaultError> {
+    Ok(connection
+        .query_row(
+            &format!("SELECT password FROM {} WHERE account = ?1", table),
+            params![account],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+fn query_user(
+    connection: &Connection,
+    condition: &str,
+    param: &dyn rusqlite::ToSql,
+) -> Result<Option<User>, ArcaneVaultError> {
+    Ok(connection
+        .query_row(
+            &format!("{} WHERE {}", SELECT_USERS, condition),
+            params![param],
+            get_user_from_row,
+        )
+        .optional()?)
+}
+
+// Same order as `func_query_users`; a negative LIMIT means no limit in SQLite.
+fn query_users(
+    connection: &Connection,
+    offset: i32,
+    limit: i32,
+) -> Result<Vec<User>, ArcaneVaultError> {
+    let mut statement = connection.prepare(&format!(
+        "{} ORDER BY u.created_at, u.id LIMIT ?1 OFFSET ?2",
+        SELECT_USERS
+    ))?;
+    let users = statement
+        .query_map(params![limit, offset], get_user_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(users)
+}
+
+// Same as `util_verify_account`.
+fn is_account_available(connection: &Connection, account: &str) -> Result<bool, ArcaneVaultError> {
+    let is_taken: bool = connection.query_row(
+        r#"
+            SELECT EXISTS (SELECT 1 FROM pending_users WHERE account = ?1)
+                OR EXISTS (SELECT 1 FROM users WHERE account = ?1)
+        "#,
+        params![account],
+        |row| row.get(0),
+    )?;
+    Ok(!is_taken)
+}
+
+fn get_user_id(
+    connection: &Connection,
+    function: &str,
+    account: &str,
+) -> Result<Uuid, ArcaneVaultError> {
+    connection
+        .query_row(
+            "SELECT id FROM users WHERE account = ?1",
+            params![account],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| raise_error(function, "PA003", &[account]))
+}
+
+fn check_timezone(
+    connection: &Connection,
+    function: &str,
+    timezone: &str,
+) -> Result<(), ArcaneVaultError> {
+    let is_valid: bool = connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM timezones WHERE name = ?1)",
+        params![timezone],
+        |row| row.get(0),
+    )?;
+    if !is_valid {
+        return Err(raise_error(function, "PA012", &[timezone]));
+    }
+    Ok(())
+}
+
+fn get_user_from_row(row: &rusqlite::Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get::<_, Uuid>("id")?.to_string(),
+        email_account: row.get("account")?,
+        created_at: Some(get_system_time(row.get("created_at")?).into()),
+        updated_at: Some(get_system_time(row.get("updated_at")?).into()),
+        last_login_at: row
+            .get::<_, Option<i64>>("last_login_at")?
+            .map(|micros| get_system_time(micros).into()),
+        status: row.get("status")?,
+        role: row.get("role")?,
+        firstname: row.get("firstname")?,
+        lastname: row.get("lastname")?,
+        gender: row.get("gender")?,
+        locale: row.get("locale")?,
+        timezone: row.get("timezone")?,
+        avatar: row.get::<_, Option<String>>("avatar")?.unwrap_or_default(),
+        signature: row
+            .get::<_, Option<String>>("signature")?
+            .unwrap_or_default(),
+    })
+}
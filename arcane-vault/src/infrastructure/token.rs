@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ethereal_core::proto::SessionTokens;
+use sha2::{Digest, Sha256};
+
+use crate::domain::{entity::SessionTokenHashes, error::ArcaneVaultError};
+
+const DEFAULT_ACCESS_TOKEN_TTL_SECS: u32 = 15 * 60;
+const DEFAULT_REFRESH_TOKEN_TTL_SECS: u32 = 30 * 24 * 60 * 60;
+const TOKEN_LENGTH: usize = 32;
+/// Tells personal access tokens apart from session access tokens, and makes them easy to spot in
+/// scripts and secret scanners.
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "enpat_";
+
+/// Hands out the tokens of a session: access tokens valid for
+/// `arcane-vault[0].access_token_ttl_secs`, 15 minutes by default, and refresh tokens valid for
+/// `arcane-vault[0].refresh_token_ttl_secs`, 30 days by default.
+pub struct SessionTokenIssuer {
+    access_token_ttl: Duration,
+    refresh_token_ttl: Duration,
+}
+
+impl SessionTokenIssuer {
+    pub fn new() -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        let get_ttl = |key: &str, default: u32| {
+            let ttl_secs = config.get::<u32>(key).unwrap_or(default);
+            // The SQL functions take seconds as INTEGER.
+            if ttl_secs == 0 || ttl_secs > i32::MAX as u32 {
+                return Err(ArcaneVaultError {
+                    message: format!("{} must be between 1 and {}", key, i32::MAX),
+                    code: None,
+                    metadata: HashMap::new(),
+                });
+            }
+            Ok(Duration::from_secs(ttl_secs.into()))
+        };
+        Ok(Self {
+            access_token_ttl: get_ttl(
+                "arcane-vault[0].access_token_ttl_secs",
+                DEFAULT_ACCESS_TOKEN_TTL_SECS,
+            )?,
+            refresh_token_ttl: get_ttl(
+                "arcane-vault[0].refresh_token_ttl_secs",
+                DEFAULT_REFRESH_TOKEN_TTL_SECS,
+            )?,
+        })
+    }
+
+    /// New tokens for the client, without the session id, and their hashes for the repository.
+    pub fn issue_tokens(&self) -> (SessionTokens, SessionTokenHashes) {
+        let (access_token, refresh_token) = (generate_token(), generate_token());
+        let now = SystemTime::now();
+        let hashes = SessionTokenHashes {
+            access_token_hash: get_token_hash(&access_token),
+            access_token_ttl: self.access_token_ttl,
+            refresh_token_hash: get_token_hash(&refresh_token),
+            refresh_token_ttl: self.refresh_token_ttl,
+        };
+        let tokens = SessionTokens {
+            session_id: String::new(),
+            access_token,
+            access_token_expires_at: Some((now + self.access_token_ttl).into()),
+            refresh_token,
+            refresh_token_expires_at: Some((now + self.refresh_token_ttl).into()),
+        };
+        (tokens, hashes)
+    }
+}
+
+/// 256 random bits from the OS random number generator, in URL-safe base64.
+pub fn generate_token() -> String {
+    let mut token = [0; TOKEN_LENGTH];
+    OsRng.fill_bytes(&mut token);
+    URL_SAFE_NO_PAD.encode(token)
+}
+
+pub fn generate_personal_access_token() -> String {
+    format!("{}{}", PERSONAL_ACCESS_TOKEN_PREFIX, generate_token())
+}
+
+/// The stored form of a token. Tokens are random enough for SHA-256, unlike passwords.
+pub fn get_token_hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use aes_gcm::{
+    Aes256Gcm, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng, Payload, rand_core::RngCore},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use totp_rs::{Algorithm, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    domain::error::{ArcaneVaultError, ArcaneVaultErrorCode},
+    infrastructure::token::get_token_hash,
+};
+
+const DEFAULT_ISSUER: &str = "EasyNote";
+// RFC 4226 recommends 160 bits; SHA-1, 6 digits and 30 seconds are what authenticator apps expect.
+const SECRET_LENGTH: usize = 20;
+const DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+const NONCE_LENGTH: usize = 12;
+
+// Same alphabet as the verification codes, 10 characters make 50 bits per recovery code.
+const RECOVERY_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const RECOVERY_CODE_LENGTH: usize = 10;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Creates and checks TOTP secrets, which are stored encrypted with AES-256-GCM under the
+/// base64 key `arcane-vault[0].totp_encryption_key` and bound to their user. Without a key,
+/// every 2FA operation fails. The issuer shown by authenticator apps is
+/// `arcane-vault[0].totp_issuer`.
+pub struct TotpAuthenticator {
+    cipher: Option<Aes256Gcm>,
+    issuer: String,
+}
+
+impl TotpAuthenticator {
+    pub fn new() -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        let cipher = match config.get::<String>("arcane-vault[0].totp_encryption_key") {
+            Ok(key) => {
+                let key = STANDARD
+                    .decode(key)
+                    .ok()
+                    .filter(|key| key.len() == 32)
+                    .ok_or_else(|| ArcaneVaultError {
+                        message: "arcane-vault[0].totp_encryption_key must be 32 bytes in base64"
+                            .into(),
+                        code: None,
+                        metadata: HashMap::new(),
+                    })?;
+                Some(Aes256Gcm::new_from_slice(&key).map_err(|e| get_cipher_error(e.to_string()))?)
+            }
+            Err(_) => None,
+        };
+        let issuer = config
+            .get::<String>("arcane-vault[0].totp_issuer")
+            .unwrap_or_else(|_| DEFAULT_ISSUER.into());
+        Ok(Self { cipher, issuer })
+    }
+
+    /// A new secret, encrypted for storage, and its `otpauth://` URI.
+    pub fn generate_secret(
+        &self,
+        user_id: Uuid,
+        account: &str,
+    ) -> Result<(Vec<u8>, String), ArcaneVaultError> {
+        let mut secret = vec![0; SECRET_LENGTH];
+        OsRng.fill_bytes(&mut secret);
+        let uri = self.get_totp(secret.clone(), account).get_url();
+        Ok((self.encrypt(user_id, &secret)?, uri))
+    }
+
+    /// The time step `code` belongs to, allowing one step of clock skew either way.
+    pub fn verify_code(
+        &self,
+        user_id: Uuid,
+        encrypted_secret: &[u8],
+        code: &str,
+    ) -> Result<Option<i64>, ArcaneVaultError> {
+        let totp = self.get_totp(self.decrypt(user_id, encrypted_secret)?, "");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let step = now / STEP_SECS;
+        Ok([step - 1, step, step + 1]
+            .into_iter()
+            .find(|step| totp.check(code, step * STEP_SECS))
+            .map(|step| step as i64))
+    }
+
+    fn get_totp(&self, secret: Vec<u8>, account: &str) -> TOTP {
+        TOTP::new_unchecked(
+            Algorithm::SHA1,
+            DIGITS,
+            1,
+            STEP_SECS,
+            secret,
+            Some(self.issuer.clone()),
+            account.to_string(),
+        )
+    }
+
+    // The nonce is stored in front of the ciphertext; the user id is authenticated with it, so a
+    // secret copied to another user does not decrypt.
+    fn encrypt(&self, user_id: Uuid, secret: &[u8]) -> Result<Vec<u8>, ArcaneVaultError> {
+        let cipher = self.get_cipher()?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: secret,
+            aad: user_id.as_bytes(),
+        };
+        let ciphertext = cipher
+            .encrypt(&nonce, payload)
+            .map_err(|e| get_cipher_error(e.to_string()))?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    fn decrypt(&self, user_id: Uuid, encrypted_secret: &[u8]) -> Result<Vec<u8>, ArcaneVaultError> {
+        let cipher = self.get_cipher()?;
+        if encrypted_secret.len() < NONCE_LENGTH {
+            return Err(get_cipher_error(
+                "encrypted TOTP secret is too short".into(),
+            ));
+        }
+        let (nonce, ciphertext) = encrypted_secret.split_at(NONCE_LENGTH);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: user_id.as_bytes(),
+        };
+        cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|e| get_cipher_error(e.to_string()))
+    }
+
+    fn get_cipher(&self) -> Result<&Aes256Gcm, ArcaneVaultError> {
+        self.cipher.as_ref().ok_or_else(|| ArcaneVaultError {
+            message: "two-factor authentication needs arcane-vault[0].totp_encryption_key".into(),
+            code: None,
+            metadata: HashMap::new(),
+        })
+    }
+}
+
+/// Whether `code` looks like a TOTP code rather than a recovery code.
+pub fn is_totp_code(code: &str) -> bool {
+    code.len() == DIGITS && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// New recovery codes such as `ABCDE-23456`, drawn from the OS random number generator.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code: String = (0..RECOVERY_CODE_LENGTH)
+                .map(|_| {
+                    let index = OsRng.next_u32() as usize % RECOVERY_CODE_CHARS.len();
+                    RECOVERY_CODE_CHARS[index] as char
+                })
+                .collect();
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// The stored form of a recovery code, ignoring case, dashes and spaces.
+pub fn get_recovery_code_hash(code: &str) -> String {
+    let code: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    get_token_hash(&code)
+}
+
+fn get_cipher_error(message: String) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message,
+        code: Some(ArcaneVaultErrorCode::InnerError("aes-gcm error".into())),
+        metadata: HashMap::new(),
+    }
+}
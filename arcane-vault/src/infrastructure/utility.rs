@@ -1,3 +0,0 @@
-pub(crate) fn get_string_from_uuid(value: uuid::Uuid) -> String {
-    value.to_string()
-}
\ No newline at end of file
@@ -0,0 +1,340 @@
+use arcane_vault::{
+    Storage, UserService,
+    domain::{
+        entity::{AUDIT_EVENT_PROFILE_CHANGED, NewUser, Principal, SessionTokenHashes},
+        error::ArcaneVaultError,
+        repository::SessionRepository,
+        service::{LoginOutcome, RequestContext, SessionDevice},
+    },
+};
+use ethereal_core::{
+    configuration::TomlConfiguration,
+    proto::{Session, SessionTokens},
+};
+use uuid::Uuid;
+
+const EMAIL: &str = "ada@example.com";
+const PASSWORD: &str = "correct horse battery staple";
+
+// The services read `setting/Config.toml` relative to the working directory, which is the
+// package directory under `cargo test`, so it is loaded by its path beforehand.
+async fn create_user_service() -> Box<dyn arcane_vault::domain::service::UserService> {
+    create_user_service_with(|sessions| sessions).await
+}
+
+async fn create_user_service_with(
+    wrap_sessions: impl FnOnce(Box<dyn SessionRepository>) -> Box<dyn SessionRepository>,
+) -> Box<dyn arcane_vault::domain::service::UserService> {
+    TomlConfiguration::from_path(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../setting/Config.toml"
+    ));
+    let repositories = Storage::Memory.create_repositories().await.unwrap();
+    UserService::create(
+        repositories.users,
+        repositories.totp,
+        wrap_sessions(repositories.sessions),
+        repositories.personal_access_tokens,
+        repositories.oidc,
+        repositories.audit_events,
+    )
+    .unwrap()
+}
+
+// Sessions as if their login was 10 minutes ago, too long to leave the password out.
+struct StaleSessions(Box<dyn SessionRepository>);
+
+#[async_trait::async_trait]
+impl SessionRepository for StaleSessions {
+    async fn add_session(
+        &self,
+        user_id: Uuid,
+        device_label: &str,
+        user_agent: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Uuid, ArcaneVaultError> {
+        self.0
+            .add_session(user_id, device_label, user_agent, tokens)
+            .await
+    }
+
+    async fn refresh_session(
+        &self,
+        refresh_token_hash: &str,
+        tokens: &SessionTokenHashes,
+    ) -> Result<Option<Uuid>, ArcaneVaultError> {
+        self.0.refresh_session(refresh_token_hash, tokens).await
+    }
+
+    async fn query_session_by_access_token(
+        &self,
+        access_token_hash: &str,
+    ) -> Result<Option<Principal>, ArcaneVaultError> {
+        self.0
+            .query_session_by_access_token(access_token_hash)
+            .await
+    }
+
+    async fn query_sessions(&self, user_id: Uuid) -> Result<Vec<Session>, ArcaneVaultError> {
+        let mut sessions = self.0.query_sessions(user_id).await?;
+        for session in &mut sessions {
+            if let Some(created_at) = &mut session.created_at {
+                created_at.seconds -= 10 * 60;
+            }
+        }
+        Ok(sessions)
+    }
+
+    async fn revoke_session(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(), ArcaneVaultError> {
+        self.0.revoke_session(user_id, session_id).await
+    }
+
+    async fn revoke_sessions(&self, user_id: Uuid) -> Result<i32, ArcaneVaultError> {
+        self.0.revoke_sessions(user_id).await
+    }
+}
+
+fn get_new_user() -> NewUser {
+    NewUser {
+        account: EMAIL.into(),
+        firstname: "Ada".into(),
+        lastname: "Lovelace".into(),
+        locale: 1,
+        ..Default::default()
+    }
+}
+
+fn get_error_code(err: &ArcaneVaultError) -> Option<&str> {
+    err.code.as_ref().and_then(|code| code.get_error_code())
+}
+
+#[tokio::test]
+async fn registers_verifies_and_logs_in() {
+    let service = create_user_service().await;
+    let context = RequestContext::default();
+
+    let verification_code = service
+        .create_user(&get_new_user(), PASSWORD, &context)
+        .await
+        .unwrap();
+    let user_id = service
+        .verify_user(EMAIL, PASSWORD, &verification_code, &context)
+        .await
+        .unwrap();
+    let outcome = service
+        .login_user(EMAIL, PASSWORD, &SessionDevice::default(), &context)
+        .await
+        .unwrap();
+
+    let LoginOutcome::LoggedIn(id, tokens) = outcome else {
+        panic!("expected a session, got {:?}", outcome);
+    };
+    assert_eq!(id, user_id);
+    let principal = service.authenticate(&tokens.access_token).await.unwrap();
+    assert_eq!(principal.user_id, user_id);
+}
+
+#[tokio::test]
+async fn rejects_a_taken_account() {
+    let service = create_user_service().await;
+    let context = RequestContext::default();
+
+    service
+        .create_user(&get_new_user(), PASSWORD, &context)
+        .await
+        .unwrap();
+    let err = service
+        .create_user(&get_new_user(), PASSWORD, &context)
+        .await
+        .unwrap_err();
+
+    assert_eq!(get_error_code(&err), Some("PA001"));
+}
+
+#[tokio::test]
+async fn rejects_a_wrong_verification_code_or_password() {
+    let service = create_user_service().await;
+    let context = RequestContext::default();
+
+    let verification_code = service
+        .create_user(&get_new_user(), PASSWORD, &context)
+        .await
+        .unwrap();
+    let err = service
+        .login_user(EMAIL, PASSWORD, &SessionDevice::default(), &context)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA004"));
+    let err = service
+        .verify_user(EMAIL, PASSWORD, "WRONG1", &context)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA002"));
+    let err = service
+        .verify_user(EMAIL, "wrong password", &verification_code, &context)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA002"));
+
+    service
+        .verify_user(EMAIL, PASSWORD, &verification_code, &context)
+        .await
+        .unwrap();
+    let err = service
+        .login_user(EMAIL, "wrong password", &SessionDevice::default(), &context)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA005"));
+}
+
+#[tokio::test]
+async fn asks_for_the_password_to_enroll_totp() {
+    let service = create_user_service().await;
+    let tokens = log_in(service.as_ref()).await;
+    let principal = service.authenticate(&tokens.access_token).await.unwrap();
+
+    let err = service
+        .enroll_totp(&principal, "wrong password")
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA005"));
+}
+
+#[tokio::test]
+async fn asks_a_stale_session_for_the_password_to_change_the_email() {
+    let service = create_user_service_with(|sessions| Box::new(StaleSessions(sessions))).await;
+    let tokens = log_in(service.as_ref()).await;
+    let principal = service.authenticate(&tokens.access_token).await.unwrap();
+
+    let err = service
+        .request_email_change(&principal, "", "ada@example.org")
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA037"));
+    let err = service
+        .request_email_change(&principal, "wrong password", "ada@example.org")
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA005"));
+    service
+        .request_email_change(&principal, PASSWORD, "ada@example.org")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn rotates_refresh_tokens_and_revokes_the_session_on_reuse() {
+    let service = create_user_service().await;
+    let tokens = log_in(service.as_ref()).await;
+
+    let refreshed_tokens = service
+        .refresh_session(&tokens.refresh_token)
+        .await
+        .unwrap();
+    assert_eq!(refreshed_tokens.session_id, tokens.session_id);
+    assert_ne!(refreshed_tokens.refresh_token, tokens.refresh_token);
+    let principal = service
+        .authenticate(&refreshed_tokens.access_token)
+        .await
+        .unwrap();
+    assert_eq!(service.list_sessions(&principal).await.unwrap().len(), 1);
+
+    let err = service
+        .refresh_session(&tokens.refresh_token)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA018"));
+    let err = service
+        .refresh_session(&refreshed_tokens.refresh_token)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA018"));
+    let err = service
+        .authenticate(&refreshed_tokens.access_token)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA020"));
+}
+
+#[tokio::test]
+async fn updates_the_profile_and_records_what_changed() {
+    let service = create_user_service().await;
+    let tokens = log_in(service.as_ref()).await;
+    let principal = service.authenticate(&tokens.access_token).await.unwrap();
+    let context = RequestContext::default();
+
+    let user = service
+        .update_user_profile(&principal, "Ada", "King", 0, 2, &context)
+        .await
+        .unwrap();
+    assert_eq!((user.lastname.as_str(), user.locale), ("King", 2));
+    // Nothing changed, so nothing is recorded.
+    service
+        .update_user_profile(&principal, "Ada", "King", 0, 2, &context)
+        .await
+        .unwrap();
+
+    let events: Vec<(String, String)> = service
+        .list_audit_events(&principal, None, 0, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|event| event.event_type == AUDIT_EVENT_PROFILE_CHANGED)
+        .map(|event| (event.actor_id, event.details))
+        .collect();
+    assert_eq!(
+        events,
+        [(principal.user_id.to_string(), "name, locale".to_string())]
+    );
+}
+
+// The service only mails the code, so this asks the repository the service counts attempts with.
+#[tokio::test]
+async fn locks_the_email_change_code_after_5_attempts() {
+    let repositories = Storage::Memory.create_repositories().await.unwrap();
+    let users = repositories.users;
+    // The service defaults the timezone, the repository does not.
+    let user = NewUser {
+        timezone: "UTC".into(),
+        ..get_new_user()
+    };
+    let verification_code = users.register_user(&user, "hash").await.unwrap();
+    let user_id = users
+        .verify_user(EMAIL, "hash", &verification_code)
+        .await
+        .unwrap();
+    users
+        .request_email_change(user_id, "ada@example.org")
+        .await
+        .unwrap();
+
+    for _ in 0..5 {
+        assert!(users.use_email_change_attempt(user_id).await.unwrap());
+    }
+    assert!(!users.use_email_change_attempt(user_id).await.unwrap());
+}
+
+// Registers, verifies and logs in the user of `get_new_user`.
+async fn log_in(service: &dyn arcane_vault::domain::service::UserService) -> SessionTokens {
+    let context = RequestContext::default();
+    let verification_code = service
+        .create_user(&get_new_user(), PASSWORD, &context)
+        .await
+        .unwrap();
+    service
+        .verify_user(EMAIL, PASSWORD, &verification_code, &context)
+        .await
+        .unwrap();
+    match service
+        .login_user(EMAIL, PASSWORD, &SessionDevice::default(), &context)
+        .await
+        .unwrap()
+    {
+        LoginOutcome::LoggedIn(_, tokens) => tokens,
+        outcome => panic!("expected a session, got {:?}", outcome),
+    }
+}
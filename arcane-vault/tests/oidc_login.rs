@@ -0,0 +1,339 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use arcane_vault::{
+    Storage, UserService,
+    domain::{
+        error::ArcaneVaultError,
+        service::{LoginOutcome, RequestContext, SessionDevice},
+    },
+};
+use axum::{
+    Form, Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ethereal_core::configuration::TomlConfiguration;
+use ring::{
+    rand::SystemRandom,
+    rsa::{KeyPair, PublicKeyComponents},
+    signature::RSA_PKCS1_SHA256,
+};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+const PROVIDER: &str = "mock";
+const CLIENT_ID: &str = "easynote";
+const KEY_ID: &str = "mock-key";
+// PKCS #8 of a 2048-bit RSA key the ID tokens are signed with; ring cannot generate RSA keys.
+const SIGNING_KEY: &str = "\
+    MIIEvQIBADANBgkqhkiG9w0BAQEFAASCBKcwggSjAgEAAoIBAQCiKnxpuSFuKTblsRYr/MdvNRqJazBKyvbXIUpFYtalg9cZ\
+    o1+9X873VPVgmcZsq8WbbUo+cL6ZxsJcWM3QQf8NsG/MdOD2479bbyf9VYmDob4CcL1kpYLuHxrlDk9rBHQ/oRHK8tB+iWb6\
+    uYro5rXhchrBZ/vCsH1dczHBUWQBrj9k31hN0100xCnprDntMvfRJ/FPQ61KjeykAMEMvx1rJrAEgeyYzSwaQWbNangnHYpE\
+    zpXKjSmnRupSnWD2zVKjE5X3xRou5ilcV7cURHR8WalXUvCDlPiHq8X4S0NuKbgHq3uhY8rEQ3JEs5E9HhhaYlJ0qUPI1+st\
+    1/i/7d9rAgMBAAECggEAT3pzL33EnLEmbry0GSrys9ZbAy69pGqFQDrYo9klSkmriq0/pxjl3uKMwKOdVHV7jAmscZ3gTI3V\
+    hF0hwggWcScgJBzEhTxp9O4ts0PQ+eqzrY6DxGg6G2qzS8APvGuabfULO2R2Ow4sooV9R1iLm8rIwjGsemb1im+C2zSAxzFO\
+    4FoAVxtm7zwbDjwMR98tHxC2oKg+ifAKTfvrxnu7+BUZHIWSWDLaLUtuzkeEYaEPtOq1FZdCYD65wMRW4hnqa0pLRKfJnrq9\
+    QiOik77kWZs7+dFGdscsm/CIoOFIECvjyTlqQ+OumNAAUtJL5vRfVbW3koeIV+ySMf8clzlggQKBgQDfVpShtjmeixnn9NXi\
+    CZ0sVODOxvBFHCf792q26Ui/GAEexwgLZ1+A89ZZA14z7YpjqWsg/3m/KCPpMoYdattJX514A5o4aB4nUZnghP0v+StZCwu5\
+    fG4CM+LH/LXaimINk5959TIKGj+1aR+RSShPhToskWvwTQWOp/hHBJNceQKBgQC54bdK5CXHgScVThwT435rKCF4eKZNcrKM\
+    Au63jItThM7Xz3/BM+/6FbtcI0NFXQvW7AbcBRBKv2Yg8jK/YfwCt+o8seVEzxlmG5E7Toyjgx7Wyw6Hfmdlwar884jXYDWv\
+    g2+CbWf+SPtP7n6FxvFVukXyLPRMBynztiFhFmuaAwKBgQCXH4Tvb9jJWSKsfFWF4C+EYeLEyISonwbHToHdRzm8ZMfNRaxB\
+    2y8amOR/0vGZfbDZAzjEWVrOaKD0GQX7PeUoXxS0sutWI83TmTngXNzd62LTm8/RMUtXHjjkTjHXsS/sM+oDolC72Usmu/1q\
+    V/B47pOQuM4rClMqVqp/k68LMQKBgA8BAzSOWeZSrH7xwR5x3IB1ys3Yn0PdIEBg9Dfopk5lh/SvkRZdRN+g5XC4f/OXYR9G\
+    Cf+0gO4nqOy74BDGrfriMYwYp/L37IrraruoyX1hVhnsZg3VzC7QhSbJ2Z/Y6jxylYunnR9PcMmw7JGLz2smEANOUaHZai97\
+    4hHeJ21VAoGAF/+lHdcG1YyxAQ4rMc6IQw9NZSZt3xhUzPpSKDAScanXqv6KnLeAxJXH7g64CDdLcSIwFpVOJPVVTbaGPhQK\
+    jjxe5+m9/HmnqzqMvX6IGQMKipM4T5yq2aYEjmoyOK2gmQb3EoZPjWat4vEplr19OVLXW1UEGN5igEvi7k2n1eY=";
+
+/// An OpenID Connect provider on a local port, which hands out the codes `authorize` grants.
+struct MockIdp {
+    issuer: String,
+    grants: Arc<Mutex<HashMap<String, Grant>>>,
+}
+
+/// What redeeming a code returns, which tests tamper with to be rejected.
+struct Grant {
+    code_challenge: String,
+    access_token: String,
+    /// Of the ID token, with `at_hash` derived from `access_token` when left out.
+    claims: Value,
+}
+
+impl MockIdp {
+    // Consents to the authorization request at `url` as `subject`, and returns the `state` and
+    // `code` the user is sent back with.
+    fn authorize(
+        &self,
+        url: &str,
+        subject: &str,
+        edit: impl FnOnce(&mut Grant),
+    ) -> (String, String) {
+        let url = reqwest::Url::parse(url).unwrap();
+        let parameters: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(parameters["code_challenge_method"], "S256");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let code = uuid::Uuid::new_v4().to_string();
+        let mut grant = Grant {
+            code_challenge: parameters["code_challenge"].clone(),
+            access_token: format!("access-{}", code),
+            claims: json!({
+                "iss": self.issuer,
+                "aud": parameters["client_id"],
+                "sub": subject,
+                "nonce": parameters["nonce"],
+                "iat": now,
+                "exp": now + 300,
+                "email": format!("{}@example.com", subject),
+                "email_verified": true,
+                "given_name": "Ada",
+                "family_name": "Lovelace",
+            }),
+        };
+        edit(&mut grant);
+        self.grants.lock().unwrap().insert(code.clone(), grant);
+        (parameters["state"].clone(), code)
+    }
+}
+
+// The provider of every test, as the configuration pointing to it is only read once.
+fn get_idp() -> &'static MockIdp {
+    static IDP: OnceLock<MockIdp> = OnceLock::new();
+    IDP.get_or_init(|| {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let grants = Arc::new(Mutex::new(HashMap::new()));
+        let router = Router::new()
+            .route("/.well-known/openid-configuration", get(get_discovery))
+            .route("/jwks", get(get_jwks))
+            .route("/token", post(redeem_code))
+            .with_state((issuer.clone(), grants.clone()));
+        // Each test has its own runtime, which would take the provider down with it.
+        std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                    axum::serve(listener, router).await.unwrap();
+                });
+        });
+
+        // Loaded by its path, so the services read it whatever the working directory is.
+        let path = std::env::temp_dir().join(format!("oidc-login-{}.toml", std::process::id()));
+        let config = format!(
+            "{}\n[[arcane-vault.oidc_providers]]\nname = \"{}\"\nissuer_url = \"{}\"\n\
+             client_id = \"{}\"\nclient_secret = \"secret\"\n\
+             redirect_url = \"http://localhost:3000/oidc/callback\"\n",
+            include_str!("../../setting/Config.toml"),
+            PROVIDER,
+            issuer,
+            CLIENT_ID
+        );
+        std::fs::write(&path, config).unwrap();
+        TomlConfiguration::from_path(path.to_str().unwrap());
+        MockIdp { issuer, grants }
+    })
+}
+
+type IdpState = (String, Arc<Mutex<HashMap<String, Grant>>>);
+
+async fn get_discovery(State((issuer, _)): State<IdpState>) -> Json<Value> {
+    Json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/authorize", issuer),
+        "token_endpoint": format!("{}/token", issuer),
+        "jwks_uri": format!("{}/jwks", issuer),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["RS256"],
+    }))
+}
+
+async fn get_jwks() -> Json<Value> {
+    let public_key = PublicKeyComponents::<Vec<u8>>::from(get_signing_key().public());
+    Json(json!({
+        "keys": [{
+            "kty": "RSA",
+            "use": "sig",
+            "alg": "RS256",
+            "kid": KEY_ID,
+            "n": URL_SAFE_NO_PAD.encode(public_key.n),
+            "e": URL_SAFE_NO_PAD.encode(public_key.e),
+        }]
+    }))
+}
+
+// Redeems a code once, when the PKCE verifier matches the challenge it was granted for.
+async fn redeem_code(
+    State((_, grants)): State<IdpState>,
+    Form(parameters): Form<HashMap<String, String>>,
+) -> Response {
+    let grant = grants.lock().unwrap().remove(&parameters["code"]);
+    let verifier = parameters.get("code_verifier").cloned().unwrap_or_default();
+    let Some(mut grant) = grant.filter(|grant| {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) == grant.code_challenge
+    }) else {
+        let error = json!({ "error": "invalid_grant" });
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    };
+    if grant.claims.get("at_hash").is_none() {
+        let hash = Sha256::digest(grant.access_token.as_bytes());
+        grant.claims["at_hash"] = URL_SAFE_NO_PAD.encode(&hash[..16]).into();
+    }
+    Json(json!({
+        "access_token": grant.access_token,
+        "token_type": "Bearer",
+        "expires_in": 300,
+        "id_token": sign(&grant.claims),
+    }))
+    .into_response()
+}
+
+fn get_signing_key() -> KeyPair {
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(SIGNING_KEY)
+        .unwrap();
+    KeyPair::from_pkcs8(&der).unwrap()
+}
+
+fn sign(claims: &Value) -> String {
+    let header = json!({ "alg": "RS256", "typ": "JWT", "kid": KEY_ID });
+    let message = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+    let key = get_signing_key();
+    let mut signature = vec![0; key.public().modulus_len()];
+    key.sign(
+        &RSA_PKCS1_SHA256,
+        &SystemRandom::new(),
+        message.as_bytes(),
+        &mut signature,
+    )
+    .unwrap();
+    format!("{}.{}", message, URL_SAFE_NO_PAD.encode(signature))
+}
+
+async fn create_user_service() -> Box<dyn arcane_vault::domain::service::UserService> {
+    get_idp();
+    let repositories = Storage::Memory.create_repositories().await.unwrap();
+    UserService::create(
+        repositories.users,
+        repositories.totp,
+        repositories.sessions,
+        repositories.personal_access_tokens,
+        repositories.oidc,
+        repositories.audit_events,
+    )
+    .unwrap()
+}
+
+// Logs in through the mock provider as `subject`, with the grant changed by `edit`.
+async fn log_in(
+    service: &dyn arcane_vault::domain::service::UserService,
+    subject: &str,
+    edit: impl FnOnce(&mut Grant),
+) -> Result<LoginOutcome, ArcaneVaultError> {
+    let url = service.start_oidc_login(PROVIDER).await?;
+    let (state, code) = get_idp().authorize(&url, subject, edit);
+    service
+        .finish_oidc_login(
+            &state,
+            &code,
+            &SessionDevice::default(),
+            &RequestContext::default(),
+        )
+        .await
+}
+
+fn get_error_code(err: &ArcaneVaultError) -> Option<&str> {
+    err.code.as_ref().and_then(|code| code.get_error_code())
+}
+
+#[tokio::test]
+async fn registers_the_user_of_a_verified_email_on_first_login() {
+    let service = create_user_service().await;
+
+    let outcome = log_in(service.as_ref(), "ada", |_| {}).await.unwrap();
+    let LoginOutcome::LoggedIn(user_id, tokens) = outcome else {
+        panic!("expected a session, got {:?}", outcome);
+    };
+    let principal = service.authenticate(&tokens.access_token).await.unwrap();
+    assert_eq!(principal.user_id, user_id);
+
+    let outcome = log_in(service.as_ref(), "ada", |_| {}).await.unwrap();
+    let LoginOutcome::LoggedIn(id, _) = outcome else {
+        panic!("expected a session, got {:?}", outcome);
+    };
+    assert_eq!(id, user_id);
+}
+
+#[tokio::test]
+async fn rejects_an_unverified_email() {
+    let service = create_user_service().await;
+
+    let err = log_in(service.as_ref(), "ada", |grant| {
+        grant.claims["email_verified"] = false.into();
+    })
+    .await
+    .unwrap_err();
+
+    assert_eq!(get_error_code(&err), Some("PA029"));
+}
+
+#[tokio::test]
+async fn rejects_id_tokens_of_another_nonce_issuer_or_audience() {
+    let service = create_user_service().await;
+
+    for (claim, value) in [
+        ("nonce", "another nonce"),
+        ("iss", "http://localhost:1/another"),
+        ("aud", "another client"),
+    ] {
+        let err = log_in(service.as_ref(), "ada", |grant| {
+            grant.claims[claim] = value.into();
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(get_error_code(&err), Some("PA027"), "{}", claim);
+    }
+}
+
+#[tokio::test]
+async fn rejects_a_code_of_another_pkce_challenge() {
+    let service = create_user_service().await;
+
+    let err = log_in(service.as_ref(), "ada", |grant| {
+        grant.code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(b"another verifier"));
+    })
+    .await
+    .unwrap_err();
+
+    assert_eq!(get_error_code(&err), Some("PA027"));
+}
+
+#[tokio::test]
+async fn rejects_a_substituted_access_token() {
+    let service = create_user_service().await;
+
+    let err = log_in(service.as_ref(), "ada", |grant| {
+        let hash = Sha256::digest(b"another access token");
+        grant.claims["at_hash"] = URL_SAFE_NO_PAD.encode(&hash[..16]).into();
+    })
+    .await
+    .unwrap_err();
+
+    assert_eq!(get_error_code(&err), Some("PA027"));
+}
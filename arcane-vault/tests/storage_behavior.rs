@@ -0,0 +1,237 @@
+use std::sync::OnceLock;
+
+use arcane_vault::{
+    Repositories, Storage,
+    domain::{
+        entity::{AUDIT_EVENT_PASSWORD_RESET, AUDIT_EVENT_PASSWORD_SET, NewUser},
+        error::ArcaneVaultError,
+    },
+};
+use ethereal_core::configuration::TomlConfiguration;
+use serde_json::json;
+use uuid::Uuid;
+
+// The storages other than PostgreSQL mirror its functions and triggers; these are checked against
+// each of them the same way.
+
+async fn create_repositories(storage: Storage) -> Repositories {
+    // SQLite writes to a file of this process, set up once for every test.
+    static CONFIG: OnceLock<()> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let path = std::env::temp_dir().join(format!("storage-behavior-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config_path = path.with_extension("toml");
+        let config = include_str!("../../setting/Config.toml").replace(
+            "sqlite_path = \"data/easynote.db\"",
+            &format!("sqlite_path = {:?}", path.display().to_string()),
+        );
+        std::fs::write(&config_path, config).unwrap();
+        TomlConfiguration::from_path(config_path.to_str().unwrap());
+    });
+    storage.create_repositories().await.unwrap()
+}
+
+fn get_error_code(err: &ArcaneVaultError) -> Option<&str> {
+    err.code.as_ref().and_then(|code| code.get_error_code())
+}
+
+async fn add_user(repositories: &Repositories) -> Uuid {
+    let user = NewUser {
+        account: format!("{}@example.com", Uuid::new_v4().simple()),
+        firstname: "Ada".into(),
+        lastname: "Lovelace".into(),
+        locale: 1,
+        timezone: "UTC".into(),
+        ..Default::default()
+    };
+    let verification_code = repositories
+        .users
+        .register_user(&user, "hash")
+        .await
+        .unwrap();
+    repositories
+        .users
+        .verify_user(&user.account, "hash", &verification_code)
+        .await
+        .unwrap()
+}
+
+async fn check_note_revisions(repositories: &Repositories) {
+    let user_id = add_user(repositories).await;
+    let collection_id = repositories
+        .collections
+        .get_default_collection(user_id)
+        .await
+        .unwrap();
+    let note_id = repositories
+        .notes
+        .add_note("first", collection_id, 2, None)
+        .await
+        .unwrap();
+
+    repositories
+        .notes
+        .update_note_meta(note_id, json!({ "tag": "a" }))
+        .await
+        .unwrap();
+    // Same meta, so no revision is kept.
+    repositories
+        .notes
+        .update_note_meta(note_id, json!({ "tag": "a" }))
+        .await
+        .unwrap();
+    repositories
+        .notes
+        .update_note_meta(note_id, json!({ "tag": "b" }))
+        .await
+        .unwrap();
+
+    let revisions = repositories
+        .note_revisions
+        .query_note_revisions(user_id, note_id, 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        revisions
+            .iter()
+            .map(|revision| (revision.revision, revision.meta.clone()))
+            .collect::<Vec<_>>(),
+        [
+            (3, json!({ "tag": "b" })),
+            (2, json!({ "tag": "a" })),
+            (1, json!({})),
+        ]
+    );
+    let revision = repositories
+        .note_revisions
+        .restore_note_revision(user_id, note_id, 1)
+        .await
+        .unwrap();
+    assert_eq!(revision, 4);
+    let restored = repositories
+        .note_revisions
+        .query_note_revision(user_id, note_id, 4)
+        .await
+        .unwrap();
+    assert_eq!(
+        (restored.content.as_str(), restored.meta),
+        ("first", json!({}))
+    );
+}
+
+async fn check_password_events(repositories: &Repositories) {
+    let user_id = add_user(repositories).await;
+    repositories
+        .users
+        .set_password(user_id, "new hash")
+        .await
+        .unwrap();
+    let account = repositories
+        .users
+        .query_user_by_id(user_id)
+        .await
+        .unwrap()
+        .unwrap()
+        .email_account;
+    let verification_code = repositories
+        .users
+        .request_reset_password(&account)
+        .await
+        .unwrap();
+    repositories
+        .users
+        .reset_password(&account, &verification_code, "reset hash")
+        .await
+        .unwrap();
+
+    let events = repositories
+        .audit_events
+        .query_audit_events(user_id, 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .map(|event| (event.event_type, event.actor_id.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (AUDIT_EVENT_PASSWORD_RESET, ""),
+            (AUDIT_EVENT_PASSWORD_SET, user_id.to_string().as_str()),
+        ]
+    );
+}
+
+async fn check_error_codes(repositories: &Repositories) {
+    let user_id = add_user(repositories).await;
+    let other_user_id = add_user(repositories).await;
+    let collection_id = repositories
+        .collections
+        .get_default_collection(user_id)
+        .await
+        .unwrap();
+    let note_id = repositories
+        .notes
+        .add_note("content", collection_id, 2, None)
+        .await
+        .unwrap();
+
+    let err = repositories
+        .note_revisions
+        .query_note_revision(other_user_id, note_id, 1)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PN005"));
+    let err = repositories
+        .note_revisions
+        .query_note_revision(user_id, note_id, 2)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PN008"));
+
+    let user = NewUser {
+        account: "taken@example.com".into(),
+        locale: 1,
+        timezone: "UTC".into(),
+        ..Default::default()
+    };
+    repositories
+        .users
+        .register_user(&user, "hash")
+        .await
+        .unwrap();
+    let err = repositories
+        .users
+        .register_user(&user, "hash")
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA001"));
+    let err = repositories
+        .users
+        .verify_user(&user.account, "hash", "WRONG1")
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA002"));
+    let err = repositories
+        .users
+        .update_profile(Uuid::new_v4(), "Ada", "Lovelace", 0, 1)
+        .await
+        .unwrap_err();
+    assert_eq!(get_error_code(&err), Some("PA007"));
+}
+
+#[tokio::test]
+async fn memory_storage_behaves_like_postgres() {
+    let repositories = create_repositories(Storage::Memory).await;
+    check_note_revisions(&repositories).await;
+    check_password_events(&repositories).await;
+    check_error_codes(&repositories).await;
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn sqlite_storage_behaves_like_postgres() {
+    let repositories = create_repositories(Storage::Sqlite).await;
+    check_note_revisions(&repositories).await;
+    check_password_events(&repositories).await;
+    check_error_codes(&repositories).await;
+}
@@ -1,8 +1,153 @@
+#[path = "build/validation.rs"]
+mod validation;
+
+// Messages exposed through the REST/JSON gateway, using the canonical proto3 JSON mapping.
+const JSON_MESSAGES: &[&str] = &[
+    ".user.User",
+    ".user.Session",
+    ".user.SessionTokens",
+    ".user.PersonalAccessToken",
+    ".user.ExternalIdentity",
+    ".user.AuditEvent",
+    ".user.CreateUserRequest",
+    ".user.CreateUserResponse",
+    ".user.VerifyUserRequest",
+    ".user.VerifyUserResponse",
+    ".user.LoginUserRequest",
+    ".user.LoginUserResponse",
+    ".user.VerifyTotpChallengeRequest",
+    ".user.VerifyTotpChallengeResponse",
+    ".user.EnrollTotpRequest",
+    ".user.EnrollTotpResponse",
+    ".user.ConfirmTotpRequest",
+    ".user.ConfirmTotpResponse",
+    ".user.DisableTotpRequest",
+    ".user.DisableTotpResponse",
+    ".user.GenerateRecoveryCodesRequest",
+    ".user.GenerateRecoveryCodesResponse",
+    ".user.RefreshSessionRequest",
+    ".user.RefreshSessionResponse",
+    ".user.ListSessionsRequest",
+    ".user.ListSessionsResponse",
+    ".user.RevokeSessionRequest",
+    ".user.RevokeSessionResponse",
+    ".user.LogoutEverywhereRequest",
+    ".user.LogoutEverywhereResponse",
+    ".user.CreatePersonalAccessTokenRequest",
+    ".user.CreatePersonalAccessTokenResponse",
+    ".user.ListPersonalAccessTokensRequest",
+    ".user.ListPersonalAccessTokensResponse",
+    ".user.RevokePersonalAccessTokenRequest",
+    ".user.RevokePersonalAccessTokenResponse",
+    ".user.StartOidcLoginRequest",
+    ".user.StartOidcLoginResponse",
+    ".user.FinishOidcLoginRequest",
+    ".user.FinishOidcLoginResponse",
+    ".user.StartOidcLinkRequest",
+    ".user.StartOidcLinkResponse",
+    ".user.FinishOidcLinkRequest",
+    ".user.FinishOidcLinkResponse",
+    ".user.ListExternalIdentitiesRequest",
+    ".user.ListExternalIdentitiesResponse",
+    ".user.UnlinkExternalIdentityRequest",
+    ".user.UnlinkExternalIdentityResponse",
+    ".user.RequestEmailChangeRequest",
+    ".user.RequestEmailChangeResponse",
+    ".user.ConfirmEmailChangeRequest",
+    ".user.ConfirmEmailChangeResponse",
+    ".user.ListAuditEventsRequest",
+    ".user.ListAuditEventsResponse",
+    ".user.UpdateProfileRequest",
+    ".user.UpdateProfileResponse",
+    ".user.UploadAvatarResponse",
+    ".user.QueryUserResponse",
+    ".user.ListUsersResponse",
+    ".note.Attachment",
+    ".note.UploadAttachmentResponse",
+    ".note.ListAttachmentsResponse",
+    ".note.DeleteAttachmentRequest",
+    ".note.DeleteAttachmentResponse",
+    ".note.NoteRevision",
+    ".note.DiffLine",
+    ".note.ListNoteRevisionsResponse",
+    ".note.GetNoteRevisionResponse",
+    ".note.DiffNoteRevisionsResponse",
+    ".note.RestoreNoteRevisionResponse",
+    ".google.rpc.BadRequest",
+    ".google.rpc.ErrorInfo",
+    ".google.rpc.RetryInfo",
+    ".google.rpc.LocalizedMessage",
+];
+const JSON_TIMESTAMP_FIELDS: &[&str] = &[
+    ".user.User.created_at",
+    ".user.User.updated_at",
+    ".user.User.last_login_at",
+    ".user.Session.created_at",
+    ".user.Session.last_used_at",
+    ".user.Session.expires_at",
+    ".user.SessionTokens.access_token_expires_at",
+    ".user.SessionTokens.refresh_token_expires_at",
+    ".user.PersonalAccessToken.created_at",
+    ".user.PersonalAccessToken.expires_at",
+    ".user.PersonalAccessToken.last_used_at",
+    ".user.CreatePersonalAccessTokenRequest.expires_at",
+    ".user.ExternalIdentity.created_at",
+    ".user.ExternalIdentity.last_login_at",
+    ".user.AuditEvent.created_at",
+    ".note.Attachment.created_at",
+    ".note.NoteRevision.created_at",
+];
+const JSON_DURATION_FIELDS: &[&str] = &[".google.rpc.RetryInfo.retry_delay"];
+const JSON_OPTIONAL_MESSAGE_FIELDS: &[&str] = &[
+    ".user.QueryUserResponse.user",
+    ".user.LoginUserResponse.session",
+    ".user.VerifyTotpChallengeResponse.session",
+    ".user.RefreshSessionResponse.session",
+    ".user.CreatePersonalAccessTokenResponse.personal_access_token",
+    ".user.FinishOidcLoginResponse.session",
+    ".user.FinishOidcLinkResponse.external_identity",
+    ".user.UpdateProfileResponse.user",
+    ".note.UploadAttachmentResponse.attachment",
+    ".note.GetNoteRevisionResponse.revision",
+    ".google.rpc.BadRequest.FieldViolation.localized_message",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::configure()
+    let mut builder = tonic_build::configure()
         .out_dir("src/proto")
-        .file_descriptor_set_path("proto/service_descriptor.bin")
-        .compile_protos(&["user.proto"], &["proto/"])
+        .file_descriptor_set_path("proto/service_descriptor.bin");
+    for message in JSON_MESSAGES {
+        builder = builder
+            .message_attribute(message, "#[derive(serde::Serialize, serde::Deserialize)]")
+            .message_attribute(message, "#[serde(rename_all = \"camelCase\", default)]");
+    }
+    for field in JSON_TIMESTAMP_FIELDS {
+        builder = builder.field_attribute(
+            field,
+            "#[serde(with = \"crate::proto::serde_timestamp\", skip_serializing_if = \"Option::is_none\")]",
+        );
+    }
+    for field in JSON_DURATION_FIELDS {
+        builder = builder.field_attribute(
+            field,
+            "#[serde(with = \"crate::proto::serde_duration\", skip_serializing_if = \"Option::is_none\")]",
+        );
+    }
+    for field in JSON_OPTIONAL_MESSAGE_FIELDS {
+        builder = builder.field_attribute(field, "#[serde(skip_serializing_if = \"Option::is_none\")]");
+    }
+    builder
+        .compile_protos(
+            &[
+                "user.proto",
+                "reference_data.proto",
+                "note.proto",
+                "google/rpc/status.proto",
+                "google/rpc/error_details.proto",
+            ],
+            &["proto/"],
+        )
         .expect("Failed to compile protos");
+    validation::generate_validators("proto/service_descriptor.bin", "src/proto/validation.rs")?;
     Ok(())
 }
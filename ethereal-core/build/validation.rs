@@ -0,0 +1,292 @@
+//! Generates `src/proto/validation.rs`: a `Validate` impl for every message with
+//! `(validate.rules)` field options, read back from the descriptor set protoc wrote.
+
+use std::fmt::Write;
+
+use prost::Message;
+
+const TYPE_INT32: i32 = 5;
+const TYPE_STRING: i32 = 9;
+const LABEL_REPEATED: i32 = 3;
+
+// The subset of descriptor.proto needed here; prost-types has no field for the extension.
+#[derive(Clone, PartialEq, Message)]
+struct FileDescriptorSet {
+    #[prost(message, repeated, tag = "1")]
+    file: Vec<FileDescriptorProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FileDescriptorProto {
+    #[prost(string, optional, tag = "2")]
+    package: Option<String>,
+    #[prost(message, repeated, tag = "4")]
+    message_type: Vec<DescriptorProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct DescriptorProto {
+    #[prost(string, optional, tag = "1")]
+    name: Option<String>,
+    #[prost(message, repeated, tag = "2")]
+    field: Vec<FieldDescriptorProto>,
+    #[prost(message, repeated, tag = "3")]
+    nested_type: Vec<DescriptorProto>,
+    #[prost(message, repeated, tag = "8")]
+    oneof_decl: Vec<OneofDescriptorProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FieldDescriptorProto {
+    #[prost(string, optional, tag = "1")]
+    name: Option<String>,
+    #[prost(int32, optional, tag = "4")]
+    label: Option<i32>,
+    #[prost(int32, optional, tag = "5")]
+    r#type: Option<i32>,
+    #[prost(message, optional, tag = "8")]
+    options: Option<FieldOptions>,
+    #[prost(int32, optional, tag = "9")]
+    oneof_index: Option<i32>,
+    #[prost(bool, optional, tag = "17")]
+    proto3_optional: Option<bool>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct OneofDescriptorProto {
+    #[prost(string, optional, tag = "1")]
+    name: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FieldOptions {
+    #[prost(message, optional, tag = "50001")]
+    rules: Option<FieldRules>,
+}
+
+// Mirrors validate.FieldRules in proto/validate/validate.proto.
+#[derive(Clone, PartialEq, Message)]
+struct FieldRules {
+    #[prost(uint32, optional, tag = "1")]
+    min_len: Option<u32>,
+    #[prost(uint32, optional, tag = "2")]
+    max_len: Option<u32>,
+    #[prost(bool, tag = "3")]
+    email: bool,
+    #[prost(bool, tag = "4")]
+    uuid: bool,
+    #[prost(bool, tag = "5")]
+    password: bool,
+    #[prost(int32, optional, tag = "6")]
+    min: Option<i32>,
+    #[prost(int32, optional, tag = "7")]
+    max: Option<i32>,
+    #[prost(bool, tag = "8")]
+    required: bool,
+}
+
+pub fn generate_validators(
+    descriptor_path: &str,
+    out_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let descriptor_set = FileDescriptorSet::decode(std::fs::read(descriptor_path)?.as_slice())?;
+
+    let mut out = String::from(
+        "// This file is @generated by ethereal-core/build.rs from the `(validate.rules)` field options.\n\
+         #![allow(clippy::needless_update)]\n\n\
+         use super::google::rpc::bad_request::FieldViolation;\n\
+         use crate::validation::{IntRules, StringRules, Validate, check_int, check_string};\n",
+    );
+    for file in &descriptor_set.file {
+        let package = file.package.clone().unwrap_or_default();
+        if package == "google.protobuf" {
+            continue;
+        }
+        let module = format!("super::{}", package.replace('.', "::"));
+        for message in &file.message_type {
+            generate_message(&mut out, &module, message)?;
+        }
+    }
+
+    if std::fs::read_to_string(out_path).ok().as_deref() != Some(out.as_str()) {
+        std::fs::write(out_path, out)?;
+    }
+    Ok(())
+}
+
+fn generate_message(
+    out: &mut String,
+    module: &str,
+    message: &DescriptorProto,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let name = message.name.clone().unwrap_or_default();
+    let nested_module = format!("{}::{}", module, get_snake_case(&name));
+    for nested in &message.nested_type {
+        generate_message(out, &nested_module, nested)?;
+    }
+
+    let mut checks = String::new();
+    for field in &message.field {
+        let Some(rules) = field
+            .options
+            .as_ref()
+            .and_then(|options| options.rules.as_ref())
+        else {
+            continue;
+        };
+        let field_name = field.name.clone().unwrap_or_default();
+        if field.label == Some(LABEL_REPEATED) {
+            return Err(format!(
+                "{}.{}: rules on repeated fields are not supported",
+                name, field_name
+            )
+            .into());
+        }
+        let get_field_check = |value: &str, deref_value: &str| match field.r#type {
+            Some(TYPE_STRING) => Ok(get_string_check(&field_name, value, rules)),
+            Some(TYPE_INT32) => Ok(get_int_check(&field_name, deref_value, rules)),
+            _ => Err(format!(
+                "{}.{}: rules need a string or int32 field",
+                name, field_name
+            )),
+        };
+
+        // Fields in a oneof, proto3 `optional` ones included, are only checked when set.
+        let condition = match field.oneof_index {
+            Some(_) if field.proto3_optional == Some(true) => {
+                Some(format!("Some(value) = &self.{}", field_name))
+            }
+            Some(index) => {
+                let oneof = message.oneof_decl[index as usize]
+                    .name
+                    .clone()
+                    .unwrap_or_default();
+                Some(format!(
+                    "Some({}::{}::{}(value)) = &self.{}",
+                    nested_module,
+                    get_upper_camel_case(&oneof),
+                    get_upper_camel_case(&field_name),
+                    oneof
+                ))
+            }
+            None => None,
+        };
+        match condition {
+            Some(condition) => {
+                let check = get_field_check("value", "*value")?;
+                writeln!(checks, "        if let {} {{", condition)?;
+                writeln!(checks, "{}", indent(&check))?;
+                writeln!(checks, "        }}")?;
+            }
+            None => {
+                let value = format!("self.{}", field_name);
+                writeln!(
+                    checks,
+                    "{}",
+                    get_field_check(&format!("&{}", value), &value)?
+                )?;
+            }
+        }
+    }
+    if checks.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out)?;
+    writeln!(out, "impl Validate for {}::{} {{", module, name)?;
+    writeln!(out, "    fn validate(&self) -> Vec<FieldViolation> {{")?;
+    writeln!(out, "        let mut violations = Vec::new();")?;
+    write!(out, "{}", checks)?;
+    writeln!(out, "        violations")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+fn get_string_check(field_name: &str, value: &str, rules: &FieldRules) -> String {
+    let mut values = Vec::new();
+    if let Some(min_len) = rules.min_len {
+        values.push(format!("min_len: Some({}),", min_len));
+    }
+    if let Some(max_len) = rules.max_len {
+        values.push(format!("max_len: Some({}),", max_len));
+    }
+    for (name, enabled) in [
+        ("required", rules.required),
+        ("email", rules.email),
+        ("uuid", rules.uuid),
+        ("password", rules.password),
+    ] {
+        if enabled {
+            values.push(format!("{}: true,", name));
+        }
+    }
+    get_check("check_string", "StringRules", field_name, value, &values)
+}
+
+fn get_int_check(field_name: &str, value: &str, rules: &FieldRules) -> String {
+    let mut values = Vec::new();
+    if let Some(min) = rules.min {
+        values.push(format!("min: Some({}),", min));
+    }
+    if let Some(max) = rules.max {
+        values.push(format!("max: Some({}),", max));
+    }
+    get_check("check_int", "IntRules", field_name, value, &values)
+}
+
+fn get_check(
+    function: &str,
+    rules_type: &str,
+    field_name: &str,
+    value: &str,
+    values: &[String],
+) -> String {
+    let mut check = String::new();
+    check.push_str(&format!("        {}(\n", function));
+    check.push_str("            &mut violations,\n");
+    check.push_str(&format!("            \"{}\",\n", field_name));
+    check.push_str(&format!("            {},\n", value));
+    check.push_str(&format!("            &{} {{\n", rules_type));
+    for value in values {
+        check.push_str(&format!("                {}\n", value));
+    }
+    check.push_str(&format!("                ..{}::default()\n", rules_type));
+    check.push_str("            },\n");
+    check.push_str("        );");
+    check
+}
+
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn get_snake_case(name: &str) -> String {
+    let mut snake_case = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake_case.push('_');
+            }
+            snake_case.push(c.to_ascii_lowercase());
+        } else {
+            snake_case.push(c);
+        }
+    }
+    snake_case
+}
+
+fn get_upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -38,6 +38,17 @@ impl TomlConfiguration {
         config_lock.as_ref().unwrap().clone()
     }
 
+    /// Loads the configuration at `config_path` and returns it from every later
+    /// [`TomlConfiguration::get_config`], whatever path that is given.
+    pub fn from_path(config_path: &str) -> Self {
+        let config = Self {
+            config: load_config(config_path),
+        };
+        print_config(&config);
+        *CONFIG.write().unwrap() = Some(config.clone());
+        config
+    }
+
     pub fn get<'a, T>(&self, key: &str) -> Result<T, impl std::error::Error>
     where
         T: serde::Deserialize<'a>,
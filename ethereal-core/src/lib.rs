@@ -1,2 +1,3 @@
 pub mod configuration;
-pub mod proto;
\ No newline at end of file
+pub mod proto;
+pub mod validation;
\ No newline at end of file
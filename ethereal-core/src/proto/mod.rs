@@ -1,3 +1,17 @@
+mod note;
+mod reference_data;
 mod user;
+mod validation;
+pub mod serde_duration;
+pub mod serde_timestamp;
+pub mod validate;
 
+pub use note::*;
+pub use reference_data::*;
 pub use user::*;
+
+pub mod google {
+    pub mod rpc {
+        include!("google.rpc.rs");
+    }
+}
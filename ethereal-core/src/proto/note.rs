@@ -0,0 +1,1053 @@
+// This file is @generated by prost-build.
+/// A file attached to a note. Files of the same content are stored once per user.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Attachment {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub note_id: ::prost::alloc::string::String,
+    /// Hex SHA-256 of the content.
+    #[prost(string, tag = "3")]
+    pub sha256: ::prost::alloc::string::String,
+    #[prost(int64, tag = "4")]
+    pub size: i64,
+    #[prost(string, tag = "5")]
+    pub file_name: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub content_type: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "7")]
+    #[serde(
+        with = "crate::proto::serde_timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// A version of a note. The note itself is its latest revision; every change of its content or
+/// meta keeps the version before as a revision, for as long as the retention settings allow.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NoteRevision {
+    #[prost(string, tag = "1")]
+    pub note_id: ::prost::alloc::string::String,
+    /// Counts up from 1 with every change.
+    #[prost(int32, tag = "2")]
+    pub revision: i32,
+    #[prost(string, tag = "3")]
+    pub content: ::prost::alloc::string::String,
+    /// The JSON of the meta of the note.
+    #[prost(string, tag = "4")]
+    pub meta: ::prost::alloc::string::String,
+    /// When the note was changed to this version.
+    #[prost(message, optional, tag = "5")]
+    #[serde(
+        with = "crate::proto::serde_timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// A line of a line-by-line diff.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiffLine {
+    /// 1 in both revisions, 2 only in the one diffed to, 3 only in the one diffed from.
+    #[prost(int32, tag = "1")]
+    pub operation: i32,
+    #[prost(string, tag = "2")]
+    pub text: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UploadAttachmentRequest {
+    /// `note_id`, `file_name` and `content_type` are read from the first message only.
+    #[prost(string, tag = "1")]
+    pub note_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub file_name: ::prost::alloc::string::String,
+    /// `application/octet-stream` when empty.
+    #[prost(string, tag = "3")]
+    pub content_type: ::prost::alloc::string::String,
+    /// The next part of the file.
+    #[prost(bytes = "vec", tag = "4")]
+    pub chunk: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UploadAttachmentResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment: ::core::option::Option<Attachment>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListAttachmentsRequest {
+    #[prost(string, tag = "1")]
+    pub note_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListAttachmentsResponse {
+    /// Oldest first.
+    #[prost(message, repeated, tag = "1")]
+    pub attachments: ::prost::alloc::vec::Vec<Attachment>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadAttachmentRequest {
+    #[prost(string, tag = "1")]
+    pub attachment_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadAttachmentResponse {
+    /// Only set in the first message, which has no chunk.
+    #[prost(message, optional, tag = "1")]
+    pub attachment: ::core::option::Option<Attachment>,
+    /// The next part of the file.
+    #[prost(bytes = "vec", tag = "2")]
+    pub chunk: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteAttachmentRequest {
+    #[prost(string, tag = "1")]
+    pub attachment_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct DeleteAttachmentResponse {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListNoteRevisionsRequest {
+    #[prost(string, tag = "1")]
+    pub note_id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub offset: i32,
+    #[prost(int32, tag = "3")]
+    pub limit: i32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListNoteRevisionsResponse {
+    /// Newest first, starting with the note itself.
+    #[prost(message, repeated, tag = "1")]
+    pub revisions: ::prost::alloc::vec::Vec<NoteRevision>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteRevisionRequest {
+    #[prost(string, tag = "1")]
+    pub note_id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub revision: i32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNoteRevisionResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: ::core::option::Option<NoteRevision>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiffNoteRevisionsRequest {
+    #[prost(string, tag = "1")]
+    pub note_id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub from_revision: i32,
+    #[prost(int32, tag = "3")]
+    pub to_revision: i32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiffNoteRevisionsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub content_diff: ::prost::alloc::vec::Vec<DiffLine>,
+    #[prost(message, repeated, tag = "2")]
+    pub meta_diff: ::prost::alloc::vec::Vec<DiffLine>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreNoteRevisionRequest {
+    #[prost(string, tag = "1")]
+    pub note_id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub revision: i32,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RestoreNoteRevisionResponse {
+    /// The revision the note is at now; the same when the latest revision is restored.
+    #[prost(int32, tag = "1")]
+    pub revision: i32,
+}
+/// Generated client implementations.
+pub mod note_service_client {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    /// Notes of the user of the bearer token; personal access tokens need the `notes:read` scope to
+    /// read and `notes:write` to change them.
+    #[derive(Debug, Clone)]
+    pub struct NoteServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl NoteServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> NoteServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::Body>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + std::marker::Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + std::marker::Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> NoteServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::Body>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::Body>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::Body>,
+            >>::Error: Into<StdError> + std::marker::Send + std::marker::Sync,
+        {
+            NoteServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// Streams a file of at most `arcane-vault[0].attachment_max_size` bytes in chunks and attaches
+        /// it to a note.
+        pub async fn upload_attachment(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::UploadAttachmentRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::UploadAttachmentResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/UploadAttachment",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "UploadAttachment"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        pub async fn list_attachments(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListAttachmentsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListAttachmentsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/ListAttachments",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "ListAttachments"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Streams the attachment, then its content in chunks.
+        pub async fn download_attachment(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DownloadAttachmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::DownloadAttachmentResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/DownloadAttachment",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "DownloadAttachment"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// The file is deleted once no attachment has referred to it for a while.
+        pub async fn delete_attachment(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteAttachmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteAttachmentResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/DeleteAttachment",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "DeleteAttachment"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_note_revisions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListNoteRevisionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListNoteRevisionsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/ListNoteRevisions",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "ListNoteRevisions"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_note_revision(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetNoteRevisionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetNoteRevisionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/GetNoteRevision",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "GetNoteRevision"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Line by line, of the content and of the pretty-printed meta.
+        pub async fn diff_note_revisions(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DiffNoteRevisionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DiffNoteRevisionsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/DiffNoteRevisions",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "DiffNoteRevisions"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Changes the note back to a revision, keeping the version it replaces as a revision too.
+        pub async fn restore_note_revision(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreNoteRevisionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreNoteRevisionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/note.NoteService/RestoreNoteRevision",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("note.NoteService", "RestoreNoteRevision"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod note_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with NoteServiceServer.
+    #[async_trait]
+    pub trait NoteService: std::marker::Send + std::marker::Sync + 'static {
+        /// Streams a file of at most `arcane-vault[0].attachment_max_size` bytes in chunks and attaches
+        /// it to a note.
+        async fn upload_attachment(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::UploadAttachmentRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<super::UploadAttachmentResponse>,
+            tonic::Status,
+        >;
+        async fn list_attachments(
+            &self,
+            request: tonic::Request<super::ListAttachmentsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListAttachmentsResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the DownloadAttachment method.
+        type DownloadAttachmentStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::DownloadAttachmentResponse,
+                    tonic::Status,
+                >,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams the attachment, then its content in chunks.
+        async fn download_attachment(
+            &self,
+            request: tonic::Request<super::DownloadAttachmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::DownloadAttachmentStream>,
+            tonic::Status,
+        >;
+        /// The file is deleted once no attachment has referred to it for a while.
+        async fn delete_attachment(
+            &self,
+            request: tonic::Request<super::DeleteAttachmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteAttachmentResponse>,
+            tonic::Status,
+        >;
+        async fn list_note_revisions(
+            &self,
+            request: tonic::Request<super::ListNoteRevisionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListNoteRevisionsResponse>,
+            tonic::Status,
+        >;
+        async fn get_note_revision(
+            &self,
+            request: tonic::Request<super::GetNoteRevisionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetNoteRevisionResponse>,
+            tonic::Status,
+        >;
+        /// Line by line, of the content and of the pretty-printed meta.
+        async fn diff_note_revisions(
+            &self,
+            request: tonic::Request<super::DiffNoteRevisionsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DiffNoteRevisionsResponse>,
+            tonic::Status,
+        >;
+        /// Changes the note back to a revision, keeping the version it replaces as a revision too.
+        async fn restore_note_revision(
+            &self,
+            request: tonic::Request<super::RestoreNoteRevisionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreNoteRevisionResponse>,
+            tonic::Status,
+        >;
+    }
+    /// Notes of the user of the bearer token; personal access tokens need the `notes:read` scope to
+    /// read and `notes:write` to change them.
+    #[derive(Debug)]
+    pub struct NoteServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> NoteServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for NoteServiceServer<T>
+    where
+        T: NoteService,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/note.NoteService/UploadAttachment" => {
+                    #[allow(non_camel_case_types)]
+                    struct UploadAttachmentSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::ClientStreamingService<
+                        super::UploadAttachmentRequest,
+                    > for UploadAttachmentSvc<T> {
+                        type Response = super::UploadAttachmentResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::UploadAttachmentRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::upload_attachment(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UploadAttachmentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/note.NoteService/ListAttachments" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListAttachmentsSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::UnaryService<super::ListAttachmentsRequest>
+                    for ListAttachmentsSvc<T> {
+                        type Response = super::ListAttachmentsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListAttachmentsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::list_attachments(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListAttachmentsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/note.NoteService/DownloadAttachment" => {
+                    #[allow(non_camel_case_types)]
+                    struct DownloadAttachmentSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::ServerStreamingService<
+                        super::DownloadAttachmentRequest,
+                    > for DownloadAttachmentSvc<T> {
+                        type Response = super::DownloadAttachmentResponse;
+                        type ResponseStream = T::DownloadAttachmentStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DownloadAttachmentRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::download_attachment(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DownloadAttachmentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/note.NoteService/DeleteAttachment" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteAttachmentSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::UnaryService<super::DeleteAttachmentRequest>
+                    for DeleteAttachmentSvc<T> {
+                        type Response = super::DeleteAttachmentResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteAttachmentRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::delete_attachment(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DeleteAttachmentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/note.NoteService/ListNoteRevisions" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListNoteRevisionsSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::UnaryService<super::ListNoteRevisionsRequest>
+                    for ListNoteRevisionsSvc<T> {
+                        type Response = super::ListNoteRevisionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListNoteRevisionsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::list_note_revisions(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListNoteRevisionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/note.NoteService/GetNoteRevision" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNoteRevisionSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::UnaryService<super::GetNoteRevisionRequest>
+                    for GetNoteRevisionSvc<T> {
+                        type Response = super::GetNoteRevisionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetNoteRevisionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::get_note_revision(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetNoteRevisionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/note.NoteService/DiffNoteRevisions" => {
+                    #[allow(non_camel_case_types)]
+                    struct DiffNoteRevisionsSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::UnaryService<super::DiffNoteRevisionsRequest>
+                    for DiffNoteRevisionsSvc<T> {
+                        type Response = super::DiffNoteRevisionsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DiffNoteRevisionsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::diff_note_revisions(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DiffNoteRevisionsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/note.NoteService/RestoreNoteRevision" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreNoteRevisionSvc<T: NoteService>(pub Arc<T>);
+                    impl<
+                        T: NoteService,
+                    > tonic::server::UnaryService<super::RestoreNoteRevisionRequest>
+                    for RestoreNoteRevisionSvc<T> {
+                        type Response = super::RestoreNoteRevisionResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreNoteRevisionRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as NoteService>::restore_note_revision(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RestoreNoteRevisionSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        let mut response = http::Response::new(
+                            tonic::body::Body::default(),
+                        );
+                        let headers = response.headers_mut();
+                        headers
+                            .insert(
+                                tonic::Status::GRPC_STATUS,
+                                (tonic::Code::Unimplemented as i32).into(),
+                            );
+                        headers
+                            .insert(
+                                http::header::CONTENT_TYPE,
+                                tonic::metadata::GRPC_CONTENT_TYPE,
+                            );
+                        Ok(response)
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for NoteServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "note.NoteService";
+    impl<T> tonic::server::NamedService for NoteServiceServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
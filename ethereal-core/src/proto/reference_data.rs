@@ -0,0 +1,790 @@
+// This file is @generated by prost-build.
+/// A row of a lookup table, e.g. `genders` or `roles`.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReferenceEntry {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub description: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Locale {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    #[prost(string, tag = "2")]
+    pub language_code: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub locale_code: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub name_en: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub native_name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "6")]
+    pub is_rtl: bool,
+    #[prost(bool, tag = "7")]
+    pub enabled: bool,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListGendersRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListGendersResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub genders: ::prost::alloc::vec::Vec<ReferenceEntry>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListLocalesRequest {
+    /// Leaves out the locales that are not `enabled`.
+    #[prost(bool, tag = "1")]
+    pub enabled_only: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListLocalesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub locales: ::prost::alloc::vec::Vec<Locale>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListTimezonesRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTimezonesResponse {
+    /// IANA names, e.g. "Europe/Berlin".
+    #[prost(string, repeated, tag = "1")]
+    pub timezones: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListRolesRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRolesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub roles: ::prost::alloc::vec::Vec<ReferenceEntry>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListUserStatusesRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListUserStatusesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub user_statuses: ::prost::alloc::vec::Vec<ReferenceEntry>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ListNoteSourceTypesRequest {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListNoteSourceTypesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub note_source_types: ::prost::alloc::vec::Vec<ReferenceEntry>,
+}
+/// Generated client implementations.
+pub mod reference_data_service_client {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    /// Values accepted by the integer and code fields of the other services.
+    #[derive(Debug, Clone)]
+    pub struct ReferenceDataServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl ReferenceDataServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> ReferenceDataServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::Body>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + std::marker::Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + std::marker::Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> ReferenceDataServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::Body>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::Body>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::Body>,
+            >>::Error: Into<StdError> + std::marker::Send + std::marker::Sync,
+        {
+            ReferenceDataServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn list_genders(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListGendersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListGendersResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reference_data.ReferenceDataService/ListGenders",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("reference_data.ReferenceDataService", "ListGenders"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_locales(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListLocalesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListLocalesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reference_data.ReferenceDataService/ListLocales",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("reference_data.ReferenceDataService", "ListLocales"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_timezones(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListTimezonesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListTimezonesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reference_data.ReferenceDataService/ListTimezones",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "reference_data.ReferenceDataService",
+                        "ListTimezones",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_roles(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListRolesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListRolesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reference_data.ReferenceDataService/ListRoles",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("reference_data.ReferenceDataService", "ListRoles"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_user_statuses(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListUserStatusesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListUserStatusesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reference_data.ReferenceDataService/ListUserStatuses",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "reference_data.ReferenceDataService",
+                        "ListUserStatuses",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_note_source_types(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListNoteSourceTypesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListNoteSourceTypesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reference_data.ReferenceDataService/ListNoteSourceTypes",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "reference_data.ReferenceDataService",
+                        "ListNoteSourceTypes",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod reference_data_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ReferenceDataServiceServer.
+    #[async_trait]
+    pub trait ReferenceDataService: std::marker::Send + std::marker::Sync + 'static {
+        async fn list_genders(
+            &self,
+            request: tonic::Request<super::ListGendersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListGendersResponse>,
+            tonic::Status,
+        >;
+        async fn list_locales(
+            &self,
+            request: tonic::Request<super::ListLocalesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListLocalesResponse>,
+            tonic::Status,
+        >;
+        async fn list_timezones(
+            &self,
+            request: tonic::Request<super::ListTimezonesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListTimezonesResponse>,
+            tonic::Status,
+        >;
+        async fn list_roles(
+            &self,
+            request: tonic::Request<super::ListRolesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListRolesResponse>,
+            tonic::Status,
+        >;
+        async fn list_user_statuses(
+            &self,
+            request: tonic::Request<super::ListUserStatusesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListUserStatusesResponse>,
+            tonic::Status,
+        >;
+        async fn list_note_source_types(
+            &self,
+            request: tonic::Request<super::ListNoteSourceTypesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListNoteSourceTypesResponse>,
+            tonic::Status,
+        >;
+    }
+    /// Values accepted by the integer and code fields of the other services.
+    #[derive(Debug)]
+    pub struct ReferenceDataServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> ReferenceDataServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>>
+    for ReferenceDataServiceServer<T>
+    where
+        T: ReferenceDataService,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/reference_data.ReferenceDataService/ListGenders" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListGendersSvc<T: ReferenceDataService>(pub Arc<T>);
+                    impl<
+                        T: ReferenceDataService,
+                    > tonic::server::UnaryService<super::ListGendersRequest>
+                    for ListGendersSvc<T> {
+                        type Response = super::ListGendersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListGendersRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReferenceDataService>::list_genders(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListGendersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reference_data.ReferenceDataService/ListLocales" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListLocalesSvc<T: ReferenceDataService>(pub Arc<T>);
+                    impl<
+                        T: ReferenceDataService,
+                    > tonic::server::UnaryService<super::ListLocalesRequest>
+                    for ListLocalesSvc<T> {
+                        type Response = super::ListLocalesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListLocalesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReferenceDataService>::list_locales(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListLocalesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reference_data.ReferenceDataService/ListTimezones" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListTimezonesSvc<T: ReferenceDataService>(pub Arc<T>);
+                    impl<
+                        T: ReferenceDataService,
+                    > tonic::server::UnaryService<super::ListTimezonesRequest>
+                    for ListTimezonesSvc<T> {
+                        type Response = super::ListTimezonesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListTimezonesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReferenceDataService>::list_timezones(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListTimezonesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reference_data.ReferenceDataService/ListRoles" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListRolesSvc<T: ReferenceDataService>(pub Arc<T>);
+                    impl<
+                        T: ReferenceDataService,
+                    > tonic::server::UnaryService<super::ListRolesRequest>
+                    for ListRolesSvc<T> {
+                        type Response = super::ListRolesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRolesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReferenceDataService>::list_roles(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListRolesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reference_data.ReferenceDataService/ListUserStatuses" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListUserStatusesSvc<T: ReferenceDataService>(pub Arc<T>);
+                    impl<
+                        T: ReferenceDataService,
+                    > tonic::server::UnaryService<super::ListUserStatusesRequest>
+                    for ListUserStatusesSvc<T> {
+                        type Response = super::ListUserStatusesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListUserStatusesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReferenceDataService>::list_user_statuses(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListUserStatusesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reference_data.ReferenceDataService/ListNoteSourceTypes" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListNoteSourceTypesSvc<T: ReferenceDataService>(pub Arc<T>);
+                    impl<
+                        T: ReferenceDataService,
+                    > tonic::server::UnaryService<super::ListNoteSourceTypesRequest>
+                    for ListNoteSourceTypesSvc<T> {
+                        type Response = super::ListNoteSourceTypesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListNoteSourceTypesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReferenceDataService>::list_note_source_types(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListNoteSourceTypesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        let mut response = http::Response::new(
+                            tonic::body::Body::default(),
+                        );
+                        let headers = response.headers_mut();
+                        headers
+                            .insert(
+                                tonic::Status::GRPC_STATUS,
+                                (tonic::Code::Unimplemented as i32).into(),
+                            );
+                        headers
+                            .insert(
+                                http::header::CONTENT_TYPE,
+                                tonic::metadata::GRPC_CONTENT_TYPE,
+                            );
+                        Ok(response)
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for ReferenceDataServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "reference_data.ReferenceDataService";
+    impl<T> tonic::server::NamedService for ReferenceDataServiceServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
@@ -0,0 +1,70 @@
+//! Canonical proto3 JSON mapping for `google.protobuf.Duration` fields, i.e. seconds with
+//! an `s` suffix such as `"1.5s"`.
+//!
+//! Wired onto the generated messages by `build.rs` through `#[serde(with = "...")]`.
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S>(value: &Option<prost_types::Duration>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(duration) => serializer.serialize_str(&format_duration(duration)),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<prost_types::Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => parse_duration(&value)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid duration: {}", value))),
+        None => Ok(None),
+    }
+}
+
+pub fn format_duration(duration: &prost_types::Duration) -> String {
+    let mut duration = *duration;
+    duration.normalize();
+
+    let sign = if duration.seconds < 0 || duration.nanos < 0 { "-" } else { "" };
+    let seconds = duration.seconds.unsigned_abs();
+    // proto3 JSON uses 0, 3, 6 or 9 fractional digits.
+    let nanos = duration.nanos.unsigned_abs();
+    let fraction = if nanos == 0 {
+        String::new()
+    } else if nanos.is_multiple_of(1_000_000) {
+        format!(".{:03}", nanos / 1_000_000)
+    } else if nanos.is_multiple_of(1_000) {
+        format!(".{:06}", nanos / 1_000)
+    } else {
+        format!(".{:09}", nanos)
+    };
+    format!("{}{}{}s", sign, seconds, fraction)
+}
+
+pub fn parse_duration(value: &str) -> Option<prost_types::Duration> {
+    let value = value.strip_suffix('s')?;
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value),
+    };
+    let (seconds, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if seconds.is_empty() || !seconds.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let seconds: i64 = seconds.parse().ok()?;
+    let nanos: i32 = format!("{:0<9}", fraction).parse().ok()?;
+    Some(prost_types::Duration {
+        seconds: sign * seconds,
+        nanos: sign as i32 * nanos,
+    })
+}
@@ -0,0 +1,140 @@
+//! Canonical proto3 JSON mapping for `google.protobuf.Timestamp` fields, i.e. RFC 3339
+//! strings in UTC such as `"2024-05-01T08:30:00.250Z"`.
+//!
+//! Wired onto the generated messages by `build.rs` through `#[serde(with = "...")]`.
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+pub fn serialize<S>(value: &Option<prost_types::Timestamp>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(timestamp) => serializer.serialize_str(&format_timestamp(timestamp)),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<prost_types::Timestamp>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) => parse_timestamp(&value)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid RFC 3339 timestamp: {}", value))),
+        None => Ok(None),
+    }
+}
+
+pub fn format_timestamp(timestamp: &prost_types::Timestamp) -> String {
+    let mut timestamp = *timestamp;
+    timestamp.normalize();
+
+    let days = timestamp.seconds.div_euclid(SECONDS_PER_DAY);
+    let seconds_of_day = timestamp.seconds.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = get_civil_from_days(days);
+    let mut result = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    );
+
+    // proto3 JSON uses 0, 3, 6 or 9 fractional digits.
+    let nanos = timestamp.nanos;
+    if nanos != 0 {
+        if nanos % 1_000_000 == 0 {
+            result.push_str(&format!(".{:03}", nanos / 1_000_000));
+        } else if nanos % 1_000 == 0 {
+            result.push_str(&format!(".{:06}", nanos / 1_000));
+        } else {
+            result.push_str(&format!(".{:09}", nanos));
+        }
+    }
+    result.push('Z');
+    result
+}
+
+pub fn parse_timestamp(value: &str) -> Option<prost_types::Timestamp> {
+    let bytes = value.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    let year: i64 = value.get(0..4)?.parse().ok()?;
+    let month: u32 = value.get(5..7)?.parse().ok()?;
+    let day: u32 = value.get(8..10)?.parse().ok()?;
+    let hour: i64 = value.get(11..13)?.parse().ok()?;
+    let minute: i64 = value.get(14..16)?.parse().ok()?;
+    let second: i64 = value.get(17..19)?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let mut rest = value.get(19..)?;
+    let mut nanos = 0i32;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 || digits > 9 {
+            return None;
+        }
+        nanos = format!("{:0<9}", &fraction[..digits]).parse().ok()?;
+        rest = &fraction[digits..];
+    }
+
+    let offset_seconds = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let offset_hour: i64 = rest.get(1..3)?.parse().ok()?;
+            let offset_minute: i64 = rest.get(4..6)?.parse().ok()?;
+            sign * (offset_hour * 3600 + offset_minute * 60)
+        }
+        _ => return None,
+    };
+
+    let days = get_days_from_civil(year, month, day);
+    Some(prost_types::Timestamp {
+        seconds: days * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second - offset_seconds,
+        nanos,
+    })
+}
+
+// Howard Hinnant's days <-> civil date algorithms, counting days from 1970-01-01.
+fn get_days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn get_civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
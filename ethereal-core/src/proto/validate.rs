@@ -0,0 +1,25 @@
+// This file is @generated by prost-build.
+/// Rules checked by the validators ethereal-core/build.rs generates for every annotated field.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct FieldRules {
+    /// Length in characters, for string fields.
+    #[prost(uint32, optional, tag = "1")]
+    pub min_len: ::core::option::Option<u32>,
+    #[prost(uint32, optional, tag = "2")]
+    pub max_len: ::core::option::Option<u32>,
+    #[prost(bool, tag = "3")]
+    pub email: bool,
+    #[prost(bool, tag = "4")]
+    pub uuid: bool,
+    /// At least one letter and one digit; combine with min_len/max_len for the length.
+    #[prost(bool, tag = "5")]
+    pub password: bool,
+    /// Inclusive range, for integer fields.
+    #[prost(int32, optional, tag = "6")]
+    pub min: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "7")]
+    pub max: ::core::option::Option<i32>,
+    /// Not empty, for string fields.
+    #[prost(bool, tag = "8")]
+    pub required: bool,
+}
@@ -0,0 +1,836 @@
+// This file is @generated by ethereal-core/build.rs from the `(validate.rules)` field options.
+#![allow(clippy::needless_update)]
+
+use super::google::rpc::bad_request::FieldViolation;
+use crate::validation::{IntRules, StringRules, Validate, check_int, check_string};
+
+impl Validate for super::user::CreateUserRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "email",
+            &self.email,
+            &StringRules {
+                max_len: Some(255),
+                email: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "password",
+            &self.password,
+            &StringRules {
+                min_len: Some(8),
+                max_len: Some(128),
+                password: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "firstname",
+            &self.firstname,
+            &StringRules {
+                max_len: Some(255),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "lastname",
+            &self.lastname,
+            &StringRules {
+                max_len: Some(255),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "gender",
+            self.gender,
+            &IntRules {
+                min: Some(0),
+                max: Some(3),
+                ..IntRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "locale",
+            self.locale,
+            &IntRules {
+                min: Some(1),
+                ..IntRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "signature",
+            &self.signature,
+            &StringRules {
+                max_len: Some(255),
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "timezone",
+            &self.timezone,
+            &StringRules {
+                max_len: Some(64),
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::VerifyUserRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "email",
+            &self.email,
+            &StringRules {
+                max_len: Some(255),
+                email: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "password",
+            &self.password,
+            &StringRules {
+                max_len: Some(128),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "verify_code",
+            &self.verify_code,
+            &StringRules {
+                min_len: Some(6),
+                max_len: Some(6),
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::LoginUserRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "email",
+            &self.email,
+            &StringRules {
+                max_len: Some(255),
+                email: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "password",
+            &self.password,
+            &StringRules {
+                max_len: Some(128),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::VerifyTotpChallengeRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "totp_challenge",
+            &self.totp_challenge,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "code",
+            &self.code,
+            &StringRules {
+                max_len: Some(16),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::EnrollTotpRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "password",
+            &self.password,
+            &StringRules {
+                max_len: Some(128),
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::ConfirmTotpRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "code",
+            &self.code,
+            &StringRules {
+                max_len: Some(16),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::DisableTotpRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "code",
+            &self.code,
+            &StringRules {
+                max_len: Some(16),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "password",
+            &self.password,
+            &StringRules {
+                max_len: Some(128),
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::GenerateRecoveryCodesRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "code",
+            &self.code,
+            &StringRules {
+                max_len: Some(16),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::QueryUserRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        if let Some(super::user::query_user_request::Identity::Id(value)) = &self.identity {
+            check_string(
+                &mut violations,
+                "id",
+                value,
+                &StringRules {
+                    uuid: true,
+                    ..StringRules::default()
+                },
+            );
+        }
+        if let Some(super::user::query_user_request::Identity::Email(value)) = &self.identity {
+            check_string(
+                &mut violations,
+                "email",
+                value,
+                &StringRules {
+                    max_len: Some(255),
+                    email: true,
+                    ..StringRules::default()
+                },
+            );
+        }
+        violations
+    }
+}
+
+impl Validate for super::user::ListUsersRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_int(
+            &mut violations,
+            "offset",
+            self.offset,
+            &IntRules {
+                min: Some(0),
+                ..IntRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "limit",
+            self.limit,
+            &IntRules {
+                min: Some(1),
+                max: Some(100),
+                ..IntRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::SetTimezoneRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "timezone",
+            &self.timezone,
+            &StringRules {
+                max_len: Some(64),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::UpdateProfileRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "firstname",
+            &self.firstname,
+            &StringRules {
+                max_len: Some(255),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "lastname",
+            &self.lastname,
+            &StringRules {
+                max_len: Some(255),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "gender",
+            self.gender,
+            &IntRules {
+                min: Some(0),
+                max: Some(3),
+                ..IntRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "locale",
+            self.locale,
+            &IntRules {
+                min: Some(1),
+                ..IntRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::RefreshSessionRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "refresh_token",
+            &self.refresh_token,
+            &StringRules {
+                max_len: Some(128),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::RevokeSessionRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "session_id",
+            &self.session_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::CreatePersonalAccessTokenRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "name",
+            &self.name,
+            &StringRules {
+                max_len: Some(100),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::RevokePersonalAccessTokenRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "token_id",
+            &self.token_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::StartOidcLoginRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "provider",
+            &self.provider,
+            &StringRules {
+                max_len: Some(64),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::FinishOidcLoginRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "state",
+            &self.state,
+            &StringRules {
+                max_len: Some(128),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "code",
+            &self.code,
+            &StringRules {
+                max_len: Some(2048),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::StartOidcLinkRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "provider",
+            &self.provider,
+            &StringRules {
+                max_len: Some(64),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::FinishOidcLinkRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "state",
+            &self.state,
+            &StringRules {
+                max_len: Some(128),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "code",
+            &self.code,
+            &StringRules {
+                max_len: Some(2048),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::UnlinkExternalIdentityRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "provider",
+            &self.provider,
+            &StringRules {
+                max_len: Some(64),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "subject",
+            &self.subject,
+            &StringRules {
+                max_len: Some(255),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::RequestEmailChangeRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "new_email",
+            &self.new_email,
+            &StringRules {
+                max_len: Some(255),
+                email: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "password",
+            &self.password,
+            &StringRules {
+                max_len: Some(128),
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::ConfirmEmailChangeRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "verification_code",
+            &self.verification_code,
+            &StringRules {
+                min_len: Some(6),
+                max_len: Some(6),
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::user::ListAuditEventsRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "user_id",
+            &self.user_id,
+            &StringRules {
+                max_len: Some(36),
+                ..StringRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "offset",
+            self.offset,
+            &IntRules {
+                min: Some(0),
+                ..IntRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "limit",
+            self.limit,
+            &IntRules {
+                min: Some(1),
+                max: Some(100),
+                ..IntRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::UploadAttachmentRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "note_id",
+            &self.note_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "file_name",
+            &self.file_name,
+            &StringRules {
+                max_len: Some(255),
+                required: true,
+                ..StringRules::default()
+            },
+        );
+        check_string(
+            &mut violations,
+            "content_type",
+            &self.content_type,
+            &StringRules {
+                max_len: Some(255),
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::ListAttachmentsRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "note_id",
+            &self.note_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::DownloadAttachmentRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "attachment_id",
+            &self.attachment_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::DeleteAttachmentRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "attachment_id",
+            &self.attachment_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::ListNoteRevisionsRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "note_id",
+            &self.note_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "offset",
+            self.offset,
+            &IntRules {
+                min: Some(0),
+                ..IntRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "limit",
+            self.limit,
+            &IntRules {
+                min: Some(1),
+                max: Some(100),
+                ..IntRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::GetNoteRevisionRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "note_id",
+            &self.note_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "revision",
+            self.revision,
+            &IntRules {
+                min: Some(1),
+                ..IntRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::DiffNoteRevisionsRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "note_id",
+            &self.note_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "from_revision",
+            self.from_revision,
+            &IntRules {
+                min: Some(1),
+                ..IntRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "to_revision",
+            self.to_revision,
+            &IntRules {
+                min: Some(1),
+                ..IntRules::default()
+            },
+        );
+        violations
+    }
+}
+
+impl Validate for super::note::RestoreNoteRevisionRequest {
+    fn validate(&self) -> Vec<FieldViolation> {
+        let mut violations = Vec::new();
+        check_string(
+            &mut violations,
+            "note_id",
+            &self.note_id,
+            &StringRules {
+                uuid: true,
+                ..StringRules::default()
+            },
+        );
+        check_int(
+            &mut violations,
+            "revision",
+            self.revision,
+            &IntRules {
+                min: Some(1),
+                ..IntRules::default()
+            },
+        );
+        violations
+    }
+}
@@ -0,0 +1,245 @@
+use crate::proto::google::rpc::bad_request::FieldViolation;
+
+/// Implemented in `proto/validation.rs`, which build.rs generates from the
+/// `(validate.rules)` field options.
+pub trait Validate {
+    /// Returns one violation per invalid field, nothing when the message is valid.
+    fn validate(&self) -> Vec<FieldViolation>;
+}
+
+#[derive(Debug, Default)]
+pub struct StringRules {
+    pub min_len: Option<usize>,
+    pub max_len: Option<usize>,
+    pub required: bool,
+    pub email: bool,
+    pub uuid: bool,
+    pub password: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct IntRules {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+pub fn check_string(
+    violations: &mut Vec<FieldViolation>,
+    field: &str,
+    value: &str,
+    rules: &StringRules,
+) {
+    if let Some((reason, description)) = get_string_violation(value, rules) {
+        violations.push(get_violation(field, reason, description));
+    }
+}
+
+pub fn check_int(violations: &mut Vec<FieldViolation>, field: &str, value: i32, rules: &IntRules) {
+    let below = rules.min.is_some_and(|min| value < min);
+    let above = rules.max.is_some_and(|max| value > max);
+    if !below && !above {
+        return;
+    }
+
+    let description = match (rules.min, rules.max) {
+        (Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+        (Some(min), None) => format!("must be at least {}", min),
+        (None, Some(max)) => format!("must be at most {}", max),
+        (None, None) => return,
+    };
+    violations.push(get_violation(field, "OUT_OF_RANGE", description));
+}
+
+fn get_string_violation(value: &str, rules: &StringRules) -> Option<(&'static str, String)> {
+    if rules.required && value.is_empty() {
+        return Some(("REQUIRED", "must not be empty".to_string()));
+    }
+
+    let length = value.chars().count();
+    if let Some(min_len) = rules.min_len
+        && length < min_len
+    {
+        return Some((
+            "TOO_SHORT",
+            format!("must be at least {} characters", min_len),
+        ));
+    }
+    if let Some(max_len) = rules.max_len
+        && length > max_len
+    {
+        return Some((
+            "TOO_LONG",
+            format!("must be at most {} characters", max_len),
+        ));
+    }
+    if rules.email && !is_email(value) {
+        return Some(("INVALID_EMAIL", "must be a valid email address".to_string()));
+    }
+    if rules.uuid && !is_uuid(value) {
+        return Some(("INVALID_UUID", "must be a UUID".to_string()));
+    }
+    if rules.password && !is_password(value) {
+        return Some((
+            "WEAK_PASSWORD",
+            "must contain at least one letter and one digit".to_string(),
+        ));
+    }
+    None
+}
+
+fn get_violation(field: &str, reason: &str, description: String) -> FieldViolation {
+    FieldViolation {
+        field: field.to_string(),
+        description,
+        reason: reason.to_string(),
+        localized_message: None,
+    }
+}
+
+// Deliberately loose: the verification email is what proves the address exists.
+fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !value.chars().any(char::is_whitespace)
+        && domain.split('.').count() > 1
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn is_password(value: &str) -> bool {
+    value.chars().any(char::is_alphabetic) && value.chars().any(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Validate;
+    use crate::proto::{
+        CreateUserRequest, QueryUserRequest, VerifyUserRequest, query_user_request,
+    };
+
+    fn get_reasons(message: &impl Validate) -> Vec<(String, String)> {
+        message
+            .validate()
+            .into_iter()
+            .map(|violation| (violation.field, violation.reason))
+            .collect()
+    }
+
+    fn get_create_user_request() -> CreateUserRequest {
+        CreateUserRequest {
+            email: "ada@example.com".into(),
+            password: "correct horse 1".into(),
+            firstname: "Ada".into(),
+            lastname: "Lovelace".into(),
+            gender: 2,
+            locale: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_a_valid_message() {
+        assert!(get_create_user_request().validate().is_empty());
+    }
+
+    #[test]
+    fn reports_one_violation_per_invalid_field() {
+        let request = CreateUserRequest {
+            email: "ada@example".into(),
+            password: "short1".into(),
+            firstname: String::new(),
+            lastname: "L".repeat(256),
+            gender: 4,
+            locale: 0,
+            ..get_create_user_request()
+        };
+
+        assert_eq!(
+            get_reasons(&request),
+            [
+                ("email".into(), "INVALID_EMAIL".into()),
+                ("password".into(), "TOO_SHORT".into()),
+                ("firstname".into(), "REQUIRED".into()),
+                ("lastname".into(), "TOO_LONG".into()),
+                ("gender".into(), "OUT_OF_RANGE".into()),
+                ("locale".into(), "OUT_OF_RANGE".into()),
+            ]
+        );
+        let descriptions: Vec<_> = request
+            .validate()
+            .into_iter()
+            .map(|violation| violation.description)
+            .collect();
+        assert_eq!(
+            descriptions,
+            [
+                "must be a valid email address",
+                "must be at least 8 characters",
+                "must not be empty",
+                "must be at most 255 characters",
+                "must be between 0 and 3",
+                "must be at least 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_lengths_in_characters() {
+        let request = CreateUserRequest {
+            firstname: "é".repeat(255),
+            ..get_create_user_request()
+        };
+        assert!(request.validate().is_empty());
+
+        let request = VerifyUserRequest {
+            email: "ada@example.com".into(),
+            password: "secret".into(),
+            verify_code: "12345".into(),
+        };
+        assert_eq!(
+            get_reasons(&request),
+            [("verify_code".into(), "TOO_SHORT".into())]
+        );
+    }
+
+    #[test]
+    fn checks_passwords_for_letters_and_digits() {
+        let request = CreateUserRequest {
+            password: "only letters".into(),
+            ..get_create_user_request()
+        };
+        assert_eq!(
+            get_reasons(&request),
+            [("password".into(), "WEAK_PASSWORD".into())]
+        );
+    }
+
+    #[test]
+    fn checks_only_the_set_field_of_a_oneof() {
+        assert!(QueryUserRequest { identity: None }.validate().is_empty());
+        let request = QueryUserRequest {
+            identity: Some(query_user_request::Identity::Email(
+                "ada@example.com".into(),
+            )),
+        };
+        assert!(request.validate().is_empty());
+
+        let request = QueryUserRequest {
+            identity: Some(query_user_request::Identity::Id("42".into())),
+        };
+        assert_eq!(
+            get_reasons(&request),
+            [("id".into(), "INVALID_UUID".into())]
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use arcane_vault::domain::{entity::Principal, error::ArcaneVaultError, service::UserService};
+use tonic::metadata::MetadataMap;
+
+/// Who sent the request, from its `authorization: Bearer <token>` metadata. Session access tokens
+/// and personal access tokens are both accepted; what the token may do is up to the handler, e.g.
+/// with [`Principal::check_scope`].
+pub async fn authenticate(
+    user_service: &dyn UserService,
+    metadata: &MetadataMap,
+) -> Result<Principal, ArcaneVaultError> {
+    user_service
+        .authenticate(get_bearer_token(metadata).unwrap_or_default())
+        .await
+}
+
+fn get_bearer_token(metadata: &MetadataMap) -> Option<&str> {
+    let (scheme, token) = metadata
+        .get("authorization")?
+        .to_str()
+        .ok()?
+        .split_once(' ')?;
+    // The scheme is case-insensitive, RFC 9110 section 11.1.
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
@@ -0,0 +1,986 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{ConnectInfo, FromRequestParts, Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use ethereal_core::proto::{
+    ConfirmEmailChangeRequest, ConfirmEmailChangeResponse, ConfirmTotpRequest, ConfirmTotpResponse,
+    CreatePersonalAccessTokenRequest, CreatePersonalAccessTokenResponse, CreateUserRequest,
+    CreateUserResponse, DeleteAttachmentRequest, DeleteAttachmentResponse,
+    DiffNoteRevisionsRequest, DiffNoteRevisionsResponse, DisableTotpRequest, DisableTotpResponse,
+    DownloadAttachmentRequest, EnrollTotpRequest, EnrollTotpResponse, ExportUsersRequest,
+    FinishOidcLinkRequest, FinishOidcLinkResponse, FinishOidcLoginRequest, FinishOidcLoginResponse,
+    GenerateRecoveryCodesRequest, GenerateRecoveryCodesResponse, GetNoteRevisionRequest,
+    GetNoteRevisionResponse, ListAttachmentsRequest, ListAttachmentsResponse,
+    ListAuditEventsRequest, ListAuditEventsResponse, ListExternalIdentitiesRequest,
+    ListExternalIdentitiesResponse, ListNoteRevisionsRequest, ListNoteRevisionsResponse,
+    ListPersonalAccessTokensRequest, ListPersonalAccessTokensResponse, ListSessionsRequest,
+    ListSessionsResponse, ListUsersRequest, ListUsersResponse, LoginUserRequest, LoginUserResponse,
+    LogoutEverywhereRequest, LogoutEverywhereResponse, QueryUserRequest, QueryUserResponse,
+    RefreshSessionRequest, RefreshSessionResponse, RequestEmailChangeRequest,
+    RequestEmailChangeResponse, RestoreNoteRevisionRequest, RestoreNoteRevisionResponse,
+    RevokePersonalAccessTokenRequest, RevokePersonalAccessTokenResponse, RevokeSessionRequest,
+    RevokeSessionResponse, StartOidcLinkRequest, StartOidcLinkResponse, StartOidcLoginRequest,
+    StartOidcLoginResponse, UnlinkExternalIdentityRequest, UnlinkExternalIdentityResponse,
+    UpdateProfileRequest, UpdateProfileResponse, UploadAttachmentRequest, UploadAttachmentResponse,
+    UploadAvatarResponse, VerifyTotpChallengeRequest, VerifyTotpChallengeResponse,
+    VerifyUserRequest, VerifyUserResponse, note_service_server::NoteService as _,
+    query_user_request::Identity, user_service_server::UserService as _,
+};
+use futures_util::StreamExt;
+use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+type SharedUserService = Arc<crate::service::UserService>;
+type SharedNoteService = Arc<crate::service::NoteService>;
+
+/// REST/JSON transcoding of `UserService` and `NoteService`, served next to the gRPC endpoint with
+/// the same metrics, tracing and CORS layers.
+pub async fn serve(
+    addr: SocketAddr,
+    user_service: SharedUserService,
+    note_service: SharedNoteService,
+    metrics_layer: crate::metrics::MetricsLayer,
+    cors_layer: CorsLayer,
+) -> Result<(), std::io::Error> {
+    let note_router = Router::new()
+        .route(
+            "/v1/notes/{note_id}/attachments",
+            get(list_attachments).post(upload_attachment),
+        )
+        .route("/v1/attachments/{attachment_id}", get(download_attachment))
+        .route("/v1/attachments:delete", post(delete_attachment))
+        .route("/v1/notes/{note_id}/revisions", get(list_note_revisions))
+        .route(
+            "/v1/notes/{note_id}/revisions/{revision}",
+            get(get_note_revision),
+        )
+        .route(
+            "/v1/notes/{note_id}/revisions:diff",
+            get(diff_note_revisions),
+        )
+        .route(
+            "/v1/notes/{note_id}/revisions:restore",
+            post(restore_note_revision),
+        )
+        .with_state(note_service);
+    let router = Router::new()
+        .route("/v1/users", get(list_users).post(create_user))
+        .route("/v1/users:export", get(export_users))
+        .route("/v1/users:verify", post(verify_user))
+        .route("/v1/users:login", post(login_user))
+        .route("/v1/users:verifyTotpChallenge", post(verify_totp_challenge))
+        .route("/v1/users:enrollTotp", post(enroll_totp))
+        .route("/v1/users:confirmTotp", post(confirm_totp))
+        .route("/v1/users:disableTotp", post(disable_totp))
+        .route(
+            "/v1/users:generateRecoveryCodes",
+            post(generate_recovery_codes),
+        )
+        .route("/v1/users:requestEmailChange", post(request_email_change))
+        .route("/v1/users:confirmEmailChange", post(confirm_email_change))
+        .route("/v1/users:updateProfile", post(update_profile))
+        .route("/v1/users:uploadAvatar", post(upload_avatar))
+        .route("/v1/users:byEmail", get(query_user_by_email))
+        .route("/v1/users/{id}", get(query_user_by_id))
+        .route("/v1/sessions", get(list_sessions))
+        .route("/v1/sessions:refresh", post(refresh_session))
+        .route("/v1/sessions:revoke", post(revoke_session))
+        .route("/v1/sessions:logoutEverywhere", post(logout_everywhere))
+        .route(
+            "/v1/personalAccessTokens",
+            get(list_personal_access_tokens).post(create_personal_access_token),
+        )
+        .route(
+            "/v1/personalAccessTokens:revoke",
+            post(revoke_personal_access_token),
+        )
+        .route("/v1/oidc:startLogin", post(start_oidc_login))
+        .route("/v1/oidc:finishLogin", post(finish_oidc_login))
+        .route("/v1/oidc:startLink", post(start_oidc_link))
+        .route("/v1/oidc:finishLink", post(finish_oidc_link))
+        .route("/v1/externalIdentities", get(list_external_identities))
+        .route(
+            "/v1/externalIdentities:unlink",
+            post(unlink_external_identity),
+        )
+        .route("/v1/auditEvents", get(list_audit_events))
+        .route("/v1/avatars/{user_id}/{version}/{file}", get(get_avatar))
+        .with_state(user_service)
+        .merge(note_router)
+        // Route layers, so requests are labeled by the route they matched.
+        .route_layer(crate::telemetry::TraceLayer)
+        .route_layer(metrics_layer)
+        .layer(cors_layer);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+}
+
+/// CORS settings shared by the gRPC-Web and REST endpoints; `*` allows any origin.
+pub fn get_cors_layer(allowed_origins: &[String]) -> CorsLayer {
+    let allow_origin = if allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers([
+            http::header::CONTENT_TYPE,
+            http::header::AUTHORIZATION,
+            http::header::ACCEPT_LANGUAGE,
+            HeaderName::from_static("x-grpc-web"),
+            HeaderName::from_static("x-user-agent"),
+            HeaderName::from_static("x-device-label"),
+            HeaderName::from_static("x-request-id"),
+            HeaderName::from_static("grpc-timeout"),
+            HeaderName::from_static("traceparent"),
+            HeaderName::from_static("tracestate"),
+        ])
+        .expose_headers([
+            HeaderName::from_static("grpc-status"),
+            HeaderName::from_static("grpc-message"),
+            HeaderName::from_static("grpc-status-details-bin"),
+        ])
+        .max_age(Duration::from_secs(24 * 60 * 60))
+}
+
+#[derive(serde::Deserialize)]
+struct QueryUserByEmailParams {
+    email: String,
+}
+
+#[derive(serde::Deserialize)]
+struct UploadAttachmentParams {
+    file_name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ListNoteRevisionsParams {
+    #[serde(default)]
+    offset: i32,
+    limit: i32,
+}
+
+#[derive(serde::Deserialize)]
+struct DiffNoteRevisionsParams {
+    from_revision: i32,
+    to_revision: i32,
+}
+
+#[derive(serde::Deserialize)]
+struct RestoreNoteRevisionParams {
+    revision: i32,
+}
+
+#[derive(serde::Deserialize)]
+struct ListUsersParams {
+    #[serde(default)]
+    offset: i32,
+    limit: i32,
+}
+
+#[derive(serde::Deserialize)]
+struct ListAuditEventsParams {
+    #[serde(default)]
+    user_id: String,
+    #[serde(default)]
+    offset: i32,
+    limit: i32,
+}
+
+// What of the HTTP request a gRPC request carries over: the headers and the peer address.
+struct GatewayContext {
+    headers: HeaderMap,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for GatewayContext {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            headers: parts.headers.clone(),
+            remote_addr: parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|connect_info| connect_info.0),
+        })
+    }
+}
+
+async fn create_user(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<CreateUserRequest>,
+) -> Result<Json<CreateUserResponse>, GatewayError> {
+    let response = user_service
+        .create_user(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn verify_user(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<VerifyUserRequest>,
+) -> Result<Json<VerifyUserResponse>, GatewayError> {
+    let response = user_service
+        .verify_user(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn login_user(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<LoginUserRequest>,
+) -> Result<Json<LoginUserResponse>, GatewayError> {
+    let response = user_service
+        .login_user(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn verify_totp_challenge(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<VerifyTotpChallengeRequest>,
+) -> Result<Json<VerifyTotpChallengeResponse>, GatewayError> {
+    let response = user_service
+        .verify_totp_challenge(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn enroll_totp(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<EnrollTotpRequest>,
+) -> Result<Json<EnrollTotpResponse>, GatewayError> {
+    let response = user_service
+        .enroll_totp(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn confirm_totp(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<ConfirmTotpRequest>,
+) -> Result<Json<ConfirmTotpResponse>, GatewayError> {
+    let response = user_service
+        .confirm_totp(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn disable_totp(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<DisableTotpRequest>,
+) -> Result<Json<DisableTotpResponse>, GatewayError> {
+    let response = user_service
+        .disable_totp(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn generate_recovery_codes(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<GenerateRecoveryCodesRequest>,
+) -> Result<Json<GenerateRecoveryCodesResponse>, GatewayError> {
+    let response = user_service
+        .generate_recovery_codes(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn query_user_by_id(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Path(id): Path<String>,
+) -> Result<Json<QueryUserResponse>, GatewayError> {
+    let request = QueryUserRequest {
+        identity: Some(Identity::Id(id)),
+    };
+    let response = user_service
+        .query_user(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn list_users(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Query(params): Query<ListUsersParams>,
+) -> Result<Json<ListUsersResponse>, GatewayError> {
+    let request = ListUsersRequest {
+        offset: params.offset,
+        limit: params.limit,
+    };
+    let response = user_service
+        .list_users(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+// One JSON user per line, streamed on like the messages of the gRPC call.
+#[allow(clippy::result_large_err)]
+async fn export_users(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+) -> Result<Response, GatewayError> {
+    let users = user_service
+        .export_users(get_grpc_request(context, ExportUsersRequest {}))
+        .await?
+        .into_inner();
+    let lines = users.map(|user| {
+        let mut line = serde_json::to_vec(&user?)
+            .map_err(|err| tonic::Status::internal(err.to_string()))?;
+        line.push(b'\n');
+        Ok::<_, tonic::Status>(line)
+    });
+    Ok((
+        [(http::header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+async fn query_user_by_email(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Query(params): Query<QueryUserByEmailParams>,
+) -> Result<Json<QueryUserResponse>, GatewayError> {
+    let request = QueryUserRequest {
+        identity: Some(Identity::Email(params.email)),
+    };
+    let response = user_service
+        .query_user(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn refresh_session(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<RefreshSessionRequest>,
+) -> Result<Json<RefreshSessionResponse>, GatewayError> {
+    let response = user_service
+        .refresh_session(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn list_sessions(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+) -> Result<Json<ListSessionsResponse>, GatewayError> {
+    let response = user_service
+        .list_sessions(get_grpc_request(context, ListSessionsRequest {}))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn revoke_session(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<RevokeSessionRequest>,
+) -> Result<Json<RevokeSessionResponse>, GatewayError> {
+    let response = user_service
+        .revoke_session(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn logout_everywhere(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+) -> Result<Json<LogoutEverywhereResponse>, GatewayError> {
+    let response = user_service
+        .logout_everywhere(get_grpc_request(context, LogoutEverywhereRequest {}))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn create_personal_access_token(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<CreatePersonalAccessTokenRequest>,
+) -> Result<Json<CreatePersonalAccessTokenResponse>, GatewayError> {
+    let response = user_service
+        .create_personal_access_token(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn list_personal_access_tokens(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+) -> Result<Json<ListPersonalAccessTokensResponse>, GatewayError> {
+    let response = user_service
+        .list_personal_access_tokens(get_grpc_request(
+            context,
+            ListPersonalAccessTokensRequest {},
+        ))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn revoke_personal_access_token(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<RevokePersonalAccessTokenRequest>,
+) -> Result<Json<RevokePersonalAccessTokenResponse>, GatewayError> {
+    let response = user_service
+        .revoke_personal_access_token(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn start_oidc_login(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<StartOidcLoginRequest>,
+) -> Result<Json<StartOidcLoginResponse>, GatewayError> {
+    let response = user_service
+        .start_oidc_login(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn finish_oidc_login(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<FinishOidcLoginRequest>,
+) -> Result<Json<FinishOidcLoginResponse>, GatewayError> {
+    let response = user_service
+        .finish_oidc_login(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn start_oidc_link(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<StartOidcLinkRequest>,
+) -> Result<Json<StartOidcLinkResponse>, GatewayError> {
+    let response = user_service
+        .start_oidc_link(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn finish_oidc_link(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<FinishOidcLinkRequest>,
+) -> Result<Json<FinishOidcLinkResponse>, GatewayError> {
+    let response = user_service
+        .finish_oidc_link(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn list_external_identities(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+) -> Result<Json<ListExternalIdentitiesResponse>, GatewayError> {
+    let response = user_service
+        .list_external_identities(get_grpc_request(context, ListExternalIdentitiesRequest {}))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn unlink_external_identity(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<UnlinkExternalIdentityRequest>,
+) -> Result<Json<UnlinkExternalIdentityResponse>, GatewayError> {
+    let response = user_service
+        .unlink_external_identity(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn request_email_change(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<RequestEmailChangeRequest>,
+) -> Result<Json<RequestEmailChangeResponse>, GatewayError> {
+    let response = user_service
+        .request_email_change(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn confirm_email_change(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<ConfirmEmailChangeRequest>,
+) -> Result<Json<ConfirmEmailChangeResponse>, GatewayError> {
+    let response = user_service
+        .confirm_email_change(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn update_profile(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Json(request): Json<UpdateProfileRequest>,
+) -> Result<Json<UpdateProfileResponse>, GatewayError> {
+    let response = user_service
+        .update_profile(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn list_audit_events(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Query(params): Query<ListAuditEventsParams>,
+) -> Result<Json<ListAuditEventsResponse>, GatewayError> {
+    let request = ListAuditEventsRequest {
+        user_id: params.user_id,
+        offset: params.offset,
+        limit: params.limit,
+    };
+    let response = user_service
+        .list_audit_events(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+// The body is the image itself, streamed on like the chunks of the gRPC call.
+async fn upload_avatar(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    body: Body,
+) -> Result<Json<UploadAvatarResponse>, GatewayError> {
+    let chunks = body
+        .into_data_stream()
+        .map(|chunk| {
+            chunk
+                .map(|chunk| chunk.to_vec())
+                .map_err(|err| crate::service::get_upload_error(err.to_string()))
+        })
+        .boxed();
+    let response = user_service
+        .upload_avatar_chunks(get_grpc_request(context, chunks))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+// Every upload gets new URLs, so the thumbnails can be cached for good.
+async fn get_avatar(
+    State(user_service): State<SharedUserService>,
+    context: GatewayContext,
+    Path((user_id, version, file)): Path<(String, String, String)>,
+) -> Result<Response, GatewayError> {
+    let metadata = tonic::metadata::MetadataMap::from_headers(context.headers);
+    match user_service
+        .get_avatar(&metadata, &format!("{}/{}/{}", user_id, version, file))
+        .await?
+    {
+        Some(avatar) => Ok((
+            [
+                (http::header::CONTENT_TYPE, "image/png"),
+                (
+                    http::header::CACHE_CONTROL,
+                    "public, max-age=31536000, immutable",
+                ),
+            ],
+            avatar,
+        )
+            .into_response()),
+        None => Err(GatewayError(tonic::Status::not_found("avatar not found"))),
+    }
+}
+
+// The body is the file itself, named by the query and typed by its `Content-Type`.
+async fn upload_attachment(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Path(note_id): Path<String>,
+    Query(params): Query<UploadAttachmentParams>,
+    body: Body,
+) -> Result<Json<UploadAttachmentResponse>, GatewayError> {
+    let content_type = context
+        .headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let request = UploadAttachmentRequest {
+        note_id,
+        file_name: params.file_name,
+        content_type,
+        chunk: Vec::new(),
+    };
+    let chunks = body
+        .into_data_stream()
+        .map(|chunk| {
+            chunk
+                .map(|chunk| chunk.to_vec())
+                .map_err(|err| crate::service::get_upload_error(err.to_string()))
+        })
+        .boxed();
+    let response = note_service
+        .upload_attachment_chunks(get_grpc_request(context, (request, chunks)))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn list_attachments(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Path(note_id): Path<String>,
+) -> Result<Json<ListAttachmentsResponse>, GatewayError> {
+    let response = note_service
+        .list_attachments(get_grpc_request(
+            context,
+            ListAttachmentsRequest { note_id },
+        ))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn download_attachment(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Path(attachment_id): Path<String>,
+) -> Result<Response, GatewayError> {
+    let (attachment, chunks) = note_service
+        .download_attachment_chunks(get_grpc_request(
+            context,
+            DownloadAttachmentRequest { attachment_id },
+        ))
+        .await?;
+    let content_disposition = format!(
+        "attachment; filename*=UTF-8''{}",
+        get_percent_encoded(&attachment.file_name)
+    );
+    Ok((
+        [
+            (http::header::CONTENT_TYPE, attachment.content_type),
+            (http::header::CONTENT_DISPOSITION, content_disposition),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+async fn delete_attachment(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Json(request): Json<DeleteAttachmentRequest>,
+) -> Result<Json<DeleteAttachmentResponse>, GatewayError> {
+    let response = note_service
+        .delete_attachment(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn list_note_revisions(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Path(note_id): Path<String>,
+    Query(params): Query<ListNoteRevisionsParams>,
+) -> Result<Json<ListNoteRevisionsResponse>, GatewayError> {
+    let request = ListNoteRevisionsRequest {
+        note_id,
+        offset: params.offset,
+        limit: params.limit,
+    };
+    let response = note_service
+        .list_note_revisions(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn get_note_revision(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Path((note_id, revision)): Path<(String, i32)>,
+) -> Result<Json<GetNoteRevisionResponse>, GatewayError> {
+    let response = note_service
+        .get_note_revision(get_grpc_request(
+            context,
+            GetNoteRevisionRequest { note_id, revision },
+        ))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+async fn diff_note_revisions(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Path(note_id): Path<String>,
+    Query(params): Query<DiffNoteRevisionsParams>,
+) -> Result<Json<DiffNoteRevisionsResponse>, GatewayError> {
+    let request = DiffNoteRevisionsRequest {
+        note_id,
+        from_revision: params.from_revision,
+        to_revision: params.to_revision,
+    };
+    let response = note_service
+        .diff_note_revisions(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+// The body only has the revision, the note being in the path.
+async fn restore_note_revision(
+    State(note_service): State<SharedNoteService>,
+    context: GatewayContext,
+    Path(note_id): Path<String>,
+    Json(params): Json<RestoreNoteRevisionParams>,
+) -> Result<Json<RestoreNoteRevisionResponse>, GatewayError> {
+    let request = RestoreNoteRevisionRequest {
+        note_id,
+        revision: params.revision,
+    };
+    let response = note_service
+        .restore_note_revision(get_grpc_request(context, request))
+        .await?;
+    Ok(Json(response.into_inner()))
+}
+
+// https://www.rfc-editor.org/rfc/rfc8187#section-3.2
+fn get_percent_encoded(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'!'
+            | b'#'
+            | b'$'
+            | b'&'
+            | b'+'
+            | b'-'
+            | b'.'
+            | b'^'
+            | b'_'
+            | b'`'
+            | b'|'
+            | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// Carry the HTTP headers over as gRPC metadata and the peer address as tonic records it, so the
+// service sees the same context either way.
+fn get_grpc_request<T>(context: GatewayContext, message: T) -> tonic::Request<T> {
+    let mut extensions = tonic::Extensions::default();
+    extensions.insert(tonic::transport::server::TcpConnectInfo {
+        local_addr: None,
+        remote_addr: context.remote_addr,
+    });
+    tonic::Request::from_parts(
+        tonic::metadata::MetadataMap::from_headers(context.headers),
+        extensions,
+        message,
+    )
+}
+
+struct GatewayError(tonic::Status);
+
+impl From<tonic::Status> for GatewayError {
+    fn from(status: tonic::Status) -> Self {
+        Self(status)
+    }
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "code": self.0.code() as i32,
+            "message": self.0.message(),
+            "details": crate::status::get_json_details(&self.0),
+        });
+        let mut response = (get_http_status(self.0.code()), Json(body)).into_response();
+        // For the metrics, which count requests by gRPC code.
+        response.extensions_mut().insert(self.0.code());
+        response
+    }
+}
+
+// https://github.com/googleapis/googleapis/blob/master/google/rpc/code.proto
+fn get_http_status(code: tonic::Code) -> StatusCode {
+    match code {
+        tonic::Code::Ok => StatusCode::OK,
+        tonic::Code::Cancelled => StatusCode::from_u16(499).unwrap(),
+        tonic::Code::InvalidArgument
+        | tonic::Code::FailedPrecondition
+        | tonic::Code::OutOfRange => StatusCode::BAD_REQUEST,
+        tonic::Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        tonic::Code::NotFound => StatusCode::NOT_FOUND,
+        tonic::Code::AlreadyExists | tonic::Code::Aborted => StatusCode::CONFLICT,
+        tonic::Code::PermissionDenied => StatusCode::FORBIDDEN,
+        tonic::Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        tonic::Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        tonic::Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        tonic::Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        tonic::Code::Unknown | tonic::Code::Internal | tonic::Code::DataLoss => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn send(router: &mut Router, request: http::Request<Body>) -> Response {
+        tower::Service::call(router, request).await.unwrap()
+    }
+
+    fn create_router(allowed_origins: &[&str]) -> Router {
+        let allowed_origins: Vec<String> = allowed_origins.iter().map(|o| o.to_string()).collect();
+        Router::new()
+            .route(
+                "/v1/users:login",
+                post(|| async { Json(serde_json::json!({})) }),
+            )
+            .layer(get_cors_layer(&allowed_origins))
+    }
+
+    fn get_preflight_request(origin: &str) -> http::Request<Body> {
+        http::Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/v1/users:login")
+            .header(http::header::ORIGIN, origin)
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(
+                http::header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "authorization",
+            )
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn maps_grpc_codes_to_http_statuses() {
+        for (code, status) in [
+            (tonic::Code::Ok, 200),
+            (tonic::Code::Cancelled, 499),
+            (tonic::Code::InvalidArgument, 400),
+            (tonic::Code::FailedPrecondition, 400),
+            (tonic::Code::Unauthenticated, 401),
+            (tonic::Code::PermissionDenied, 403),
+            (tonic::Code::NotFound, 404),
+            (tonic::Code::AlreadyExists, 409),
+            (tonic::Code::Aborted, 409),
+            (tonic::Code::ResourceExhausted, 429),
+            (tonic::Code::Internal, 500),
+            (tonic::Code::Unavailable, 503),
+            (tonic::Code::DeadlineExceeded, 504),
+        ] {
+            assert_eq!(get_http_status(code).as_u16(), status, "{:?}", code);
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_errors_with_the_code_and_message_of_the_status() {
+        let response = GatewayError(tonic::Status::not_found("no such note")).into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.extensions().get::<tonic::Code>(),
+            Some(&tonic::Code::NotFound)
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], tonic::Code::NotFound as i32);
+        assert_eq!(body["message"], "no such note");
+    }
+
+    #[tokio::test]
+    async fn allows_preflights_of_the_allowed_origins() {
+        let mut router = create_router(&["http://localhost:3000"]);
+
+        let response = send(&mut router, get_preflight_request("http://localhost:3000")).await;
+
+        assert!(response.status().is_success());
+        let headers = response.headers();
+        assert_eq!(
+            headers[http::header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:3000"
+        );
+        assert!(
+            headers[http::header::ACCESS_CONTROL_ALLOW_METHODS]
+                .to_str()
+                .unwrap()
+                .contains("POST")
+        );
+        assert!(
+            headers[http::header::ACCESS_CONTROL_ALLOW_HEADERS]
+                .to_str()
+                .unwrap()
+                .contains("authorization")
+        );
+        assert_eq!(headers[http::header::ACCESS_CONTROL_MAX_AGE], "86400");
+    }
+
+    #[tokio::test]
+    async fn leaves_out_the_cors_headers_for_other_origins() {
+        let mut router = create_router(&["http://localhost:3000"]);
+
+        let response = send(&mut router, get_preflight_request("http://evil.example")).await;
+
+        assert!(
+            !response
+                .headers()
+                .contains_key(http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[tokio::test]
+    async fn allows_any_origin_for_a_wildcard() {
+        let mut router = create_router(&["*"]);
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .uri("/v1/users:login")
+            .header(http::header::ORIGIN, "http://evil.example")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = send(&mut router, request).await;
+
+        assert_eq!(
+            response.headers()[http::header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "*"
+        );
+        assert!(
+            response
+                .headers()
+                .get_all(http::header::ACCESS_CONTROL_EXPOSE_HEADERS)
+                .iter()
+                .any(|value| value.to_str().unwrap().contains("grpc-status"))
+        );
+    }
+}
@@ -1,34 +1,170 @@
+use std::{sync::Arc, time::Duration};
+
 use tonic::transport::Server;
 
+mod auth;
+mod gateway;
+mod metrics;
 mod service;
+mod status;
+mod telemetry;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("[sacred-gate] is starting...");
+    // `sacred-gate check-schema` only checks the database schema, as startup does when it opens
+    // the pool.
+    let is_check_schema = std::env::args().nth(1).as_deref() == Some("check-schema");
+    if !is_check_schema {
+        println!("[sacred-gate] is starting...");
+    }
     let config = ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
 
+    let storage = config
+        .get::<String>("sacred-gate[0].storage")
+        .unwrap_or_else(|_| "postgres".to_string())
+        .parse::<arcane_vault::Storage>()?;
+    if is_check_schema {
+        if let Err(err) = arcane_vault::check_schema().await {
+            eprintln!("[sacred-gate] {}", err.message);
+            std::process::exit(1);
+        }
+        println!("[sacred-gate] database schema is compatible");
+        return Ok(());
+    }
+    let repositories = match storage.create_repositories().await {
+        Ok(repositories) => repositories,
+        Err(err) => {
+            eprintln!("[sacred-gate] {}", err.message);
+            std::process::exit(1);
+        }
+    };
+
     let addr = format!(
         "{}:{}",
         config.get::<String>("sacred-gate[0].ip_address").unwrap(),
         config.get::<String>("sacred-gate[0].port").unwrap(),
     )
     .parse()?;
+    let metrics_addr = format!(
+        "{}:{}",
+        config.get::<String>("sacred-gate[0].ip_address").unwrap(),
+        config.get::<String>("sacred-gate[0].metrics_port").unwrap(),
+    )
+    .parse()?;
+    let rest_addr = format!(
+        "{}:{}",
+        config.get::<String>("sacred-gate[0].ip_address").unwrap(),
+        config.get::<String>("sacred-gate[0].rest_port").unwrap(),
+    )
+    .parse()?;
+    let cors_layer = crate::gateway::get_cors_layer(
+        &config
+            .get::<Vec<String>>("sacred-gate[0].cors_allowed_origins")
+            .unwrap(),
+    );
+
+    let tracer_provider =
+        crate::telemetry::init(config.get::<String>("sacred-gate[0].otlp_endpoint").ok())?;
 
     let encoded_file_descriptor_set =
         include_bytes!("../../ethereal-core/proto/service_descriptor.bin");
     let reflection_service = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(encoded_file_descriptor_set)
         .build_v1()?;
+    let metrics_layer = crate::metrics::MetricsLayer::new(&[
+        encoded_file_descriptor_set,
+        tonic_reflection::pb::v1::FILE_DESCRIPTOR_SET,
+    ])?;
 
-    let user_service = crate::service::UserService::new().await;
+    // Shared, so error messages can look up locales in the same cache.
+    let vault_reference_data_service: Arc<dyn arcane_vault::domain::service::ReferenceDataService> =
+        arcane_vault::ReferenceDataService::create(repositories.reference_data).into();
+    let user_service = Arc::new(crate::service::UserService::new(
+        repositories.users,
+        repositories.totp,
+        repositories.sessions,
+        repositories.personal_access_tokens,
+        repositories.oidc,
+        repositories.audit_events,
+        vault_reference_data_service.clone(),
+    )?);
+    let reference_data_service =
+        crate::service::ReferenceDataService::new(vault_reference_data_service);
+    let note_service = Arc::new(crate::service::NoteService::new(
+        repositories.attachments,
+        repositories.note_revisions,
+        user_service.clone(),
+    )?);
+    let attachment_gc_interval = config
+        .get::<u64>("sacred-gate[0].attachment_gc_interval_secs")
+        .unwrap_or(60 * 60);
+    let note_revision_prune_interval = config
+        .get::<u64>("sacred-gate[0].note_revision_prune_interval_secs")
+        .unwrap_or(60 * 60);
+
+    tokio::spawn(async move {
+        if let Err(err) = crate::metrics::serve(metrics_addr).await {
+            eprintln!("[sacred-gate] metrics endpoint stopped: {}", err);
+        }
+    });
+    let gc_note_service = note_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(attachment_gc_interval));
+        loop {
+            interval.tick().await;
+            gc_note_service.collect_attachment_blobs().await;
+        }
+    });
+    let prune_note_service = note_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(note_revision_prune_interval));
+        loop {
+            interval.tick().await;
+            prune_note_service.prune_note_revisions().await;
+        }
+    });
+    let rest_user_service = user_service.clone();
+    let rest_note_service = note_service.clone();
+    let rest_metrics_layer = metrics_layer.clone();
+    let rest_cors_layer = cors_layer.clone();
+    tokio::spawn(async move {
+        if let Err(err) = crate::gateway::serve(
+            rest_addr,
+            rest_user_service,
+            rest_note_service,
+            rest_metrics_layer,
+            rest_cors_layer,
+        )
+        .await
+        {
+            eprintln!("[sacred-gate] REST gateway stopped: {}", err);
+        }
+    });
 
     Server::builder()
+        .accept_http1(true)
+        .layer(cors_layer)
+        .layer(tonic_web::GrpcWebLayer::new())
+        .layer(metrics_layer)
+        .layer(crate::telemetry::TraceLayer)
         .add_service(reflection_service)
         .add_service(
-            ethereal_core::proto::user_service_server::UserServiceServer::new(user_service),
+            ethereal_core::proto::user_service_server::UserServiceServer::from_arc(user_service),
+        )
+        .add_service(
+            ethereal_core::proto::reference_data_service_server::ReferenceDataServiceServer::new(
+                reference_data_service,
+            ),
+        )
+        .add_service(
+            ethereal_core::proto::note_service_server::NoteServiceServer::from_arc(note_service),
         )
         .serve(addr)
         .await?;
 
+    if let Some(tracer_provider) = tracer_provider {
+        tracer_provider.shutdown()?;
+    }
+
     Ok(())
 }
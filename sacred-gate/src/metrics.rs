@@ -0,0 +1,445 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use lazy_static::lazy_static;
+use prometheus::{Encoder, HistogramVec, IntCounterVec};
+use prost::Message;
+
+lazy_static! {
+    static ref GRPC_REQUESTS: IntCounterVec = prometheus::register_int_counter_vec!(
+        "sacred_gate_grpc_requests_total",
+        "Number of gRPC and REST requests handled, by method and status code.",
+        &["method", "code"]
+    )
+    .unwrap();
+    static ref GRPC_REQUEST_DURATION: HistogramVec = prometheus::register_histogram_vec!(
+        "sacred_gate_grpc_request_duration_seconds",
+        "gRPC and REST request latency in seconds, by method, until the status is sent.",
+        &["method"]
+    )
+    .unwrap();
+}
+
+/// Serves the Prometheus text exposition of the default registry at `/metrics`.
+pub async fn serve(addr: SocketAddr) -> Result<(), std::io::Error> {
+    let router = axum::Router::new().route("/metrics", axum::routing::get(get_metrics));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router).await
+}
+
+async fn get_metrics() -> Result<String, (http::StatusCode, String)> {
+    let mut buffer = Vec::new();
+    prometheus::TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|err| (http::StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    String::from_utf8(buffer)
+        .map_err(|err| (http::StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+}
+
+// The method label of requests for paths no service serves, which clients choose freely.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// Counts requests by their gRPC method, as `/package.Service/Method`, and status code. REST
+/// requests are counted by their route, as `POST /v1/users`, when the layer wraps the routes.
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    methods: Arc<HashSet<String>>,
+}
+
+impl MetricsLayer {
+    /// Labels requests with the methods of the services in `file_descriptor_sets`, and any other
+    /// path as `unknown`.
+    pub fn new(file_descriptor_sets: &[&[u8]]) -> Result<Self, prost::DecodeError> {
+        let mut methods = HashSet::new();
+        for file_descriptor_set in file_descriptor_sets {
+            for file in prost_types::FileDescriptorSet::decode(*file_descriptor_set)?.file {
+                for service in &file.service {
+                    for method in &service.method {
+                        methods.insert(format!(
+                            "/{}.{}/{}",
+                            file.package(),
+                            service.name(),
+                            method.name()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            methods: Arc::new(methods),
+        })
+    }
+}
+
+impl<S> tower::Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            methods: self.methods.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    methods: Arc<HashSet<String>>,
+}
+
+impl<S, ReqBody, ResBody> tower::Service<http::Request<ReqBody>> for MetricsService<S>
+where
+    S: tower::Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    ResBody: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+    ResBody::Error: Into<tonic::codegen::StdError>,
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let path = request.uri().path();
+        let route = request
+            .extensions()
+            .get::<axum::extract::MatchedPath>()
+            .map(|route| format!("{} {}", request.method(), route.as_str()));
+        let is_rest = route.is_some();
+        let observation = Observation {
+            method: match route {
+                Some(route) => route,
+                None if self.methods.contains(path) => path.to_string(),
+                None => UNKNOWN_METHOD.to_string(),
+            },
+            start: Instant::now(),
+        };
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let response = match future.await {
+                Ok(response) => response,
+                Err(err) => {
+                    observation.observe(tonic::Code::Unknown);
+                    return Err(err);
+                }
+            };
+            if is_rest {
+                observation.observe(get_rest_code(&response));
+                return Ok(response.map(tonic::body::Body::new));
+            }
+            // Handler errors are sent as trailers-only responses, so the status is in the headers.
+            // Otherwise it comes in the trailers, after the last message of a stream.
+            if let Some(status) = tonic::Status::from_header_map(response.headers()) {
+                observation.observe(status.code());
+                return Ok(response.map(tonic::body::Body::new));
+            }
+            if response.body().is_end_stream() {
+                observation.observe(tonic::Code::Unknown);
+                return Ok(response.map(tonic::body::Body::new));
+            }
+            Ok(response.map(|body| {
+                tonic::body::Body::new(MetricsBody {
+                    inner: Box::pin(body),
+                    observation: Some(observation),
+                })
+            }))
+        })
+    }
+}
+
+// The REST gateway keeps the code of the status it answers with; other failures are requests the
+// extractors rejected before they reached the service.
+fn get_rest_code<B>(response: &http::Response<B>) -> tonic::Code {
+    if let Some(code) = response.extensions().get::<tonic::Code>() {
+        *code
+    } else if response.status().is_success() {
+        tonic::Code::Ok
+    } else if response.status().is_client_error() {
+        tonic::Code::InvalidArgument
+    } else {
+        tonic::Code::Unknown
+    }
+}
+
+struct Observation {
+    method: String,
+    start: Instant,
+}
+
+impl Observation {
+    fn observe(self, code: tonic::Code) {
+        let code = format!("{:?}", code);
+        GRPC_REQUESTS
+            .with_label_values(&[self.method.as_str(), code.as_str()])
+            .inc();
+        GRPC_REQUEST_DURATION
+            .with_label_values(&[self.method.as_str()])
+            .observe(self.start.elapsed().as_secs_f64());
+    }
+}
+
+/// A response body that observes its request once the status arrives in the trailers.
+struct MetricsBody<B> {
+    inner: Pin<Box<B>>,
+    observation: Option<Observation>,
+}
+
+impl<B> MetricsBody<B> {
+    fn observe(&mut self, code: tonic::Code) {
+        if let Some(observation) = self.observation.take() {
+            observation.observe(code);
+        }
+    }
+}
+
+impl<B: http_body::Body> http_body::Body for MetricsBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        let frame = std::task::ready!(self.inner.as_mut().poll_frame(cx));
+        match &frame {
+            Some(Ok(frame)) => {
+                if let Some(trailers) = frame.trailers_ref() {
+                    let code = tonic::Status::from_header_map(trailers)
+                        .map(|status| status.code())
+                        .unwrap_or(tonic::Code::Unknown);
+                    self.observe(code);
+                }
+            }
+            Some(Err(_)) => self.observe(tonic::Code::Unknown),
+            // A gRPC response without trailers was cut off.
+            None => self.observe(tonic::Code::Unknown),
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B> Drop for MetricsBody<B> {
+    // The client went away before the stream ended.
+    fn drop(&mut self) {
+        self.observe(tonic::Code::Cancelled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, convert::Infallible};
+
+    use super::*;
+
+    fn create_layer() -> MetricsLayer {
+        let file = prost_types::FileDescriptorProto {
+            package: Some("metrics.test".to_string()),
+            service: vec![prost_types::ServiceDescriptorProto {
+                name: Some("Demo".to_string()),
+                method: ["Get", "Stream", "Watch"]
+                    .iter()
+                    .map(|name| prost_types::MethodDescriptorProto {
+                        name: Some(name.to_string()),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let file_descriptor_set =
+            prost_types::FileDescriptorSet { file: vec![file] }.encode_to_vec();
+        MetricsLayer::new(&[&file_descriptor_set]).unwrap()
+    }
+
+    fn get_count(method: &str, code: &str) -> u64 {
+        GRPC_REQUESTS.with_label_values(&[method, code]).get()
+    }
+
+    /// A response body of the given frames.
+    struct Frames(VecDeque<http_body::Frame<bytes::Bytes>>);
+
+    impl http_body::Body for Frames {
+        type Data = bytes::Bytes;
+        type Error = Infallible;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+            Poll::Ready(self.0.pop_front().map(Ok))
+        }
+    }
+
+    fn get_trailers(code: tonic::Code) -> http::HeaderMap {
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", (code as i32).to_string().parse().unwrap());
+        trailers
+    }
+
+    async fn call(
+        path: &str,
+        response: http::Response<Frames>,
+    ) -> http::Response<tonic::body::Body> {
+        let mut response = Some(response);
+        let mut service = tower::Layer::layer(
+            &create_layer(),
+            tower::service_fn(move |_request: http::Request<()>| {
+                std::future::ready(Ok::<_, Infallible>(response.take().unwrap()))
+            }),
+        );
+        let request = http::Request::builder().uri(path).body(()).unwrap();
+        tower::Service::call(&mut service, request).await.unwrap()
+    }
+
+    async fn get_next_frame(
+        body: &mut tonic::body::Body,
+    ) -> Option<http_body::Frame<bytes::Bytes>> {
+        std::future::poll_fn(|cx| http_body::Body::poll_frame(Pin::new(&mut *body), cx))
+            .await
+            .map(|frame| frame.unwrap())
+    }
+
+    #[tokio::test]
+    async fn counts_trailers_only_responses_by_method_and_code() {
+        let method = "/metrics.test.Demo/Get";
+        let count = get_count(method, "NotFound");
+        let observed = GRPC_REQUEST_DURATION
+            .with_label_values(&[method])
+            .get_sample_count();
+        let response = http::Response::builder()
+            .header("grpc-status", (tonic::Code::NotFound as i32).to_string())
+            .body(Frames(VecDeque::new()))
+            .unwrap();
+
+        call(method, response).await;
+
+        assert_eq!(get_count(method, "NotFound"), count + 1);
+        assert_eq!(
+            GRPC_REQUEST_DURATION
+                .with_label_values(&[method])
+                .get_sample_count(),
+            observed + 1
+        );
+    }
+
+    #[tokio::test]
+    async fn labels_paths_of_no_method_as_unknown() {
+        let count = get_count(UNKNOWN_METHOD, "Unimplemented");
+        let response = http::Response::builder()
+            .header(
+                "grpc-status",
+                (tonic::Code::Unimplemented as i32).to_string(),
+            )
+            .body(Frames(VecDeque::new()))
+            .unwrap();
+
+        call("/metrics.test.Demo/Other", response).await;
+
+        assert_eq!(get_count(UNKNOWN_METHOD, "Unimplemented"), count + 1);
+        assert_eq!(get_count("/metrics.test.Demo/Other", "Unimplemented"), 0);
+    }
+
+    #[tokio::test]
+    async fn counts_streams_by_the_code_of_their_trailers() {
+        let method = "/metrics.test.Demo/Stream";
+        let count = get_count(method, "Ok");
+        let response = http::Response::new(Frames(VecDeque::from([
+            http_body::Frame::data(bytes::Bytes::from_static(b"message")),
+            http_body::Frame::trailers(get_trailers(tonic::Code::Ok)),
+        ])));
+
+        let mut body = call(method, response).await.into_body();
+        assert!(get_next_frame(&mut body).await.unwrap().is_data());
+        assert_eq!(get_count(method, "Ok"), count);
+        assert!(get_next_frame(&mut body).await.unwrap().is_trailers());
+        assert_eq!(get_count(method, "Ok"), count + 1);
+        drop(body);
+
+        assert_eq!(get_count(method, "Cancelled"), 0);
+    }
+
+    #[tokio::test]
+    async fn counts_streams_dropped_before_their_trailers_as_cancelled() {
+        let method = "/metrics.test.Demo/Watch";
+        let count = get_count(method, "Cancelled");
+        let response = http::Response::new(Frames(VecDeque::from([
+            http_body::Frame::data(bytes::Bytes::from_static(b"message")),
+            http_body::Frame::trailers(get_trailers(tonic::Code::Ok)),
+        ])));
+
+        let mut body = call(method, response).await.into_body();
+        get_next_frame(&mut body).await;
+        drop(body);
+
+        assert_eq!(get_count(method, "Cancelled"), count + 1);
+        assert_eq!(get_count(method, "Ok"), 0);
+    }
+
+    #[tokio::test]
+    async fn counts_rest_requests_by_route_and_the_code_of_the_gateway() {
+        let mut router = axum::Router::new()
+            .route(
+                "/v1/metrics/{id}",
+                axum::routing::post(|| async { axum::Json(serde_json::json!({})) }),
+            )
+            .route(
+                "/v1/metrics/{id}/missing",
+                axum::routing::post(|| async {
+                    let mut response =
+                        axum::response::IntoResponse::into_response(http::StatusCode::NOT_FOUND);
+                    response.extensions_mut().insert(tonic::Code::NotFound);
+                    response
+                }),
+            )
+            .route(
+                "/v1/metrics",
+                axum::routing::post(|_: axum::Json<serde_json::Value>| async {}),
+            )
+            .route_layer(create_layer());
+        let counts = [
+            get_count("POST /v1/metrics/{id}", "Ok"),
+            get_count("POST /v1/metrics/{id}/missing", "NotFound"),
+            get_count("POST /v1/metrics", "InvalidArgument"),
+        ];
+
+        for path in ["/v1/metrics/1", "/v1/metrics/2/missing", "/v1/metrics"] {
+            let request = http::Request::builder()
+                .method(http::Method::POST)
+                .uri(path)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            tower::Service::call(&mut router, request).await.unwrap();
+        }
+
+        assert_eq!(get_count("POST /v1/metrics/{id}", "Ok"), counts[0] + 1);
+        assert_eq!(
+            get_count("POST /v1/metrics/{id}/missing", "NotFound"),
+            counts[1] + 1
+        );
+        assert_eq!(
+            get_count("POST /v1/metrics", "InvalidArgument"),
+            counts[2] + 1
+        );
+        assert_eq!(get_count(UNKNOWN_METHOD, "Ok"), 0);
+    }
+}
@@ -1,3 +1,7 @@
+mod note_service;
+mod reference_data_service;
 mod user_service;
 
+pub use note_service::*;
+pub use reference_data_service::*;
 pub use user_service::*;
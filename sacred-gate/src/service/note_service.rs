@@ -0,0 +1,340 @@
+use std::sync::Arc;
+
+use arcane_vault::domain::error::ArcaneVaultError;
+use ethereal_core::proto::{
+    Attachment, DeleteAttachmentRequest, DeleteAttachmentResponse, DiffNoteRevisionsRequest,
+    DiffNoteRevisionsResponse, DownloadAttachmentRequest, DownloadAttachmentResponse,
+    GetNoteRevisionRequest, GetNoteRevisionResponse, ListAttachmentsRequest,
+    ListAttachmentsResponse, ListNoteRevisionsRequest, ListNoteRevisionsResponse,
+    RestoreNoteRevisionRequest, RestoreNoteRevisionResponse, UploadAttachmentRequest,
+    UploadAttachmentResponse,
+};
+use futures_util::{StreamExt, stream::BoxStream};
+use uuid::Uuid;
+
+use crate::{service::get_upload_error, status::validate};
+
+// The first message of UploadAttachment, and the chunks of the file after it.
+type AttachmentUpload = (
+    UploadAttachmentRequest,
+    BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>,
+);
+
+pub struct NoteService {
+    vault_note_service: Box<dyn arcane_vault::domain::service::NoteService>,
+    // Authenticates requests and localizes errors the same way for notes as for users.
+    user_service: Arc<crate::service::UserService>,
+}
+
+impl NoteService {
+    pub fn new(
+        attachment_repository: Box<dyn arcane_vault::domain::repository::AttachmentRepository>,
+        note_revision_repository: Box<dyn arcane_vault::domain::repository::NoteRevisionRepository>,
+        user_service: Arc<crate::service::UserService>,
+    ) -> Result<Self, ArcaneVaultError> {
+        Ok(Self {
+            vault_note_service: arcane_vault::NoteService::create(
+                attachment_repository,
+                note_revision_repository,
+            )?,
+            user_service,
+        })
+    }
+
+    /// UploadAttachment of the first message and the chunks after it, of either the gRPC stream
+    /// or a REST request.
+    pub async fn upload_attachment_chunks(
+        &self,
+        request: tonic::Request<AttachmentUpload>,
+    ) -> Result<tonic::Response<UploadAttachmentResponse>, tonic::Status> {
+        validate(&request.get_ref().0)?;
+        let (metadata, _, (request, chunks)) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let note_id = Uuid::parse_str(&request.note_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+        let chunks = futures_util::stream::once(async move { Ok(request.chunk) })
+            .chain(chunks)
+            .boxed();
+
+        match self
+            .vault_note_service
+            .upload_attachment(
+                &principal,
+                note_id,
+                &request.file_name,
+                &request.content_type,
+                chunks,
+            )
+            .await
+        {
+            Ok(attachment) => Ok(tonic::Response::new(UploadAttachmentResponse {
+                attachment: Some(attachment),
+            })),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+
+    /// DownloadAttachment as the attachment and its file in chunks, for the gRPC stream or the
+    /// body of a REST response.
+    #[allow(clippy::result_large_err)]
+    pub async fn download_attachment_chunks(
+        &self,
+        request: tonic::Request<DownloadAttachmentRequest>,
+    ) -> Result<
+        (
+            Attachment,
+            BoxStream<'static, Result<Vec<u8>, tonic::Status>>,
+        ),
+        tonic::Status,
+    > {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let attachment_id = Uuid::parse_str(&request.attachment_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .vault_note_service
+            .download_attachment(&principal, attachment_id)
+            .await
+        {
+            Ok((attachment, chunks)) => Ok((
+                attachment,
+                chunks.map(|chunk| chunk.map_err(Into::into)).boxed(),
+            )),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+
+    /// Deletes the blobs orphaned for the grace period, logging how many.
+    pub async fn collect_attachment_blobs(&self) {
+        match self.vault_note_service.collect_attachment_blobs().await {
+            Ok(0) => {}
+            Ok(collected) => tracing::info!(collected, "attachment blobs collected"),
+            Err(err) => tracing::warn!(error = %err.message, "attachment blobs not collected"),
+        }
+    }
+
+    /// Deletes the note revisions the retention settings no longer keep, logging how many.
+    pub async fn prune_note_revisions(&self) {
+        match self.vault_note_service.prune_note_revisions().await {
+            Ok(0) => {}
+            Ok(pruned) => tracing::info!(pruned, "note revisions pruned"),
+            Err(err) => tracing::warn!(error = %err.message, "note revisions not pruned"),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ethereal_core::proto::note_service_server::NoteService for NoteService {
+    async fn upload_attachment(
+        &self,
+        request: tonic::Request<tonic::Streaming<UploadAttachmentRequest>>,
+    ) -> std::result::Result<tonic::Response<UploadAttachmentResponse>, tonic::Status> {
+        let (metadata, extensions, mut stream) = request.into_parts();
+        let Some(first) = stream.message().await? else {
+            return Err(tonic::Status::invalid_argument(
+                "invalid request: no message was sent",
+            ));
+        };
+        let chunks = stream
+            .map(|message| {
+                message
+                    .map(|message| message.chunk)
+                    .map_err(|status| get_upload_error(status.message().to_string()))
+            })
+            .boxed();
+        self.upload_attachment_chunks(tonic::Request::from_parts(
+            metadata,
+            extensions,
+            (first, chunks),
+        ))
+        .await
+    }
+
+    async fn list_attachments(
+        &self,
+        request: tonic::Request<ListAttachmentsRequest>,
+    ) -> std::result::Result<tonic::Response<ListAttachmentsResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let note_id = Uuid::parse_str(&request.note_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .vault_note_service
+            .list_attachments(&principal, note_id)
+            .await
+        {
+            Ok(attachments) => Ok(tonic::Response::new(ListAttachmentsResponse {
+                attachments,
+            })),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+
+    type DownloadAttachmentStream =
+        BoxStream<'static, Result<DownloadAttachmentResponse, tonic::Status>>;
+
+    #[allow(clippy::result_large_err)]
+    async fn download_attachment(
+        &self,
+        request: tonic::Request<DownloadAttachmentRequest>,
+    ) -> std::result::Result<tonic::Response<Self::DownloadAttachmentStream>, tonic::Status> {
+        let (attachment, chunks) = self.download_attachment_chunks(request).await?;
+        let first = DownloadAttachmentResponse {
+            attachment: Some(attachment),
+            chunk: Vec::new(),
+        };
+        let chunks = chunks.map(|chunk| {
+            chunk.map(|chunk| DownloadAttachmentResponse {
+                attachment: None,
+                chunk,
+            })
+        });
+        Ok(tonic::Response::new(
+            futures_util::stream::once(async move { Ok(first) })
+                .chain(chunks)
+                .boxed(),
+        ))
+    }
+
+    async fn delete_attachment(
+        &self,
+        request: tonic::Request<DeleteAttachmentRequest>,
+    ) -> std::result::Result<tonic::Response<DeleteAttachmentResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let attachment_id = Uuid::parse_str(&request.attachment_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .vault_note_service
+            .delete_attachment(&principal, attachment_id)
+            .await
+        {
+            Ok(()) => Ok(tonic::Response::new(DeleteAttachmentResponse {})),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+
+    async fn list_note_revisions(
+        &self,
+        request: tonic::Request<ListNoteRevisionsRequest>,
+    ) -> std::result::Result<tonic::Response<ListNoteRevisionsResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let note_id = Uuid::parse_str(&request.note_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .vault_note_service
+            .list_note_revisions(&principal, note_id, request.offset, request.limit)
+            .await
+        {
+            Ok(revisions) => Ok(tonic::Response::new(ListNoteRevisionsResponse {
+                revisions,
+            })),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+
+    async fn get_note_revision(
+        &self,
+        request: tonic::Request<GetNoteRevisionRequest>,
+    ) -> std::result::Result<tonic::Response<GetNoteRevisionResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let note_id = Uuid::parse_str(&request.note_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .vault_note_service
+            .get_note_revision(&principal, note_id, request.revision)
+            .await
+        {
+            Ok(revision) => Ok(tonic::Response::new(GetNoteRevisionResponse {
+                revision: Some(revision),
+            })),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+
+    async fn diff_note_revisions(
+        &self,
+        request: tonic::Request<DiffNoteRevisionsRequest>,
+    ) -> std::result::Result<tonic::Response<DiffNoteRevisionsResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let note_id = Uuid::parse_str(&request.note_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .vault_note_service
+            .diff_note_revisions(
+                &principal,
+                note_id,
+                request.from_revision,
+                request.to_revision,
+            )
+            .await
+        {
+            Ok((content_diff, meta_diff)) => Ok(tonic::Response::new(DiffNoteRevisionsResponse {
+                content_diff,
+                meta_diff,
+            })),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+
+    async fn restore_note_revision(
+        &self,
+        request: tonic::Request<RestoreNoteRevisionRequest>,
+    ) -> std::result::Result<tonic::Response<RestoreNoteRevisionResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.user_service.authenticate(&metadata).await?;
+        let note_id = Uuid::parse_str(&request.note_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .vault_note_service
+            .restore_note_revision(&principal, note_id, request.revision)
+            .await
+        {
+            Ok(revision) => Ok(tonic::Response::new(RestoreNoteRevisionResponse {
+                revision,
+            })),
+            Err(err) => Err(self
+                .user_service
+                .get_user_status(err, &metadata, &principal)
+                .await),
+        }
+    }
+}
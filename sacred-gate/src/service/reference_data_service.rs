@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use arcane_vault::domain::entity::ReferenceData;
+use ethereal_core::proto::{
+    ListGendersRequest, ListGendersResponse, ListLocalesRequest, ListLocalesResponse,
+    ListNoteSourceTypesRequest, ListNoteSourceTypesResponse, ListRolesRequest, ListRolesResponse,
+    ListTimezonesRequest, ListTimezonesResponse, ListUserStatusesRequest, ListUserStatusesResponse,
+};
+
+pub struct ReferenceDataService {
+    vault_reference_data_service: Arc<dyn arcane_vault::domain::service::ReferenceDataService>,
+}
+
+impl ReferenceDataService {
+    pub fn new(
+        vault_reference_data_service: Arc<dyn arcane_vault::domain::service::ReferenceDataService>,
+    ) -> Self {
+        Self {
+            vault_reference_data_service,
+        }
+    }
+
+    async fn get_reference_data(&self) -> Result<Arc<ReferenceData>, tonic::Status> {
+        self.vault_reference_data_service
+            .get_reference_data()
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[tonic::async_trait]
+impl ethereal_core::proto::reference_data_service_server::ReferenceDataService
+    for ReferenceDataService
+{
+    async fn list_genders(
+        &self,
+        _: tonic::Request<ListGendersRequest>,
+    ) -> std::result::Result<tonic::Response<ListGendersResponse>, tonic::Status> {
+        let reference_data = self.get_reference_data().await?;
+        Ok(tonic::Response::new(ListGendersResponse {
+            genders: reference_data.genders.clone(),
+        }))
+    }
+
+    async fn list_locales(
+        &self,
+        request: tonic::Request<ListLocalesRequest>,
+    ) -> std::result::Result<tonic::Response<ListLocalesResponse>, tonic::Status> {
+        let enabled_only = request.into_inner().enabled_only;
+        let reference_data = self.get_reference_data().await?;
+        Ok(tonic::Response::new(ListLocalesResponse {
+            locales: reference_data
+                .locales
+                .iter()
+                .filter(|locale| locale.enabled || !enabled_only)
+                .cloned()
+                .collect(),
+        }))
+    }
+
+    async fn list_timezones(
+        &self,
+        _: tonic::Request<ListTimezonesRequest>,
+    ) -> std::result::Result<tonic::Response<ListTimezonesResponse>, tonic::Status> {
+        let reference_data = self.get_reference_data().await?;
+        Ok(tonic::Response::new(ListTimezonesResponse {
+            timezones: reference_data.timezones.clone(),
+        }))
+    }
+
+    async fn list_roles(
+        &self,
+        _: tonic::Request<ListRolesRequest>,
+    ) -> std::result::Result<tonic::Response<ListRolesResponse>, tonic::Status> {
+        let reference_data = self.get_reference_data().await?;
+        Ok(tonic::Response::new(ListRolesResponse {
+            roles: reference_data.roles.clone(),
+        }))
+    }
+
+    async fn list_user_statuses(
+        &self,
+        _: tonic::Request<ListUserStatusesRequest>,
+    ) -> std::result::Result<tonic::Response<ListUserStatusesResponse>, tonic::Status> {
+        let reference_data = self.get_reference_data().await?;
+        Ok(tonic::Response::new(ListUserStatusesResponse {
+            user_statuses: reference_data.user_statuses.clone(),
+        }))
+    }
+
+    async fn list_note_source_types(
+        &self,
+        _: tonic::Request<ListNoteSourceTypesRequest>,
+    ) -> std::result::Result<tonic::Response<ListNoteSourceTypesResponse>, tonic::Status> {
+        let reference_data = self.get_reference_data().await?;
+        Ok(tonic::Response::new(ListNoteSourceTypesResponse {
+            note_source_types: reference_data.note_source_types.clone(),
+        }))
+    }
+}
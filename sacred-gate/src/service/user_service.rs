@@ -1,17 +1,217 @@
 use ethereal_core::proto::{
-    query_user_request::Identity, CreateUserRequest, CreateUserResponse, QueryUserRequest, QueryUserResponse, VerifyUserRequest, VerifyUserResponse
+    ConfirmEmailChangeRequest, ConfirmEmailChangeResponse, ConfirmTotpRequest, ConfirmTotpResponse,
+    CreatePersonalAccessTokenRequest, CreatePersonalAccessTokenResponse, CreateUserRequest,
+    CreateUserResponse, DisableTotpRequest, DisableTotpResponse, EnrollTotpRequest,
+    EnrollTotpResponse, ExportUsersRequest, FinishOidcLinkRequest, FinishOidcLinkResponse,
+    FinishOidcLoginRequest, FinishOidcLoginResponse, GenerateRecoveryCodesRequest,
+    GenerateRecoveryCodesResponse, ListAuditEventsRequest, ListAuditEventsResponse,
+    ListExternalIdentitiesRequest, ListExternalIdentitiesResponse, ListPersonalAccessTokensRequest,
+    ListPersonalAccessTokensResponse, ListSessionsRequest, ListSessionsResponse, ListUsersRequest,
+    ListUsersResponse, LoginUserRequest, LoginUserResponse, LogoutEverywhereRequest,
+    LogoutEverywhereResponse, QueryUserRequest, QueryUserResponse, RefreshSessionRequest,
+    RefreshSessionResponse, RequestEmailChangeRequest, RequestEmailChangeResponse,
+    RevokePersonalAccessTokenRequest, RevokePersonalAccessTokenResponse, RevokeSessionRequest,
+    RevokeSessionResponse, SetTimezoneRequest, SetTimezoneResponse, StartOidcLinkRequest,
+    StartOidcLinkResponse, StartOidcLoginRequest, StartOidcLoginResponse,
+    UnlinkExternalIdentityRequest, UnlinkExternalIdentityResponse, UpdateProfileRequest,
+    UpdateProfileResponse, UploadAvatarRequest, UploadAvatarResponse, User,
+    VerifyTotpChallengeRequest, VerifyTotpChallengeResponse, VerifyUserRequest, VerifyUserResponse,
+    query_user_request::Identity,
 };
+use std::{collections::HashMap, sync::Arc};
+
+use arcane_vault::domain::{
+    entity::{NewUser, Principal},
+    error::{ArcaneVaultError, ArcaneVaultErrorCode, get_error_locale},
+    service::{LoginOutcome, RequestContext, SessionDevice},
+};
+use futures_util::{StreamExt, stream::BoxStream};
+use tonic::metadata::MetadataMap;
 use uuid::Uuid;
+
+use crate::status::{get_accept_languages, validate};
+
 pub struct UserService {
     valut_signup_service: Box<dyn arcane_vault::domain::service::UserService>,
+    vault_reference_data_service: Arc<dyn arcane_vault::domain::service::ReferenceDataService>,
+    // Whether `x-forwarded-for` names the client, which only holds behind a proxy setting it.
+    trust_forwarded_for: bool,
+}
+
+// Whose profile locale an error falls back to when `accept-language` has none we support. Only
+// the caller's own profile is looked up, so errors cannot tell the locale of another account.
+enum Profile<'a> {
+    Locale(i32),
+    Principal(&'a Principal),
+    Unknown,
 }
 
 impl UserService {
-    pub async fn new() -> Self {
-        Self {
-            valut_signup_service: arcane_vault::UserService::new().await,
+    pub fn new(
+        user_repository: Box<dyn arcane_vault::domain::repository::UserRepository>,
+        totp_repository: Box<dyn arcane_vault::domain::repository::TotpRepository>,
+        session_repository: Box<dyn arcane_vault::domain::repository::SessionRepository>,
+        personal_access_token_repository: Box<
+            dyn arcane_vault::domain::repository::PersonalAccessTokenRepository,
+        >,
+        oidc_repository: Box<dyn arcane_vault::domain::repository::OidcRepository>,
+        audit_repository: Box<dyn arcane_vault::domain::repository::AuditRepository>,
+        vault_reference_data_service: Arc<dyn arcane_vault::domain::service::ReferenceDataService>,
+    ) -> Result<Self, ArcaneVaultError> {
+        let config =
+            ethereal_core::configuration::TomlConfiguration::get_config("setting/Config.toml");
+        Ok(Self {
+            valut_signup_service: arcane_vault::UserService::create(
+                user_repository,
+                totp_repository,
+                session_repository,
+                personal_access_token_repository,
+                oidc_repository,
+                audit_repository,
+            )?,
+            vault_reference_data_service,
+            trust_forwarded_for: config
+                .get::<bool>("sacred-gate[0].trust_forwarded_for")
+                .unwrap_or(false),
+        })
+    }
+
+    fn get_request_context<T>(&self, request: &tonic::Request<T>) -> RequestContext {
+        let get_value = |key: &str| {
+            request
+                .metadata()
+                .get(key)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+        };
+        let forwarded_for = get_value("x-forwarded-for")
+            .filter(|_| self.trust_forwarded_for)
+            .and_then(|value| value.split(',').next().map(|ip| ip.trim().to_string()))
+            .filter(|ip| !ip.is_empty());
+        RequestContext {
+            ip_address: forwarded_for
+                .or_else(|| request.remote_addr().map(|addr| addr.ip().to_string()))
+                .unwrap_or_default(),
+            user_agent: get_value("user-agent").unwrap_or_default(),
+            request_id: get_value("x-request-id").unwrap_or_default(),
+        }
+    }
+
+    pub(crate) async fn authenticate(
+        &self,
+        metadata: &MetadataMap,
+    ) -> Result<Principal, tonic::Status> {
+        match crate::auth::authenticate(self.valut_signup_service.as_ref(), metadata).await {
+            Ok(principal) => Ok(principal),
+            Err(err) => Err(self.get_status(err, metadata, Profile::Unknown).await),
+        }
+    }
+
+    /// UploadAvatar of the chunks of either the gRPC stream or the body of a REST request.
+    pub async fn upload_avatar_chunks(
+        &self,
+        request: tonic::Request<BoxStream<'static, Result<Vec<u8>, ArcaneVaultError>>>,
+    ) -> Result<tonic::Response<UploadAvatarResponse>, tonic::Status> {
+        let context = self.get_request_context(&request);
+        let (metadata, _, chunks) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .upload_avatar(&principal, chunks, &context)
+            .await
+        {
+            Ok(thumbnails) => Ok(tonic::Response::new(UploadAvatarResponse { thumbnails })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    /// The avatar thumbnail at `path` for the REST gateway to serve, `None` when there is none.
+    pub async fn get_avatar(
+        &self,
+        metadata: &MetadataMap,
+        path: &str,
+    ) -> Result<Option<Vec<u8>>, tonic::Status> {
+        match self.valut_signup_service.get_avatar(path).await {
+            Ok(avatar) => Ok(avatar),
+            Err(err) => Err(self.get_status(err, metadata, Profile::Unknown).await),
         }
     }
+
+    /// The status of `err` of a request by `principal`, localized like the errors of this service.
+    pub(crate) async fn get_user_status(
+        &self,
+        err: ArcaneVaultError,
+        metadata: &MetadataMap,
+        principal: &Principal,
+    ) -> tonic::Status {
+        self.get_status(err, metadata, Profile::Principal(principal)).await
+    }
+
+    async fn get_status(
+        &self,
+        err: ArcaneVaultError,
+        metadata: &MetadataMap,
+        profile: Profile<'_>,
+    ) -> tonic::Status {
+        let mut locales = get_accept_languages(metadata);
+        if !locales.iter().any(|locale| get_error_locale(locale).is_some())
+            && let Some(locale) = self.get_profile_locale(profile).await
+        {
+            locales.push(locale);
+        }
+        err.into_localized_status(&locales)
+    }
+
+    async fn get_profile_locale(&self, profile: Profile<'_>) -> Option<String> {
+        let locale_id = match profile {
+            Profile::Locale(locale_id) => locale_id,
+            Profile::Principal(principal) => {
+                self.valut_signup_service
+                    .query_user_by_id(principal.user_id)
+                    .await
+                    .ok()??
+                    .locale
+            }
+            Profile::Unknown => return None,
+        };
+        let reference_data = self.vault_reference_data_service.get_reference_data().await.ok()?;
+        reference_data
+            .locales
+            .iter()
+            .find(|locale| locale.id == locale_id)
+            .map(|locale| locale.locale_code.clone())
+    }
+}
+
+// VARCHAR(100) and VARCHAR(512) in `sessions`; clients choose these, so they are cut rather than
+// rejected.
+const DEVICE_LABEL_MAX_LEN: usize = 100;
+const USER_AGENT_MAX_LEN: usize = 512;
+
+/// An upload stream that broke off, e.g. because the client went away.
+pub fn get_upload_error(message: String) -> ArcaneVaultError {
+    ArcaneVaultError {
+        message,
+        code: Some(ArcaneVaultErrorCode::InnerError("upload error".into())),
+        metadata: HashMap::new(),
+    }
+}
+
+fn get_session_device(metadata: &MetadataMap) -> SessionDevice {
+    let get_value = |key: &str, max_len: usize| {
+        metadata
+            .get(key)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().chars().take(max_len).collect())
+            .unwrap_or_default()
+    };
+    SessionDevice {
+        device_label: get_value("x-device-label", DEVICE_LABEL_MAX_LEN),
+        user_agent: get_value("user-agent", USER_AGENT_MAX_LEN),
+    }
 }
 
 /// Generated trait containing gRPC methods that should be implemented for use with UserServiceServer.
@@ -21,27 +221,30 @@ impl ethereal_core::proto::user_service_server::UserService for UserService {
         &self,
         request: tonic::Request<CreateUserRequest>,
     ) -> std::result::Result<tonic::Response<CreateUserResponse>, tonic::Status> {
-        let request = request.into_inner();
-        let email: String = request.email;
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
         let password: String = request.password;
-        let firstname: String = request.firstname;
-        let lastname: String = request.lastname;
-        let gender: i32 = request.gender;
         let locale: i32 = request.locale;
-        let avatar: String = request.avatar;
-        let signature: String = request.signature;
+        let user = NewUser {
+            account: request.email,
+            firstname: request.firstname,
+            lastname: request.lastname,
+            gender: request.gender,
+            locale,
+            signature: request.signature,
+            timezone: request.timezone,
+        };
 
         let result = self
             .valut_signup_service
-            .create_user(
-                &email, &password, &firstname, &lastname, gender, locale, &avatar, &signature,
-            )
+            .create_user(&user, &password, &context)
             .await;
         match result {
             Ok(verification_code) => Ok(tonic::Response::new(CreateUserResponse {
                 verification_code,
             })),
-            Err(err) => Err(err.into()),
+            Err(err) => Err(self.get_status(err, &metadata, Profile::Locale(locale)).await),
         }
     }
 
@@ -50,22 +253,170 @@ impl ethereal_core::proto::user_service_server::UserService for UserService {
         &self,
         request: tonic::Request<VerifyUserRequest>,
     ) -> std::result::Result<tonic::Response<VerifyUserResponse>, tonic::Status> {
-        let request = request.into_inner();
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
         let email: String = request.email;
         let password: String = request.password;
         let verify_code: String = request.verify_code;
 
         let result = self
             .valut_signup_service
-            .verify_user(
-                &email, &password, &verify_code
-            )
+            .verify_user(&email, &password, &verify_code, &context)
             .await;
         match result {
             Ok(user_id) => Ok(tonic::Response::new(VerifyUserResponse {
                 user_id: user_id.to_string(),
             })),
-            Err(err) => Err(err.into()),
+            Err(err) => Err(self.get_status(err, &metadata, Profile::Unknown).await),
+        }
+    }
+
+    async fn login_user(
+        &self,
+        request: tonic::Request<LoginUserRequest>,
+    ) -> std::result::Result<tonic::Response<LoginUserResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
+
+        match self
+            .valut_signup_service
+            .login_user(
+                &request.email,
+                &request.password,
+                &get_session_device(&metadata),
+                &context,
+            )
+            .await
+        {
+            Ok(LoginOutcome::LoggedIn(user_id, session)) => {
+                Ok(tonic::Response::new(LoginUserResponse {
+                    user_id: user_id.to_string(),
+                    totp_challenge: String::new(),
+                    session: Some(session),
+                }))
+            }
+            Ok(LoginOutcome::TotpRequired(challenge_id)) => {
+                Ok(tonic::Response::new(LoginUserResponse {
+                    user_id: String::new(),
+                    totp_challenge: challenge_id.to_string(),
+                    session: None,
+                }))
+            }
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Unknown)
+                .await),
+        }
+    }
+
+    async fn verify_totp_challenge(
+        &self,
+        request: tonic::Request<VerifyTotpChallengeRequest>,
+    ) -> std::result::Result<tonic::Response<VerifyTotpChallengeResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
+        let challenge_id = Uuid::parse_str(&request.totp_challenge)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .valut_signup_service
+            .verify_totp_challenge(
+                challenge_id,
+                &request.code,
+                &get_session_device(&metadata),
+                &context,
+            )
+            .await
+        {
+            Ok((user_id, session)) => Ok(tonic::Response::new(VerifyTotpChallengeResponse {
+                user_id: user_id.to_string(),
+                session: Some(session),
+            })),
+            Err(err) => Err(self.get_status(err, &metadata, Profile::Unknown).await),
+        }
+    }
+
+    async fn enroll_totp(
+        &self,
+        request: tonic::Request<EnrollTotpRequest>,
+    ) -> std::result::Result<tonic::Response<EnrollTotpResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .enroll_totp(&principal, &request.password)
+            .await
+        {
+            Ok(otpauth_uri) => Ok(tonic::Response::new(EnrollTotpResponse { otpauth_uri })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn confirm_totp(
+        &self,
+        request: tonic::Request<ConfirmTotpRequest>,
+    ) -> std::result::Result<tonic::Response<ConfirmTotpResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .confirm_totp(&principal, &request.code)
+            .await
+        {
+            Ok(recovery_codes) => Ok(tonic::Response::new(ConfirmTotpResponse { recovery_codes })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn disable_totp(
+        &self,
+        request: tonic::Request<DisableTotpRequest>,
+    ) -> std::result::Result<tonic::Response<DisableTotpResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .disable_totp(&principal, &request.password, &request.code)
+            .await
+        {
+            Ok(()) => Ok(tonic::Response::new(DisableTotpResponse {})),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn generate_recovery_codes(
+        &self,
+        request: tonic::Request<GenerateRecoveryCodesRequest>,
+    ) -> std::result::Result<tonic::Response<GenerateRecoveryCodesResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .generate_recovery_codes(&principal, &request.code)
+            .await
+        {
+            Ok(recovery_codes) => Ok(tonic::Response::new(GenerateRecoveryCodesResponse {
+                recovery_codes,
+            })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
         }
     }
 
@@ -73,27 +424,491 @@ impl ethereal_core::proto::user_service_server::UserService for UserService {
         &self,
         request: tonic::Request<QueryUserRequest>,
     ) -> std::result::Result<tonic::Response<QueryUserResponse>, tonic::Status> {
-        let request = request.into_inner();
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
         match request.identity {
-            Some(Identity::Id(id)) => match self
-                .valut_signup_service
-                .query_user_by_id(
-                    Uuid::parse_str(&id).map_err(|err| tonic::Status::from_error(err.into()))?,
-                )
-                .await
-            {
-                Ok(user) => Ok(tonic::Response::new(QueryUserResponse { user })),
-                Err(err) => Err(err.into()),
-            },
+            Some(Identity::Id(id)) => {
+                let id = Uuid::parse_str(&id).map_err(|err| tonic::Status::from_error(err.into()))?;
+                match self.valut_signup_service.query_user_by_id(id).await {
+                    Ok(user) => Ok(tonic::Response::new(QueryUserResponse { user })),
+                    Err(err) => Err(self.get_status(err, &metadata, Profile::Unknown).await),
+                }
+            }
             Some(Identity::Email(email)) => match self
                 .valut_signup_service
                 .query_user_by_email_account(&email)
                 .await
             {
                 Ok(user) => Ok(tonic::Response::new(QueryUserResponse { user })),
-                Err(err) => Err(err.into()),
+                Err(err) => Err(self.get_status(err, &metadata, Profile::Unknown).await),
             },
             None => Ok(tonic::Response::new(QueryUserResponse { user: None })),
         }
     }
+
+    async fn list_users(
+        &self,
+        request: tonic::Request<ListUsersRequest>,
+    ) -> std::result::Result<tonic::Response<ListUsersResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .query_users(&principal, request.offset, request.limit)
+            .await
+        {
+            Ok(users) => Ok(tonic::Response::new(ListUsersResponse { users })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    type ExportUsersStream = BoxStream<'static, Result<User, tonic::Status>>;
+
+    #[allow(clippy::result_large_err)]
+    async fn export_users(
+        &self,
+        request: tonic::Request<ExportUsersRequest>,
+    ) -> std::result::Result<tonic::Response<Self::ExportUsersStream>, tonic::Status> {
+        let (metadata, _, _) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self.valut_signup_service.export_users(&principal).await {
+            Ok(users) => Ok(tonic::Response::new(
+                users.map(|user| user.map_err(Into::into)).boxed(),
+            )),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn set_timezone(
+        &self,
+        request: tonic::Request<SetTimezoneRequest>,
+    ) -> std::result::Result<tonic::Response<SetTimezoneResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .set_timezone(&principal, &request.timezone, &context)
+            .await
+        {
+            Ok(()) => Ok(tonic::Response::new(SetTimezoneResponse {})),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn update_profile(
+        &self,
+        request: tonic::Request<UpdateProfileRequest>,
+    ) -> std::result::Result<tonic::Response<UpdateProfileResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .update_user_profile(
+                &principal,
+                &request.firstname,
+                &request.lastname,
+                request.gender,
+                request.locale,
+                &context,
+            )
+            .await
+        {
+            Ok(user) => Ok(tonic::Response::new(UpdateProfileResponse { user: Some(user) })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn refresh_session(
+        &self,
+        request: tonic::Request<RefreshSessionRequest>,
+    ) -> std::result::Result<tonic::Response<RefreshSessionResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+
+        match self
+            .valut_signup_service
+            .refresh_session(&request.refresh_token)
+            .await
+        {
+            Ok(session) => Ok(tonic::Response::new(RefreshSessionResponse {
+                session: Some(session),
+            })),
+            Err(err) => Err(self.get_status(err, &metadata, Profile::Unknown).await),
+        }
+    }
+
+    async fn list_sessions(
+        &self,
+        request: tonic::Request<ListSessionsRequest>,
+    ) -> std::result::Result<tonic::Response<ListSessionsResponse>, tonic::Status> {
+        let (metadata, _, _) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self.valut_signup_service.list_sessions(&principal).await {
+            Ok(sessions) => Ok(tonic::Response::new(ListSessionsResponse { sessions })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn revoke_session(
+        &self,
+        request: tonic::Request<RevokeSessionRequest>,
+    ) -> std::result::Result<tonic::Response<RevokeSessionResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+        let session_id = Uuid::parse_str(&request.session_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+
+        match self
+            .valut_signup_service
+            .revoke_session(&principal, session_id)
+            .await
+        {
+            Ok(()) => Ok(tonic::Response::new(RevokeSessionResponse {})),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn logout_everywhere(
+        &self,
+        request: tonic::Request<LogoutEverywhereRequest>,
+    ) -> std::result::Result<tonic::Response<LogoutEverywhereResponse>, tonic::Status> {
+        let (metadata, _, _) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self.valut_signup_service.logout_everywhere(&principal).await {
+            Ok(revoked_sessions) => Ok(tonic::Response::new(LogoutEverywhereResponse {
+                revoked_sessions,
+            })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn create_personal_access_token(
+        &self,
+        request: tonic::Request<CreatePersonalAccessTokenRequest>,
+    ) -> std::result::Result<tonic::Response<CreatePersonalAccessTokenResponse>, tonic::Status>
+    {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .create_personal_access_token(
+                &principal,
+                &request.name,
+                &request.scopes,
+                request.expires_at,
+            )
+            .await
+        {
+            Ok((personal_access_token, token)) => {
+                Ok(tonic::Response::new(CreatePersonalAccessTokenResponse {
+                    personal_access_token: Some(personal_access_token),
+                    token,
+                }))
+            }
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn list_personal_access_tokens(
+        &self,
+        request: tonic::Request<ListPersonalAccessTokensRequest>,
+    ) -> std::result::Result<tonic::Response<ListPersonalAccessTokensResponse>, tonic::Status> {
+        let (metadata, _, _) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .list_personal_access_tokens(&principal)
+            .await
+        {
+            Ok(personal_access_tokens) => {
+                Ok(tonic::Response::new(ListPersonalAccessTokensResponse {
+                    personal_access_tokens,
+                }))
+            }
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn revoke_personal_access_token(
+        &self,
+        request: tonic::Request<RevokePersonalAccessTokenRequest>,
+    ) -> std::result::Result<tonic::Response<RevokePersonalAccessTokenResponse>, tonic::Status>
+    {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let token_id = Uuid::parse_str(&request.token_id)
+            .map_err(|err| tonic::Status::from_error(err.into()))?;
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .revoke_personal_access_token(&principal, token_id)
+            .await
+        {
+            Ok(()) => Ok(tonic::Response::new(RevokePersonalAccessTokenResponse {})),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn start_oidc_login(
+        &self,
+        request: tonic::Request<StartOidcLoginRequest>,
+    ) -> std::result::Result<tonic::Response<StartOidcLoginResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+
+        match self
+            .valut_signup_service
+            .start_oidc_login(&request.provider)
+            .await
+        {
+            Ok(authorization_url) => Ok(tonic::Response::new(StartOidcLoginResponse {
+                authorization_url,
+            })),
+            Err(err) => Err(self.get_status(err, &metadata, Profile::Unknown).await),
+        }
+    }
+
+    async fn finish_oidc_login(
+        &self,
+        request: tonic::Request<FinishOidcLoginRequest>,
+    ) -> std::result::Result<tonic::Response<FinishOidcLoginResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
+
+        match self
+            .valut_signup_service
+            .finish_oidc_login(
+                &request.state,
+                &request.code,
+                &get_session_device(&metadata),
+                &context,
+            )
+            .await
+        {
+            Ok(LoginOutcome::LoggedIn(user_id, session)) => {
+                Ok(tonic::Response::new(FinishOidcLoginResponse {
+                    user_id: user_id.to_string(),
+                    totp_challenge: String::new(),
+                    session: Some(session),
+                }))
+            }
+            Ok(LoginOutcome::TotpRequired(challenge_id)) => {
+                Ok(tonic::Response::new(FinishOidcLoginResponse {
+                    user_id: String::new(),
+                    totp_challenge: challenge_id.to_string(),
+                    session: None,
+                }))
+            }
+            Err(err) => Err(self.get_status(err, &metadata, Profile::Unknown).await),
+        }
+    }
+
+    async fn start_oidc_link(
+        &self,
+        request: tonic::Request<StartOidcLinkRequest>,
+    ) -> std::result::Result<tonic::Response<StartOidcLinkResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .start_oidc_link(&principal, &request.provider)
+            .await
+        {
+            Ok(authorization_url) => Ok(tonic::Response::new(StartOidcLinkResponse {
+                authorization_url,
+            })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn finish_oidc_link(
+        &self,
+        request: tonic::Request<FinishOidcLinkRequest>,
+    ) -> std::result::Result<tonic::Response<FinishOidcLinkResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .finish_oidc_link(&principal, &request.state, &request.code)
+            .await
+        {
+            Ok(external_identity) => Ok(tonic::Response::new(FinishOidcLinkResponse {
+                external_identity: Some(external_identity),
+            })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn list_external_identities(
+        &self,
+        request: tonic::Request<ListExternalIdentitiesRequest>,
+    ) -> std::result::Result<tonic::Response<ListExternalIdentitiesResponse>, tonic::Status> {
+        let (metadata, _, _) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .list_external_identities(&principal)
+            .await
+        {
+            Ok(external_identities) => Ok(tonic::Response::new(ListExternalIdentitiesResponse {
+                external_identities,
+            })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn unlink_external_identity(
+        &self,
+        request: tonic::Request<UnlinkExternalIdentityRequest>,
+    ) -> std::result::Result<tonic::Response<UnlinkExternalIdentityResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .unlink_external_identity(&principal, &request.provider, &request.subject)
+            .await
+        {
+            Ok(()) => Ok(tonic::Response::new(UnlinkExternalIdentityResponse {})),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn request_email_change(
+        &self,
+        request: tonic::Request<RequestEmailChangeRequest>,
+    ) -> std::result::Result<tonic::Response<RequestEmailChangeResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .request_email_change(&principal, &request.password, &request.new_email)
+            .await
+        {
+            Ok(()) => Ok(tonic::Response::new(RequestEmailChangeResponse {})),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn confirm_email_change(
+        &self,
+        request: tonic::Request<ConfirmEmailChangeRequest>,
+    ) -> std::result::Result<tonic::Response<ConfirmEmailChangeResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let context = self.get_request_context(&request);
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+
+        match self
+            .valut_signup_service
+            .confirm_email_change(&principal, &request.verification_code, &context)
+            .await
+        {
+            Ok(email) => Ok(tonic::Response::new(ConfirmEmailChangeResponse { email })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn list_audit_events(
+        &self,
+        request: tonic::Request<ListAuditEventsRequest>,
+    ) -> std::result::Result<tonic::Response<ListAuditEventsResponse>, tonic::Status> {
+        validate(request.get_ref())?;
+        let (metadata, _, request) = request.into_parts();
+        let principal = self.authenticate(&metadata).await?;
+        let user_id = if request.user_id.is_empty() {
+            None
+        } else {
+            Some(
+                Uuid::parse_str(&request.user_id)
+                    .map_err(|err| tonic::Status::from_error(err.into()))?,
+            )
+        };
+
+        match self
+            .valut_signup_service
+            .list_audit_events(&principal, user_id, request.offset, request.limit)
+            .await
+        {
+            Ok(audit_events) => Ok(tonic::Response::new(ListAuditEventsResponse {
+                audit_events,
+            })),
+            Err(err) => Err(self
+                .get_status(err, &metadata, Profile::Principal(&principal))
+                .await),
+        }
+    }
+
+    async fn upload_avatar(
+        &self,
+        request: tonic::Request<tonic::Streaming<UploadAvatarRequest>>,
+    ) -> std::result::Result<tonic::Response<UploadAvatarResponse>, tonic::Status> {
+        self.upload_avatar_chunks(request.map(|stream| {
+            stream
+                .map(|message| {
+                    message
+                        .map(|message| message.chunk)
+                        .map_err(|status| get_upload_error(status.message().to_string()))
+                })
+                .boxed()
+        }))
+        .await
+    }
 }
@@ -0,0 +1,99 @@
+use ethereal_core::proto::google::rpc::{
+    BadRequest, ErrorInfo, LocalizedMessage, RetryInfo, Status,
+};
+use ethereal_core::validation::Validate;
+use prost::Message;
+
+const BAD_REQUEST_TYPE_URL: &str = "type.googleapis.com/google.rpc.BadRequest";
+const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+const RETRY_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RetryInfo";
+const LOCALIZED_MESSAGE_TYPE_URL: &str = "type.googleapis.com/google.rpc.LocalizedMessage";
+
+/// Rejects the request with `INVALID_ARGUMENT` and a `google.rpc.BadRequest` listing every
+/// field that breaks its `(validate.rules)`.
+#[allow(clippy::result_large_err)]
+pub fn validate<T: Validate>(message: &T) -> Result<(), tonic::Status> {
+    let field_violations = message.validate();
+    if field_violations.is_empty() {
+        return Ok(());
+    }
+
+    let message = format!(
+        "invalid request: {}",
+        field_violations
+            .iter()
+            .map(|violation| format!("{} {}", violation.field, violation.description))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let bad_request = BadRequest { field_violations };
+    let status = Status {
+        code: tonic::Code::InvalidArgument as i32,
+        message: message.clone(),
+        details: vec![prost_types::Any {
+            type_url: BAD_REQUEST_TYPE_URL.to_string(),
+            value: bad_request.encode_to_vec(),
+        }],
+    };
+    Err(tonic::Status::with_details(
+        tonic::Code::InvalidArgument,
+        message,
+        status.encode_to_vec().into(),
+    ))
+}
+
+/// The `details` of the `google.rpc.Status` carried by `status`, in the proto3 JSON mapping.
+pub fn get_json_details(status: &tonic::Status) -> Vec<serde_json::Value> {
+    let Ok(status) = Status::decode(status.details()) else {
+        return Vec::new();
+    };
+    status
+        .details
+        .iter()
+        .filter_map(|detail| {
+            let value = detail.value.as_slice();
+            let mut value = match detail.type_url.as_str() {
+                BAD_REQUEST_TYPE_URL => serde_json::to_value(BadRequest::decode(value).ok()?),
+                ERROR_INFO_TYPE_URL => serde_json::to_value(ErrorInfo::decode(value).ok()?),
+                RETRY_INFO_TYPE_URL => serde_json::to_value(RetryInfo::decode(value).ok()?),
+                LOCALIZED_MESSAGE_TYPE_URL => {
+                    serde_json::to_value(LocalizedMessage::decode(value).ok()?)
+                }
+                _ => return None,
+            }
+            .ok()?;
+            value["@type"] = detail.type_url.clone().into();
+            Some(value)
+        })
+        .collect()
+}
+
+/// The language ranges of the `accept-language` metadata, most preferred first.
+pub fn get_accept_languages(metadata: &tonic::metadata::MetadataMap) -> Vec<String> {
+    let Some(accept_language) = metadata
+        .get("accept-language")
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut language_ranges: Vec<(f32, &str)> = accept_language
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let language_range = parts
+                .next()
+                .filter(|range| !range.is_empty() && *range != "*")?;
+            let quality = parts
+                .find_map(|part| part.strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.parse().ok())?;
+            (quality > 0.0).then_some((quality, language_range))
+        })
+        .collect();
+    // Stable, so ranges of the same quality keep their order.
+    language_ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+    language_ranges
+        .into_iter()
+        .map(|(_, language_range)| language_range.to_string())
+        .collect()
+}
@@ -1,6 +1,17 @@
 CREATE EXTENSION IF NOT EXISTS pgcrypto;
 
 
+DROP TABLE IF EXISTS audit_events CASCADE;
+DROP TABLE IF EXISTS audit_event_types CASCADE;
+DROP TABLE IF EXISTS user_identities CASCADE;
+DROP TABLE IF EXISTS oidc_login_attempts CASCADE;
+DROP TABLE IF EXISTS personal_access_tokens CASCADE;
+DROP TABLE IF EXISTS session_refresh_tokens CASCADE;
+DROP TABLE IF EXISTS sessions CASCADE;
+DROP TABLE IF EXISTS totp_challenges CASCADE;
+DROP TABLE IF EXISTS user_recovery_codes CASCADE;
+DROP TABLE IF EXISTS user_totp CASCADE;
+DROP TABLE IF EXISTS pending_email_changes CASCADE;
 DROP TABLE IF EXISTS pending_reset_passwords CASCADE;
 DROP TABLE IF EXISTS pending_users CASCADE;
 DROP TABLE IF EXISTS user_profiles CASCADE;
@@ -10,34 +21,65 @@ DROP TABLE IF EXISTS roles CASCADE;
 DROP TABLE IF EXISTS user_statuses CASCADE;
 DROP TABLE IF EXISTS locales CASCADE;
 DROP TABLE IF EXISTS timezones CASCADE;
+DROP TABLE IF EXISTS error_code_translations CASCADE;
 DROP TABLE IF EXISTS error_codes CASCADE;
 		
 CREATE TABLE error_codes (
     errcode TEXT PRIMARY KEY,
-    param_count INT NOT NULL,
+    -- One name per %s, the keys of the JSON object util_raise_error puts into DETAIL.
+    param_names TEXT[] NOT NULL,
     message_template TEXT NOT NULL
 );
-INSERT INTO error_codes (errcode, param_count, message_template) VALUES
+INSERT INTO error_codes (errcode, param_names, message_template) VALUES
 -- ACCOUNT
-('PA001', 1, 'Account %s is unavailable.'),
-('PA002', 1, 'Account %s is not registered.'),
-('PA003', 1, 'Account %s was not found.'),
-('PA004', 1, 'Account %s is pending activation.'),
-('PA005', 1, 'Invalid password for account %s.'),
-('PA006', 1, 'User profile for account %s was not found.'),
-('PA007', 1, 'User ID %s not found.'),
-
-('PA008', 1, 'Verification code for account %s was recently generated. Please wait before requesting again.'),
-('PA009', 0, 'Invalid verification code.'),
-('PA010', 0, 'Verification code expired.'),
-('PA011', 1, 'No password reset request found for account %s.'),
-
-
-('PN001', 2, 'The category name %s" already exists for user %s.'),
-('PN002', 2, 'Category %s does not belong to user %s.'),
-('PN003', 2, 'Collection %s of user %s cannot be deleted because it still contains notes.'),
-('PN004', 1, 'Source type %s is invalid.'),
-('PN005', 1, 'Note %s does not exist.');
+('PA001', '{account}', 'Account %s is unavailable.'),
+('PA002', '{account}', 'Account %s is not registered.'),
+('PA003', '{account}', 'Account %s was not found.'),
+('PA004', '{account}', 'Account %s is pending activation.'),
+('PA005', '{account}', 'Invalid password for account %s.'),
+('PA006', '{account}', 'User profile for account %s was not found.'),
+('PA007', '{user_id}', 'User ID %s not found.'),
+
+('PA008', '{account}', 'Verification code for account %s was recently generated. Please wait before requesting again.'),
+('PA009', '{}', 'Invalid verification code.'),
+('PA010', '{}', 'Verification code expired.'),
+('PA011', '{account}', 'No password reset request found for account %s.'),
+('PA012', '{timezone}', 'Timezone %s is invalid.'),
+('PA013', '{account}', 'Two-factor authentication is already enabled for account %s.'),
+('PA014', '{account}', 'Account %s has no two-factor enrollment to confirm.'),
+('PA015', '{account}', 'Two-factor authentication is not enabled for account %s.'),
+('PA016', '{}', 'Invalid two-factor authentication code.'),
+('PA017', '{}', 'The two-factor challenge is invalid or has expired.'),
+('PA018', '{}', 'The refresh token is invalid or has expired.'),
+('PA019', '{session_id}', 'Session %s was not found.'),
+('PA020', '{}', 'The access token is missing, invalid or has expired.'),
+('PA021', '{scope}', 'The access token does not grant the %s scope.'),
+('PA022', '{scopes}', 'Scopes "%s" are invalid.'),
+('PA023', '{token_id}', 'Personal access token %s was not found.'),
+('PA024', '{}', 'This can only be done with a session access token.'),
+('PA025', '{}', 'The expiry time must be in the future.'),
+('PA026', '{provider}', 'OpenID Connect provider %s is not configured.'),
+('PA027', '{}', 'The OpenID Connect login is invalid or has expired.'),
+('PA028', '{provider}', 'The %s identity is already linked to another account.'),
+('PA029', '{provider}', 'The %s identity has no verified email address.'),
+('PA030', '{provider,subject}', 'The %s identity %s is not linked to this account.'),
+('PA031', '{}', 'The last sign-in method of an account without a password cannot be removed.'),
+('PA032', '{account}', 'No email address change request found for account %s.'),
+('PA033', '{}', 'This can only be done by an administrator.'),
+('PA034', '{max_size}', 'The avatar must not be larger than %s bytes.'),
+('PA035', '{max_dimension}', 'The avatar must be a PNG, JPEG, GIF or WebP image of at most %s pixels per side.'),
+('PA036', '{}', 'Too many invalid two-factor authentication codes. Please try again later.'),
+('PA037', '{}', 'Please enter your password, or log in again.'),
+
+
+('PN001', '{collection_name,account}', 'The category name %s" already exists for user %s.'),
+('PN002', '{collection_id,account}', 'Category %s does not belong to user %s.'),
+('PN003', '{collection_name,account}', 'Collection %s of user %s cannot be deleted because it still contains notes.'),
+('PN004', '{source_type}', 'Source type %s is invalid.'),
+('PN005', '{note_id}', 'Note %s does not exist.'),
+('PN006', '{attachment_id}', 'Attachment %s does not exist.'),
+('PN007', '{max_size}', 'The attachment must not be larger than %s bytes.'),
+('PN008', '{revision,note_id}', 'Revision %s of note %s does not exist.');
 
 CREATE TABLE genders (
     id INTEGER PRIMARY KEY,
@@ -71,6 +113,21 @@ INSERT INTO user_statuses (id, name, description) VALUES
 (2, 'Suspended', 'User account is suspended'),
 (3, 'Deleted', 'User account is deleted');
 
+CREATE TABLE audit_event_types (
+    id INTEGER PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT
+);
+INSERT INTO audit_event_types (id, name, description) VALUES
+(1, 'Registered', 'Account registered, waiting for verification'),
+(2, 'Verified', 'Account verified and user created'),
+(3, 'LoginSucceeded', 'Login finished with a new session'),
+(4, 'LoginFailed', 'Login rejected'),
+(5, 'PasswordSet', 'Password set by the user'),
+(6, 'PasswordReset', 'Password reset with a verification code'),
+(7, 'ProfileChanged', 'Profile changed'),
+(8, 'EmailChanged', 'Email address changed');
+
 CREATE TABLE locales (
     id INTEGER PRIMARY KEY,
     language_code VARCHAR(10) NOT NULL,
@@ -104,6 +161,60 @@ INSERT INTO locales (
     now()
 );
 
+-- error_codes.message_template is en-US, these are the templates of the other locales.
+CREATE TABLE error_code_translations (
+    errcode TEXT NOT NULL REFERENCES error_codes(errcode),
+    locale_code VARCHAR(10) NOT NULL REFERENCES locales(locale_code),
+    message_template TEXT NOT NULL,
+    PRIMARY KEY (errcode, locale_code)
+);
+INSERT INTO error_code_translations (errcode, locale_code, message_template) VALUES
+('PA001', 'zh-CN', '账户 %s 不可用。'),
+('PA002', 'zh-CN', '账户 %s 尚未注册。'),
+('PA003', 'zh-CN', '未找到账户 %s。'),
+('PA004', 'zh-CN', '账户 %s 正在等待激活。'),
+('PA005', 'zh-CN', '账户 %s 的密码错误。'),
+('PA006', 'zh-CN', '未找到账户 %s 的用户资料。'),
+('PA007', 'zh-CN', '未找到用户 ID %s。'),
+('PA008', 'zh-CN', '账户 %s 的验证码刚刚生成，请稍后再试。'),
+('PA009', 'zh-CN', '验证码无效。'),
+('PA010', 'zh-CN', '验证码已过期。'),
+('PA011', 'zh-CN', '未找到账户 %s 的密码重置请求。'),
+('PA012', 'zh-CN', '时区 %s 无效。'),
+('PA013', 'zh-CN', '账户 %s 已启用两步验证。'),
+('PA014', 'zh-CN', '账户 %s 没有待确认的两步验证。'),
+('PA015', 'zh-CN', '账户 %s 未启用两步验证。'),
+('PA016', 'zh-CN', '两步验证码无效。'),
+('PA017', 'zh-CN', '两步验证请求无效或已过期。'),
+('PA018', 'zh-CN', '刷新令牌无效或已过期。'),
+('PA019', 'zh-CN', '未找到会话 %s。'),
+('PA020', 'zh-CN', '访问令牌缺失、无效或已过期。'),
+('PA021', 'zh-CN', '访问令牌未授予 %s 权限范围。'),
+('PA022', 'zh-CN', '权限范围 "%s" 无效。'),
+('PA023', 'zh-CN', '未找到个人访问令牌 %s。'),
+('PA024', 'zh-CN', '只能使用会话访问令牌执行此操作。'),
+('PA025', 'zh-CN', '过期时间必须晚于当前时间。'),
+('PA026', 'zh-CN', '未配置 OpenID Connect 提供方 %s。'),
+('PA027', 'zh-CN', 'OpenID Connect 登录无效或已过期。'),
+('PA028', 'zh-CN', '该 %s 身份已关联到其他账户。'),
+('PA029', 'zh-CN', '该 %s 身份没有已验证的电子邮件地址。'),
+('PA030', 'zh-CN', '%s 身份 %s 未关联到此账户。'),
+('PA031', 'zh-CN', '无法移除没有密码的账户的最后一种登录方式。'),
+('PA032', 'zh-CN', '未找到账户 %s 的邮箱地址变更请求。'),
+('PA033', 'zh-CN', '只有管理员才能执行此操作。'),
+('PA034', 'zh-CN', '头像不能大于 %s 字节。'),
+('PA035', 'zh-CN', '头像必须是每边不超过 %s 像素的 PNG、JPEG、GIF 或 WebP 图片。'),
+('PA036', 'zh-CN', '两步验证码错误次数过多，请稍后再试。'),
+('PA037', 'zh-CN', '请输入密码，或重新登录。'),
+('PN001', 'zh-CN', '分类名称 %s 已被用户 %s 使用。'),
+('PN002', 'zh-CN', '分类 %s 不属于用户 %s。'),
+('PN003', 'zh-CN', '集合 %s（用户 %s）仍包含笔记，无法删除。'),
+('PN004', 'zh-CN', '来源类型 %s 无效。'),
+('PN005', 'zh-CN', '笔记 %s 不存在。'),
+('PN006', 'zh-CN', '附件 %s 不存在。'),
+('PN007', 'zh-CN', '附件不能大于 %s 字节。'),
+('PN008', 'zh-CN', '版本 %s（笔记 %s）不存在。');
+
 CREATE TABLE timezones (
     name TEXT PRIMARY KEY
 );
@@ -121,10 +232,11 @@ CREATE TABLE pending_users (
     lastname VARCHAR(255) NOT NULL,
     gender INTEGER NOT NULL,
     locale INTEGER NOT NULL,
-    avatar VARCHAR(255),
+    timezone TEXT NOT NULL DEFAULT 'UTC',
     signature VARCHAR(255),
 	CONSTRAINT fk_gender FOREIGN KEY (gender) REFERENCES genders(id),
-	CONSTRAINT fk_locale FOREIGN KEY (locale) REFERENCES locales(id)
+	CONSTRAINT fk_locale FOREIGN KEY (locale) REFERENCES locales(id),
+	CONSTRAINT fk_timezone FOREIGN KEY (timezone) REFERENCES timezones(name)
 );
 CREATE OR REPLACE FUNCTION trgfn_pending_users_updated_at()
 RETURNS TRIGGER
@@ -210,6 +322,155 @@ BEFORE UPDATE ON pending_reset_passwords
 FOR EACH ROW
 EXECUTE FUNCTION trgfn_pending_reset_passwords_updated_at();
 
+-- account is the new address, which only replaces users.account once its code is confirmed.
+-- attempts counts the codes tried against verification_code, to lock out guessing.
+CREATE TABLE pending_email_changes (
+    id UUID PRIMARY KEY,
+    account VARCHAR(255) NOT NULL,
+    verification_code VARCHAR(6) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_pending_email_changes_id FOREIGN KEY (id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- The secret is encrypted by arcane-vault. enabled_at stays NULL until the enrollment is confirmed
+-- with a first code, and last_used_step keeps a code from being used twice. attempts counts the
+-- codes tried since the last one that was right, to lock out guessing.
+CREATE TABLE user_totp (
+    id UUID PRIMARY KEY,
+    secret BYTEA NOT NULL,
+    last_used_step BIGINT NOT NULL DEFAULT 0,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_attempt_at TIMESTAMPTZ DEFAULT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    enabled_at TIMESTAMPTZ DEFAULT NULL,
+    CONSTRAINT fk_user_totp_id FOREIGN KEY (id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- SHA-256 of the codes, which are random enough not to need a password hash.
+CREATE TABLE user_recovery_codes (
+    id UUID NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ DEFAULT NULL,
+    PRIMARY KEY (id, code_hash),
+    CONSTRAINT fk_user_recovery_codes_id FOREIGN KEY (id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- A login that passed the password check and waits for a TOTP or recovery code.
+CREATE TABLE totp_challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_totp_challenges_user_id FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- A signed-in device. Tokens are random and stored as SHA-256; the access token is replaced on
+-- every refresh, and expires_at moves forward with it.
+CREATE TABLE sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    device_label VARCHAR(100) NOT NULL DEFAULT '',
+    user_agent VARCHAR(512) NOT NULL DEFAULT '',
+    access_token_hash VARCHAR(64) NOT NULL UNIQUE,
+    access_token_expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ DEFAULT NULL,
+    CONSTRAINT fk_sessions_user_id FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_sessions_user_id ON sessions (user_id);
+
+-- Every refresh token a session was given, the family of the session. Only the one not used yet
+-- is valid; presenting a used one means it leaked, so the whole session is revoked.
+CREATE TABLE session_refresh_tokens (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    session_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    used_at TIMESTAMPTZ DEFAULT NULL,
+    CONSTRAINT fk_session_refresh_tokens_session_id FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- Long-lived credential for scripts, limited to its scopes. Like the session tokens, the token is
+-- random and stored as SHA-256.
+CREATE TABLE personal_access_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes VARCHAR(32)[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ DEFAULT NULL,
+    last_used_at TIMESTAMPTZ DEFAULT NULL,
+    revoked_at TIMESTAMPTZ DEFAULT NULL,
+    CONSTRAINT fk_personal_access_tokens_user_id FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_personal_access_tokens_user_id ON personal_access_tokens (user_id);
+
+-- An OpenID Connect authorization request waiting for the provider to redirect back, found by the
+-- SHA-256 of its state. user_id is set when the identity is to be linked to that user.
+CREATE TABLE oidc_login_attempts (
+    state_hash VARCHAR(64) PRIMARY KEY,
+    provider VARCHAR(64) NOT NULL,
+    pkce_verifier VARCHAR(128) NOT NULL,
+    nonce VARCHAR(128) NOT NULL,
+    user_id UUID DEFAULT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_oidc_login_attempts_user_id FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- An account at an OpenID Connect provider, by the subject of its ID tokens, that signs in as the
+-- user. Users created by the first login of an identity have the password '!', which is no hash.
+CREATE TABLE user_identities (
+    provider VARCHAR(64) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    user_id UUID NOT NULL,
+    email VARCHAR(255) NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_login_at TIMESTAMPTZ DEFAULT NULL,
+    PRIMARY KEY (provider, subject),
+    CONSTRAINT fk_user_identities_user_id FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_user_identities_user_id ON user_identities (user_id);
+
+-- Security events of accounts, which are only ever added. user_id is NULL for an account that is
+-- no user, e.g. a registration not verified yet, and actor_id when nobody was signed in. Neither
+-- references users, so the log outlives them.
+CREATE TABLE audit_events (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    user_id UUID DEFAULT NULL,
+    actor_id UUID DEFAULT NULL,
+    event_type INTEGER NOT NULL,
+    account VARCHAR(255) NOT NULL DEFAULT '',
+    details TEXT NOT NULL DEFAULT '',
+    ip_address VARCHAR(45) NOT NULL DEFAULT '',
+    user_agent VARCHAR(512) NOT NULL DEFAULT '',
+    request_id VARCHAR(128) NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_audit_events_event_type FOREIGN KEY (event_type) REFERENCES audit_event_types(id)
+);
+CREATE INDEX idx_audit_events_user_id ON audit_events (user_id, id);
+CREATE INDEX idx_audit_events_account ON audit_events (account, id) WHERE user_id IS NULL;
+CREATE OR REPLACE FUNCTION trgfn_audit_events_append_only()
+RETURNS TRIGGER
+AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+DROP TRIGGER IF EXISTS trg_audit_events_append_only ON audit_events;
+CREATE TRIGGER trg_audit_events_append_only
+BEFORE UPDATE OR DELETE ON audit_events
+FOR EACH ROW
+EXECUTE FUNCTION trgfn_audit_events_append_only();
+DROP TRIGGER IF EXISTS trg_audit_events_no_truncate ON audit_events;
+CREATE TRIGGER trg_audit_events_no_truncate
+BEFORE TRUNCATE ON audit_events
+FOR EACH STATEMENT
+EXECUTE FUNCTION trgfn_audit_events_append_only();
+
 CREATE OR REPLACE FUNCTION util_raise_error(
     p_errcode TEXT,
     VARIADIC p_args TEXT[]
@@ -217,10 +478,10 @@ CREATE OR REPLACE FUNCTION util_raise_error(
 AS $$
 DECLARE
     v_template TEXT;
-    v_param_count INT;
+    v_param_names TEXT[];
 BEGIN
-    SELECT message_template, param_count
-    INTO v_template, v_param_count
+    SELECT message_template, param_names
+    INTO v_template, v_param_names
     FROM error_codes
     WHERE errcode = p_errcode;
 
@@ -228,13 +489,14 @@ BEGIN
         RAISE EXCEPTION 'Error in function %s: Unknown error code: %', util_get_current_function_name(), p_errcode;
     END IF;
 
-    IF array_length(p_args, 1) <> v_param_count THEN
+    IF array_length(p_args, 1) <> cardinality(v_param_names) THEN
         RAISE EXCEPTION 'Error in function %s: Incorrect number of arguments for error code %: expected %, got %',
-            util_get_current_function_name(), p_errcode, v_param_count, array_length(p_args, 1);
+            util_get_current_function_name(), p_errcode, cardinality(v_param_names), array_length(p_args, 1);
     END IF;
 
     RAISE EXCEPTION '%', format('Error in function %s: %s', util_get_current_function_name(), format(v_template, VARIADIC p_args))
-        USING HINT = p_errcode;
+        USING HINT = p_errcode,
+              DETAIL = COALESCE(json_object(v_param_names, p_args)::text, '{}');
 END;
 $$ LANGUAGE plpgsql;
 
@@ -282,15 +544,35 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE OR REPLACE FUNCTION util_verify_timezone (
+    p_timezone TEXT
+)
+RETURNS void
+AS $$
+BEGIN
+	IF NOT EXISTS (SELECT 1 FROM timezones WHERE name = p_timezone) THEN
+	    PERFORM util_raise_error('PA012', p_timezone);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Passwords are hashed by arcane-vault, so the functions below only ever see and store hashes.
+-- Parameters were renamed, which CREATE OR REPLACE cannot do, so the old signatures are dropped.
+DROP FUNCTION IF EXISTS func_register_user(VARCHAR, VARCHAR, VARCHAR, VARCHAR, INTEGER, INTEGER, VARCHAR, VARCHAR);
+DROP FUNCTION IF EXISTS func_register_user(VARCHAR, VARCHAR, VARCHAR, VARCHAR, INTEGER, INTEGER, VARCHAR, VARCHAR, TEXT);
+DROP FUNCTION IF EXISTS func_verify_user(VARCHAR, VARCHAR, VARCHAR);
+DROP FUNCTION IF EXISTS func_login_user(VARCHAR, VARCHAR);
+DROP FUNCTION IF EXISTS func_reset_password(VARCHAR, VARCHAR, VARCHAR);
+DROP FUNCTION IF EXISTS func_set_password(UUID, VARCHAR);
 CREATE OR REPLACE FUNCTION func_register_user (
     p_account VARCHAR,
-	p_password VARCHAR,
+	p_password_hash VARCHAR,
     p_firstname VARCHAR,
     p_lastname VARCHAR,
     p_gender INTEGER,
     p_locale INTEGER,
-    p_avatar VARCHAR DEFAULT NULL,
-    p_signature VARCHAR DEFAULT NULL
+    p_signature VARCHAR DEFAULT NULL,
+    p_timezone TEXT DEFAULT 'UTC'
 )
 RETURNS VARCHAR
 AS $$
@@ -300,6 +582,7 @@ BEGIN
 	IF NOT util_verify_account(p_account) THEN
 	    PERFORM util_raise_error('PA001', p_account);
     END IF;
+    PERFORM util_verify_timezone(p_timezone);
 
 	v_code := util_generate_verification_code();
 
@@ -313,11 +596,11 @@ BEGIN
         lastname,
         gender,
         locale,
-        avatar,
+        timezone,
         signature
     ) VALUES (
         p_account,
-        crypt(p_password, gen_salt('bf')),
+        p_password_hash,
 		v_code,
         now(),
         now(),
@@ -325,7 +608,7 @@ BEGIN
         p_lastname,
         p_gender,
         p_locale,
-        p_avatar,
+        p_timezone,
         p_signature
     );
 	RETURN v_code;
@@ -335,7 +618,7 @@ $$ LANGUAGE plpgsql;
 
 CREATE OR REPLACE FUNCTION func_verify_user (
     p_account VARCHAR,
-	p_password VARCHAR,
+	p_password_hash VARCHAR,
     p_verification_code VARCHAR
 )
 RETURNS UUID
@@ -349,7 +632,7 @@ BEGIN
         INTO v_pending_user
         FROM pending_users
         WHERE account = p_account
-            AND crypt(p_password, password) = password
+            AND password = p_password_hash
             AND verification_code = UPPER(p_verification_code);
         
         IF NOT FOUND THEN
@@ -372,15 +655,15 @@ BEGIN
 
         INSERT INTO user_profiles (
             id, firstname, lastname,
-            gender, locale,
-            avatar, signature
+            gender, locale, timezone,
+            signature
         ) VALUES (
             v_id,
             v_pending_user.firstname,
             v_pending_user.lastname,
             v_pending_user.gender,
             v_pending_user.locale,
-            v_pending_user.avatar,
+            v_pending_user.timezone,
             v_pending_user.signature
         );
         
@@ -428,7 +711,7 @@ $$ LANGUAGE plpgsql;
 
 CREATE OR REPLACE FUNCTION func_login_user (
     p_account VARCHAR,
-	p_password VARCHAR
+	p_password_hash VARCHAR
 )
 RETURNS UUID
 AS $$
@@ -438,7 +721,7 @@ BEGIN
 	SELECT users.id INTO v_id
 	FROM users
 	WHERE account = p_account
-	  AND password = crypt(p_password, password)
+	  AND password = p_password_hash
 	LIMIT 1;
 	
     IF FOUND THEN
@@ -518,7 +801,7 @@ $$ LANGUAGE plpgsql;
 CREATE OR REPLACE FUNCTION func_reset_password (
     p_account VARCHAR,
     p_verification_code VARCHAR,
-	p_new_password VARCHAR
+	p_new_password_hash VARCHAR
 )
 RETURNS void
 AS $$
@@ -551,16 +834,18 @@ BEGIN
     END IF;
 
     UPDATE users
-	SET password = crypt(p_new_password, gen_salt('bf'))
+	SET password = p_new_password_hash
     WHERE id = v_user_id;
 
     DELETE FROM pending_reset_passwords WHERE id = v_user_id;
+
+    PERFORM func_add_audit_event(6, v_user_id, NULL, p_account, '', '', '', '');
 END;
 $$ LANGUAGE plpgsql;
 
 CREATE OR REPLACE FUNCTION func_set_password(
 	p_user_id UUID,
-	p_password VARCHAR
+	p_password_hash VARCHAR
 ) RETURNS void
 AS $$
 DECLARE
@@ -576,12 +861,14 @@ BEGIN
     END IF;
 	
     UPDATE users
-	SET password = crypt(p_password, gen_salt('bf'))
+	SET password = p_password_hash
     WHERE id = p_user_id;
 
     IF NOT FOUND THEN
 	    PERFORM util_raise_error('PA006', v_account);
     END IF;
+
+    PERFORM func_add_audit_event(5, p_user_id, p_user_id, v_account, '', '', '', '');
 END;
 $$ LANGUAGE plpgsql;
 
@@ -732,6 +1019,36 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE OR REPLACE FUNCTION func_set_timezone(
+    p_user_id UUID,
+    p_timezone TEXT
+) RETURNS void
+AS $$
+DECLARE
+    v_account VARCHAR;
+BEGIN
+    SELECT account
+    INTO v_account
+    FROM users
+    WHERE id = p_user_id;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+    PERFORM util_verify_timezone(p_timezone);
+	
+    UPDATE user_profiles up
+    SET timezone = p_timezone
+	From users u
+	WHERE up.id = u.id
+		AND up.id = p_user_id;
+
+    IF NOT FOUND THEN
+	    PERFORM util_raise_error('PA006', v_account);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
 CREATE OR REPLACE FUNCTION cron_func_cleanup_pending_records(
 ) RETURNS void
 AS $$
@@ -741,87 +1058,1064 @@ BEGIN
 
     DELETE FROM pending_reset_passwords
     WHERE updated_at < now() - INTERVAL '24 hours';
+
+    DELETE FROM pending_email_changes
+    WHERE updated_at < now() - INTERVAL '24 hours';
+
+    DELETE FROM totp_challenges
+    WHERE created_at < now() - INTERVAL '24 hours';
+
+    DELETE FROM sessions
+    WHERE expires_at < now() - INTERVAL '24 hours'
+        OR revoked_at < now() - INTERVAL '24 hours';
+
+    DELETE FROM personal_access_tokens
+    WHERE expires_at < now() - INTERVAL '24 hours'
+        OR revoked_at < now() - INTERVAL '24 hours';
+
+    DELETE FROM oidc_login_attempts
+    WHERE created_at < now() - INTERVAL '24 hours';
 END;
 $$ LANGUAGE plpgsql;
 
-CREATE OR REPLACE FUNCTION func_query_user_by_id(
+CREATE OR REPLACE FUNCTION func_enroll_totp(
+    p_user_id UUID,
+    p_secret BYTEA
+) RETURNS void
+AS $$
+DECLARE
+    v_account VARCHAR;
+BEGIN
+    SELECT account
+    INTO v_account
+    FROM users
+    WHERE id = p_user_id;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM user_totp WHERE id = p_user_id AND enabled_at IS NOT NULL) THEN
+        PERFORM util_raise_error('PA013', v_account);
+    END IF;
+
+    -- Enrolling again replaces an unconfirmed secret.
+    INSERT INTO user_totp (id, secret)
+    VALUES (p_user_id, p_secret)
+    ON CONFLICT (id) DO UPDATE
+    SET secret = EXCLUDED.secret,
+        last_used_step = 0,
+        created_at = now();
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_query_totp(
     p_user_id UUID
 ) RETURNS TABLE (
-	id UUID,
-    account VARCHAR,
-	created_at TIMESTAMPTZ,
-	updated_at TIMESTAMPTZ,
-	last_login_at TIMESTAMPTZ,
-	status INTEGER,
-	role INTEGER,
-    firstname VARCHAR,
-    lastname VARCHAR,
-    gender INTEGER,
-    locale INTEGER,
-    avatar VARCHAR,
-    signature VARCHAR
+    secret BYTEA,
+    last_used_step BIGINT,
+    is_enabled BOOLEAN
 )
 AS $$
 BEGIN
     RETURN QUERY
-    SELECT
-		u.id,
-        u.account,
-        u.created_at,
-        u.updated_at,
-		u.last_login_at,
-        u.status,
-        u.role,
-        up.firstname,
-        up.lastname,
-        up.gender,
-        up.locale,
-        up.avatar,
-        up.signature
-    FROM users u
-    JOIN user_profiles up ON u.id = up.id
-    WHERE u.id = p_user_id;
+    SELECT t.secret, t.last_used_step, t.enabled_at IS NOT NULL
+    FROM user_totp t
+    WHERE t.id = p_user_id;
 END;
 $$ LANGUAGE plpgsql;
 
+CREATE OR REPLACE FUNCTION func_confirm_totp(
+    p_user_id UUID,
+    p_step BIGINT,
+    p_recovery_code_hashes TEXT[]
+) RETURNS void
+AS $$
+DECLARE
+    v_account VARCHAR;
+    v_enabled_at TIMESTAMPTZ;
+BEGIN
+    SELECT account
+    INTO v_account
+    FROM users
+    WHERE id = p_user_id;
 
-CREATE OR REPLACE FUNCTION func_query_user_by_account(
-    p_account VARCHAR
-) RETURNS TABLE (
-	id UUID,
-    account VARCHAR,
-	created_at TIMESTAMPTZ,
-	updated_at TIMESTAMPTZ,
-	last_login_at TIMESTAMPTZ,
-	status INTEGER,
-	role INTEGER,
-    firstname VARCHAR,
-    lastname VARCHAR,
-    gender INTEGER,
-    locale INTEGER,
-    avatar VARCHAR,
-    signature VARCHAR
-)
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    SELECT enabled_at
+    INTO v_enabled_at
+    FROM user_totp
+    WHERE id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA014', v_account);
+    END IF;
+
+    IF v_enabled_at IS NOT NULL THEN
+        PERFORM util_raise_error('PA013', v_account);
+    END IF;
+
+    UPDATE user_totp
+    SET enabled_at = now(),
+        last_used_step = p_step
+    WHERE id = p_user_id;
+
+    DELETE FROM user_recovery_codes WHERE id = p_user_id;
+    INSERT INTO user_recovery_codes (id, code_hash)
+    SELECT p_user_id, unnest(p_recovery_code_hashes);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_disable_totp(
+    p_user_id UUID
+) RETURNS void
 AS $$
+DECLARE
+    v_account VARCHAR;
 BEGIN
-    RETURN QUERY
-    SELECT
-		u.id,
-        u.account,
-        u.created_at,
-        u.updated_at,
-		u.last_login_at,
-        u.status,
-        u.role,
-        up.firstname,
-        up.lastname,
-        up.gender,
-        up.locale,
-        up.avatar,
-        up.signature
-    FROM users u
-    JOIN user_profiles up ON u.id = up.id
-    WHERE u.account = p_account;
+    SELECT account
+    INTO v_account
+    FROM users
+    WHERE id = p_user_id;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    DELETE FROM user_totp
+    WHERE id = p_user_id AND enabled_at IS NOT NULL;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA015', v_account);
+    END IF;
+
+    DELETE FROM user_recovery_codes WHERE id = p_user_id;
+    DELETE FROM totp_challenges WHERE user_id = p_user_id;
+END;
+$$ LANGUAGE plpgsql;
+
+-- FALSE when the step, or a later one, was already used.
+CREATE OR REPLACE FUNCTION func_use_totp_step(
+    p_user_id UUID,
+    p_step BIGINT
+) RETURNS BOOLEAN
+AS $$
+BEGIN
+    UPDATE user_totp
+    SET last_used_step = p_step
+    WHERE id = p_user_id
+        AND enabled_at IS NOT NULL
+        AND last_used_step < p_step;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_use_recovery_code(
+    p_user_id UUID,
+    p_code_hash VARCHAR
+) RETURNS BOOLEAN
+AS $$
+BEGIN
+    UPDATE user_recovery_codes
+    SET used_at = now()
+    WHERE id = p_user_id
+        AND code_hash = p_code_hash
+        AND used_at IS NULL;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Counts an attempt at the second factor of the user, or returns FALSE while the last 5 attempts
+-- failed within 15 minutes of each other and of now. A count older than that starts over.
+CREATE OR REPLACE FUNCTION func_use_totp_attempt(
+    p_user_id UUID
+) RETURNS BOOLEAN
+AS $$
+BEGIN
+    UPDATE user_totp
+    SET attempts = CASE
+            WHEN last_attempt_at > now() - INTERVAL '15 minutes' THEN attempts + 1
+            ELSE 1
+        END,
+        last_attempt_at = now()
+    WHERE id = p_user_id
+        AND (attempts < 5 OR last_attempt_at <= now() - INTERVAL '15 minutes');
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_reset_totp_attempts(
+    p_user_id UUID
+) RETURNS void
+AS $$
+BEGIN
+    UPDATE user_totp
+    SET attempts = 0
+    WHERE id = p_user_id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_replace_recovery_codes(
+    p_user_id UUID,
+    p_recovery_code_hashes TEXT[]
+) RETURNS void
+AS $$
+DECLARE
+    v_account VARCHAR;
+BEGIN
+    SELECT account
+    INTO v_account
+    FROM users
+    WHERE id = p_user_id;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM user_totp WHERE id = p_user_id AND enabled_at IS NOT NULL) THEN
+        PERFORM util_raise_error('PA015', v_account);
+    END IF;
+
+    DELETE FROM user_recovery_codes WHERE id = p_user_id;
+    INSERT INTO user_recovery_codes (id, code_hash)
+    SELECT p_user_id, unnest(p_recovery_code_hashes);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_add_totp_challenge(
+    p_user_id UUID
+) RETURNS UUID
+AS $$
+DECLARE
+    v_id UUID;
+BEGIN
+    INSERT INTO totp_challenges (user_id)
+    VALUES (p_user_id)
+    RETURNING id INTO v_id;
+
+    RETURN v_id;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Counts an attempt and returns the user of the challenge, or NULL once it expired after
+-- 5 minutes or 5 attempts.
+CREATE OR REPLACE FUNCTION func_use_totp_challenge(
+    p_challenge_id UUID
+) RETURNS UUID
+AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    UPDATE totp_challenges
+    SET attempts = attempts + 1
+    WHERE id = p_challenge_id
+        AND attempts < 5
+        AND created_at > now() - INTERVAL '5 minutes'
+    RETURNING user_id INTO v_user_id;
+
+    RETURN v_user_id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_delete_totp_challenge(
+    p_challenge_id UUID
+) RETURNS void
+AS $$
+BEGIN
+    DELETE FROM totp_challenges WHERE id = p_challenge_id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_add_session(
+    p_user_id UUID,
+    p_device_label VARCHAR,
+    p_user_agent VARCHAR,
+    p_access_token_hash VARCHAR,
+    p_refresh_token_hash VARCHAR,
+    p_access_token_ttl_secs INTEGER,
+    p_refresh_token_ttl_secs INTEGER
+) RETURNS UUID
+AS $$
+DECLARE
+    v_id UUID;
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM users WHERE id = p_user_id) THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    INSERT INTO sessions (
+        user_id,
+        device_label,
+        user_agent,
+        access_token_hash,
+        access_token_expires_at,
+        expires_at
+    ) VALUES (
+        p_user_id,
+        p_device_label,
+        p_user_agent,
+        p_access_token_hash,
+        now() + p_access_token_ttl_secs * INTERVAL '1 second',
+        now() + p_refresh_token_ttl_secs * INTERVAL '1 second'
+    )
+    RETURNING id INTO v_id;
+
+    INSERT INTO session_refresh_tokens (token_hash, session_id)
+    VALUES (p_refresh_token_hash, v_id);
+
+    RETURN v_id;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Rotates the tokens of the session the refresh token belongs to and returns its id. NULL when
+-- the token is unknown or its session expired or was revoked; a token that was already used
+-- revokes its session. Nothing is raised, so the revocation is not rolled back.
+CREATE OR REPLACE FUNCTION func_refresh_session(
+    p_refresh_token_hash VARCHAR,
+    p_access_token_hash VARCHAR,
+    p_new_refresh_token_hash VARCHAR,
+    p_access_token_ttl_secs INTEGER,
+    p_refresh_token_ttl_secs INTEGER
+) RETURNS UUID
+AS $$
+DECLARE
+    v_session_id UUID;
+    v_used_at TIMESTAMPTZ;
+BEGIN
+    SELECT session_id, used_at
+    INTO v_session_id, v_used_at
+    FROM session_refresh_tokens
+    WHERE token_hash = p_refresh_token_hash
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    IF v_used_at IS NOT NULL THEN
+        UPDATE sessions
+        SET revoked_at = now()
+        WHERE id = v_session_id AND revoked_at IS NULL;
+
+        RETURN NULL;
+    END IF;
+
+    UPDATE sessions
+    SET access_token_hash = p_access_token_hash,
+        access_token_expires_at = now() + p_access_token_ttl_secs * INTERVAL '1 second',
+        last_used_at = now(),
+        expires_at = now() + p_refresh_token_ttl_secs * INTERVAL '1 second'
+    WHERE id = v_session_id
+        AND revoked_at IS NULL
+        AND expires_at > now();
+
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    UPDATE session_refresh_tokens
+    SET used_at = now()
+    WHERE token_hash = p_refresh_token_hash;
+
+    INSERT INTO session_refresh_tokens (token_hash, session_id)
+    VALUES (p_new_refresh_token_hash, v_session_id);
+
+    RETURN v_session_id;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Sessions that are neither expired nor revoked, most recently used first.
+CREATE OR REPLACE FUNCTION func_query_sessions(
+    p_user_id UUID
+) RETURNS TABLE (
+    id UUID,
+    device_label VARCHAR,
+    user_agent VARCHAR,
+    created_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT s.id, s.device_label, s.user_agent, s.created_at, s.last_used_at, s.expires_at
+    FROM sessions s
+    WHERE s.user_id = p_user_id
+        AND s.revoked_at IS NULL
+        AND s.expires_at > now()
+    ORDER BY s.last_used_at DESC;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_revoke_session(
+    p_user_id UUID,
+    p_session_id UUID
+) RETURNS void
+AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM users WHERE id = p_user_id) THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    UPDATE sessions
+    SET revoked_at = now()
+    WHERE id = p_session_id
+        AND user_id = p_user_id
+        AND revoked_at IS NULL
+        AND expires_at > now();
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA019', p_session_id);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Returns how many sessions were revoked.
+CREATE OR REPLACE FUNCTION func_revoke_sessions(
+    p_user_id UUID
+) RETURNS INTEGER
+AS $$
+DECLARE
+    v_count INTEGER;
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM users WHERE id = p_user_id) THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    UPDATE sessions
+    SET revoked_at = now()
+    WHERE user_id = p_user_id
+        AND revoked_at IS NULL
+        AND expires_at > now();
+
+    GET DIAGNOSTICS v_count = ROW_COUNT;
+    RETURN v_count;
+END;
+$$ LANGUAGE plpgsql;
+
+-- The user and session of an access token that has not expired; no row for any other token.
+CREATE OR REPLACE FUNCTION func_query_session_by_access_token(
+    p_access_token_hash VARCHAR
+) RETURNS TABLE (
+    user_id UUID,
+    session_id UUID
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT s.user_id, s.id
+    FROM sessions s
+    WHERE s.access_token_hash = p_access_token_hash
+        AND s.access_token_expires_at > now()
+        AND s.revoked_at IS NULL
+        AND s.expires_at > now();
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_add_personal_access_token(
+    p_user_id UUID,
+    p_name VARCHAR,
+    p_token_hash VARCHAR,
+    p_scopes VARCHAR[],
+    p_expires_at TIMESTAMPTZ
+) RETURNS TABLE (
+    id UUID,
+    name VARCHAR,
+    scopes VARCHAR[],
+    created_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ
+)
+AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM users u WHERE u.id = p_user_id) THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    RETURN QUERY
+    INSERT INTO personal_access_tokens AS t (user_id, name, token_hash, scopes, expires_at)
+    VALUES (p_user_id, p_name, p_token_hash, p_scopes, p_expires_at)
+    RETURNING t.id, t.name, t.scopes, t.created_at, t.expires_at, t.last_used_at;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Tokens that are neither expired nor revoked, newest first.
+CREATE OR REPLACE FUNCTION func_query_personal_access_tokens(
+    p_user_id UUID
+) RETURNS TABLE (
+    id UUID,
+    name VARCHAR,
+    scopes VARCHAR[],
+    created_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.id, t.name, t.scopes, t.created_at, t.expires_at, t.last_used_at
+    FROM personal_access_tokens t
+    WHERE t.user_id = p_user_id
+        AND t.revoked_at IS NULL
+        AND (t.expires_at IS NULL OR t.expires_at > now())
+    ORDER BY t.created_at DESC;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_revoke_personal_access_token(
+    p_user_id UUID,
+    p_token_id UUID
+) RETURNS void
+AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM users WHERE id = p_user_id) THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    UPDATE personal_access_tokens
+    SET revoked_at = now()
+    WHERE id = p_token_id
+        AND user_id = p_user_id
+        AND revoked_at IS NULL
+        AND (expires_at IS NULL OR expires_at > now());
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA023', p_token_id);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Records the use of a token that is neither expired nor revoked and returns its user and
+-- scopes; no row for any other token.
+CREATE OR REPLACE FUNCTION func_use_personal_access_token(
+    p_token_hash VARCHAR
+) RETURNS TABLE (
+    user_id UUID,
+    token_id UUID,
+    scopes VARCHAR[]
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    UPDATE personal_access_tokens t
+    SET last_used_at = now()
+    WHERE t.token_hash = p_token_hash
+        AND t.revoked_at IS NULL
+        AND (t.expires_at IS NULL OR t.expires_at > now())
+    RETURNING t.user_id, t.id, t.scopes;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_add_oidc_login_attempt(
+    p_state_hash VARCHAR,
+    p_provider VARCHAR,
+    p_pkce_verifier VARCHAR,
+    p_nonce VARCHAR,
+    p_user_id UUID
+) RETURNS void
+AS $$
+BEGIN
+    IF p_user_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM users WHERE id = p_user_id) THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    INSERT INTO oidc_login_attempts (state_hash, provider, pkce_verifier, nonce, user_id)
+    VALUES (p_state_hash, p_provider, p_pkce_verifier, p_nonce, p_user_id);
+END;
+$$ LANGUAGE plpgsql;
+
+-- Deletes the attempt of a state, so it is only used once, and returns it unless it is older than
+-- 10 minutes; no row for any other state.
+CREATE OR REPLACE FUNCTION func_use_oidc_login_attempt(
+    p_state_hash VARCHAR
+) RETURNS TABLE (
+    provider VARCHAR,
+    pkce_verifier VARCHAR,
+    nonce VARCHAR,
+    user_id UUID
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    WITH attempt AS (
+        DELETE FROM oidc_login_attempts a
+        WHERE a.state_hash = p_state_hash
+        RETURNING a.provider, a.pkce_verifier, a.nonce, a.user_id, a.created_at
+    )
+    SELECT attempt.provider, attempt.pkce_verifier, attempt.nonce, attempt.user_id
+    FROM attempt
+    WHERE attempt.created_at > now() - INTERVAL '10 minutes';
+END;
+$$ LANGUAGE plpgsql;
+
+-- Records a login with an identity and returns its user, NULL when no user has the identity.
+CREATE OR REPLACE FUNCTION func_login_user_identity(
+    p_provider VARCHAR,
+    p_subject VARCHAR,
+    p_email VARCHAR
+) RETURNS UUID
+AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    UPDATE user_identities
+    SET email = p_email,
+        last_login_at = now()
+    WHERE provider = p_provider
+        AND subject = p_subject
+    RETURNING user_id INTO v_user_id;
+
+    IF FOUND THEN
+        UPDATE users
+        SET last_login_at = now()
+        WHERE id = v_user_id;
+    END IF;
+
+    RETURN v_user_id;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Creates the user of the first login with an identity. The provider verified the email, so the
+-- user is active at once, without a password. The locale is the enabled one matching the BCP 47
+-- tag best, en-US otherwise, and an unknown timezone falls back to UTC.
+CREATE OR REPLACE FUNCTION func_register_user_identity(
+    p_provider VARCHAR,
+    p_subject VARCHAR,
+    p_email VARCHAR,
+    p_firstname VARCHAR,
+    p_lastname VARCHAR,
+    p_locale VARCHAR,
+    p_timezone TEXT
+) RETURNS UUID
+AS $$
+DECLARE
+    v_id UUID;
+    v_locale INTEGER;
+    v_timezone TEXT := 'UTC';
+BEGIN
+    IF NOT util_verify_account(p_email) THEN
+        PERFORM util_raise_error('PA001', p_email);
+    END IF;
+
+    SELECT l.id INTO v_locale
+    FROM locales l
+    WHERE l.enabled
+        AND (lower(l.locale_code) = lower(p_locale)
+            OR l.language_code = lower(split_part(p_locale, '-', 1)))
+    ORDER BY lower(l.locale_code) = lower(p_locale) DESC, l.id
+    LIMIT 1;
+    IF EXISTS (SELECT 1 FROM timezones WHERE name = p_timezone) THEN
+        v_timezone := p_timezone;
+    END IF;
+
+    INSERT INTO users (account, password, status, role, last_login_at)
+    VALUES (p_email, '!', 1, 2, now())
+    RETURNING id INTO v_id;
+
+    INSERT INTO user_profiles (id, firstname, lastname, gender, locale, timezone)
+    VALUES (v_id, p_firstname, p_lastname, 0, COALESCE(v_locale, 1), v_timezone);
+
+    INSERT INTO user_identities (provider, subject, user_id, email, last_login_at)
+    VALUES (p_provider, p_subject, v_id, p_email, now());
+
+    RETURN v_id;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Links an identity to the user, or updates its email when the user already has it.
+CREATE OR REPLACE FUNCTION func_link_user_identity(
+    p_user_id UUID,
+    p_provider VARCHAR,
+    p_subject VARCHAR,
+    p_email VARCHAR
+) RETURNS TABLE (
+    provider VARCHAR,
+    subject VARCHAR,
+    email VARCHAR,
+    created_at TIMESTAMPTZ,
+    last_login_at TIMESTAMPTZ
+)
+AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM users u WHERE u.id = p_user_id) THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+    IF EXISTS (
+        SELECT 1 FROM user_identities i
+        WHERE i.provider = p_provider
+            AND i.subject = p_subject
+            AND i.user_id <> p_user_id
+    ) THEN
+        PERFORM util_raise_error('PA028', p_provider);
+    END IF;
+
+    RETURN QUERY
+    INSERT INTO user_identities AS i (provider, subject, user_id, email)
+    VALUES (p_provider, p_subject, p_user_id, p_email)
+    ON CONFLICT ON CONSTRAINT user_identities_pkey DO UPDATE SET email = EXCLUDED.email
+    RETURNING i.provider, i.subject, i.email, i.created_at, i.last_login_at;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_query_user_identities(
+    p_user_id UUID
+) RETURNS TABLE (
+    provider VARCHAR,
+    subject VARCHAR,
+    email VARCHAR,
+    created_at TIMESTAMPTZ,
+    last_login_at TIMESTAMPTZ
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT i.provider, i.subject, i.email, i.created_at, i.last_login_at
+    FROM user_identities i
+    WHERE i.user_id = p_user_id
+    ORDER BY i.created_at;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Raises PA031 rather than leave a user without a password and without identities.
+CREATE OR REPLACE FUNCTION func_unlink_user_identity(
+    p_user_id UUID,
+    p_provider VARCHAR,
+    p_subject VARCHAR
+) RETURNS void
+AS $$
+DECLARE
+    v_password VARCHAR;
+BEGIN
+    SELECT password INTO v_password FROM users WHERE id = p_user_id FOR UPDATE;
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+    IF NOT EXISTS (
+        SELECT 1 FROM user_identities
+        WHERE user_id = p_user_id
+            AND provider = p_provider
+            AND subject = p_subject
+    ) THEN
+        PERFORM util_raise_error('PA030', p_provider, p_subject);
+    END IF;
+    IF v_password = '!'
+        AND (SELECT count(*) FROM user_identities WHERE user_id = p_user_id) = 1 THEN
+        PERFORM util_raise_error('PA031');
+    END IF;
+
+    DELETE FROM user_identities
+    WHERE user_id = p_user_id
+        AND provider = p_provider
+        AND subject = p_subject;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_request_email_change(
+    p_user_id UUID,
+    p_account VARCHAR
+) RETURNS TEXT
+AS $$
+DECLARE
+    v_account VARCHAR;
+    v_code TEXT;
+BEGIN
+    SELECT account INTO v_account FROM users WHERE id = p_user_id;
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+    IF NOT util_verify_account(p_account) THEN
+        PERFORM util_raise_error('PA001', p_account);
+    END IF;
+    IF EXISTS (
+        SELECT 1
+        FROM pending_email_changes
+        WHERE id = p_user_id
+            AND now() - updated_at < interval '5 minutes'
+    ) THEN
+        PERFORM util_raise_error('PA008', v_account);
+    END IF;
+
+    v_code := util_generate_verification_code();
+    INSERT INTO pending_email_changes (id, account, verification_code, created_at, updated_at)
+    VALUES (p_user_id, p_account, v_code, now(), now())
+    ON CONFLICT (id) DO UPDATE SET
+        account = EXCLUDED.account,
+        verification_code = EXCLUDED.verification_code,
+        attempts = 0,
+        updated_at = EXCLUDED.updated_at;
+    RETURN v_code;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Counts an attempt at the code of the pending email change of the user, or returns FALSE once 5
+-- were made, which leaves the code unusable until another one is requested. It is counted apart
+-- from func_confirm_email_change, whose errors would roll the count back.
+CREATE OR REPLACE FUNCTION func_use_email_change_attempt(
+    p_user_id UUID
+) RETURNS BOOLEAN
+AS $$
+BEGIN
+    UPDATE pending_email_changes
+    SET attempts = attempts + 1
+    WHERE id = p_user_id
+        AND attempts < 5;
+
+    -- Without a pending change there is nothing to lock, and func_confirm_email_change raises
+    -- PA032.
+    RETURN FOUND OR NOT EXISTS (SELECT 1 FROM pending_email_changes WHERE id = p_user_id);
+END;
+$$ LANGUAGE plpgsql;
+
+-- The new address is checked again, as it may have been taken since the request; the unique
+-- users.account settles a registration racing the change.
+CREATE OR REPLACE FUNCTION func_confirm_email_change(
+    p_user_id UUID,
+    p_verification_code VARCHAR
+) RETURNS TABLE (
+    old_account VARCHAR,
+    new_account VARCHAR
+)
+AS $$
+DECLARE
+    v_account VARCHAR;
+    v_row pending_email_changes%ROWTYPE;
+BEGIN
+    SELECT account INTO v_account FROM users WHERE id = p_user_id FOR UPDATE;
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    SELECT * INTO v_row FROM pending_email_changes WHERE id = p_user_id;
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA032', v_account);
+    END IF;
+    IF v_row.verification_code <> UPPER(p_verification_code) THEN
+        PERFORM util_raise_error('PA009');
+    END IF;
+    IF now() - v_row.updated_at > interval '15 minutes' THEN
+        PERFORM util_raise_error('PA010');
+    END IF;
+    IF NOT util_verify_account(v_row.account) THEN
+        PERFORM util_raise_error('PA001', v_row.account);
+    END IF;
+
+    BEGIN
+        UPDATE users SET account = v_row.account WHERE id = p_user_id;
+    EXCEPTION WHEN unique_violation THEN
+        PERFORM util_raise_error('PA001', v_row.account);
+    END;
+    DELETE FROM pending_email_changes WHERE id = p_user_id;
+
+    RETURN QUERY SELECT v_account, v_row.account;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Values longer than their columns are cut, as they come from clients.
+CREATE OR REPLACE FUNCTION func_add_audit_event(
+    p_event_type INTEGER,
+    p_user_id UUID,
+    p_actor_id UUID,
+    p_account VARCHAR,
+    p_details TEXT,
+    p_ip_address VARCHAR,
+    p_user_agent VARCHAR,
+    p_request_id VARCHAR
+) RETURNS void
+AS $$
+BEGIN
+    INSERT INTO audit_events (
+        user_id, actor_id, event_type, account, details, ip_address, user_agent, request_id
+    ) VALUES (
+        p_user_id,
+        p_actor_id,
+        p_event_type,
+        left(p_account, 255),
+        p_details,
+        left(p_ip_address, 45),
+        left(p_user_agent, 512),
+        left(p_request_id, 128)
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+-- Newest first. Events of the current account from before it was a user, e.g. its registration,
+-- belong to the user as well.
+CREATE OR REPLACE FUNCTION func_query_audit_events(
+    p_user_id UUID,
+    p_offset INTEGER,
+    p_limit INTEGER
+) RETURNS TABLE (
+    id BIGINT,
+    user_id UUID,
+    actor_id UUID,
+    event_type INTEGER,
+    account VARCHAR,
+    details TEXT,
+    ip_address VARCHAR,
+    user_agent VARCHAR,
+    request_id VARCHAR,
+    created_at TIMESTAMPTZ
+)
+AS $$
+DECLARE
+    v_account VARCHAR;
+BEGIN
+    SELECT u.account INTO v_account FROM users u WHERE u.id = p_user_id;
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PA007', p_user_id);
+    END IF;
+
+    RETURN QUERY
+    SELECT e.id, e.user_id, e.actor_id, e.event_type, e.account, e.details, e.ip_address,
+        e.user_agent, e.request_id, e.created_at
+    FROM audit_events e
+    WHERE e.user_id = p_user_id
+        OR (e.user_id IS NULL AND e.account = v_account)
+    ORDER BY e.id DESC
+    OFFSET p_offset
+    LIMIT p_limit;
+END;
+$$ LANGUAGE plpgsql;
+
+-- The returned columns changed, which CREATE OR REPLACE cannot do.
+DROP FUNCTION IF EXISTS func_query_user_by_id(UUID);
+CREATE OR REPLACE FUNCTION func_query_user_by_id(
+    p_user_id UUID
+) RETURNS TABLE (
+	id UUID,
+    account VARCHAR,
+	created_at TIMESTAMPTZ,
+	updated_at TIMESTAMPTZ,
+	last_login_at TIMESTAMPTZ,
+	status INTEGER,
+	role INTEGER,
+    firstname VARCHAR,
+    lastname VARCHAR,
+    gender INTEGER,
+    locale INTEGER,
+    timezone TEXT,
+    avatar VARCHAR,
+    signature VARCHAR
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT
+		u.id,
+        u.account,
+        u.created_at,
+        u.updated_at,
+		u.last_login_at,
+        u.status,
+        u.role,
+        up.firstname,
+        up.lastname,
+        up.gender,
+        up.locale,
+        up.timezone,
+        up.avatar,
+        up.signature
+    FROM users u
+    JOIN user_profiles up ON u.id = up.id
+    WHERE u.id = p_user_id;
+END;
+$$ LANGUAGE plpgsql;
+
+
+-- The returned columns changed, which CREATE OR REPLACE cannot do.
+DROP FUNCTION IF EXISTS func_query_user_by_account(VARCHAR);
+CREATE OR REPLACE FUNCTION func_query_user_by_account(
+    p_account VARCHAR
+) RETURNS TABLE (
+	id UUID,
+    account VARCHAR,
+	created_at TIMESTAMPTZ,
+	updated_at TIMESTAMPTZ,
+	last_login_at TIMESTAMPTZ,
+	status INTEGER,
+	role INTEGER,
+    firstname VARCHAR,
+    lastname VARCHAR,
+    gender INTEGER,
+    locale INTEGER,
+    timezone TEXT,
+    avatar VARCHAR,
+    signature VARCHAR
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT
+		u.id,
+        u.account,
+        u.created_at,
+        u.updated_at,
+		u.last_login_at,
+        u.status,
+        u.role,
+        up.firstname,
+        up.lastname,
+        up.gender,
+        up.locale,
+        up.timezone,
+        up.avatar,
+        up.signature
+    FROM users u
+    JOIN user_profiles up ON u.id = up.id
+    WHERE u.account = p_account;
+END;
+$$ LANGUAGE plpgsql;
+
+-- The returned columns changed, which CREATE OR REPLACE cannot do.
+DROP FUNCTION IF EXISTS func_query_users(INTEGER, INTEGER);
+CREATE OR REPLACE FUNCTION func_query_users(
+    p_offset INTEGER,
+    p_limit INTEGER
+) RETURNS TABLE (
+	id UUID,
+    account VARCHAR,
+	created_at TIMESTAMPTZ,
+	updated_at TIMESTAMPTZ,
+	last_login_at TIMESTAMPTZ,
+	status INTEGER,
+	role INTEGER,
+    firstname VARCHAR,
+    lastname VARCHAR,
+    gender INTEGER,
+    locale INTEGER,
+    timezone TEXT,
+    avatar VARCHAR,
+    signature VARCHAR
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT
+		u.id,
+        u.account,
+        u.created_at,
+        u.updated_at,
+		u.last_login_at,
+        u.status,
+        u.role,
+        up.firstname,
+        up.lastname,
+        up.gender,
+        up.locale,
+        up.timezone,
+        up.avatar,
+        up.signature
+    FROM users u
+    JOIN user_profiles up ON u.id = up.id
+    ORDER BY u.created_at, u.id
+    OFFSET p_offset
+    LIMIT p_limit;
 END;
 $$ LANGUAGE plpgsql;
 
@@ -1,6 +1,9 @@
 DROP TABLE IF EXISTS note_source_types CASCADE;
 DROP TABLE IF EXISTS collections CASCADE;
 DROP TABLE IF EXISTS notes CASCADE;
+DROP TABLE IF EXISTS note_revisions CASCADE;
+DROP TABLE IF EXISTS attachment_blobs CASCADE;
+DROP TABLE IF EXISTS note_attachments CASCADE;
 
 CREATE TABLE note_source_types (
     id INTEGER PRIMARY KEY,
@@ -89,7 +92,9 @@ CREATE TABLE notes (
     content TEXT NOT NULL,
     source_type INTEGER NOT NULL REFERENCES note_source_types(id),
     
-    meta JSONB DEFAULT '{}'::jsonb
+    meta JSONB DEFAULT '{}'::jsonb,
+    -- Counts up with every change of content or meta; earlier ones are in note_revisions.
+    revision INTEGER NOT NULL DEFAULT 1
 );
 CREATE INDEX IF NOT EXISTS idx_notes_collection ON notes(collection_id);
 CREATE INDEX IF NOT EXISTS idx_notes_source_type ON notes(source_type);
@@ -136,6 +141,100 @@ BEFORE INSERT ON notes
 FOR EACH ROW
 EXECUTE FUNCTION trgfn_notes_set_default_collection();
 
+-- Earlier versions of notes; the note itself is its latest revision.
+CREATE TABLE note_revisions (
+    note_id UUID NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    meta JSONB,
+    -- When the note was changed to this version.
+    created_at TIMESTAMPTZ NOT NULL,
+    -- When the next version replaced it; retention counts from here.
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (note_id, revision)
+);
+CREATE INDEX IF NOT EXISTS idx_note_revisions_replaced ON note_revisions(replaced_at);
+
+CREATE OR REPLACE FUNCTION trgfn_notes_save_revision()
+RETURNS TRIGGER
+AS $$
+BEGIN
+  IF NEW.content IS DISTINCT FROM OLD.content OR NEW.meta IS DISTINCT FROM OLD.meta THEN
+    INSERT INTO note_revisions (note_id, revision, content, meta, created_at)
+    VALUES (OLD.id, OLD.revision, OLD.content, OLD.meta, OLD.updated_at);
+    NEW.revision := OLD.revision + 1;
+  ELSE
+    NEW.revision := OLD.revision;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+DROP TRIGGER IF EXISTS trg_notes_save_revision_on_update ON notes;
+CREATE TRIGGER trg_notes_save_revision_on_update
+BEFORE UPDATE ON notes
+FOR EACH ROW
+EXECUTE FUNCTION trgfn_notes_save_revision();
+
+-- Files of a user are stored once per SHA-256, under `attachments/<user_id>/<sha256>.<id>`; the id
+-- changes when a collected blob is uploaded again, so collecting never deletes a newer copy.
+CREATE TABLE attachment_blobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    sha256 CHAR(64) NOT NULL,
+    size BIGINT NOT NULL,
+    -- Attachments of the blob, kept by the triggers of note_attachments.
+    ref_count INTEGER NOT NULL DEFAULT 0,
+    -- Whether the file is in the blob store; set once it is written.
+    is_stored BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    -- When the last attachment went away; collected after a grace period.
+    orphaned_at TIMESTAMPTZ,
+
+    UNIQUE(user_id, sha256)
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_blobs_orphaned
+    ON attachment_blobs(orphaned_at)
+    WHERE ref_count = 0;
+
+CREATE TABLE note_attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    note_id UUID NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+    blob_id UUID NOT NULL REFERENCES attachment_blobs(id) ON DELETE RESTRICT,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS idx_note_attachments_note ON note_attachments(note_id);
+CREATE INDEX IF NOT EXISTS idx_note_attachments_blob ON note_attachments(blob_id);
+
+-- Counts the attachments of each blob, including the ones deleted with their note.
+CREATE OR REPLACE FUNCTION trgfn_note_attachments_count_blob_refs()
+RETURNS TRIGGER
+AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    UPDATE attachment_blobs
+    SET ref_count = ref_count + 1,
+        orphaned_at = NULL
+    WHERE id = NEW.blob_id;
+    RETURN NEW;
+  END IF;
+
+  UPDATE attachment_blobs
+  SET ref_count = ref_count - 1,
+      orphaned_at = CASE WHEN ref_count = 1 THEN now() ELSE orphaned_at END
+  WHERE id = OLD.blob_id;
+  RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_note_attachments_count_blob_refs ON note_attachments;
+CREATE TRIGGER trg_note_attachments_count_blob_refs
+AFTER INSERT OR DELETE ON note_attachments
+FOR EACH ROW
+EXECUTE FUNCTION trgfn_note_attachments_count_blob_refs();
+
 -- Collections
 CREATE OR REPLACE FUNCTION func_add_collection(
     p_user_id UUID,
@@ -165,17 +264,19 @@ BEGIN
         PERFORM util_raise_error('PN001', p_name, v_account);
     END IF;
 
-    INSERT INTO collections (user_id, name, description, is_active)
-    VALUES (p_user_id, p_name, p_description, p_is_active)
-    RETURNING id INTO v_collection_id;
-
-    -- If the new item should be default, we need to set the others as undefault.
+    -- If the new item should be default, we need to set the current default as undefault first,
+    -- otherwise the insert violates idx_collections_user_default. Updating the inactive ones too
+    -- would make trgfn_collections_set_active pick one of them as the default again.
     IF p_is_active THEN
         UPDATE collections
         SET is_active = FALSE
-        WHERE user_id = p_user_id AND id <> v_collection_id;
+        WHERE user_id = p_user_id AND is_active = TRUE;
     END IF;
 
+    INSERT INTO collections (user_id, name, description, is_active)
+    VALUES (p_user_id, p_name, p_description, p_is_active)
+    RETURNING id INTO v_collection_id;
+
 	RETURN v_collection_id;
 END;
 $$ LANGUAGE plpgsql;
@@ -203,10 +304,16 @@ BEGIN
         PERFORM util_raise_error('PN002', p_collection_id, v_account);
     END IF;
 
-    -- Set current as active, and others as inactive
+    -- Unset the current default, then set the new one, so idx_collections_user_default holds row by
+    -- row. Only the active row is updated: trgfn_collections_set_active would make an inactive one
+    -- updated after it the default again.
+    UPDATE collections
+    SET is_active = FALSE
+    WHERE user_id = p_user_id AND id <> p_collection_id AND is_active = TRUE;
+
     UPDATE collections
-    SET is_active = (id = p_collection_id)
-    WHERE user_id = p_user_id;
+    SET is_active = TRUE
+    WHERE id = p_collection_id;
 END;
 $$ LANGUAGE plpgsql;
 
@@ -323,3 +430,316 @@ BEGIN
     WHERE id = p_note_id;
 END;
 $$ LANGUAGE plpgsql;
+
+
+-- Attachments
+CREATE OR REPLACE FUNCTION util_check_note_owner(p_user_id UUID, p_note_id UUID)
+RETURNS VOID
+AS $$
+BEGIN
+    -- Notes of other users do not exist as far as the caller is concerned.
+    IF NOT EXISTS (
+        SELECT 1
+        FROM notes n
+        JOIN collections c ON c.id = n.collection_id
+        WHERE n.id = p_note_id AND c.user_id = p_user_id
+    ) THEN
+        PERFORM util_raise_error('PN005', p_note_id::text);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Attaches a file to a note, adding its blob unless the user already has one of the same hash.
+-- The file still has to be written when `is_stored` is false.
+CREATE OR REPLACE FUNCTION func_add_attachment(
+    p_user_id UUID,
+    p_note_id UUID,
+    p_sha256 VARCHAR,
+    p_size BIGINT,
+    p_file_name VARCHAR,
+    p_content_type VARCHAR
+) RETURNS TABLE (
+    id UUID,
+    note_id UUID,
+    blob_id UUID,
+    sha256 VARCHAR,
+    size BIGINT,
+    file_name VARCHAR,
+    content_type VARCHAR,
+    created_at TIMESTAMPTZ,
+    is_stored BOOLEAN
+)
+AS $$
+DECLARE
+    v_blob_id UUID;
+    v_is_stored BOOLEAN;
+    v_attachment_id UUID;
+BEGIN
+    PERFORM util_check_note_owner(p_user_id, p_note_id);
+
+    -- Updating an existing row locks it, so func_collect_attachment_blobs cannot take it away
+    -- before the attachment is counted. The constraint is named, as `sha256` is an output column.
+    INSERT INTO attachment_blobs AS b (user_id, sha256, size)
+    VALUES (p_user_id, p_sha256, p_size)
+    ON CONFLICT ON CONSTRAINT attachment_blobs_user_id_sha256_key DO UPDATE
+    SET size = EXCLUDED.size
+    RETURNING b.id, b.is_stored INTO v_blob_id, v_is_stored;
+
+    INSERT INTO note_attachments (note_id, blob_id, file_name, content_type)
+    VALUES (p_note_id, v_blob_id, p_file_name, p_content_type)
+    RETURNING note_attachments.id INTO v_attachment_id;
+
+    RETURN QUERY
+    SELECT a.id, a.note_id, a.blob_id, b.sha256::VARCHAR, b.size, a.file_name, a.content_type,
+        a.created_at, v_is_stored
+    FROM note_attachments a
+    JOIN attachment_blobs b ON b.id = a.blob_id
+    WHERE a.id = v_attachment_id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_set_attachment_blob_stored(p_blob_id UUID)
+RETURNS VOID
+AS $$
+BEGIN
+    UPDATE attachment_blobs
+    SET is_stored = TRUE
+    WHERE id = p_blob_id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_query_attachments(
+    p_user_id UUID,
+    p_note_id UUID
+) RETURNS TABLE (
+    id UUID,
+    note_id UUID,
+    blob_id UUID,
+    sha256 VARCHAR,
+    size BIGINT,
+    file_name VARCHAR,
+    content_type VARCHAR,
+    created_at TIMESTAMPTZ,
+    is_stored BOOLEAN
+)
+AS $$
+BEGIN
+    PERFORM util_check_note_owner(p_user_id, p_note_id);
+
+    RETURN QUERY
+    SELECT a.id, a.note_id, a.blob_id, b.sha256::VARCHAR, b.size, a.file_name, a.content_type,
+        a.created_at, b.is_stored
+    FROM note_attachments a
+    JOIN attachment_blobs b ON b.id = a.blob_id
+    WHERE a.note_id = p_note_id
+    ORDER BY a.created_at, a.id;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_query_attachment(
+    p_user_id UUID,
+    p_attachment_id UUID
+) RETURNS TABLE (
+    id UUID,
+    note_id UUID,
+    blob_id UUID,
+    sha256 VARCHAR,
+    size BIGINT,
+    file_name VARCHAR,
+    content_type VARCHAR,
+    created_at TIMESTAMPTZ,
+    is_stored BOOLEAN
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT a.id, a.note_id, a.blob_id, b.sha256::VARCHAR, b.size, a.file_name, a.content_type,
+        a.created_at, b.is_stored
+    FROM note_attachments a
+    JOIN attachment_blobs b ON b.id = a.blob_id
+    WHERE a.id = p_attachment_id AND b.user_id = p_user_id;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PN006', p_attachment_id::text);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_delete_attachment(
+    p_user_id UUID,
+    p_attachment_id UUID
+)
+RETURNS VOID
+AS $$
+BEGIN
+    DELETE FROM note_attachments a
+    USING attachment_blobs b
+    WHERE a.id = p_attachment_id AND b.id = a.blob_id AND b.user_id = p_user_id;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PN006', p_attachment_id::text);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Deletes up to `p_limit` blobs orphaned before `p_orphaned_before` and returns them, so their
+-- files can be deleted next. A file whose deletion fails is left behind, never one still in use.
+CREATE OR REPLACE FUNCTION func_collect_attachment_blobs(
+    p_orphaned_before TIMESTAMPTZ,
+    p_limit INTEGER
+) RETURNS TABLE (
+    id UUID,
+    user_id UUID,
+    sha256 VARCHAR
+)
+AS $$
+BEGIN
+    RETURN QUERY
+    DELETE FROM attachment_blobs b
+    WHERE b.id IN (
+        SELECT o.id
+        FROM attachment_blobs o
+        WHERE o.ref_count = 0 AND o.orphaned_at < p_orphaned_before
+        LIMIT p_limit
+        FOR UPDATE SKIP LOCKED
+    )
+      AND b.ref_count = 0
+    RETURNING b.id, b.user_id, b.sha256::VARCHAR;
+END;
+$$ LANGUAGE plpgsql;
+
+
+-- Revisions
+-- Newest first, starting with the note itself.
+CREATE OR REPLACE FUNCTION func_query_note_revisions(
+    p_user_id UUID,
+    p_note_id UUID,
+    p_offset INTEGER,
+    p_limit INTEGER
+) RETURNS TABLE (
+    note_id UUID,
+    revision INTEGER,
+    content TEXT,
+    meta JSONB,
+    created_at TIMESTAMPTZ
+)
+AS $$
+BEGIN
+    PERFORM util_check_note_owner(p_user_id, p_note_id);
+
+    RETURN QUERY
+    SELECT n.id, n.revision, n.content, COALESCE(n.meta, '{}'::jsonb), n.updated_at
+    FROM notes n
+    WHERE n.id = p_note_id
+    UNION ALL
+    SELECT r.note_id, r.revision, r.content, COALESCE(r.meta, '{}'::jsonb), r.created_at
+    FROM note_revisions r
+    WHERE r.note_id = p_note_id
+    ORDER BY 2 DESC
+    OFFSET p_offset
+    LIMIT p_limit;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION func_query_note_revision(
+    p_user_id UUID,
+    p_note_id UUID,
+    p_revision INTEGER
+) RETURNS TABLE (
+    note_id UUID,
+    revision INTEGER,
+    content TEXT,
+    meta JSONB,
+    created_at TIMESTAMPTZ
+)
+AS $$
+BEGIN
+    PERFORM util_check_note_owner(p_user_id, p_note_id);
+
+    RETURN QUERY
+    SELECT n.id, n.revision, n.content, COALESCE(n.meta, '{}'::jsonb), n.updated_at
+    FROM notes n
+    WHERE n.id = p_note_id AND n.revision = p_revision
+    UNION ALL
+    SELECT r.note_id, r.revision, r.content, COALESCE(r.meta, '{}'::jsonb), r.created_at
+    FROM note_revisions r
+    WHERE r.note_id = p_note_id AND r.revision = p_revision;
+
+    IF NOT FOUND THEN
+        PERFORM util_raise_error('PN008', p_revision::text, p_note_id::text);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Sets the note back to an earlier revision, which saves the current one as a revision too, and
+-- returns the revision of the note now. Restoring the latest revision changes nothing.
+CREATE OR REPLACE FUNCTION func_restore_note_revision(
+    p_user_id UUID,
+    p_note_id UUID,
+    p_revision INTEGER
+) RETURNS INTEGER
+AS $$
+DECLARE
+    v_content TEXT;
+    v_meta JSONB;
+    v_revision INTEGER;
+BEGIN
+    PERFORM util_check_note_owner(p_user_id, p_note_id);
+
+    SELECT r.content, r.meta
+    INTO v_content, v_meta
+    FROM note_revisions r
+    WHERE r.note_id = p_note_id AND r.revision = p_revision;
+
+    IF NOT FOUND THEN
+        SELECT n.revision
+        INTO v_revision
+        FROM notes n
+        WHERE n.id = p_note_id AND n.revision = p_revision;
+
+        IF NOT FOUND THEN
+            PERFORM util_raise_error('PN008', p_revision::text, p_note_id::text);
+        END IF;
+        RETURN v_revision;
+    END IF;
+
+    UPDATE notes
+    SET content = v_content,
+        meta = v_meta
+    WHERE id = p_note_id
+    RETURNING notes.revision INTO v_revision;
+
+    RETURN v_revision;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Deletes the revisions that are neither among the `p_keep_count` latest of their note nor
+-- replaced after `p_keep_after`, and returns how many. A NULL leaves its rule out; with both NULL
+-- every revision is kept.
+CREATE OR REPLACE FUNCTION func_prune_note_revisions(
+    p_keep_count INTEGER,
+    p_keep_after TIMESTAMPTZ
+) RETURNS INTEGER
+AS $$
+DECLARE
+    v_count INTEGER;
+BEGIN
+    IF p_keep_count IS NULL AND p_keep_after IS NULL THEN
+        RETURN 0;
+    END IF;
+
+    DELETE FROM note_revisions r
+    USING (
+        SELECT note_id, revision,
+            row_number() OVER (PARTITION BY note_id ORDER BY revision DESC) AS rank
+        FROM note_revisions
+    ) ranked
+    WHERE r.note_id = ranked.note_id
+      AND r.revision = ranked.revision
+      AND (p_keep_count IS NULL OR ranked.rank > p_keep_count)
+      AND (p_keep_after IS NULL OR r.replaced_at < p_keep_after);
+
+    GET DIAGNOSTICS v_count = ROW_COUNT;
+    RETURN v_count;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,372 @@
+-- Schema of the SQLite storage, applied by arcane-vault when it opens the database.
+-- Mirrors easynote_common.sql and easynote_note.sql; the functions are implemented in Rust.
+-- UUIDs are stored as 16-byte BLOBs, timestamps as microseconds since the Unix epoch and
+-- meta as JSON text.
+
+CREATE TABLE IF NOT EXISTS genders (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT
+);
+INSERT OR IGNORE INTO genders (id, name, description) VALUES
+(0, 'Unknown', 'Gender not specified or unknown'),
+(1, 'Male', 'Male gender'),
+(2, 'Female', 'Female gender'),
+(3, 'Other', 'Other or non-binary gender');
+
+CREATE TABLE IF NOT EXISTS roles (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT
+);
+INSERT OR IGNORE INTO roles (id, name, description) VALUES
+(1, 'Admin', 'Administrator with full permissions'),
+(2, 'User', 'Regular user with limited permissions'),
+(3, 'Guest', 'Guest user with minimal permissions');
+
+CREATE TABLE IF NOT EXISTS user_statuses (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT
+);
+INSERT OR IGNORE INTO user_statuses (id, name, description) VALUES
+(0, 'Inactive', 'User account is inactive'),
+(1, 'Active', 'User account is active'),
+(2, 'Suspended', 'User account is suspended'),
+(3, 'Deleted', 'User account is deleted');
+
+CREATE TABLE IF NOT EXISTS audit_event_types (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT
+);
+INSERT OR IGNORE INTO audit_event_types (id, name, description) VALUES
+(1, 'Registered', 'Account registered, waiting for verification'),
+(2, 'Verified', 'Account verified and user created'),
+(3, 'LoginSucceeded', 'Login finished with a new session'),
+(4, 'LoginFailed', 'Login rejected'),
+(5, 'PasswordSet', 'Password set by the user'),
+(6, 'PasswordReset', 'Password reset with a verification code'),
+(7, 'ProfileChanged', 'Profile changed'),
+(8, 'EmailChanged', 'Email address changed');
+
+CREATE TABLE IF NOT EXISTS locales (
+    id INTEGER PRIMARY KEY,
+    language_code TEXT NOT NULL,
+    locale_code TEXT NOT NULL UNIQUE,
+    name_en TEXT NOT NULL,
+    native_name TEXT NOT NULL,
+    is_rtl INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 1
+);
+INSERT OR IGNORE INTO locales (id, language_code, locale_code, name_en, native_name, is_rtl, enabled) VALUES
+(1, 'en', 'en-US', 'English (United States)', 'English', 0, 1),
+(2, 'zh', 'zh-CN', 'Chinese (Simplified)', '简体中文', 0, 1);
+
+CREATE TABLE IF NOT EXISTS timezones (
+    name TEXT PRIMARY KEY
+);
+-- SQLite has no `pg_timezone_names`, so only the common zones are seeded.
+INSERT OR IGNORE INTO timezones (name) VALUES
+('Africa/Cairo'),
+('Africa/Johannesburg'),
+('Africa/Lagos'),
+('America/Chicago'),
+('America/Denver'),
+('America/Los_Angeles'),
+('America/Mexico_City'),
+('America/New_York'),
+('America/Sao_Paulo'),
+('America/Toronto'),
+('Asia/Dubai'),
+('Asia/Hong_Kong'),
+('Asia/Jakarta'),
+('Asia/Kolkata'),
+('Asia/Seoul'),
+('Asia/Shanghai'),
+('Asia/Singapore'),
+('Asia/Taipei'),
+('Asia/Tokyo'),
+('Australia/Melbourne'),
+('Australia/Sydney'),
+('Europe/Berlin'),
+('Europe/Istanbul'),
+('Europe/London'),
+('Europe/Madrid'),
+('Europe/Moscow'),
+('Europe/Paris'),
+('Europe/Rome'),
+('Pacific/Auckland'),
+('UTC');
+
+CREATE TABLE IF NOT EXISTS pending_users (
+    account TEXT PRIMARY KEY,
+    password TEXT NOT NULL,
+    verification_code TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    firstname TEXT NOT NULL,
+    lastname TEXT NOT NULL,
+    gender INTEGER NOT NULL REFERENCES genders(id),
+    locale INTEGER NOT NULL REFERENCES locales(id),
+    timezone TEXT NOT NULL DEFAULT 'UTC' REFERENCES timezones(name),
+    signature TEXT
+);
+
+CREATE TABLE IF NOT EXISTS users (
+    id BLOB PRIMARY KEY,
+    account TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    last_login_at INTEGER,
+    status INTEGER NOT NULL REFERENCES user_statuses(id),
+    role INTEGER NOT NULL REFERENCES roles(id)
+);
+
+CREATE TABLE IF NOT EXISTS user_profiles (
+    id BLOB PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    firstname TEXT NOT NULL,
+    lastname TEXT NOT NULL,
+    gender INTEGER NOT NULL REFERENCES genders(id),
+    locale INTEGER NOT NULL REFERENCES locales(id),
+    timezone TEXT NOT NULL DEFAULT 'UTC' REFERENCES timezones(name),
+    avatar TEXT,
+    signature TEXT
+);
+
+CREATE TABLE IF NOT EXISTS pending_reset_passwords (
+    id BLOB PRIMARY KEY REFERENCES users(id),
+    verification_code TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pending_email_changes (
+    id BLOB PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    account TEXT NOT NULL,
+    verification_code TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS user_totp (
+    id BLOB PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret BLOB NOT NULL,
+    last_used_step INTEGER NOT NULL DEFAULT 0,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_attempt_at INTEGER,
+    created_at INTEGER NOT NULL,
+    enabled_at INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    used_at INTEGER,
+    PRIMARY KEY (id, code_hash)
+);
+
+CREATE TABLE IF NOT EXISTS totp_challenges (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sessions (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    device_label TEXT NOT NULL DEFAULT '',
+    user_agent TEXT NOT NULL DEFAULT '',
+    access_token_hash TEXT NOT NULL UNIQUE,
+    access_token_expires_at INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_used_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    revoked_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id);
+
+CREATE TABLE IF NOT EXISTS session_refresh_tokens (
+    token_hash TEXT PRIMARY KEY,
+    session_id BLOB NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    created_at INTEGER NOT NULL,
+    used_at INTEGER
+);
+
+-- scopes are separated by spaces, like OAuth scopes.
+CREATE TABLE IF NOT EXISTS personal_access_tokens (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER,
+    last_used_at INTEGER,
+    revoked_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user ON personal_access_tokens(user_id);
+
+CREATE TABLE IF NOT EXISTS oidc_login_attempts (
+    state_hash TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    pkce_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    user_id BLOB REFERENCES users(id) ON DELETE CASCADE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS user_identities (
+    provider TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    email TEXT NOT NULL DEFAULT '',
+    created_at INTEGER NOT NULL,
+    last_login_at INTEGER,
+
+    PRIMARY KEY (provider, subject)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_identities_user ON user_identities(user_id);
+
+-- Only ever added to; user_id and actor_id do not reference users, so the log outlives them.
+CREATE TABLE IF NOT EXISTS audit_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id BLOB,
+    actor_id BLOB,
+    event_type INTEGER NOT NULL REFERENCES audit_event_types(id),
+    account TEXT NOT NULL DEFAULT '',
+    details TEXT NOT NULL DEFAULT '',
+    ip_address TEXT NOT NULL DEFAULT '',
+    user_agent TEXT NOT NULL DEFAULT '',
+    request_id TEXT NOT NULL DEFAULT '',
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_user ON audit_events(user_id, id);
+CREATE INDEX IF NOT EXISTS idx_audit_events_account ON audit_events(account, id) WHERE user_id IS NULL;
+
+CREATE TRIGGER IF NOT EXISTS trg_audit_events_no_update
+BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_audit_events_no_delete
+BEFORE DELETE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
+
+CREATE TABLE IF NOT EXISTS note_source_types (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT
+);
+INSERT OR IGNORE INTO note_source_types (id, name, description) VALUES
+(0, 'Unknown', 'Unknown source'),
+(1, 'Web', 'Collected from website'),
+(2, 'Manual', 'Manually added by user');
+
+CREATE TABLE IF NOT EXISTS collections (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    is_active INTEGER NOT NULL,
+    meta TEXT NOT NULL DEFAULT '{}',
+
+    UNIQUE(user_id, name)
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_collections_user_default
+    ON collections(user_id)
+    WHERE is_active = 1;
+
+CREATE TABLE IF NOT EXISTS notes (
+    id BLOB PRIMARY KEY,
+    collection_id BLOB NOT NULL REFERENCES collections(id) ON DELETE RESTRICT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    source_type INTEGER NOT NULL REFERENCES note_source_types(id),
+    meta TEXT NOT NULL DEFAULT '{}',
+    revision INTEGER NOT NULL DEFAULT 1
+);
+CREATE INDEX IF NOT EXISTS idx_notes_collection ON notes(collection_id);
+CREATE INDEX IF NOT EXISTS idx_notes_source_type ON notes(source_type);
+
+CREATE TABLE IF NOT EXISTS note_revisions (
+    note_id BLOB NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    meta TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    replaced_at INTEGER NOT NULL,
+
+    PRIMARY KEY (note_id, revision)
+);
+CREATE INDEX IF NOT EXISTS idx_note_revisions_replaced ON note_revisions(replaced_at);
+
+-- Save the version an update of content or meta replaces; updates set `updated_at` themselves.
+CREATE TRIGGER IF NOT EXISTS trg_notes_save_revision
+AFTER UPDATE OF content, meta ON notes
+WHEN NEW.content IS NOT OLD.content OR NEW.meta IS NOT OLD.meta
+BEGIN
+    INSERT INTO note_revisions (note_id, revision, content, meta, created_at, replaced_at)
+    VALUES (OLD.id, OLD.revision, OLD.content, OLD.meta, OLD.updated_at, NEW.updated_at);
+    UPDATE notes SET revision = OLD.revision + 1 WHERE id = OLD.id;
+END;
+
+CREATE TABLE IF NOT EXISTS attachment_blobs (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    sha256 TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    ref_count INTEGER NOT NULL DEFAULT 0,
+    is_stored INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    orphaned_at INTEGER,
+
+    UNIQUE(user_id, sha256)
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_blobs_orphaned
+    ON attachment_blobs(orphaned_at)
+    WHERE ref_count = 0;
+
+CREATE TABLE IF NOT EXISTS note_attachments (
+    id BLOB PRIMARY KEY,
+    note_id BLOB NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+    blob_id BLOB NOT NULL REFERENCES attachment_blobs(id) ON DELETE RESTRICT,
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_note_attachments_note ON note_attachments(note_id);
+CREATE INDEX IF NOT EXISTS idx_note_attachments_blob ON note_attachments(blob_id);
+
+-- Count the attachments of each blob, including the ones deleted with their note.
+CREATE TRIGGER IF NOT EXISTS trg_note_attachments_add_blob_ref
+AFTER INSERT ON note_attachments
+BEGIN
+    UPDATE attachment_blobs
+    SET ref_count = ref_count + 1,
+        orphaned_at = NULL
+    WHERE id = NEW.blob_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_note_attachments_remove_blob_ref
+AFTER DELETE ON note_attachments
+BEGIN
+    UPDATE attachment_blobs
+    SET ref_count = ref_count - 1,
+        orphaned_at = CASE
+            WHEN ref_count = 1 THEN CAST((julianday('now') - 2440587.5) * 86400000000 AS INTEGER)
+            ELSE orphaned_at
+        END
+    WHERE id = OLD.blob_id;
+END;
@@ -2,6 +2,7 @@ SELECT * FROM util_verify_account('puppywin@163.com');
 
 SELECT * FROM util_generate_verification_code();
 
+-- The functions take password hashes from arcane-vault; any string stands in for one here.
 SELECT * FROM func_register_user(
     'puppywin@163.com',
     'Wenxuan815',
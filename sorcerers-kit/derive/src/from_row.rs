@@ -0,0 +1,296 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, GenericArgument, LitStr, PathArguments, Type};
+
+enum Conversion {
+    None,
+    Uuid,
+    Timestamp,
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "FromRow requires a struct with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FromRow can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut initializers = Vec::with_capacity(fields.len());
+    let mut checks = Vec::with_capacity(fields.len());
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut column = ident.to_string();
+        let mut is_uuid = false;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("from_row")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    column = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("uuid") {
+                    is_uuid = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `rename = \"...\"` or `uuid`"))
+                }
+            })?;
+        }
+
+        let (inner_type, is_option) = match get_option_inner_type(&field.ty) {
+            Some(inner_type) => (inner_type, true),
+            None => (&field.ty, false),
+        };
+        let conversion = if is_uuid {
+            Conversion::Uuid
+        } else if is_timestamp(inner_type) {
+            Conversion::Timestamp
+        } else {
+            Conversion::None
+        };
+
+        let field_type = &field.ty;
+        let (read_type, value) = match (conversion, is_option) {
+            (Conversion::None, _) => (
+                quote! { #field_type },
+                quote! { row.try_get::<_, #field_type>(#column)? },
+            ),
+            (Conversion::Uuid, false) => (
+                quote! { ::sorcerers_kit::__private::uuid::Uuid },
+                quote! { row.try_get::<_, ::sorcerers_kit::__private::uuid::Uuid>(#column)?.to_string() },
+            ),
+            (Conversion::Uuid, true) => (
+                quote! { ::std::option::Option<::sorcerers_kit::__private::uuid::Uuid> },
+                quote! {
+                    row.try_get::<_, ::std::option::Option<::sorcerers_kit::__private::uuid::Uuid>>(#column)?
+                        .map(|value| value.to_string())
+                },
+            ),
+            (Conversion::Timestamp, false) => (
+                quote! { ::std::time::SystemTime },
+                quote! {
+                    ::sorcerers_kit::__private::prost_types::Timestamp::from(
+                        row.try_get::<_, ::std::time::SystemTime>(#column)?
+                    )
+                },
+            ),
+            (Conversion::Timestamp, true) => (
+                quote! { ::std::option::Option<::std::time::SystemTime> },
+                quote! {
+                    row.try_get::<_, ::std::option::Option<::std::time::SystemTime>>(#column)?
+                        .map(::sorcerers_kit::__private::prost_types::Timestamp::from)
+                },
+            ),
+        };
+        initializers.push(quote! { #ident: #value });
+        checks.push(quote! {
+            match columns.iter().find(|column| column.name() == #column) {
+                ::std::option::Option::Some(column) => {
+                    if !<#read_type as ::sorcerers_kit::__private::tokio_postgres::types::FromSql>::accepts(column.type_()) {
+                        problems.push(::std::format!(
+                            "column `{}` is {} but {} is expected",
+                            #column,
+                            column.type_(),
+                            ::std::any::type_name::<#read_type>()
+                        ));
+                    }
+                }
+                ::std::option::Option::None => {
+                    problems.push(::std::format!("missing column `{}`", #column));
+                }
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sorcerers_kit::FromRow for #ident #type_generics #where_clause {
+            fn from_row(
+                row: &::sorcerers_kit::__private::tokio_postgres::Row,
+            ) -> ::std::result::Result<Self, ::sorcerers_kit::__private::tokio_postgres::Error> {
+                ::std::result::Result::Ok(Self {
+                    #(#initializers,)*
+                })
+            }
+
+            fn check_columns(
+                columns: &[::sorcerers_kit::__private::tokio_postgres::Column],
+            ) -> ::std::vec::Vec<::std::string::String> {
+                let mut problems = ::std::vec::Vec::new();
+                #(#checks)*
+                problems
+            }
+        }
+    })
+}
+
+fn get_option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        GenericArgument::Type(inner_type) => Some(inner_type),
+        _ => None,
+    }
+}
+
+/// Whether `ty` is spelled `prost_types::Timestamp`; other types named `Timestamp` are read as is.
+fn is_timestamp(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    type_path.qself.is_none()
+        && type_path.path.segments.len() == 2
+        && type_path.path.segments[0].ident == "prost_types"
+        && type_path.path.segments[1].ident == "Timestamp"
+        && type_path.path.segments[0].arguments.is_none()
+        && type_path.path.segments[1].arguments.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote;
+
+    use super::expand;
+
+    fn get_error(input: syn::DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn implements_from_row() {
+        let input = parse_quote! {
+            struct Entity {
+                #[from_row(rename = "account")]
+                email: String,
+            }
+        };
+        let expected = quote! {
+            impl ::sorcerers_kit::FromRow for Entity {
+                fn from_row(
+                    row: &::sorcerers_kit::__private::tokio_postgres::Row,
+                ) -> ::std::result::Result<Self, ::sorcerers_kit::__private::tokio_postgres::Error> {
+                    ::std::result::Result::Ok(Self {
+                        email: row.try_get::<_, String>("account")?,
+                    })
+                }
+
+                fn check_columns(
+                    columns: &[::sorcerers_kit::__private::tokio_postgres::Column],
+                ) -> ::std::vec::Vec<::std::string::String> {
+                    let mut problems = ::std::vec::Vec::new();
+                    match columns.iter().find(|column| column.name() == "account") {
+                        ::std::option::Option::Some(column) => {
+                            if !<String as ::sorcerers_kit::__private::tokio_postgres::types::FromSql>::accepts(column.type_()) {
+                                problems.push(::std::format!(
+                                    "column `{}` is {} but {} is expected",
+                                    "account",
+                                    column.type_(),
+                                    ::std::any::type_name::<String>()
+                                ));
+                            }
+                        }
+                        ::std::option::Option::None => {
+                            problems.push(::std::format!("missing column `{}`", "account"));
+                        }
+                    }
+                    problems
+                }
+            }
+        };
+        assert_eq!(expand(input).unwrap().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn reads_conversions_as_their_column_types() {
+        let input = parse_quote! {
+            struct Entity {
+                #[from_row(uuid)]
+                id: String,
+                #[from_row(uuid)]
+                owner_id: Option<String>,
+                created_at: prost_types::Timestamp,
+                deleted_at: Option<prost_types::Timestamp>,
+            }
+        };
+        let expansion = expand(input).unwrap().to_string();
+        for expected in [
+            quote! { row.try_get::<_, ::sorcerers_kit::__private::uuid::Uuid>("id")?.to_string() },
+            quote! {
+                row.try_get::<_, ::std::option::Option<::sorcerers_kit::__private::uuid::Uuid>>("owner_id")?
+                    .map(|value| value.to_string())
+            },
+            quote! {
+                ::sorcerers_kit::__private::prost_types::Timestamp::from(
+                    row.try_get::<_, ::std::time::SystemTime>("created_at")?
+                )
+            },
+            quote! {
+                row.try_get::<_, ::std::option::Option<::std::time::SystemTime>>("deleted_at")?
+                    .map(::sorcerers_kit::__private::prost_types::Timestamp::from)
+            },
+        ] {
+            let expected = expected.to_string();
+            assert!(expansion.contains(&expected), "{} not in {}", expected, expansion);
+        }
+    }
+
+    #[test]
+    fn reads_other_timestamps_as_they_are() {
+        let input = parse_quote! {
+            struct Entity {
+                created_at: chrono::Timestamp,
+                updated_at: Timestamp,
+            }
+        };
+        let expansion = expand(input).unwrap().to_string();
+        for expected in [
+            quote! { row.try_get::<_, chrono::Timestamp>("created_at")? },
+            quote! { row.try_get::<_, Timestamp>("updated_at")? },
+        ] {
+            let expected = expected.to_string();
+            assert!(expansion.contains(&expected), "{} not in {}", expected, expansion);
+        }
+        assert!(!expansion.contains("SystemTime"), "{}", expansion);
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_read() {
+        assert_eq!(
+            get_error(parse_quote! { enum Entity { A } }),
+            "FromRow can only be derived for structs"
+        );
+        assert_eq!(
+            get_error(parse_quote! { struct Entity(i32); }),
+            "FromRow requires a struct with named fields"
+        );
+        assert_eq!(
+            get_error(parse_quote! {
+                struct Entity {
+                    #[from_row(column = "id")]
+                    id: i32,
+                }
+            }),
+            "expected `rename = \"...\"` or `uuid`"
+        );
+    }
+}
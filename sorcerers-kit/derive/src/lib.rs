@@ -0,0 +1,12 @@
+mod from_row;
+
+use proc_macro::TokenStream;
+
+/// Implements `sorcerers_kit::FromRow`; see there for the attributes.
+#[proc_macro_derive(FromRow, attributes(from_row))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    from_row::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
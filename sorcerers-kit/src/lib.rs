@@ -1 +1,28 @@
+use tokio_postgres::{Column, Error, Row};
 
+/// Derives [`FromRow`] for a struct with named fields, reading each field from the column of the
+/// same name.
+///
+/// - `#[from_row(rename = "column")]` reads the field from another column.
+/// - `#[from_row(uuid)]` reads a `UUID` column into a `String` field.
+/// - `prost_types::Timestamp` fields are read from `TIMESTAMPTZ` columns as `SystemTime`.
+/// - `Option<_>` fields accept `NULL`, which is what nullable columns map to; the conversions
+///   above apply to the inner value.
+pub use sorcerers_kit_derive::FromRow;
+
+/// The crates the derived code names, so deriving crates need not depend on them.
+#[doc(hidden)]
+pub mod __private {
+    pub use prost_types;
+    pub use tokio_postgres;
+    pub use uuid;
+}
+
+/// A type read from the rows of a statement, usually derived.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self, Error>;
+
+    /// Describes every column `from_row` would fail to read from a statement returning `columns`,
+    /// e.g. after `Client::prepare`.
+    fn check_columns(columns: &[Column]) -> Vec<String>;
+}
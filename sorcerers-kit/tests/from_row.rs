@@ -0,0 +1,42 @@
+use sorcerers_kit::FromRow;
+
+// Every conversion of the derive, so the generated code is compiled against the real types.
+#[allow(dead_code)]
+#[derive(FromRow)]
+struct Entity {
+    #[from_row(uuid)]
+    id: String,
+    #[from_row(uuid)]
+    owner_id: Option<String>,
+    #[from_row(rename = "account")]
+    email: String,
+    signature: Option<String>,
+    scopes: Vec<String>,
+    created_at: prost_types::Timestamp,
+    last_login_at: Option<prost_types::Timestamp>,
+}
+
+#[allow(dead_code)]
+#[derive(FromRow)]
+struct Empty {}
+
+fn get_missing_columns<T: FromRow>() -> Vec<String> {
+    T::check_columns(&[])
+}
+
+#[test]
+fn reports_every_missing_column() {
+    assert_eq!(
+        get_missing_columns::<Entity>(),
+        [
+            "missing column `id`",
+            "missing column `owner_id`",
+            "missing column `account`",
+            "missing column `signature`",
+            "missing column `scopes`",
+            "missing column `created_at`",
+            "missing column `last_login_at`",
+        ]
+    );
+    assert!(get_missing_columns::<Empty>().is_empty());
+}